@@ -11,7 +11,6 @@ use super::event_bus::BusEvent;
 
 pub const CATEGORY_TASK: &str = "task";
 pub const CATEGORY_AGENT: &str = "agent";
-#[allow(dead_code)]
 pub const CATEGORY_TOOL: &str = "tool";
 #[allow(dead_code)]
 pub const CATEGORY_USER: &str = "user";
@@ -32,6 +31,15 @@ pub const EVENT_AGENT_MESSAGE_STREAM_COMPLETED: &str = "agent.message_stream_com
 pub const EVENT_AGENT_MESSAGE_STREAM_CANCELLED: &str = "agent.message_stream_cancelled";
 pub const EVENT_AGENT_QUESTION_REQUIRED: &str = "agent.question_required";
 pub const EVENT_AGENT_QUESTION_ANSWERED: &str = "agent.question_answered";
+pub const EVENT_AGENT_SUBAGENT_CLOSED: &str = "agent.subagent_closed";
+pub const EVENT_AGENT_CONFLICT_RESOLUTION_STARTED: &str = "agent.conflict_resolution_started";
+pub const EVENT_AGENT_CONFLICT_RESOLUTION_FINISHED: &str = "agent.conflict_resolution_finished";
+pub const EVENT_AGENT_USAGE: &str = "agent.usage";
+pub const EVENT_TOOL_APPROVAL_REQUIRED: &str = "tool.approval_required";
+pub const EVENT_TOOL_APPROVAL_RESOLVED: &str = "tool.approval_resolved";
+pub const EVENT_TOOL_OUTPUT_DELTA: &str = "tool.output_delta";
+pub const EVENT_TOOL_PROGRESS: &str = "tool.progress";
+pub const EVENT_TOOL_CHECK_FINISHED: &str = "tool.check_finished";
 
 // ---------------------------------------------------------------------------
 // Flush policy
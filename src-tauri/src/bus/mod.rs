@@ -19,7 +19,11 @@ mod event_types;
 pub use batcher::EventBatcher;
 pub use event_bus::{BusEvent, EventBus};
 pub use event_types::{
-    CATEGORY_AGENT, EVENT_AGENT_DECIDING, EVENT_AGENT_MESSAGE_DELTA,
+    CATEGORY_AGENT, CATEGORY_TASK, CATEGORY_TOOL, EVENT_AGENT_CONFLICT_RESOLUTION_FINISHED,
+    EVENT_AGENT_CONFLICT_RESOLUTION_STARTED, EVENT_AGENT_DECIDING, EVENT_AGENT_MESSAGE_DELTA,
     EVENT_AGENT_MESSAGE_STREAM_CANCELLED, EVENT_AGENT_MESSAGE_STREAM_COMPLETED,
-    EVENT_AGENT_MESSAGE_STREAM_STARTED, EVENT_AGENT_TOOL_CALLS_PREPARING,
+    EVENT_AGENT_MESSAGE_STREAM_STARTED, EVENT_AGENT_QUESTION_ANSWERED,
+    EVENT_AGENT_QUESTION_REQUIRED, EVENT_AGENT_SUBAGENT_CLOSED, EVENT_AGENT_TOOL_CALLS_PREPARING,
+    EVENT_AGENT_USAGE, EVENT_TOOL_APPROVAL_REQUIRED, EVENT_TOOL_APPROVAL_RESOLVED,
+    EVENT_TOOL_CHECK_FINISHED, EVENT_TOOL_OUTPUT_DELTA, EVENT_TOOL_PROGRESS,
 };
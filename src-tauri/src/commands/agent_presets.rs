@@ -14,6 +14,8 @@ pub struct CreateAgentPresetInput {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_models: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub steps: Option<u32>,
@@ -116,6 +118,7 @@ pub fn create_agent_preset(
         description: input.description,
         mode,
         model: input.model,
+        fallback_models: input.fallback_models.unwrap_or_default(),
         temperature: input.temperature,
         steps: input.steps,
         tools,
@@ -7,6 +7,7 @@ pub mod execution;
 pub mod mcp;
 pub mod messages;
 pub mod plan_mode;
+pub mod policy;
 pub mod providers;
 pub mod questions;
 pub mod runs;
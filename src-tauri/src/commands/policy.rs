@@ -0,0 +1,56 @@
+//! Policy file validation and effective-policy preview commands
+
+use std::path::PathBuf;
+
+use crate::load_workspace_root;
+use crate::policy::commands::{default_rules, CommandRule};
+use crate::policy::config::{LoadedPolicy, PolicyConfig, PolicyFormat, PolicySource};
+use crate::{AppError, AppState};
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PolicyPreview {
+    pub workspace_root: String,
+    pub sources: Vec<PolicySource>,
+    /// Merged global and workspace configuration.
+    pub effective: PolicyConfig,
+    /// Every command rule in effect, configured rules first.
+    pub command_rules: Vec<CommandRule>,
+}
+
+/// Parse a policy file body without saving it. `format` is `toml` (default)
+/// or `json`.
+#[tauri::command]
+pub fn validate_policy(content: String, format: Option<String>) -> Result<PolicyConfig, AppError> {
+    let format = match format.as_deref().map(str::to_ascii_lowercase).as_deref() {
+        None | Some("toml") => PolicyFormat::Toml,
+        Some("json") => PolicyFormat::Json,
+        Some(other) => {
+            return Err(AppError::Other(format!(
+                "unsupported policy format: {other}"
+            )))
+        }
+    };
+    PolicyConfig::parse(&content, format)
+        .map_err(|e| AppError::Other(format!("invalid policy: {e}")))
+}
+
+/// Show the policy that applies to a workspace (the current one by default).
+#[tauri::command]
+pub fn preview_effective_policy(
+    state: tauri::State<'_, AppState>,
+    workspace_root: Option<String>,
+) -> Result<PolicyPreview, AppError> {
+    let root = workspace_root
+        .map(PathBuf::from)
+        .unwrap_or_else(|| load_workspace_root(&state.db));
+    let loaded = LoadedPolicy::load(&root);
+    let mut command_rules = loaded.config.commands.clone();
+    command_rules.extend(default_rules());
+
+    Ok(PolicyPreview {
+        workspace_root: root.to_string_lossy().to_string(),
+        sources: loaded.sources,
+        effective: loaded.config,
+        command_rules,
+    })
+}
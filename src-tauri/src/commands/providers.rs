@@ -4,15 +4,19 @@ use std::str::FromStr;
 use tauri::Emitter;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-use crate::db::queries;
+use crate::db::{queries, Database};
+use crate::model::discovery::{self, CachedModelList};
 use crate::model::providers::chatgpt::{
     build_authorize_url, calculate_expires_at, exchange_code_for_tokens,
     extract_account_id_from_tokens, generate_state, PkceCodes,
 };
+use crate::model::providers::custom::CustomProviderSettings;
+use crate::model::retry::{self, RetryPolicy};
 use crate::model::{ModelCatalog, ProviderId};
 use crate::{
-    load_provider_config, provider_setting_key, AppError, AppState, ModelCatalogEntry, ModelInfo,
-    ProviderConfig, ProviderConfigView,
+    load_custom_provider_settings, load_provider_config, model_list_cache_key,
+    provider_retry_setting_key, provider_setting_key, refresh_provider_secret, AppError, AppState,
+    ModelCatalogEntry, ModelInfo, ProviderConfig, ProviderConfigView, CUSTOM_PROVIDER_SETTINGS_KEY,
 };
 
 #[tauri::command]
@@ -22,11 +26,18 @@ pub fn set_provider_config(
     api_key: String,
     default_model: Option<String>,
     base_url: Option<String>,
+    retry: Option<RetryPolicy>,
 ) -> Result<(), AppError> {
     let provider = provider.to_ascii_lowercase();
-    ProviderId::from_str(&provider)
+    let provider_id = ProviderId::from_str(&provider)
         .map_err(|_| AppError::Other(format!("unsupported provider: {provider}")))?;
 
+    if let Some(policy) = retry.as_ref() {
+        policy
+            .validate()
+            .map_err(|e| AppError::Other(format!("invalid retry policy: {e}")))?;
+    }
+
     let value = serde_json::to_string(&ProviderConfig {
         api_key,
         default_model,
@@ -34,12 +45,21 @@ pub fn set_provider_config(
     })
     .map_err(|e| AppError::Other(e.to_string()))?;
 
-    queries::upsert_setting(
-        &state.db,
-        &provider_setting_key(&provider),
-        &value,
-        &Utc::now().to_rfc3339(),
-    )?;
+    let now = Utc::now().to_rfc3339();
+    queries::upsert_setting(&state.db, &provider_setting_key(&provider), &value, &now)?;
+    refresh_provider_secret(&state.db, provider_id.as_str());
+
+    // Omitting `retry` keeps the current policy
+    if let Some(policy) = retry {
+        let value = serde_json::to_string(&policy).map_err(|e| AppError::Other(e.to_string()))?;
+        queries::upsert_setting(
+            &state.db,
+            &provider_retry_setting_key(provider_id.as_str()),
+            &value,
+            &now,
+        )?;
+        retry::set_provider_policy(provider_id, Some(policy));
+    }
     Ok(())
 }
 
@@ -58,8 +78,10 @@ pub fn get_provider_configs(
             default_model: cfg
                 .as_ref()
                 .and_then(|v| v.default_model.clone())
-                .or_else(|| Some(ModelCatalog::default_model_for_provider(*provider))),
+                .or_else(|| Some(ModelCatalog::default_model_for_provider(*provider)))
+                .filter(|model| !model.is_empty()),
             base_url: cfg.and_then(|v| v.base_url),
+            retry: retry::policy_for(*provider),
         });
     }
     Ok(result)
@@ -71,18 +93,152 @@ pub fn remove_provider_config(
     provider: String,
 ) -> Result<(), AppError> {
     let provider = provider.to_ascii_lowercase();
-    ProviderId::from_str(&provider)
+    let provider_id = ProviderId::from_str(&provider)
         .map_err(|_| AppError::Other(format!("unsupported provider: {provider}")))?;
 
     let key = provider_setting_key(&provider);
     queries::delete_setting(&state.db, &key)
         .map_err(|e| AppError::Other(format!("Failed to remove provider config: {e}")))?;
+    queries::delete_setting(&state.db, &provider_retry_setting_key(provider_id.as_str()))
+        .map_err(|e| AppError::Other(format!("Failed to remove provider config: {e}")))?;
+    retry::set_provider_policy(provider_id, None);
+    refresh_provider_secret(&state.db, provider_id.as_str());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_custom_provider_settings(
+    state: tauri::State<'_, AppState>,
+) -> Result<CustomProviderSettings, AppError> {
+    Ok(load_custom_provider_settings(&state.db)?.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_custom_provider_settings(
+    state: tauri::State<'_, AppState>,
+    settings: CustomProviderSettings,
+) -> Result<(), AppError> {
+    if settings.models.iter().any(|m| m.name.trim().is_empty()) {
+        return Err(AppError::Other(
+            "custom provider model names must not be empty".to_string(),
+        ));
+    }
+    if let Some(name) = settings
+        .headers
+        .keys()
+        .find(|name| reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err())
+    {
+        return Err(AppError::Other(format!("invalid header name: {name}")));
+    }
+
+    let value = serde_json::to_string(&settings).map_err(|e| AppError::Other(e.to_string()))?;
+    queries::upsert_setting(
+        &state.db,
+        CUSTOM_PROVIDER_SETTINGS_KEY,
+        &value,
+        &Utc::now().to_rfc3339(),
+    )?;
+    refresh_provider_secret(&state.db, ProviderId::Custom.as_str());
     Ok(())
 }
 
+/// Static catalog plus the models declared for the custom provider, merged
+/// with each provider's cached `/models` listing.
+fn catalog_entries(state: &AppState) -> Vec<crate::model::catalog::ProviderEntry> {
+    let mut entries = ModelCatalog::all_models();
+    match load_custom_provider_settings(&state.db) {
+        Ok(Some(settings)) => entries.push(ModelCatalog::custom_provider_entry(&settings)),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("ignoring custom provider models: {e}"),
+    }
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let Ok(provider) = ProviderId::from_str(&entry.provider) else {
+                return entry;
+            };
+            match load_cached_model_list(&state.db, provider) {
+                Some(cached) => discovery::merge_discovered(
+                    entry,
+                    &cached.models,
+                    &ModelCatalog::default_model_for_provider(provider),
+                ),
+                None => entry,
+            }
+        })
+        .collect()
+}
+
+fn load_cached_model_list(db: &Database, provider: ProviderId) -> Option<CachedModelList> {
+    let raw = match queries::get_setting(db, &model_list_cache_key(provider.as_str())) {
+        Ok(raw) => raw?,
+        Err(e) => {
+            tracing::warn!("failed to load cached {provider} models: {e}");
+            return None;
+        }
+    };
+    serde_json::from_str(&raw)
+        .map_err(|e| tracing::warn!("ignoring invalid cached {provider} models: {e}"))
+        .ok()
+}
+
+/// Re-fetch the model listing of every configured provider whose cache is
+/// missing or older than the TTL (or all of them when `force` is set).
+/// Failures are logged and keep the previous cache.
+pub(crate) async fn refresh_model_lists(db: &Database, force: bool) {
+    let now = Utc::now();
+    for provider in ProviderId::all() {
+        if !force
+            && load_cached_model_list(db, *provider).is_some_and(|cached| cached.is_fresh(now))
+        {
+            continue;
+        }
+        let config = match load_provider_config(db, provider.as_str()) {
+            Ok(Some(config)) => config,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("skipping {provider} model refresh: {e}");
+                continue;
+            }
+        };
+
+        let models = match discovery::discover_models(
+            *provider,
+            &config.api_key,
+            config.base_url.as_deref(),
+        )
+        .await
+        {
+            Ok(Some(models)) => models,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("failed to list {provider} models: {e}");
+                continue;
+            }
+        };
+
+        let cached = CachedModelList::new(models);
+        let stored = serde_json::to_string(&cached)
+            .map_err(|e| e.to_string())
+            .and_then(|value| {
+                queries::upsert_setting(
+                    db,
+                    &model_list_cache_key(provider.as_str()),
+                    &value,
+                    &cached.fetched_at,
+                )
+                .map_err(|e| e.to_string())
+            });
+        if let Err(e) = stored {
+            tracing::warn!("failed to cache {provider} models: {e}");
+        }
+    }
+}
+
 /// Returns the context window size for a given model.
-fn get_model_context_window(model: &str) -> usize {
-    ModelCatalog::all_models()
+fn get_model_context_window(state: &AppState, model: &str) -> usize {
+    catalog_entries(state)
         .into_iter()
         .flat_map(|entry| entry.models)
         .find(|entry| entry.name == model)
@@ -91,8 +247,8 @@ fn get_model_context_window(model: &str) -> usize {
 }
 
 #[tauri::command]
-pub fn get_model_catalog() -> Vec<ModelCatalogEntry> {
-    ModelCatalog::all_models()
+pub fn get_model_catalog(state: tauri::State<'_, AppState>) -> Vec<ModelCatalogEntry> {
+    catalog_entries(&state)
         .into_iter()
         .map(|entry| ModelCatalogEntry {
             provider: entry.provider,
@@ -102,15 +258,32 @@ pub fn get_model_catalog() -> Vec<ModelCatalogEntry> {
                 .map(|model| ModelInfo {
                     name: model.name,
                     context_window: model.context_window as usize,
+                    output_limit: model.output_limit as usize,
+                    description: model.description,
+                    deprecated: model.deprecated,
+                    deprecation_reason: model.deprecation_reason,
+                    suggested_alternative: model.suggested_alternative,
+                    capabilities: model.capabilities,
                 })
                 .collect(),
         })
         .collect()
 }
 
+/// Refresh provider model listings, then return the merged catalog.
 #[tauri::command]
-pub fn get_context_window_for_model(model: String) -> usize {
-    get_model_context_window(&model)
+pub async fn refresh_model_catalog(
+    state: tauri::State<'_, AppState>,
+    force: Option<bool>,
+) -> Result<Vec<ModelCatalogEntry>, AppError> {
+    let db = state.db.clone();
+    refresh_model_lists(&db, force.unwrap_or(false)).await;
+    Ok(get_model_catalog(state))
+}
+
+#[tauri::command]
+pub fn get_context_window_for_model(state: tauri::State<'_, AppState>, model: String) -> usize {
+    get_model_context_window(&state, &model)
 }
 
 /// Start ChatGPT OAuth flow, open the auth URL in the system browser, then
@@ -265,6 +438,7 @@ async fn run_callback_server(
 
     queries::upsert_setting(&db, "chatgpt_oauth_auth", &value, &Utc::now().to_rfc3339())
         .map_err(|e| format!("Failed to persist auth data: {e}"))?;
+    refresh_provider_secret(&db, ProviderId::OpenAIChatGPT.as_str());
 
     Ok(account_id)
 }
@@ -323,6 +497,7 @@ pub async fn complete_chatgpt_oauth(
         &value,
         &Utc::now().to_rfc3339(),
     )?;
+    refresh_provider_secret(&state.db, ProviderId::OpenAIChatGPT.as_str());
 
     Ok(())
 }
@@ -362,6 +537,7 @@ pub fn get_chatgpt_auth_status(
 pub fn remove_chatgpt_auth(state: tauri::State<'_, AppState>) -> Result<(), AppError> {
     queries::delete_setting(&state.db, "chatgpt_oauth_auth")
         .map_err(|e| AppError::Other(format!("Failed to remove auth: {}", e)))?;
+    refresh_provider_secret(&state.db, ProviderId::OpenAIChatGPT.as_str());
     Ok(())
 }
 
@@ -522,6 +698,24 @@ async fn fetch_provider_usage(
 
             fetch_openai_usage(provider, &cfg.api_key, &now).await
         }
+        "custom" => {
+            if load_provider_config(&state.db, provider)?.is_none() {
+                return Ok(not_configured_snapshot(provider, &now));
+            }
+
+            Ok(ProviderUsageSnapshotView {
+                provider: provider.to_string(),
+                available: false,
+                status: "not_applicable".to_string(),
+                balance: None,
+                currency: None,
+                remaining_quota: None,
+                period: None,
+                last_updated_at: Some(now),
+                note: Some("Custom endpoints do not expose a usage API".to_string()),
+                error: None,
+            })
+        }
         _ => Ok(ProviderUsageSnapshotView {
             provider: provider.to_string(),
             available: false,
@@ -1,7 +1,8 @@
 //! Run query commands
 
-use crate::db::queries;
-use crate::{AppError, AppState};
+use crate::db::queries::{self, FileSnapshotScope};
+use crate::runtime::snapshots::{revert_file_changes, RevertReport};
+use crate::{AppError, AppState, RunUsageView};
 
 #[tauri::command]
 pub fn get_latest_run(
@@ -71,3 +72,76 @@ pub fn get_task_events(
     // This ensures conversation history is preserved across follow-up messages
     Ok(queries::list_events_for_task(&state.db, &task_id)?)
 }
+
+#[tauri::command]
+pub fn get_run_usage(
+    state: tauri::State<'_, AppState>,
+    run_id: String,
+) -> Result<RunUsageView, AppError> {
+    Ok(RunUsageView {
+        totals: queries::get_run_usage_totals(&state.db, &run_id)?,
+        sub_agents: queries::list_sub_agent_usage_for_run(&state.db, &run_id)?,
+        models: queries::list_model_usage_for_run(&state.db, &run_id)?,
+        requests: queries::list_api_requests_for_run(&state.db, &run_id)?,
+        run_id,
+    })
+}
+
+#[tauri::command]
+pub fn get_task_usage(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+) -> Result<queries::UsageTotalsRow, AppError> {
+    Ok(queries::get_task_usage_totals(&state.db, &task_id)?)
+}
+
+/// Revert the file changes made by one tool call.
+#[tauri::command]
+pub fn revert_tool_call(
+    state: tauri::State<'_, AppState>,
+    tool_call_id: String,
+    force: Option<bool>,
+) -> Result<RevertReport, AppError> {
+    revert(
+        &state,
+        FileSnapshotScope::ToolCall(tool_call_id),
+        force.unwrap_or(false),
+    )
+}
+
+/// Revert the file changes made by the tool calls of one plan step.
+#[tauri::command]
+pub fn revert_step(
+    state: tauri::State<'_, AppState>,
+    run_id: String,
+    step_idx: i64,
+    force: Option<bool>,
+) -> Result<RevertReport, AppError> {
+    revert(
+        &state,
+        FileSnapshotScope::Step { run_id, step_idx },
+        force.unwrap_or(false),
+    )
+}
+
+/// Revert the file changes made by every tool call of a run.
+#[tauri::command]
+pub fn revert_run(
+    state: tauri::State<'_, AppState>,
+    run_id: String,
+    force: Option<bool>,
+) -> Result<RevertReport, AppError> {
+    revert(
+        &state,
+        FileSnapshotScope::Run(run_id),
+        force.unwrap_or(false),
+    )
+}
+
+fn revert(
+    state: &AppState,
+    scope: FileSnapshotScope,
+    force: bool,
+) -> Result<RevertReport, AppError> {
+    revert_file_changes(&state.db, &scope, force).map_err(AppError::Other)
+}
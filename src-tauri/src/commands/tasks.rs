@@ -2,7 +2,10 @@ use chrono::Utc;
 use serde_json::Value;
 use uuid::Uuid;
 
+use crate::bus::CATEGORY_TASK;
 use crate::db::queries;
+use crate::runtime::budget::{update_active_budgets, TaskBudget, EVENT_TASK_BUDGET_UPDATED};
+use crate::runtime::failover::store_task_fallback_chain;
 use crate::runtime::planner::emit_and_record;
 use crate::{load_workspace_root, AppError, AppState, CreateTaskOptions};
 
@@ -51,6 +54,22 @@ pub fn create_task(
         queries::upsert_task_link(&state.db, &row.id, parent_id, &row.created_at)?;
     }
 
+    if let Some(budget) = options
+        .as_ref()
+        .and_then(|opts| opts.budget)
+        .filter(|budget| !budget.is_unlimited())
+    {
+        queries::upsert_task_budget(&state.db, &budget.to_row(&row.id))?;
+    }
+
+    if let Some(chain) = options
+        .as_ref()
+        .and_then(|opts| opts.model_fallbacks.as_ref())
+        .filter(|chain| !chain.is_empty())
+    {
+        store_task_fallback_chain(&state.db, &row.id, chain).map_err(AppError::Other)?;
+    }
+
     if let Some(reference_ids) = options.and_then(|opts| opts.reference_task_ids) {
         for reference_id in reference_ids {
             if reference_id == row.id {
@@ -365,6 +384,72 @@ pub fn cancel_task(state: tauri::State<'_, AppState>, task_id: String) -> Result
     Ok(())
 }
 
+#[tauri::command]
+pub fn get_task_budget(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+) -> Result<Option<TaskBudget>, AppError> {
+    Ok(queries::get_task_budget(&state.db, &task_id)?
+        .as_ref()
+        .map(TaskBudget::from_row))
+}
+
+/// Set or clear a task's budget. Applies immediately to a run that is executing.
+#[tauri::command]
+pub fn set_task_budget(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+    budget: Option<TaskBudget>,
+) -> Result<(), AppError> {
+    if queries::get_task(&state.db, &task_id)?.is_none() {
+        return Err(AppError::Other(format!("task not found: {task_id}")));
+    }
+
+    let budget = budget.unwrap_or_default();
+    if budget.is_unlimited() {
+        queries::delete_task_budget(&state.db, &task_id)?;
+    } else {
+        queries::upsert_task_budget(&state.db, &budget.to_row(&task_id))?;
+    }
+    update_active_budgets(&state.db, &task_id, budget);
+
+    emit_and_record(
+        &state.db,
+        &state.bus,
+        CATEGORY_TASK,
+        EVENT_TASK_BUDGET_UPDATED,
+        None,
+        serde_json::json!({
+            "task_id": task_id,
+            "budget": budget,
+        }),
+    )
+    .map_err(AppError::Other)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_task_model_fallbacks(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+) -> Result<Vec<String>, AppError> {
+    Ok(queries::get_task_model_fallbacks(&state.db, &task_id)?.unwrap_or_default())
+}
+
+/// Set or clear a task's model fallback chain. Takes effect on the next run.
+#[tauri::command]
+pub fn set_task_model_fallbacks(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+    chain: Vec<String>,
+) -> Result<(), AppError> {
+    if queries::get_task(&state.db, &task_id)?.is_none() {
+        return Err(AppError::Other(format!("task not found: {task_id}")));
+    }
+    store_task_fallback_chain(&state.db, &task_id, &chain).map_err(AppError::Other)
+}
+
 #[tauri::command]
 pub async fn start_task(
     state: tauri::State<'_, AppState>,
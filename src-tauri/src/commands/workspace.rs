@@ -4,6 +4,10 @@ use std::process::Command;
 use crate::core::agent_presets;
 use crate::db::queries;
 use crate::embeddings;
+use crate::runtime::check_command::{load_check_command, save_check_command, CheckCommandSettings};
+use crate::runtime::conflict_resolution::{
+    load_conflict_resolution_mode, save_conflict_resolution_mode, ConflictResolutionMode,
+};
 use crate::{load_workspace_root, AppError, AppState, ArtifactContentView, WorkspaceRootView};
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -54,6 +58,44 @@ pub fn get_workspace_root(
     })
 }
 
+/// Check command run after the agent edits files in the current workspace.
+#[tauri::command]
+pub fn get_workspace_check_command(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<CheckCommandSettings>, AppError> {
+    let workspace_root = load_workspace_root(&state.db);
+    load_check_command(&state.db, &workspace_root).map_err(AppError::Other)
+}
+
+/// Set the current workspace's check command; `null` or a blank command
+/// turns the check off.
+#[tauri::command]
+pub fn set_workspace_check_command(
+    state: tauri::State<'_, AppState>,
+    settings: Option<CheckCommandSettings>,
+) -> Result<(), AppError> {
+    let workspace_root = load_workspace_root(&state.db);
+    save_check_command(&state.db, &workspace_root, settings.as_ref()).map_err(AppError::Other)
+}
+
+/// How the current workspace handles sub-agent merges that conflict.
+#[tauri::command]
+pub fn get_workspace_conflict_resolution(
+    state: tauri::State<'_, AppState>,
+) -> Result<ConflictResolutionMode, AppError> {
+    let workspace_root = load_workspace_root(&state.db);
+    load_conflict_resolution_mode(&state.db, &workspace_root).map_err(AppError::Other)
+}
+
+#[tauri::command]
+pub fn set_workspace_conflict_resolution(
+    state: tauri::State<'_, AppState>,
+    mode: ConflictResolutionMode,
+) -> Result<(), AppError> {
+    let workspace_root = load_workspace_root(&state.db);
+    save_conflict_resolution_mode(&state.db, &workspace_root, mode).map_err(AppError::Other)
+}
+
 #[tauri::command]
 pub fn search_workspace_references(
     state: tauri::State<'_, AppState>,
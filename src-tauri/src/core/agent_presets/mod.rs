@@ -64,6 +64,9 @@ pub struct AgentPreset {
     /// The model to use (e.g., "anthropic/claude-sonnet-4-5", "minimax/MiniMax-M2.1").
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
+    /// Ordered `<provider>/<model>` entries to fail over to when `model` errors.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub fallback_models: Vec<String>,
     /// Temperature setting (0.0 - 1.0).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
@@ -178,6 +181,7 @@ struct AgentFrontmatter {
     description: Option<String>,
     mode: Option<AgentMode>,
     model: Option<String>,
+    fallback_models: Option<Vec<String>>,
     temperature: Option<f32>,
     steps: Option<u32>,
     tools: Option<HashMap<String, ToolPermission>>,
@@ -290,6 +294,17 @@ pub fn parse_model_override(value: &str) -> (Option<String>, String) {
     (provider, model)
 }
 
+/// Parse a fallback chain written as `a -> b -> c` or `a, b, c`.
+pub fn parse_fallback_models(value: &str) -> Vec<String> {
+    value
+        .split("->")
+        .flat_map(|part| part.split(','))
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 /// Read the content of an agent file.
 pub fn read_agent_file(file_path: &str) -> Result<String, String> {
     std::fs::read_to_string(file_path).map_err(|e| format!("failed to read agent file: {e}"))
@@ -323,6 +338,12 @@ pub fn write_agent_preset(
     if let Some(model) = &preset.model {
         frontmatter.push_str(&format!("model: {}\n", model));
     }
+    if !preset.fallback_models.is_empty() {
+        frontmatter.push_str("fallback_models:\n");
+        for entry in &preset.fallback_models {
+            frontmatter.push_str(&format!("  - {}\n", entry));
+        }
+    }
     if let Some(temp) = preset.temperature {
         frontmatter.push_str(&format!("temperature: {}\n", temp));
     }
@@ -491,6 +512,7 @@ fn parse_agent_file(id: &str, raw: &str, path: &Path, source: &str) -> (AgentPre
         description: fm.description.unwrap_or_default(),
         mode,
         model: fm.model,
+        fallback_models: fm.fallback_models.unwrap_or_default(),
         temperature,
         steps,
         tools: fm.tools,
@@ -537,6 +559,7 @@ fn parse_agent_frontmatter(block: &str, issues: &mut Vec<String>) -> AgentFrontm
     let mut tools: HashMap<String, ToolPermission> = HashMap::new();
     let mut permission = PermissionConfig::default();
     let mut tags: Vec<String> = Vec::new();
+    let mut fallback_models: Vec<String> = Vec::new();
 
     for raw_line in block.lines() {
         let line = raw_line.trim_end();
@@ -574,6 +597,11 @@ fn parse_agent_frontmatter(block: &str, issues: &mut Vec<String>) -> AgentFrontm
                         }
                     }
                 }
+                Some("fallback_models") => {
+                    if let Some(item) = trimmed.strip_prefix("- ") {
+                        fallback_models.extend(parse_fallback_models(item));
+                    }
+                }
                 _ => {}
             }
             continue;
@@ -598,6 +626,13 @@ fn parse_agent_frontmatter(block: &str, issues: &mut Vec<String>) -> AgentFrontm
                     };
                 }
                 "model" => fm.model = Some(value.to_string()),
+                "fallback_models" => {
+                    if value.is_empty() {
+                        current_section = Some("fallback_models");
+                    } else {
+                        fallback_models.extend(parse_fallback_models(value));
+                    }
+                }
                 "temperature" => match value.parse::<f32>() {
                     Ok(v) => fm.temperature = Some(v),
                     Err(_) => {
@@ -646,6 +681,19 @@ fn parse_agent_frontmatter(block: &str, issues: &mut Vec<String>) -> AgentFrontm
     if !tags.is_empty() {
         fm.tags = Some(tags);
     }
+    for entry in &fallback_models {
+        if let Some((provider, _)) = entry.split_once('/') {
+            if parse_provider_model_override(entry).0.is_none() {
+                issues.push(format!(
+                    "unknown provider '{}' in fallback_models entry '{}'",
+                    provider, entry
+                ));
+            }
+        }
+    }
+    if !fallback_models.is_empty() {
+        fm.fallback_models = Some(fallback_models);
+    }
 
     fm
 }
@@ -773,6 +821,7 @@ You are a code reviewer. Focus on quality."#;
             description: "Test".to_string(),
             mode: AgentMode::Primary,
             model: None,
+            fallback_models: vec![],
             temperature: None,
             steps: None,
             tools: None,
@@ -811,6 +860,7 @@ You are a code reviewer. Focus on quality."#;
             description: "Test".to_string(),
             mode: AgentMode::Subagent,
             model: None,
+            fallback_models: vec![],
             temperature: None,
             steps: None,
             tools: Some(tools),
@@ -845,6 +895,10 @@ You are a code reviewer. Focus on quality."#;
         assert_eq!(provider.as_deref(), Some("kimi"));
         assert_eq!(model, "kimi-k2.5");
 
+        let (provider, model) = parse_model_override("anthropic/claude-sonnet-4-5");
+        assert_eq!(provider.as_deref(), Some("anthropic"));
+        assert_eq!(model, "claude-sonnet-4-5");
+
         let (provider, model) = parse_model_override("MiniMax-M2.1");
         assert_eq!(provider, None);
         assert_eq!(model, "MiniMax-M2.1");
@@ -875,4 +929,39 @@ You are a code reviewer. Focus on quality."#;
 
         std::fs::remove_dir_all(&root).expect("cleanup temp dir");
     }
+
+    #[test]
+    fn test_parse_fallback_models() {
+        let mut issues = Vec::new();
+        let fm = parse_agent_frontmatter(
+            "model: minimax/MiniMax-M2.5\nfallback_models: kimi/kimi-k2.5 -> zhipu/glm-5",
+            &mut issues,
+        );
+        assert_eq!(
+            fm.fallback_models,
+            Some(vec![
+                "kimi/kimi-k2.5".to_string(),
+                "zhipu/glm-5".to_string()
+            ])
+        );
+        assert!(issues.is_empty());
+
+        let fm = parse_agent_frontmatter(
+            "fallback_models:\n  - kimi/kimi-k2.5\n  - acme/model-x",
+            &mut issues,
+        );
+        assert_eq!(
+            fm.fallback_models,
+            Some(vec![
+                "kimi/kimi-k2.5".to_string(),
+                "acme/model-x".to_string()
+            ])
+        );
+        assert_eq!(issues.len(), 1);
+
+        assert_eq!(
+            parse_fallback_models(" kimi/kimi-k2.5, glm-5 ,"),
+            vec!["kimi/kimi-k2.5", "glm-5"]
+        );
+    }
 }
@@ -1,12 +1,9 @@
 use std::collections::HashMap;
-use std::future::Future;
 use std::io::{BufRead, BufReader, Read, Write};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
 use serde::{Deserialize, Serialize};
-use tokio::runtime::Handle;
-use tokio::task;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpServerConfig {
@@ -25,6 +22,9 @@ pub struct McpToolEntry {
     pub tool_name: String,
     pub description: String,
     pub input_schema: serde_json::Value,
+    /// The server's `readOnlyHint` annotation for the tool.
+    #[serde(default)]
+    pub read_only_hint: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +98,7 @@ pub fn refresh_mcp_tools_cache() -> Result<Vec<McpToolEntry>, String> {
                 tool_name: t.name,
                 description: t.description,
                 input_schema: t.input_schema,
+                read_only_hint: t.annotations.read_only_hint,
             }));
         }
     }
@@ -118,26 +119,13 @@ pub fn refresh_mcp_tools_cache() -> Result<Vec<McpToolEntry>, String> {
     Ok(tools)
 }
 
-pub fn call_mcp_tool_by_server_and_name(
+pub async fn call_mcp_tool_async(
     server_id: &str,
     tool_name: &str,
     arguments: serde_json::Value,
 ) -> Result<serde_json::Value, String> {
-    block_on_mcp_async(async move {
-        let manager = crate::mcp::McpClientManager::new().await?;
-        manager.call_tool(server_id, tool_name, arguments).await
-    })
-}
-
-fn block_on_mcp_async<F>(fut: F) -> Result<serde_json::Value, String>
-where
-    F: Future<Output = Result<serde_json::Value, String>>,
-{
-    if Handle::try_current().is_ok() {
-        task::block_in_place(|| tauri::async_runtime::block_on(fut))
-    } else {
-        tauri::async_runtime::block_on(fut)
-    }
+    let manager = crate::mcp::McpClientManager::new().await?;
+    manager.call_tool(server_id, tool_name, arguments).await
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -147,6 +135,14 @@ struct McpToolDescriptor {
     description: String,
     #[serde(default)]
     input_schema: serde_json::Value,
+    #[serde(default)]
+    annotations: McpToolAnnotations,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct McpToolAnnotations {
+    #[serde(default, rename = "readOnlyHint")]
+    read_only_hint: Option<bool>,
 }
 
 fn list_server_tools(server: &McpServerConfig) -> Result<Vec<McpToolDescriptor>, String> {
@@ -322,6 +322,86 @@ CREATE TABLE pending_questions (
 CREATE INDEX idx_pending_questions_task ON pending_questions(task_id);
 CREATE INDEX idx_pending_questions_run ON pending_questions(run_id);
 CREATE INDEX idx_pending_questions_expires ON pending_questions(expires_at);
+"#,
+    },
+    Migration {
+        version: 14,
+        sql: r#"
+-- Attribute model calls to sub-agents and record reasoning tokens and cost
+ALTER TABLE api_requests ADD COLUMN sub_agent_id TEXT;
+ALTER TABLE api_requests ADD COLUMN tokens_reasoning INTEGER DEFAULT 0;
+ALTER TABLE api_requests ADD COLUMN cost_usd REAL;
+
+ALTER TABLE runs ADD COLUMN total_tokens_reasoning INTEGER DEFAULT 0;
+ALTER TABLE runs ADD COLUMN total_cost_usd REAL DEFAULT 0.0;
+
+CREATE INDEX idx_api_requests_sub_agent ON api_requests(sub_agent_id);
+"#,
+    },
+    Migration {
+        version: 15,
+        sql: r#"
+-- Optional per-task token, step and wall-clock limits
+CREATE TABLE task_budgets (
+    task_id             TEXT PRIMARY KEY REFERENCES tasks(id),
+    max_tokens          INTEGER,
+    max_steps           INTEGER,
+    max_wall_clock_secs INTEGER,
+    updated_at          TEXT NOT NULL
+);
+"#,
+    },
+    Migration {
+        version: 16,
+        sql: r#"
+-- Ordered provider/model fallback chain per task
+CREATE TABLE task_model_fallbacks (
+    task_id     TEXT PRIMARY KEY REFERENCES tasks(id),
+    chain_json  TEXT NOT NULL,
+    updated_at  TEXT NOT NULL
+);
+"#,
+    },
+    Migration {
+        version: 17,
+        sql: r#"
+-- Policy decisions (path classes, redactions, approvals, denials) per tool call
+ALTER TABLE tool_calls ADD COLUMN policy_json TEXT;
+"#,
+    },
+    Migration {
+        version: 18,
+        sql: r#"
+-- Pre-images of files changed by tool calls, for reverting them
+CREATE TABLE file_snapshots (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_id        TEXT NOT NULL,
+    tool_call_id  TEXT NOT NULL,
+    path          TEXT NOT NULL,
+    pre_content   BLOB,
+    revertible    INTEGER NOT NULL DEFAULT 1,  -- 0 = too large to keep pre_content
+    post_hash     TEXT,
+    created_at    TEXT NOT NULL,
+    reverted_at   TEXT
+);
+
+CREATE INDEX idx_file_snapshots_run ON file_snapshots(run_id);
+CREATE INDEX idx_file_snapshots_tool_call ON file_snapshots(tool_call_id);
+"#,
+    },
+    Migration {
+        version: 19,
+        sql: r#"
+-- Token counts guessed from text length for calls whose provider reported none
+ALTER TABLE api_requests ADD COLUMN estimated INTEGER NOT NULL DEFAULT 0;
+"#,
+    },
+    Migration {
+        version: 20,
+        sql: r#"
+-- Token and step limits granted by extending a budget, apart from the declared ones
+ALTER TABLE task_budgets ADD COLUMN extended_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE task_budgets ADD COLUMN extended_steps INTEGER NOT NULL DEFAULT 0;
 "#,
     },
 ];
@@ -44,9 +44,9 @@ pub struct RunRow {
     pub started_at: Option<String>,
     pub finished_at: Option<String>,
     pub failure_reason: Option<String>,
-    // Note: Usage tracking fields added to DB in migration 9
+    // Note: Usage tracking fields added to DB in migrations 9 and 14
     // but not included here to maintain backward compatibility.
-    // Use get_run_cache_stats() to retrieve usage stats.
+    // Use get_run_usage_totals() to retrieve usage stats.
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -113,6 +113,8 @@ pub struct ToolCallRow {
     pub started_at: Option<String>,
     pub finished_at: Option<String>,
     pub error: Option<String>,
+    /// JSON object describing the policy decisions made for this call.
+    pub policy_json: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -461,6 +463,14 @@ pub fn delete_task_cascade(db: &Database, task_id: &str) -> Result<(), DbError>
         )?;
         tx.execute("DELETE FROM artifacts WHERE run_id = ?1", params![run_id])?;
         tx.execute("DELETE FROM tool_calls WHERE run_id = ?1", params![run_id])?;
+        tx.execute(
+            "DELETE FROM file_snapshots WHERE run_id = ?1",
+            params![run_id],
+        )?;
+        tx.execute(
+            "DELETE FROM api_requests WHERE run_id = ?1",
+            params![run_id],
+        )?;
         tx.execute("DELETE FROM sub_agents WHERE run_id = ?1", params![run_id])?;
         tx.execute("DELETE FROM events WHERE run_id = ?1", params![run_id])?;
         tx.execute("DELETE FROM checkpoints WHERE run_id = ?1", params![run_id])?;
@@ -488,6 +498,14 @@ pub fn delete_task_cascade(db: &Database, task_id: &str) -> Result<(), DbError>
         "DELETE FROM user_messages WHERE task_id = ?1",
         params![task_id],
     )?;
+    tx.execute(
+        "DELETE FROM task_budgets WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    tx.execute(
+        "DELETE FROM task_model_fallbacks WHERE task_id = ?1",
+        params![task_id],
+    )?;
     tx.execute("DELETE FROM tasks WHERE id = ?1", params![task_id])?;
 
     tx.commit()?;
@@ -1163,8 +1181,8 @@ pub fn insert_tool_call(db: &Database, row: &ToolCallRow) -> Result<(), DbError>
     let conn = db.conn();
     conn.execute(
         "INSERT INTO tool_calls
-         (id, run_id, step_idx, tool_name, input_json, output_json, status, started_at, finished_at, error)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+         (id, run_id, step_idx, tool_name, input_json, output_json, status, started_at, finished_at, error, policy_json)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
         params![
             row.id,
             row.run_id,
@@ -1176,11 +1194,31 @@ pub fn insert_tool_call(db: &Database, row: &ToolCallRow) -> Result<(), DbError>
             row.started_at,
             row.finished_at,
             row.error,
+            row.policy_json,
         ],
     )?;
     Ok(())
 }
 
+/// Merge `policy` into the call's policy record (JSON merge patch).
+pub fn merge_tool_call_policy(
+    db: &Database,
+    id: &str,
+    policy: &serde_json::Value,
+) -> Result<(), DbError> {
+    let conn = db.conn();
+    let changed = conn.execute(
+        "UPDATE tool_calls
+         SET policy_json = json_patch(COALESCE(policy_json, '{}'), ?1)
+         WHERE id = ?2",
+        params![policy.to_string(), id],
+    )?;
+    if changed == 0 {
+        return Err(DbError::NotFound(format!("tool_call {id}")));
+    }
+    Ok(())
+}
+
 pub fn update_tool_call_result(
     db: &Database,
     id: &str,
@@ -1208,7 +1246,7 @@ pub fn update_tool_call_result(
 pub fn list_tool_calls_for_run(db: &Database, run_id: &str) -> Result<Vec<ToolCallRow>, DbError> {
     let conn = db.conn();
     let mut stmt = conn.prepare(
-        "SELECT id, run_id, step_idx, tool_name, input_json, output_json, status, started_at, finished_at, error
+        "SELECT id, run_id, step_idx, tool_name, input_json, output_json, status, started_at, finished_at, error, policy_json
          FROM tool_calls
          WHERE run_id = ?1
          ORDER BY started_at ASC",
@@ -1226,6 +1264,7 @@ pub fn list_tool_calls_for_run(db: &Database, run_id: &str) -> Result<Vec<ToolCa
                 started_at: row.get(7)?,
                 finished_at: row.get(8)?,
                 error: row.get(9)?,
+                policy_json: row.get(10)?,
             })
         })?
         .collect::<Result<Vec<_>, _>>()?;
@@ -1235,7 +1274,7 @@ pub fn list_tool_calls_for_run(db: &Database, run_id: &str) -> Result<Vec<ToolCa
 pub fn list_tool_calls_for_task(db: &Database, task_id: &str) -> Result<Vec<ToolCallRow>, DbError> {
     let conn = db.conn();
     let mut stmt = conn.prepare(
-        "SELECT tc.id, tc.run_id, tc.step_idx, tc.tool_name, tc.input_json, tc.output_json, tc.status, tc.started_at, tc.finished_at, tc.error
+        "SELECT tc.id, tc.run_id, tc.step_idx, tc.tool_name, tc.input_json, tc.output_json, tc.status, tc.started_at, tc.finished_at, tc.error, tc.policy_json
          FROM tool_calls tc
          INNER JOIN runs r ON r.id = tc.run_id
          WHERE r.task_id = ?1
@@ -1254,12 +1293,248 @@ pub fn list_tool_calls_for_task(db: &Database, task_id: &str) -> Result<Vec<Tool
                 started_at: row.get(7)?,
                 finished_at: row.get(8)?,
                 error: row.get(9)?,
+                policy_json: row.get(10)?,
             })
         })?
         .collect::<Result<Vec<_>, _>>()?;
     Ok(rows)
 }
 
+// ---------------------------------------------------------------------------
+// Model usage queries
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiRequestRow {
+    pub id: String,
+    pub run_id: String,
+    pub sub_agent_id: Option<String>,
+    pub step_idx: Option<i64>,
+    pub provider: String,
+    pub model: String,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub tokens_reasoning: i64,
+    pub tokens_cached: i64,
+    pub latency_ms: Option<i64>,
+    pub cost_usd: Option<f64>,
+    /// Token counts were estimated from text length, not reported by the provider.
+    pub estimated: bool,
+    pub created_at: String,
+}
+
+/// Aggregated usage for a run, a sub-agent or a model.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageTotalsRow {
+    /// Sub-agent id or model name when grouped; `None` for whole-run totals.
+    pub key: Option<String>,
+    pub request_count: i64,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub tokens_reasoning: i64,
+    pub tokens_cached: i64,
+    pub cost_usd: f64,
+}
+
+/// Insert a model call record and roll its counts into the run's usage columns.
+pub fn insert_api_request(db: &Database, row: &ApiRequestRow) -> Result<(), DbError> {
+    let conn = db.conn();
+    let tx = conn.unchecked_transaction()?;
+    let cache_hit = if row.tokens_cached > 0 { 1i64 } else { 0i64 };
+    tx.execute(
+        "INSERT INTO api_requests
+         (id, run_id, sub_agent_id, step_idx, provider, model, tokens_in, tokens_out,
+          tokens_reasoning, tokens_cached, cache_hit, latency_ms, cost_usd, estimated, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        params![
+            row.id,
+            row.run_id,
+            row.sub_agent_id,
+            row.step_idx,
+            row.provider,
+            row.model,
+            row.tokens_in,
+            row.tokens_out,
+            row.tokens_reasoning,
+            row.tokens_cached,
+            cache_hit,
+            row.latency_ms,
+            row.cost_usd,
+            row.estimated,
+            row.created_at,
+        ],
+    )?;
+    tx.execute(
+        "UPDATE runs
+         SET total_tokens_in = COALESCE(total_tokens_in, 0) + ?1,
+             total_tokens_out = COALESCE(total_tokens_out, 0) + ?2,
+             total_tokens_reasoning = COALESCE(total_tokens_reasoning, 0) + ?3,
+             total_tokens_cached = COALESCE(total_tokens_cached, 0) + ?4,
+             total_cost_usd = COALESCE(total_cost_usd, 0.0) + ?5,
+             api_request_count = COALESCE(api_request_count, 0) + 1,
+             cache_hit_count = COALESCE(cache_hit_count, 0) + ?6,
+             cache_hit_rate = CAST(COALESCE(cache_hit_count, 0) + ?6 AS REAL)
+                 / (COALESCE(api_request_count, 0) + 1)
+         WHERE id = ?7",
+        params![
+            row.tokens_in,
+            row.tokens_out,
+            row.tokens_reasoning,
+            row.tokens_cached,
+            row.cost_usd.unwrap_or(0.0),
+            cache_hit,
+            row.run_id,
+        ],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn list_api_requests_for_run(
+    db: &Database,
+    run_id: &str,
+) -> Result<Vec<ApiRequestRow>, DbError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(
+        "SELECT id, run_id, sub_agent_id, step_idx, provider, model, tokens_in, tokens_out,
+                tokens_reasoning, tokens_cached, latency_ms, cost_usd, estimated, created_at
+         FROM api_requests
+         WHERE run_id = ?1
+         ORDER BY created_at ASC",
+    )?;
+    let rows = stmt
+        .query_map(params![run_id], |row| {
+            Ok(ApiRequestRow {
+                id: row.get(0)?,
+                run_id: row.get(1)?,
+                sub_agent_id: row.get(2)?,
+                step_idx: row.get(3)?,
+                provider: row.get(4)?,
+                model: row.get(5)?,
+                tokens_in: row.get::<_, Option<i64>>(6)?.unwrap_or(0),
+                tokens_out: row.get::<_, Option<i64>>(7)?.unwrap_or(0),
+                tokens_reasoning: row.get::<_, Option<i64>>(8)?.unwrap_or(0),
+                tokens_cached: row.get::<_, Option<i64>>(9)?.unwrap_or(0),
+                latency_ms: row.get(10)?,
+                cost_usd: row.get(11)?,
+                estimated: row.get(12)?,
+                created_at: row.get(13)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+fn usage_totals_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<UsageTotalsRow> {
+    Ok(UsageTotalsRow {
+        key: row.get(0)?,
+        request_count: row.get(1)?,
+        tokens_in: row.get::<_, Option<i64>>(2)?.unwrap_or(0),
+        tokens_out: row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+        tokens_reasoning: row.get::<_, Option<i64>>(4)?.unwrap_or(0),
+        tokens_cached: row.get::<_, Option<i64>>(5)?.unwrap_or(0),
+        cost_usd: row.get::<_, Option<f64>>(6)?.unwrap_or(0.0),
+    })
+}
+
+/// Usage totals across every model call in a run.
+pub fn get_run_usage_totals(db: &Database, run_id: &str) -> Result<UsageTotalsRow, DbError> {
+    let conn = db.conn();
+    let row = conn.query_row(
+        "SELECT NULL, COUNT(*), SUM(tokens_in), SUM(tokens_out), SUM(tokens_reasoning),
+                SUM(tokens_cached), SUM(cost_usd)
+         FROM api_requests
+         WHERE run_id = ?1",
+        params![run_id],
+        usage_totals_from_row,
+    )?;
+    Ok(row)
+}
+
+pub fn list_sub_agent_usage_for_run(
+    db: &Database,
+    run_id: &str,
+) -> Result<Vec<UsageTotalsRow>, DbError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(
+        "SELECT sub_agent_id, COUNT(*), SUM(tokens_in), SUM(tokens_out), SUM(tokens_reasoning),
+                SUM(tokens_cached), SUM(cost_usd)
+         FROM api_requests
+         WHERE run_id = ?1
+         GROUP BY sub_agent_id
+         ORDER BY MIN(created_at) ASC",
+    )?;
+    let rows = stmt
+        .query_map(params![run_id], usage_totals_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+pub fn list_model_usage_for_run(
+    db: &Database,
+    run_id: &str,
+) -> Result<Vec<UsageTotalsRow>, DbError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(
+        "SELECT provider || '/' || model, COUNT(*), SUM(tokens_in), SUM(tokens_out),
+                SUM(tokens_reasoning), SUM(tokens_cached), SUM(cost_usd)
+         FROM api_requests
+         WHERE run_id = ?1
+         GROUP BY provider, model
+         ORDER BY MIN(created_at) ASC",
+    )?;
+    let rows = stmt
+        .query_map(params![run_id], usage_totals_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+pub fn get_task_usage_totals(db: &Database, task_id: &str) -> Result<UsageTotalsRow, DbError> {
+    task_usage_totals(db, task_id, false)
+}
+
+/// Usage totals across a task's runs counting only provider-reported token
+/// counts; estimated calls are left out of budget enforcement.
+pub fn get_task_reported_usage_totals(
+    db: &Database,
+    task_id: &str,
+) -> Result<UsageTotalsRow, DbError> {
+    task_usage_totals(db, task_id, true)
+}
+
+fn task_usage_totals(
+    db: &Database,
+    task_id: &str,
+    reported_only: bool,
+) -> Result<UsageTotalsRow, DbError> {
+    let conn = db.conn();
+    let row = conn.query_row(
+        "SELECT NULL, COUNT(*), SUM(a.tokens_in), SUM(a.tokens_out), SUM(a.tokens_reasoning),
+                SUM(a.tokens_cached), SUM(a.cost_usd)
+         FROM api_requests a
+         INNER JOIN runs r ON r.id = a.run_id
+         WHERE r.task_id = ?1 AND (?2 = 0 OR a.estimated = 0)",
+        params![task_id, reported_only],
+        usage_totals_from_row,
+    )?;
+    Ok(row)
+}
+
+/// Worker decision turns across a task's runs: the model calls made on behalf
+/// of a sub-agent, leaving out planner calls.
+pub fn count_task_worker_requests(db: &Database, task_id: &str) -> Result<i64, DbError> {
+    let conn = db.conn();
+    let count = conn.query_row(
+        "SELECT COUNT(*)
+         FROM api_requests a
+         INNER JOIN runs r ON r.id = a.run_id
+         WHERE r.task_id = ?1 AND a.sub_agent_id IS NOT NULL",
+        params![task_id],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
 // ---------------------------------------------------------------------------
 // Worktree log queries
 // ---------------------------------------------------------------------------
@@ -1422,6 +1697,148 @@ pub fn get_events_after_seq(
     Ok(rows)
 }
 
+// ---------------------------------------------------------------------------
+// Task budget queries
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskBudgetRow {
+    pub task_id: String,
+    pub max_tokens: Option<i64>,
+    pub max_steps: Option<i64>,
+    pub max_wall_clock_secs: Option<i64>,
+    /// Granted on top of `max_tokens` by extending the budget.
+    pub extended_tokens: i64,
+    /// Granted on top of `max_steps` by extending the budget.
+    pub extended_steps: i64,
+    pub updated_at: String,
+}
+
+/// Store declared limits. Extensions are replaced too, so setting a new
+/// budget starts over from what was declared.
+pub fn upsert_task_budget(db: &Database, row: &TaskBudgetRow) -> Result<(), DbError> {
+    let conn = db.conn();
+    conn.execute(
+        "INSERT INTO task_budgets
+         (task_id, max_tokens, max_steps, max_wall_clock_secs, extended_tokens, extended_steps,
+          updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(task_id) DO UPDATE SET
+             max_tokens = excluded.max_tokens,
+             max_steps = excluded.max_steps,
+             max_wall_clock_secs = excluded.max_wall_clock_secs,
+             extended_tokens = excluded.extended_tokens,
+             extended_steps = excluded.extended_steps,
+             updated_at = excluded.updated_at",
+        params![
+            row.task_id,
+            row.max_tokens,
+            row.max_steps,
+            row.max_wall_clock_secs,
+            row.extended_tokens,
+            row.extended_steps,
+            row.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Record the token and step limits granted by extensions, leaving the
+/// declared limits alone.
+pub fn set_task_budget_extensions(
+    db: &Database,
+    task_id: &str,
+    extended_tokens: i64,
+    extended_steps: i64,
+) -> Result<(), DbError> {
+    let conn = db.conn();
+    conn.execute(
+        "UPDATE task_budgets SET extended_tokens = ?2, extended_steps = ?3 WHERE task_id = ?1",
+        params![task_id, extended_tokens, extended_steps],
+    )?;
+    Ok(())
+}
+
+pub fn get_task_budget(db: &Database, task_id: &str) -> Result<Option<TaskBudgetRow>, DbError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(
+        "SELECT task_id, max_tokens, max_steps, max_wall_clock_secs, extended_tokens,
+                extended_steps, updated_at
+         FROM task_budgets
+         WHERE task_id = ?1",
+    )?;
+    let mut rows = stmt.query_map(params![task_id], |row| {
+        Ok(TaskBudgetRow {
+            task_id: row.get(0)?,
+            max_tokens: row.get(1)?,
+            max_steps: row.get(2)?,
+            max_wall_clock_secs: row.get(3)?,
+            extended_tokens: row.get(4)?,
+            extended_steps: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    })?;
+    match rows.next() {
+        Some(row) => Ok(Some(row?)),
+        None => Ok(None),
+    }
+}
+
+pub fn delete_task_budget(db: &Database, task_id: &str) -> Result<(), DbError> {
+    let conn = db.conn();
+    conn.execute(
+        "DELETE FROM task_budgets WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Task model fallback queries
+// ---------------------------------------------------------------------------
+
+pub fn upsert_task_model_fallbacks(
+    db: &Database,
+    task_id: &str,
+    chain: &[String],
+    updated_at: &str,
+) -> Result<(), DbError> {
+    let chain_json = serde_json::to_string(chain).unwrap_or_else(|_| "[]".to_string());
+    let conn = db.conn();
+    conn.execute(
+        "INSERT INTO task_model_fallbacks (task_id, chain_json, updated_at)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(task_id) DO UPDATE SET
+             chain_json = excluded.chain_json,
+             updated_at = excluded.updated_at",
+        params![task_id, chain_json, updated_at],
+    )?;
+    Ok(())
+}
+
+pub fn get_task_model_fallbacks(
+    db: &Database,
+    task_id: &str,
+) -> Result<Option<Vec<String>>, DbError> {
+    let conn = db.conn();
+    let mut stmt =
+        conn.prepare("SELECT chain_json FROM task_model_fallbacks WHERE task_id = ?1")?;
+    let mut rows = stmt.query_map(params![task_id], |row| row.get::<_, String>(0))?;
+    match rows.next() {
+        Some(raw) => Ok(Some(serde_json::from_str(&raw?).unwrap_or_default())),
+        None => Ok(None),
+    }
+}
+
+pub fn delete_task_model_fallbacks(db: &Database, task_id: &str) -> Result<(), DbError> {
+    let conn = db.conn();
+    conn.execute(
+        "DELETE FROM task_model_fallbacks WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // Pending questions queries
 // ---------------------------------------------------------------------------
@@ -1556,3 +1973,121 @@ pub fn delete_pending_questions_for_task(db: &Database, task_id: &str) -> Result
     )?;
     Ok(())
 }
+
+// ---------------------------------------------------------------------------
+// File snapshot queries
+// ---------------------------------------------------------------------------
+
+/// A file's content before a tool call changed it.
+#[derive(Debug, Clone)]
+pub struct FileSnapshotRow {
+    pub id: i64,
+    pub run_id: String,
+    pub tool_call_id: String,
+    /// Step of the tool call, when it ran inside one.
+    pub step_idx: Option<i64>,
+    pub path: String,
+    /// `None` when the file did not exist before the call, or was not kept.
+    pub pre_content: Option<Vec<u8>>,
+    /// `false` when the file was too large to keep its pre-image.
+    pub revertible: bool,
+    /// SHA-256 of the file after the call; `None` when the call removed it.
+    pub post_hash: Option<String>,
+    pub created_at: String,
+    pub reverted_at: Option<String>,
+}
+
+/// Which tool calls' snapshots to select.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileSnapshotScope {
+    ToolCall(String),
+    Step { run_id: String, step_idx: i64 },
+    Run(String),
+}
+
+pub fn insert_file_snapshot(db: &Database, row: &FileSnapshotRow) -> Result<(), DbError> {
+    let conn = db.conn();
+    conn.execute(
+        "INSERT INTO file_snapshots
+         (run_id, tool_call_id, path, pre_content, revertible, post_hash, created_at,
+          reverted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            row.run_id,
+            row.tool_call_id,
+            row.path,
+            row.pre_content,
+            row.revertible,
+            row.post_hash,
+            row.created_at,
+            row.reverted_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Snapshots in `scope` that have not been reverted, oldest first.
+pub fn list_file_snapshots(
+    db: &Database,
+    scope: &FileSnapshotScope,
+) -> Result<Vec<FileSnapshotRow>, DbError> {
+    let (filter, first, second) = match scope {
+        FileSnapshotScope::ToolCall(id) => ("s.tool_call_id = ?1", id.clone(), None),
+        FileSnapshotScope::Step { run_id, step_idx } => (
+            "s.run_id = ?1 AND t.step_idx = ?2",
+            run_id.clone(),
+            Some(*step_idx),
+        ),
+        FileSnapshotScope::Run(run_id) => ("s.run_id = ?1", run_id.clone(), None),
+    };
+    let sql = format!(
+        "SELECT s.id, s.run_id, s.tool_call_id, t.step_idx, s.path, s.pre_content,
+                s.revertible, s.post_hash, s.created_at, s.reverted_at
+         FROM file_snapshots s
+         LEFT JOIN tool_calls t ON t.id = s.tool_call_id
+         WHERE {filter} AND s.reverted_at IS NULL
+         ORDER BY s.id ASC"
+    );
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&sql)?;
+    let map_row = |row: &rusqlite::Row<'_>| {
+        Ok(FileSnapshotRow {
+            id: row.get(0)?,
+            run_id: row.get(1)?,
+            tool_call_id: row.get(2)?,
+            step_idx: row.get(3)?,
+            path: row.get(4)?,
+            pre_content: row.get(5)?,
+            revertible: row.get(6)?,
+            post_hash: row.get(7)?,
+            created_at: row.get(8)?,
+            reverted_at: row.get(9)?,
+        })
+    };
+    let rows = match second {
+        Some(step_idx) => stmt
+            .query_map(params![first, step_idx], map_row)?
+            .collect::<Result<Vec<_>, _>>()?,
+        None => stmt
+            .query_map(params![first], map_row)?
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    Ok(rows)
+}
+
+pub fn mark_file_snapshots_reverted(
+    db: &Database,
+    ids: &[i64],
+    reverted_at: &str,
+) -> Result<(), DbError> {
+    let conn = db.conn();
+    let tx = conn.unchecked_transaction()?;
+    for id in ids {
+        tx.execute(
+            "UPDATE file_snapshots SET reverted_at = ?1 WHERE id = ?2",
+            params![reverted_at, id],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
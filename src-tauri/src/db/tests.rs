@@ -76,6 +76,7 @@ mod tests {
                 started_at: Some(now.clone()),
                 finished_at: Some(now.clone()),
                 error: None,
+                policy_json: None,
             },
         )
         .unwrap();
@@ -392,4 +393,219 @@ mod tests {
         let logs = queries::list_worktree_logs_for_run(&db, &run_id).unwrap();
         assert!(logs[0].cleaned_at.is_some());
     }
+
+    #[test]
+    fn test_db_api_request_usage_totals() {
+        let db = Database::open_in_memory().expect("in-memory DB");
+        let task_id = Uuid::new_v4().to_string();
+        let run_id = Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        queries::insert_task(
+            &db,
+            &queries::TaskRow {
+                id: task_id.clone(),
+                prompt: "test".to_string(),
+                parent_task_id: None,
+                status: "executing".to_string(),
+                created_at: now.clone(),
+                updated_at: now.clone(),
+                workspace_root: None,
+            },
+        )
+        .unwrap();
+        queries::insert_run(
+            &db,
+            &queries::RunRow {
+                id: run_id.clone(),
+                task_id: task_id.clone(),
+                status: "executing".to_string(),
+                plan_json: None,
+                started_at: Some(now.clone()),
+                finished_at: None,
+                failure_reason: None,
+            },
+        )
+        .unwrap();
+
+        let request = |sub_agent_id: Option<&str>, model: &str, cached: i64, cost: Option<f64>| {
+            queries::ApiRequestRow {
+                id: Uuid::new_v4().to_string(),
+                run_id: run_id.clone(),
+                sub_agent_id: sub_agent_id.map(str::to_string),
+                step_idx: Some(0),
+                provider: "kimi".to_string(),
+                model: model.to_string(),
+                tokens_in: 1_000,
+                tokens_out: 200,
+                tokens_reasoning: 50,
+                tokens_cached: cached,
+                latency_ms: Some(120),
+                cost_usd: cost,
+                estimated: false,
+                created_at: now.clone(),
+            }
+        };
+
+        queries::insert_api_request(&db, &request(None, "kimi-k2.5", 0, Some(0.01))).unwrap();
+        queries::insert_api_request(&db, &request(Some("sa-1"), "kimi-k2.5", 400, Some(0.02)))
+            .unwrap();
+        queries::insert_api_request(&db, &request(Some("sa-1"), "kimi-k2", 0, None)).unwrap();
+
+        let totals = queries::get_run_usage_totals(&db, &run_id).unwrap();
+        assert_eq!(totals.request_count, 3);
+        assert_eq!(totals.tokens_in, 3_000);
+        assert_eq!(totals.tokens_out, 600);
+        assert_eq!(totals.tokens_reasoning, 150);
+        assert_eq!(totals.tokens_cached, 400);
+        assert!((totals.cost_usd - 0.03).abs() < 1e-9);
+
+        let by_agent = queries::list_sub_agent_usage_for_run(&db, &run_id).unwrap();
+        assert_eq!(by_agent.len(), 2);
+        let sub_agent = by_agent
+            .iter()
+            .find(|row| row.key.as_deref() == Some("sa-1"))
+            .unwrap();
+        assert_eq!(sub_agent.request_count, 2);
+
+        let by_model = queries::list_model_usage_for_run(&db, &run_id).unwrap();
+        assert_eq!(by_model.len(), 2);
+
+        let task_totals = queries::get_task_usage_totals(&db, &task_id).unwrap();
+        assert_eq!(task_totals.request_count, 3);
+        assert_eq!(
+            queries::count_task_worker_requests(&db, &task_id).unwrap(),
+            2
+        );
+
+        // Estimated calls show in the totals but not in what budgets count
+        let mut estimated = request(None, "kimi-k2.5", 0, Some(0.01));
+        estimated.estimated = true;
+        queries::insert_api_request(&db, &estimated).unwrap();
+        let task_totals = queries::get_task_usage_totals(&db, &task_id).unwrap();
+        assert_eq!(task_totals.request_count, 4);
+        let reported = queries::get_task_reported_usage_totals(&db, &task_id).unwrap();
+        assert_eq!(reported.request_count, 3);
+        assert_eq!(reported.tokens_in, 3_000);
+
+        let requests = queries::list_api_requests_for_run(&db, &run_id).unwrap();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[3].estimated);
+    }
+
+    #[test]
+    fn test_task_model_fallbacks_roundtrip_and_cascade() {
+        let db = Database::open_in_memory().expect("in-memory DB");
+        let task_id = Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        queries::insert_task(
+            &db,
+            &queries::TaskRow {
+                id: task_id.clone(),
+                prompt: "fallbacks".to_string(),
+                parent_task_id: None,
+                status: "pending".to_string(),
+                created_at: now.clone(),
+                updated_at: now.clone(),
+                workspace_root: None,
+            },
+        )
+        .unwrap();
+
+        assert!(queries::get_task_model_fallbacks(&db, &task_id)
+            .unwrap()
+            .is_none());
+
+        let chain = vec!["kimi/kimi-k2.5".to_string(), "zhipu/glm-5".to_string()];
+        queries::upsert_task_model_fallbacks(&db, &task_id, &chain, &now).unwrap();
+        queries::upsert_task_model_fallbacks(&db, &task_id, &chain[1..], &now).unwrap();
+        assert_eq!(
+            queries::get_task_model_fallbacks(&db, &task_id).unwrap(),
+            Some(vec!["zhipu/glm-5".to_string()])
+        );
+
+        queries::delete_task_cascade(&db, &task_id).unwrap();
+        assert!(queries::get_task_model_fallbacks(&db, &task_id)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_tool_call_policy_record_merges() {
+        let db = Database::open_in_memory().expect("in-memory DB");
+        let task_id = Uuid::new_v4().to_string();
+        let run_id = Uuid::new_v4().to_string();
+        let call_id = Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        queries::insert_task(
+            &db,
+            &queries::TaskRow {
+                id: task_id.clone(),
+                prompt: "policy".to_string(),
+                parent_task_id: None,
+                status: "executing".to_string(),
+                created_at: now.clone(),
+                updated_at: now.clone(),
+                workspace_root: None,
+            },
+        )
+        .unwrap();
+        queries::insert_run(
+            &db,
+            &queries::RunRow {
+                id: run_id.clone(),
+                task_id,
+                status: "executing".to_string(),
+                plan_json: None,
+                started_at: Some(now.clone()),
+                finished_at: None,
+                failure_reason: None,
+            },
+        )
+        .unwrap();
+        queries::insert_tool_call(
+            &db,
+            &queries::ToolCallRow {
+                id: call_id.clone(),
+                run_id: run_id.clone(),
+                step_idx: None,
+                tool_name: "fs.write".to_string(),
+                input_json: "{}".to_string(),
+                output_json: None,
+                status: "running".to_string(),
+                started_at: Some(now),
+                finished_at: None,
+                error: None,
+                policy_json: None,
+            },
+        )
+        .unwrap();
+
+        queries::merge_tool_call_policy(
+            &db,
+            &call_id,
+            &serde_json::json!({"decision": "needs_approval", "approved": true}),
+        )
+        .unwrap();
+        queries::merge_tool_call_policy(
+            &db,
+            &call_id,
+            &serde_json::json!({"path_classes": {".git/config": "protected"}}),
+        )
+        .unwrap();
+
+        let calls = queries::list_tool_calls_for_run(&db, &run_id).unwrap();
+        let record: serde_json::Value =
+            serde_json::from_str(calls[0].policy_json.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            record,
+            serde_json::json!({
+                "decision": "needs_approval",
+                "approved": true,
+                "path_classes": {".git/config": "protected"}
+            })
+        );
+    }
 }
@@ -25,6 +25,7 @@ mod commands;
 mod core;
 mod db;
 pub mod embeddings;
+mod lsp;
 pub mod mcp;
 mod model;
 mod policy;
@@ -81,12 +82,20 @@ pub(crate) struct ProviderConfigView {
     pub configured: bool,
     pub default_model: Option<String>,
     pub base_url: Option<String>,
+    /// Effective retry/backoff policy for the provider's requests.
+    pub retry: model::retry::RetryPolicy,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub(crate) struct ModelInfo {
     pub name: String,
     pub context_window: usize,
+    pub output_limit: usize,
+    pub description: String,
+    pub deprecated: bool,
+    pub deprecation_reason: Option<String>,
+    pub suggested_alternative: Option<String>,
+    pub capabilities: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -107,10 +116,22 @@ pub(crate) struct ArtifactContentView {
     pub is_markdown: bool,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct RunUsageView {
+    pub run_id: String,
+    pub totals: queries::UsageTotalsRow,
+    pub sub_agents: Vec<queries::UsageTotalsRow>,
+    pub models: Vec<queries::UsageTotalsRow>,
+    pub requests: Vec<queries::ApiRequestRow>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub(crate) struct CreateTaskOptions {
     pub parent_task_id: Option<String>,
     pub reference_task_ids: Option<Vec<String>>,
+    pub budget: Option<runtime::budget::TaskBudget>,
+    /// Ordered `<provider>/<model>` entries to fail over to.
+    pub model_fallbacks: Option<Vec<String>>,
 }
 
 pub(crate) struct AppState {
@@ -159,6 +180,60 @@ pub(crate) fn provider_setting_key(provider: &str) -> String {
     format!("provider_config:{provider}")
 }
 
+/// Setting key for a provider's retry/backoff policy override.
+pub(crate) fn provider_retry_setting_key(provider: &str) -> String {
+    format!("provider_retry:{provider}")
+}
+
+/// Setting key for a provider's cached `/models` listing.
+pub(crate) fn model_list_cache_key(provider: &str) -> String {
+    format!("model_list_cache:{provider}")
+}
+
+/// Populate the in-memory retry policy cache from stored overrides.
+fn load_retry_policies(db: &Database) {
+    for provider in model::ProviderId::all() {
+        let raw = match queries::get_setting(db, &provider_retry_setting_key(provider.as_str())) {
+            Ok(Some(raw)) => raw,
+            Ok(None) => continue,
+            Err(error) => {
+                tracing::warn!("failed to load {provider} retry policy: {error}");
+                continue;
+            }
+        };
+        match serde_json::from_str::<model::retry::RetryPolicy>(&raw) {
+            Ok(policy) => model::retry::set_provider_policy(*provider, Some(policy)),
+            Err(error) => tracing::warn!("invalid {provider} retry policy: {error}"),
+        }
+    }
+}
+
+/// Register a provider's configured credentials with the redaction pipeline
+/// so they are masked wherever they show up in tool output.
+pub(crate) fn refresh_provider_secret(db: &Database, provider: &str) {
+    match load_provider_config(db, provider) {
+        Ok(config) => policy::redaction::set_provider_secret(
+            provider,
+            config.as_ref().map(|config| config.api_key.as_str()),
+        ),
+        Err(error) => tracing::warn!("failed to load {provider} secret for redaction: {error}"),
+    }
+}
+
+/// Setting key for the custom provider's endpoint description (headers, models, capabilities).
+pub(crate) const CUSTOM_PROVIDER_SETTINGS_KEY: &str = "custom_provider_settings";
+
+pub(crate) fn load_custom_provider_settings(
+    db: &Database,
+) -> Result<Option<model::providers::custom::CustomProviderSettings>, AppError> {
+    match queries::get_setting(db, CUSTOM_PROVIDER_SETTINGS_KEY)? {
+        Some(raw) => serde_json::from_str(&raw)
+            .map(Some)
+            .map_err(|e| AppError::Other(format!("invalid custom provider settings: {e}"))),
+        None => Ok(None),
+    }
+}
+
 fn env_for_provider(provider: &str) -> (Option<String>, Option<String>, Option<String>) {
     match provider {
         "kimi" => (
@@ -186,11 +261,25 @@ fn env_for_provider(provider: &str) -> (Option<String>, Option<String>, Option<S
             std::env::var("OPENAI_MODEL").ok(),
             None,
         ),
+        "anthropic" => (
+            std::env::var("ANTHROPIC_API_KEY").ok(),
+            std::env::var("ANTHROPIC_MODEL").ok(),
+            std::env::var("ANTHROPIC_BASE_URL").ok(),
+        ),
+        "custom" => (
+            std::env::var("CUSTOM_API_KEY").ok(),
+            std::env::var("CUSTOM_MODEL").ok(),
+            std::env::var("CUSTOM_BASE_URL").ok(),
+        ),
         "gemini" => (
             std::env::var("GEMINI_API_KEY").ok(),
             std::env::var("GEMINI_MODEL").ok(),
             std::env::var("GEMINI_BASE_URL").ok(),
         ),
+        // The replay "key" is the path of a recorded cassette
+        model::cassette::REPLAY_PROVIDER => {
+            (std::env::var("ORCHESTRIX_REPLAY_CASSETTE").ok(), None, None)
+        }
         _ => (
             std::env::var("MINIMAX_API_KEY").ok(),
             std::env::var("MINIMAX_MODEL").ok(),
@@ -247,6 +336,10 @@ pub(crate) fn load_provider_config(
         // Fall through to manual API key path if no OAuth tokens
     }
 
+    if provider == "custom" {
+        return load_custom_provider_config(db);
+    }
+
     // Check database first - this is the authoritative source for UI
     let raw = queries::get_setting(db, &provider_setting_key(provider))?;
     if let Some(raw) = raw {
@@ -288,6 +381,50 @@ pub(crate) fn load_provider_config(
     Ok(None)
 }
 
+/// Custom endpoints may be keyless (local Ollama), so the provider counts as
+/// configured once it has a provider config, endpoint settings or `CUSTOM_BASE_URL`.
+/// The endpoint settings are packed into `api_key` for the runtime to unpack.
+fn load_custom_provider_config(db: &Database) -> Result<Option<ProviderConfig>, AppError> {
+    let settings = load_custom_provider_settings(db)?;
+    let base = match queries::get_setting(db, &provider_setting_key("custom"))? {
+        Some(raw) => Some(
+            serde_json::from_str::<ProviderConfig>(&raw)
+                .map_err(|e| AppError::Other(format!("invalid custom provider config: {e}")))?,
+        ),
+        None => {
+            let (env_key, env_model, env_base_url) = env_for_provider("custom");
+            if env_key.is_some() || env_base_url.is_some() {
+                Some(ProviderConfig {
+                    api_key: env_key.unwrap_or_default(),
+                    default_model: env_model,
+                    base_url: env_base_url,
+                })
+            } else {
+                None
+            }
+        }
+    };
+
+    if base.is_none() && settings.is_none() {
+        return Ok(None);
+    }
+
+    let settings = settings.unwrap_or_default();
+    let (api_key, default_model, base_url) = match base {
+        Some(cfg) => (cfg.api_key, cfg.default_model, cfg.base_url),
+        None => (String::new(), None, None),
+    };
+    let default_model = default_model
+        .filter(|m| !m.trim().is_empty())
+        .or_else(|| settings.default_model());
+
+    Ok(Some(ProviderConfig {
+        api_key: model::CustomClient::encode_api_key_payload(&api_key, &settings),
+        default_model,
+        base_url,
+    }))
+}
+
 fn default_workspace_root() -> PathBuf {
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
     if cwd.ends_with("src-tauri") {
@@ -297,7 +434,7 @@ fn default_workspace_root() -> PathBuf {
     }
 }
 
-fn orchestrix_data_dir() -> PathBuf {
+pub(crate) fn orchestrix_data_dir() -> PathBuf {
     if let Ok(path) = std::env::var("ORCHESTRIX_DATA_DIR") {
         let trimmed = path.trim();
         if !trimmed.is_empty() {
@@ -401,6 +538,10 @@ pub fn run() {
 
     let db_path = stable_db_path().expect("failed to resolve stable database path");
     let db = Arc::new(Database::open(&db_path).expect("failed to open database"));
+    load_retry_policies(&db);
+    for provider in model::ProviderId::all() {
+        refresh_provider_secret(&db, provider.as_str());
+    }
     let bus = Arc::new(EventBus::new());
     let workspace_root = load_workspace_root(&db);
     let orchestrator = Arc::new(Orchestrator::new(db.clone(), bus.clone(), workspace_root));
@@ -446,6 +587,12 @@ pub fn run() {
         embedding_index_service.ensure_workspace_index_started(load_workspace_root(&db));
     }
 
+    // Refresh stale provider model listings without delaying startup.
+    let db_for_models = db.clone();
+    tauri::async_runtime::spawn(async move {
+        commands::providers::refresh_model_lists(&db_for_models, false).await;
+    });
+
     // Initialize MCP in the background so a slow/unhealthy server cannot block app startup.
     let mcp_manager_for_init = mcp_manager.clone();
     tauri::async_runtime::spawn(async move {
@@ -469,6 +616,10 @@ pub fn run() {
             commands::tasks::unlink_tasks,
             commands::tasks::delete_task,
             commands::tasks::get_task,
+            commands::tasks::get_task_budget,
+            commands::tasks::set_task_budget,
+            commands::tasks::get_task_model_fallbacks,
+            commands::tasks::set_task_model_fallbacks,
             commands::tasks::start_task,
             commands::tasks::cancel_task,
             commands::tasks::get_task_canvas,
@@ -482,6 +633,11 @@ pub fn run() {
             commands::runs::list_user_messages,
             commands::runs::get_events_after,
             commands::runs::get_task_events,
+            commands::runs::get_run_usage,
+            commands::runs::get_task_usage,
+            commands::runs::revert_tool_call,
+            commands::runs::revert_step,
+            commands::runs::revert_run,
             // execution
             commands::execution::run_plan_mode,
             commands::execution::run_build_mode,
@@ -517,6 +673,9 @@ pub fn run() {
             commands::plan_mode::get_plan_mode_settings,
             commands::plan_mode::set_plan_mode_settings,
             commands::plan_mode::get_plan_mode_max_tokens_command,
+            // policy
+            commands::policy::validate_policy,
+            commands::policy::preview_effective_policy,
             // benchmarks
             commands::benchmarks::run_model_benchmark,
             commands::benchmarks::list_business_ops_scenarios_command,
@@ -527,7 +686,10 @@ pub fn run() {
             commands::providers::set_provider_config,
             commands::providers::remove_provider_config,
             commands::providers::get_provider_configs,
+            commands::providers::get_custom_provider_settings,
+            commands::providers::set_custom_provider_settings,
             commands::providers::get_model_catalog,
+            commands::providers::refresh_model_catalog,
             commands::providers::get_context_window_for_model,
             commands::providers::get_provider_usage_snapshot,
             // ChatGPT OAuth
@@ -581,6 +743,10 @@ pub fn run() {
             // workspace
             commands::workspace::set_workspace_root,
             commands::workspace::get_workspace_root,
+            commands::workspace::get_workspace_check_command,
+            commands::workspace::set_workspace_check_command,
+            commands::workspace::get_workspace_conflict_resolution,
+            commands::workspace::set_workspace_conflict_resolution,
             commands::workspace::search_workspace_references,
             commands::workspace::read_artifact_content,
             commands::workspace::write_file_content,
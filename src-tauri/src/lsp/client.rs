@@ -0,0 +1,443 @@
+//! A language server process spoken to over stdio.
+//!
+//! Messages use the LSP base protocol: a `Content-Length` header followed by
+//! a JSON-RPC body. A reader thread routes responses to the waiting request,
+//! answers the few requests servers send to the client, and keeps the latest
+//! `textDocument/publishDiagnostics` per document.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use super::{path_to_uri, LanguageServer};
+use crate::tools::sandbox::Sandbox;
+
+/// How long a request may take. Servers answer slowly while indexing.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest wait for a server to finish starting up.
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// After the first diagnostics for a document arrive, how long to keep
+/// waiting for a more complete set.
+const DIAGNOSTICS_SETTLE: Duration = Duration::from_millis(500);
+
+type PendingRequests = Mutex<HashMap<i64, mpsc::Sender<Result<Value, String>>>>;
+
+/// Diagnostics published for one document.
+#[derive(Debug, Clone, Default)]
+struct PublishedDiagnostics {
+    items: Vec<Value>,
+    /// Bumped on every publish, so waiters can tell fresh results apart.
+    generation: u64,
+}
+
+#[derive(Default)]
+struct DiagnosticsStore {
+    by_uri: Mutex<HashMap<String, PublishedDiagnostics>>,
+    published: Condvar,
+}
+
+struct OpenDocument {
+    version: i64,
+    text: String,
+}
+
+pub struct LspClient {
+    child: Mutex<Child>,
+    writer: Arc<Mutex<ChildStdin>>,
+    next_id: AtomicI64,
+    pending: Arc<PendingRequests>,
+    diagnostics: Arc<DiagnosticsStore>,
+    documents: Mutex<HashMap<String, OpenDocument>>,
+    alive: Arc<AtomicBool>,
+}
+
+impl LspClient {
+    /// Start `server` in `sandbox` for the project at `root` and complete
+    /// the `initialize` handshake within `timeout` (at most
+    /// `INITIALIZE_TIMEOUT`).
+    pub fn start(
+        server: &LanguageServer,
+        root: &Path,
+        sandbox: &Sandbox,
+        timeout: Duration,
+    ) -> Result<Self, String> {
+        let mut command = Command::new(server.command);
+        command.args(server.args).current_dir(root);
+        let mut child = sandbox
+            .wrap(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    format!("{} is not installed or not on PATH", server.command)
+                }
+                _ => format!("failed to start {}: {e}", server.command),
+            })?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| "missing stdin pipe".to_string())?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| "missing stdout pipe".to_string())?;
+
+        let client = Self {
+            child: Mutex::new(child),
+            writer: Arc::new(Mutex::new(stdin)),
+            next_id: AtomicI64::new(1),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            diagnostics: Arc::new(DiagnosticsStore::default()),
+            documents: Mutex::new(HashMap::new()),
+            alive: Arc::new(AtomicBool::new(true)),
+        };
+        client.spawn_reader(stdout);
+
+        let root_uri = path_to_uri(root);
+        let name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        client.request_with_timeout(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": root_uri,
+                "workspaceFolders": [{ "uri": root_uri, "name": name }],
+                "capabilities": {
+                    "textDocument": {
+                        "synchronization": { "didSave": true },
+                        "definition": { "linkSupport": true },
+                        "references": {},
+                        "hover": { "contentFormat": ["markdown", "plaintext"] },
+                        "rename": { "prepareSupport": false },
+                        "publishDiagnostics": { "relatedInformation": false },
+                    },
+                    "workspace": {
+                        "workspaceEdit": { "documentChanges": true },
+                        "configuration": true,
+                        "workspaceFolders": true,
+                    },
+                },
+            }),
+            timeout.min(INITIALIZE_TIMEOUT),
+        )?;
+        client.notify("initialized", json!({}))?;
+        Ok(client)
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    pub fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        self.request_with_timeout(method, params, REQUEST_TIMEOUT)
+    }
+
+    fn request_with_timeout(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, String> {
+        if !self.is_alive() {
+            return Err("language server has exited".to_string());
+        }
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = mpsc::channel();
+        self.pending
+            .lock()
+            .map_err(|_| "language client poisoned".to_string())?
+            .insert(id, sender);
+
+        let sent = write_message(
+            &self.writer,
+            &json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+        );
+        let result = sent.and_then(|()| {
+            receiver
+                .recv_timeout(timeout)
+                .map_err(|_| format!("{method} timed out after {}s", timeout.as_secs()))?
+        });
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&id);
+        }
+        result
+    }
+
+    pub fn notify(&self, method: &str, params: Value) -> Result<(), String> {
+        write_message(
+            &self.writer,
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+        )
+    }
+
+    /// Tell the server about the current content of `path`: open it the
+    /// first time, send the full text again when it changed since. Returns
+    /// whether anything was sent.
+    pub fn sync_document(
+        &self,
+        path: &Path,
+        language_id: &str,
+        text: &str,
+    ) -> Result<bool, String> {
+        let uri = path_to_uri(path);
+        let mut documents = self
+            .documents
+            .lock()
+            .map_err(|_| "language client poisoned".to_string())?;
+        match documents.get_mut(&uri) {
+            Some(document) if document.text == text => Ok(false),
+            Some(document) => {
+                document.version += 1;
+                document.text = text.to_string();
+                self.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": { "uri": uri, "version": document.version },
+                        "contentChanges": [{ "text": text }],
+                    }),
+                )?;
+                Ok(true)
+            }
+            None => {
+                self.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": language_id,
+                            "version": 1,
+                            "text": text,
+                        },
+                    }),
+                )?;
+                documents.insert(
+                    uri,
+                    OpenDocument {
+                        version: 1,
+                        text: text.to_string(),
+                    },
+                );
+                Ok(true)
+            }
+        }
+    }
+
+    /// Number of diagnostics publishes seen so far for `path`; pass it to
+    /// [`Self::wait_for_diagnostics`] to wait for a newer one.
+    pub fn diagnostics_generation(&self, path: &Path) -> u64 {
+        let uri = path_to_uri(path);
+        self.diagnostics
+            .by_uri
+            .lock()
+            .ok()
+            .and_then(|by_uri| by_uri.get(&uri).map(|d| d.generation))
+            .unwrap_or(0)
+    }
+
+    /// Diagnostics for `path` published after `after_generation`, or the
+    /// latest known ones when none arrive within `timeout`. Returns whether
+    /// the result is fresh.
+    pub fn wait_for_diagnostics(
+        &self,
+        path: &Path,
+        after_generation: u64,
+        timeout: Duration,
+    ) -> (Vec<Value>, bool) {
+        let uri = path_to_uri(path);
+        let Ok(mut by_uri) = self.diagnostics.by_uri.lock() else {
+            return (Vec::new(), false);
+        };
+        let started = Instant::now();
+        let mut deadline = started + timeout;
+        let mut fresh = false;
+        loop {
+            let generation = by_uri.get(&uri).map(|d| d.generation).unwrap_or(0);
+            if generation > after_generation && !fresh {
+                // Servers often publish a quick partial set first
+                fresh = true;
+                deadline = deadline.min(Instant::now() + DIAGNOSTICS_SETTLE);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            by_uri = match self
+                .diagnostics
+                .published
+                .wait_timeout(by_uri, deadline - now)
+            {
+                Ok((guard, _)) => guard,
+                Err(_) => return (Vec::new(), false),
+            };
+        }
+        let items = by_uri
+            .get(&uri)
+            .map(|d| d.items.clone())
+            .unwrap_or_default();
+        (items, fresh)
+    }
+
+    fn spawn_reader(&self, stdout: impl Read + Send + 'static) {
+        let pending = self.pending.clone();
+        let diagnostics = self.diagnostics.clone();
+        let writer = self.writer.clone();
+        let alive = self.alive.clone();
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            while let Some(message) = read_message(&mut reader) {
+                let id = message.get("id").cloned();
+                let method = message.get("method").and_then(Value::as_str);
+                match (method, id) {
+                    (None, Some(id)) => {
+                        let result = match message.get("error") {
+                            Some(error) => Err(error
+                                .get("message")
+                                .and_then(Value::as_str)
+                                .unwrap_or("language server error")
+                                .to_string()),
+                            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                        };
+                        let sender = id.as_i64().and_then(|id| pending.lock().ok()?.remove(&id));
+                        if let Some(sender) = sender {
+                            let _ = sender.send(result);
+                        }
+                    }
+                    (Some(method), Some(id)) => {
+                        let result = server_request_result(method, message.get("params"));
+                        let _ = write_message(
+                            &writer,
+                            &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        );
+                    }
+                    (Some("textDocument/publishDiagnostics"), None) => {
+                        store_diagnostics(&diagnostics, message.get("params"));
+                    }
+                    _ => {}
+                }
+            }
+
+            alive.store(false, Ordering::SeqCst);
+            if let Ok(mut pending) = pending.lock() {
+                for (_, sender) in pending.drain() {
+                    let _ = sender.send(Err("language server exited".to_string()));
+                }
+            }
+            diagnostics.published.notify_all();
+        });
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        let _ = self.notify("exit", Value::Null);
+        if let Ok(mut child) = self.child.lock() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Answer a request the server sent to us. Configuration is left to the
+/// server's defaults; everything else is acknowledged.
+fn server_request_result(method: &str, params: Option<&Value>) -> Value {
+    match method {
+        "workspace/configuration" => {
+            let items = params
+                .and_then(|p| p.get("items"))
+                .and_then(Value::as_array)
+                .map(Vec::len)
+                .unwrap_or(0);
+            Value::Array(vec![Value::Null; items])
+        }
+        _ => Value::Null,
+    }
+}
+
+fn store_diagnostics(store: &DiagnosticsStore, params: Option<&Value>) {
+    let Some(params) = params else {
+        return;
+    };
+    let Some(uri) = params.get("uri").and_then(Value::as_str) else {
+        return;
+    };
+    let items = params
+        .get("diagnostics")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    if let Ok(mut by_uri) = store.by_uri.lock() {
+        let entry = by_uri.entry(uri.to_string()).or_default();
+        entry.items = items;
+        entry.generation += 1;
+    }
+    store.published.notify_all();
+}
+
+fn write_message(writer: &Mutex<ChildStdin>, message: &Value) -> Result<(), String> {
+    let body = message.to_string();
+    let mut writer = writer
+        .lock()
+        .map_err(|_| "language client poisoned".to_string())?;
+    write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len())
+        .and_then(|()| writer.flush())
+        .map_err(|e| format!("failed writing to language server: {e}"))
+}
+
+/// Read one message, or `None` at end of stream or on a framing error.
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0u8; length?];
+    reader.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_framed_messages() {
+        let first = r#"{"jsonrpc":"2.0","id":1,"result":null}"#;
+        let second = r#"{"jsonrpc":"2.0","method":"window/logMessage","params":{}}"#;
+        let stream = format!(
+            "Content-Length: {}\r\nContent-Type: application/vscode-jsonrpc\r\n\r\n{first}content-length: {}\r\n\r\n{second}",
+            first.len(),
+            second.len()
+        );
+        let mut reader = BufReader::new(stream.as_bytes());
+
+        assert_eq!(read_message(&mut reader).unwrap()["id"], 1);
+        assert_eq!(
+            read_message(&mut reader).unwrap()["method"],
+            "window/logMessage"
+        );
+        assert!(read_message(&mut reader).is_none());
+    }
+}
@@ -0,0 +1,282 @@
+//! Language Server Protocol client for the `code.*` tools.
+//!
+//! A server is picked by file extension and started once per project root,
+//! under the same sandbox as `cmd.exec`, then kept running for later calls. The project root is found by walking
+//! up from the file to a marker such as `Cargo.toml` or `package.json`,
+//! never leaving the workspace.
+//!
+//! Positions cross this boundary in two forms: tools speak 1-based lines and
+//! columns counted in characters, servers speak 0-based lines and UTF-16
+//! code units. [`to_lsp_position`] and [`from_lsp_position`] convert.
+
+mod client;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::tools::sandbox::Sandbox;
+
+pub use client::LspClient;
+
+/// A language server the client knows how to start.
+#[derive(Debug)]
+pub struct LanguageServer {
+    pub name: &'static str,
+    pub command: &'static str,
+    pub args: &'static [&'static str],
+    extensions: &'static [&'static str],
+    root_markers: &'static [&'static str],
+    /// Use the outermost marker rather than the nearest, for workspaces
+    /// whose members share one server (Cargo workspaces).
+    outermost_root: bool,
+}
+
+pub const LANGUAGE_SERVERS: &[LanguageServer] = &[
+    LanguageServer {
+        name: "rust-analyzer",
+        command: "rust-analyzer",
+        args: &[],
+        extensions: &["rs"],
+        root_markers: &["Cargo.toml"],
+        outermost_root: true,
+    },
+    LanguageServer {
+        name: "typescript-language-server",
+        command: "typescript-language-server",
+        args: &["--stdio"],
+        extensions: &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+        root_markers: &["tsconfig.json", "jsconfig.json", "package.json"],
+        outermost_root: false,
+    },
+    LanguageServer {
+        name: "pyright",
+        command: "pyright-langserver",
+        args: &["--stdio"],
+        extensions: &["py", "pyi"],
+        root_markers: &[
+            "pyrightconfig.json",
+            "pyproject.toml",
+            "setup.py",
+            "setup.cfg",
+        ],
+        outermost_root: false,
+    },
+];
+
+/// A server's running client, locked while it starts so that only callers
+/// of the same server and root wait for it.
+type ClientSlot = Arc<Mutex<Option<Arc<LspClient>>>>;
+
+/// Running servers by project root and server name.
+static CLIENTS: OnceLock<Mutex<HashMap<(PathBuf, &'static str), ClientSlot>>> = OnceLock::new();
+
+/// The server for `path`, by extension.
+pub fn server_for_path(path: &Path) -> Option<&'static LanguageServer> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    LANGUAGE_SERVERS
+        .iter()
+        .find(|server| server.extensions.contains(&extension.as_str()))
+}
+
+/// LSP `languageId` of a document.
+pub fn language_id(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+    {
+        "rs" => "rust",
+        "ts" => "typescript",
+        "tsx" => "typescriptreact",
+        "jsx" => "javascriptreact",
+        "js" | "mjs" | "cjs" => "javascript",
+        "py" | "pyi" => "python",
+        _ => "plaintext",
+    }
+}
+
+/// Project root for `path`: the directory holding the server's root marker,
+/// or `workspace` when there is none below it.
+pub fn project_root(server: &LanguageServer, path: &Path, workspace: &Path) -> PathBuf {
+    let mut root = None;
+    for dir in path.ancestors().skip(1) {
+        if !dir.starts_with(workspace) {
+            break;
+        }
+        if server
+            .root_markers
+            .iter()
+            .any(|marker| dir.join(marker).is_file())
+        {
+            root = Some(dir.to_path_buf());
+            if !server.outermost_root {
+                break;
+            }
+        }
+    }
+    root.unwrap_or_else(|| workspace.to_path_buf())
+}
+
+/// The running client for `server` at `root`, started in `sandbox` on first
+/// use or when the previous process has exited. `startup_timeout` bounds the
+/// `initialize` handshake.
+pub fn client_for(
+    server: &'static LanguageServer,
+    root: &Path,
+    sandbox: &Sandbox,
+    startup_timeout: Duration,
+) -> Result<Arc<LspClient>, String> {
+    let slot = {
+        let clients = CLIENTS.get_or_init(|| Mutex::new(HashMap::new()));
+        let mut clients = clients
+            .lock()
+            .map_err(|_| "language server registry poisoned".to_string())?;
+        clients
+            .entry((root.to_path_buf(), server.name))
+            .or_default()
+            .clone()
+    };
+    let mut slot = slot
+        .lock()
+        .map_err(|_| format!("{} client poisoned", server.name))?;
+    if let Some(client) = slot.as_ref().filter(|client| client.is_alive()) {
+        return Ok(client.clone());
+    }
+    let client = Arc::new(LspClient::start(server, root, sandbox, startup_timeout)?);
+    *slot = Some(client.clone());
+    Ok(client)
+}
+
+pub fn path_to_uri(path: &Path) -> String {
+    let normalized = path.to_string_lossy().replace('\\', "/");
+    let encoded: Vec<String> = normalized
+        .split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect();
+    let encoded = encoded.join("/").replacen("%3A", ":", 1);
+    if encoded.starts_with('/') {
+        format!("file://{encoded}")
+    } else {
+        // Windows drive paths
+        format!("file:///{encoded}")
+    }
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let decoded = urlencoding::decode(rest).ok()?.into_owned();
+    // `/C:/dir` is a Windows drive path
+    let bytes = decoded.as_bytes();
+    if bytes.len() > 2 && bytes[0] == b'/' && bytes[2] == b':' {
+        return Some(PathBuf::from(&decoded[1..]));
+    }
+    Some(PathBuf::from(decoded))
+}
+
+/// LSP position of a 1-based `line` and character `column` in `text`.
+pub fn to_lsp_position(text: &str, line: u32, column: u32) -> Value {
+    let line_idx = line.saturating_sub(1);
+    let line_text = text.lines().nth(line_idx as usize).unwrap_or_default();
+    let character: usize = line_text
+        .chars()
+        .take(column.saturating_sub(1) as usize)
+        .map(char::len_utf16)
+        .sum();
+    json!({ "line": line_idx, "character": character })
+}
+
+/// 1-based line and character column of an LSP `position` in `text`.
+pub fn from_lsp_position(text: &str, position: &Value) -> (u32, u32) {
+    let line = position.get("line").and_then(Value::as_u64).unwrap_or(0);
+    let units = position
+        .get("character")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as usize;
+    let line_text = text.lines().nth(line as usize).unwrap_or_default();
+    let mut seen = 0;
+    let mut column = 0;
+    for ch in line_text.chars() {
+        if seen >= units {
+            break;
+        }
+        seen += ch.len_utf16();
+        column += 1;
+    }
+    (line as u32 + 1, column + 1)
+}
+
+/// Byte offset of an LSP `position` in `text`, clamped to the text.
+pub fn lsp_position_offset(text: &str, position: &Value) -> usize {
+    let line = position.get("line").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let units = position
+        .get("character")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as usize;
+    let mut line_start = 0;
+    for _ in 0..line {
+        match text[line_start..].find('\n') {
+            Some(newline) => line_start += newline + 1,
+            None => return text.len(),
+        }
+    }
+    let line_end = text[line_start..]
+        .find('\n')
+        .map(|end| line_start + end)
+        .unwrap_or(text.len());
+    let mut seen = 0;
+    for (offset, ch) in text[line_start..line_end].char_indices() {
+        if seen >= units {
+            return line_start + offset;
+        }
+        seen += ch.len_utf16();
+    }
+    line_end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_server_and_root_by_file() {
+        let workspace = crate::tests::temp_workspace();
+        std::fs::create_dir_all(workspace.join("crates/core/src")).unwrap();
+        std::fs::write(workspace.join("Cargo.toml"), "[workspace]").unwrap();
+        std::fs::write(workspace.join("crates/core/Cargo.toml"), "[package]").unwrap();
+        let file = workspace.join("crates/core/src/lib.rs");
+
+        let server = server_for_path(&file).expect("rust server");
+        assert_eq!(server.name, "rust-analyzer");
+        assert_eq!(project_root(server, &file, &workspace), workspace);
+        assert_eq!(
+            server_for_path(Path::new("web/app.tsx")).map(|s| s.name),
+            Some("typescript-language-server")
+        );
+        assert!(server_for_path(Path::new("README.md")).is_none());
+
+        crate::tests::cleanup(&workspace);
+    }
+
+    #[test]
+    fn converts_positions_through_utf16() {
+        let text = "fn main() {\n    let é = \"😀x\";\n}\n";
+        let position = to_lsp_position(text, 2, 15);
+        // `😀` is two UTF-16 units
+        assert_eq!(position, json!({ "line": 1, "character": 15 }));
+        assert_eq!(from_lsp_position(text, &position), (2, 15));
+        assert_eq!(&text[lsp_position_offset(text, &position)..][..1], "x");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_uris_round_trip() {
+        let path = Path::new("/tmp/my project/src/lib.rs");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///tmp/my%20project/src/lib.rs");
+        assert_eq!(uri_to_path(&uri).as_deref(), Some(path));
+    }
+}
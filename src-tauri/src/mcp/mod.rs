@@ -607,7 +607,8 @@ impl McpClientManager {
                 .unwrap_or_else(|| serde_json::json!({}));
 
             let read_only_hint = tool
-                .get("readOnlyHint")
+                .pointer("/annotations/readOnlyHint")
+                .or_else(|| tool.get("readOnlyHint"))
                 .or_else(|| tool.get("read_only_hint"))
                 .and_then(|v| v.as_bool());
 
@@ -0,0 +1,649 @@
+//! Record/replay of model transcripts for offline tests.
+//!
+//! A cassette is a JSON file holding every model interaction of a session in
+//! order: planner and worker decisions (with the deltas they streamed) and
+//! plain or structured completions. When `ORCHESTRIX_RECORD_CASSETTE` names a
+//! file, the worker, the planner and `CompletionModel` append each live
+//! interaction to it. The `replay` provider, whose api key is a cassette path,
+//! serves the recorded responses back in order, so complete plan → approve →
+//! build flows run deterministically without network access or API keys.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::model::structured::OutputSchema;
+use crate::model::{
+    AgentModelClient, CompletionClient, ModelError, StreamDelta, WorkerActionRequest,
+    WorkerDecision,
+};
+use crate::policy::redaction;
+
+/// Provider id that replays a cassette instead of calling a vendor API.
+pub const REPLAY_PROVIDER: &str = "replay";
+
+/// Environment variable naming the cassette file live interactions are recorded to.
+pub const RECORD_CASSETTE_ENV: &str = "ORCHESTRIX_RECORD_CASSETTE";
+
+const CASSETTE_VERSION: u32 = 1;
+
+/// Model label used when a replayed config does not name a model.
+const REPLAY_MODEL: &str = "replay";
+
+/// A recorded sequence of model interactions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cassette {
+    pub version: u32,
+    pub interactions: Vec<Interaction>,
+}
+
+impl Default for Cassette {
+    fn default() -> Self {
+        Self {
+            version: CASSETTE_VERSION,
+            interactions: Vec::new(),
+        }
+    }
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read cassette {}: {e}", path.display()))?;
+        let cassette: Self = serde_json::from_str(&raw)
+            .map_err(|e| format!("invalid cassette {}: {e}", path.display()))?;
+        if cassette.version != CASSETTE_VERSION {
+            return Err(format!(
+                "unsupported cassette version {} in {}",
+                cassette.version,
+                path.display()
+            ));
+        }
+        Ok(cassette)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let raw = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, raw)
+            .map_err(|e| format!("failed to write cassette {}: {e}", path.display()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InteractionKind {
+    /// A planner or worker `decide_action` call.
+    Decide,
+    /// A plain `CompletionClient::complete` call.
+    Complete,
+    /// A `CompletionClient::complete_json` call.
+    CompleteJson,
+}
+
+impl std::fmt::Display for InteractionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Decide => "decide",
+            Self::Complete => "complete",
+            Self::CompleteJson => "complete_json",
+        })
+    }
+}
+
+/// One model call and its outcome.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub kind: InteractionKind,
+    /// Model that produced the response.
+    pub model: String,
+    pub request: RecordedRequest,
+    /// Deltas streamed before the response, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deltas: Vec<StreamDelta>,
+    pub response: RecordedResponse,
+}
+
+/// Summary of the request, kept so cassettes can be reviewed and diffed.
+/// Replay serves interactions by position and does not match on it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observations: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+}
+
+impl RecordedRequest {
+    fn for_decision(req: &WorkerActionRequest) -> Self {
+        Self {
+            task_prompt: Some(req.task_prompt.clone()),
+            goal_summary: Some(req.goal_summary.clone()),
+            tools: req.available_tools.clone(),
+            observations: Some(req.prior_observations.len()),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedResponse {
+    Decision(WorkerDecision),
+    Text(String),
+    Error {
+        kind: RecordedErrorKind,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedErrorKind {
+    Request,
+    InvalidResponse,
+    Auth,
+}
+
+impl RecordedResponse {
+    fn from_error(error: &ModelError) -> Self {
+        let (kind, message) = match error {
+            ModelError::Request(message) => (RecordedErrorKind::Request, message),
+            ModelError::InvalidResponse(message) => (RecordedErrorKind::InvalidResponse, message),
+            ModelError::Auth(message) => (RecordedErrorKind::Auth, message),
+        };
+        Self::Error {
+            kind,
+            message: message.clone(),
+        }
+    }
+
+    fn error(kind: RecordedErrorKind, message: String) -> ModelError {
+        match kind {
+            RecordedErrorKind::Request => ModelError::Request(message),
+            RecordedErrorKind::InvalidResponse => ModelError::InvalidResponse(message),
+            RecordedErrorKind::Auth => ModelError::Auth(message),
+        }
+    }
+
+    fn into_decision(self) -> Result<WorkerDecision, ModelError> {
+        match self {
+            Self::Decision(decision) => Ok(decision),
+            Self::Error { kind, message } => Err(Self::error(kind, message)),
+            Self::Text(_) => Err(ModelError::InvalidResponse(
+                "cassette recorded text where a decision was expected".to_string(),
+            )),
+        }
+    }
+
+    fn into_text(self) -> Result<String, ModelError> {
+        match self {
+            Self::Text(text) => Ok(text),
+            Self::Error { kind, message } => Err(Self::error(kind, message)),
+            Self::Decision(_) => Err(ModelError::InvalidResponse(
+                "cassette recorded a decision where text was expected".to_string(),
+            )),
+        }
+    }
+}
+
+/// Appends interactions to a cassette file as they happen.
+///
+/// The file is rewritten after every interaction, so a run that crashes or is
+/// cancelled still leaves a usable cassette behind.
+pub struct CassetteRecorder {
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl CassetteRecorder {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            cassette: Mutex::new(Cassette::default()),
+        }
+    }
+
+    /// Append `interaction` with any secret in it masked.
+    pub fn record(&self, interaction: Interaction) {
+        let interaction = redact_interaction(interaction);
+        let mut cassette = self.cassette.lock().expect("cassette mutex poisoned");
+        cassette.interactions.push(interaction);
+        if let Err(error) = cassette.save(&self.path) {
+            tracing::warn!("failed to record model interaction: {error}");
+        }
+    }
+
+    pub fn record_decision(
+        &self,
+        model: &str,
+        req: &WorkerActionRequest,
+        deltas: Vec<StreamDelta>,
+        result: &Result<WorkerDecision, ModelError>,
+    ) {
+        self.record(Interaction {
+            kind: InteractionKind::Decide,
+            model: model.to_string(),
+            request: RecordedRequest::for_decision(req),
+            deltas,
+            response: match result {
+                Ok(decision) => RecordedResponse::Decision(decision.clone()),
+                Err(error) => RecordedResponse::from_error(error),
+            },
+        });
+    }
+
+    pub fn record_completion(
+        &self,
+        model: &str,
+        system: &str,
+        conversation: &str,
+        schema: Option<&OutputSchema>,
+        result: &Result<String, ModelError>,
+    ) {
+        self.record(Interaction {
+            kind: if schema.is_some() {
+                InteractionKind::CompleteJson
+            } else {
+                InteractionKind::Complete
+            },
+            model: model.to_string(),
+            request: RecordedRequest {
+                system: Some(system.to_string()),
+                conversation: Some(conversation.to_string()),
+                schema: schema.map(|schema| schema.name.clone()),
+                ..RecordedRequest::default()
+            },
+            deltas: Vec::new(),
+            response: match result {
+                Ok(text) => RecordedResponse::Text(text.clone()),
+                Err(error) => RecordedResponse::from_error(error),
+            },
+        });
+    }
+}
+
+fn redact_interaction(interaction: Interaction) -> Interaction {
+    let Ok(mut value) = serde_json::to_value(&interaction) else {
+        return interaction;
+    };
+    if redaction::redact_value(&mut value) == 0 {
+        return interaction;
+    }
+    serde_json::from_value(value).unwrap_or(interaction)
+}
+
+static RECORDER: OnceLock<Option<Arc<CassetteRecorder>>> = OnceLock::new();
+
+/// The process-wide recorder, if `ORCHESTRIX_RECORD_CASSETTE` is set.
+/// Recording starts from an empty cassette each time the app starts.
+pub fn active_recorder() -> Option<Arc<CassetteRecorder>> {
+    RECORDER
+        .get_or_init(|| {
+            let path = std::env::var(RECORD_CASSETTE_ENV).ok()?;
+            let path = path.trim();
+            if path.is_empty() {
+                return None;
+            }
+            tracing::info!("recording model interactions to {path}");
+            Some(Arc::new(CassetteRecorder::new(path)))
+        })
+        .clone()
+}
+
+/// Playback position in a cassette. Each interaction kind is consumed in its
+/// own order, so background completions (summaries, suggestions) finishing at
+/// different times do not shift the decisions of the main flow.
+struct ReplaySession {
+    queues: Mutex<HashMap<InteractionKind, VecDeque<Interaction>>>,
+    load_error: Option<String>,
+}
+
+impl ReplaySession {
+    fn from_cassette(cassette: Cassette) -> Self {
+        let mut queues: HashMap<InteractionKind, VecDeque<Interaction>> = HashMap::new();
+        for interaction in cassette.interactions {
+            queues
+                .entry(interaction.kind)
+                .or_default()
+                .push_back(interaction);
+        }
+        Self {
+            queues: Mutex::new(queues),
+            load_error: None,
+        }
+    }
+
+    fn failed(error: String) -> Self {
+        Self {
+            queues: Mutex::new(HashMap::new()),
+            load_error: Some(error),
+        }
+    }
+
+    fn next(&self, kind: InteractionKind) -> Result<Interaction, ModelError> {
+        if let Some(error) = &self.load_error {
+            return Err(ModelError::InvalidResponse(error.clone()));
+        }
+        self.queues
+            .lock()
+            .expect("replay mutex poisoned")
+            .get_mut(&kind)
+            .and_then(VecDeque::pop_front)
+            .ok_or_else(|| {
+                ModelError::InvalidResponse(format!("cassette has no {kind} interactions left"))
+            })
+    }
+
+    fn remaining(&self) -> usize {
+        self.queues
+            .lock()
+            .expect("replay mutex poisoned")
+            .values()
+            .map(VecDeque::len)
+            .sum()
+    }
+}
+
+static REPLAY_SESSIONS: OnceLock<DashMap<PathBuf, Arc<ReplaySession>>> = OnceLock::new();
+
+fn replay_sessions() -> &'static DashMap<PathBuf, Arc<ReplaySession>> {
+    REPLAY_SESSIONS.get_or_init(DashMap::new)
+}
+
+/// Model client that serves responses from a cassette.
+///
+/// Errors are `ModelError::InvalidResponse` when the cassette is missing or
+/// exhausted, so a replayed run fails loudly instead of failing over.
+#[derive(Clone)]
+pub struct ReplayClient {
+    session: Arc<ReplaySession>,
+    model: String,
+}
+
+impl ReplayClient {
+    /// Replay the cassette at `path`. Clients opened on the same path share
+    /// one playback position, so the planner, the worker and completion jobs
+    /// of a flow consume the cassette in the order it was recorded.
+    pub fn open(path: impl AsRef<Path>, model: Option<String>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let session = replay_sessions()
+            .entry(path.clone())
+            .or_insert_with(|| {
+                Arc::new(match Cassette::load(&path) {
+                    Ok(cassette) => ReplaySession::from_cassette(cassette),
+                    Err(error) => ReplaySession::failed(error),
+                })
+            })
+            .clone();
+        Self {
+            session,
+            model: model.unwrap_or_else(|| REPLAY_MODEL.to_string()),
+        }
+    }
+
+    /// Replay an in-memory cassette with its own playback position.
+    pub fn from_cassette(cassette: Cassette, model: Option<String>) -> Self {
+        Self {
+            session: Arc::new(ReplaySession::from_cassette(cassette)),
+            model: model.unwrap_or_else(|| REPLAY_MODEL.to_string()),
+        }
+    }
+
+    pub fn model_id(&self) -> String {
+        self.model.clone()
+    }
+
+    /// Interactions not yet served.
+    #[allow(dead_code)]
+    pub fn remaining(&self) -> usize {
+        self.session.remaining()
+    }
+
+    pub async fn decide_action_streaming<F>(
+        &self,
+        req: WorkerActionRequest,
+        mut on_delta: F,
+    ) -> Result<WorkerDecision, ModelError>
+    where
+        F: FnMut(StreamDelta) -> Result<(), String> + Send,
+    {
+        let interaction = self.session.next(InteractionKind::Decide)?;
+        if interaction
+            .request
+            .task_prompt
+            .as_deref()
+            .is_some_and(|recorded| recorded != req.task_prompt)
+        {
+            tracing::warn!("replayed decision was recorded for a different task prompt");
+        }
+        for delta in interaction.deltas {
+            on_delta(delta).map_err(ModelError::Request)?;
+        }
+        interaction.response.into_decision()
+    }
+
+    fn next_text(&self, kind: InteractionKind) -> Result<String, ModelError> {
+        self.session.next(kind)?.response.into_text()
+    }
+}
+
+impl AgentModelClient for ReplayClient {
+    fn model_id(&self) -> String {
+        self.model_id()
+    }
+
+    async fn decide_action(&self, req: WorkerActionRequest) -> Result<WorkerDecision, ModelError> {
+        self.decide_action_streaming(req, |_| Ok(())).await
+    }
+}
+
+impl CompletionClient for ReplayClient {
+    async fn complete(
+        &self,
+        _system: &str,
+        _conversation: &str,
+        _max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.next_text(InteractionKind::Complete)
+    }
+
+    async fn complete_json(
+        &self,
+        _system: &str,
+        _conversation: &str,
+        _schema: &OutputSchema,
+        _max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.next_text(InteractionKind::CompleteJson)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::WorkerAction;
+    use serde_json::json;
+
+    fn request(prompt: &str) -> WorkerActionRequest {
+        WorkerActionRequest {
+            task_prompt: prompt.to_string(),
+            goal_summary: "goal".to_string(),
+            context: String::new(),
+            available_tools: vec!["fs.read".to_string()],
+            tool_descriptors: Vec::new(),
+            prior_observations: Vec::new(),
+            max_tokens: None,
+        }
+    }
+
+    fn complete(summary: &str) -> WorkerDecision {
+        WorkerDecision {
+            action: WorkerAction::Complete {
+                summary: summary.to_string(),
+            },
+            reasoning: None,
+            raw_response: None,
+            usage: None,
+        }
+    }
+
+    fn temp_cassette_path() -> PathBuf {
+        std::env::temp_dir()
+            .join("orchestrix-test-cassettes")
+            .join(format!("{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn recorded_interactions_replay_in_order_with_deltas() {
+        let path = temp_cassette_path();
+        let recorder = CassetteRecorder::new(&path);
+        recorder.record_decision(
+            "MiniMax-M2.5",
+            &request("build it"),
+            vec![
+                StreamDelta::Reasoning("thinking".to_string()),
+                StreamDelta::Content("Done".to_string()),
+            ],
+            &Ok(complete("Done")),
+        );
+        recorder.record_completion(
+            "MiniMax-M2.5",
+            "Summarize",
+            "conversation",
+            None,
+            &Ok("A summary.".to_string()),
+        );
+        recorder.record_decision(
+            "MiniMax-M2.5",
+            &request("build it"),
+            Vec::new(),
+            &Err(ModelError::Auth("bad key".to_string())),
+        );
+
+        let client = ReplayClient::open(&path, None);
+        // A second client on the same path shares the playback position.
+        let other = ReplayClient::open(&path, Some("MiniMax-M2.5".to_string()));
+        assert_eq!(client.remaining(), 3);
+
+        let mut streamed = Vec::new();
+        let decision = client
+            .decide_action_streaming(request("build it"), |delta| {
+                streamed.push(delta);
+                Ok(())
+            })
+            .await
+            .expect("recorded decision");
+        assert!(
+            matches!(decision.action, WorkerAction::Complete { ref summary } if summary == "Done")
+        );
+        assert_eq!(
+            streamed,
+            vec![
+                StreamDelta::Reasoning("thinking".to_string()),
+                StreamDelta::Content("Done".to_string()),
+            ]
+        );
+
+        let summary = other
+            .complete("Summarize", "conversation", 256)
+            .await
+            .expect("recorded text");
+        assert_eq!(summary, "A summary.");
+
+        let error = client
+            .decide_action(request("build it"))
+            .await
+            .expect_err("recorded error");
+        assert!(matches!(error, ModelError::Auth(message) if message == "bad key"));
+
+        let exhausted = client.decide_action(request("build it")).await;
+        assert!(matches!(exhausted, Err(ModelError::InvalidResponse(_))));
+        assert_eq!(other.remaining(), 0);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn interaction_kinds_are_consumed_independently() {
+        let cassette: Cassette = serde_json::from_value(json!({
+            "version": 1,
+            "interactions": [
+                {
+                    "kind": "complete_json",
+                    "model": "m",
+                    "request": {"schema": "plan"},
+                    "response": {"text": "{\"steps\":[]}"}
+                },
+                {
+                    "kind": "decide",
+                    "model": "m",
+                    "request": {},
+                    "deltas": [{"type": "content", "text": "ok"}],
+                    "response": {"decision": {"action": {"action": "complete", "summary": "ok"}}}
+                }
+            ]
+        }))
+        .expect("cassette json");
+
+        let client = ReplayClient::from_cassette(cassette, None);
+        let decision = client
+            .decide_action(request("anything"))
+            .await
+            .expect("decision");
+        assert!(matches!(decision.action, WorkerAction::Complete { .. }));
+
+        let schema = OutputSchema::new("plan", "plan", json!({"type": "object"}));
+        let text = client
+            .complete_json("system", "conversation", &schema, 256)
+            .await
+            .expect("structured text");
+        assert_eq!(text, "{\"steps\":[]}");
+
+        assert!(client
+            .complete("system", "conversation", 256)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn recorded_interactions_are_redacted() {
+        let path = temp_cassette_path();
+        let secret = "sk-abcdefghijklmnopqrstuvwxyz012345";
+        CassetteRecorder::new(&path).record_completion(
+            "m",
+            "Summarize",
+            &format!("OPENAI_API_KEY={secret}"),
+            None,
+            &Ok(format!("The key is {secret}.")),
+        );
+
+        let saved = std::fs::read_to_string(&path).expect("cassette file");
+        assert!(!saved.contains(secret), "{saved}");
+        assert!(saved.contains(redaction::REDACTED), "{saved}");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn missing_cassette_fails_without_failover() {
+        let client = ReplayClient::open(temp_cassette_path(), None);
+        let result = client.decide_action(request("anything")).await;
+        assert!(matches!(result, Err(ModelError::InvalidResponse(_))));
+    }
+}
@@ -4,8 +4,11 @@
 //! - Available models per provider
 //! - Context window sizes
 //! - Default models
+//! - Per-model token pricing
 
 use crate::model::provider::ProviderId;
+use crate::model::providers::custom::CustomProviderSettings;
+use crate::model::types::TokenUsage;
 
 /// Model metadata entry.
 #[derive(Debug, Clone, serde::Serialize)]
@@ -20,6 +23,35 @@ pub struct ModelInfo {
     pub capabilities: Vec<String>,
 }
 
+/// Token pricing for a model, in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct ModelPricing {
+    pub input_per_mtok: f64,
+    pub cached_input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+impl ModelPricing {
+    const fn new(input_per_mtok: f64, cached_input_per_mtok: f64, output_per_mtok: f64) -> Self {
+        Self {
+            input_per_mtok,
+            cached_input_per_mtok,
+            output_per_mtok,
+        }
+    }
+
+    /// Cost in USD for the given usage. Cached prompt tokens are billed at the
+    /// cached rate; reasoning tokens are already part of the completion count.
+    pub fn cost_usd(&self, usage: &TokenUsage) -> f64 {
+        let cached = usage.cached_tokens.min(usage.prompt_tokens);
+        let uncached = usage.prompt_tokens - cached;
+        (uncached as f64 * self.input_per_mtok
+            + cached as f64 * self.cached_input_per_mtok
+            + usage.completion_tokens as f64 * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
 /// Provider entry with its models.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct ProviderEntry {
@@ -356,9 +388,103 @@ impl ModelCatalog {
                     },
                 ],
             },
+            ProviderEntry {
+                provider: ProviderId::Anthropic.as_str().to_string(),
+                models: vec![
+                    ModelInfo {
+                        name: "claude-sonnet-4-5".to_string(),
+                        context_window: 200_000,
+                        output_limit: 64_000,
+                        description: "Claude Sonnet 4.5 - Balanced coding and agentic model".to_string(),
+                        deprecated: false,
+                        deprecation_reason: None,
+                        suggested_alternative: None,
+                        capabilities: vec!["text".to_string(), "image".to_string(), "function_calling".to_string(), "thinking".to_string()],
+                    },
+                    ModelInfo {
+                        name: "claude-opus-4-5".to_string(),
+                        context_window: 200_000,
+                        output_limit: 64_000,
+                        description: "Claude Opus 4.5 - Most capable model for complex tasks".to_string(),
+                        deprecated: false,
+                        deprecation_reason: None,
+                        suggested_alternative: None,
+                        capabilities: vec!["text".to_string(), "image".to_string(), "function_calling".to_string(), "thinking".to_string()],
+                    },
+                    ModelInfo {
+                        name: "claude-haiku-4-5".to_string(),
+                        context_window: 200_000,
+                        output_limit: 64_000,
+                        description: "Claude Haiku 4.5 - Fast, low-cost model".to_string(),
+                        deprecated: false,
+                        deprecation_reason: None,
+                        suggested_alternative: None,
+                        capabilities: vec!["text".to_string(), "image".to_string(), "function_calling".to_string(), "thinking".to_string()],
+                    },
+                ],
+            },
         ]
     }
 
+    /// Most output tokens a model accepts per request, when the catalog lists it.
+    pub fn output_limit_for(provider: ProviderId, model: &str) -> Option<u32> {
+        Self::all_models()
+            .into_iter()
+            .find(|entry| entry.provider == provider.as_str())?
+            .models
+            .into_iter()
+            .find(|info| info.name == model)
+            .map(|info| info.output_limit)
+    }
+
+    /// Get list-price token rates for a model.
+    ///
+    /// Returns `None` when the provider does not bill per token (ChatGPT
+    /// subscriptions, self-hosted Modal deployments, custom endpoints) or the
+    /// model is unknown.
+    pub fn pricing_for(provider: ProviderId, model: &str) -> Option<ModelPricing> {
+        let pricing = match provider {
+            ProviderId::MiniMax => match model {
+                "MiniMax-M2.5" | "MiniMax-M2.1" | "MiniMax-M2" => {
+                    ModelPricing::new(0.30, 0.03, 1.20)
+                }
+                _ => return None,
+            },
+            ProviderId::Kimi => match model {
+                "kimi-k2.5" => ModelPricing::new(0.60, 0.10, 3.00),
+                "kimi-k2-thinking" => ModelPricing::new(0.60, 0.15, 2.50),
+                _ => return None,
+            },
+            ProviderId::Zhipu => match model {
+                "glm-5" => ModelPricing::new(1.00, 0.20, 3.20),
+                "glm-4.7" | "glm-4.6" | "glm-4.5" => ModelPricing::new(0.60, 0.11, 2.20),
+                "glm-4.6v" => ModelPricing::new(0.30, 0.05, 0.90),
+                "glm-4.5-air" => ModelPricing::new(0.20, 0.03, 1.10),
+                "glm-4.7-flash" | "glm-4.5-flash" => ModelPricing::new(0.0, 0.0, 0.0),
+                _ => return None,
+            },
+            ProviderId::Gemini => match model.trim_start_matches("models/") {
+                "gemini-3.1-pro-preview" | "gemini-3-pro-preview" => {
+                    ModelPricing::new(2.00, 0.20, 12.00)
+                }
+                "gemini-3-flash-preview" => ModelPricing::new(0.50, 0.05, 3.00),
+                "gemini-2.5-pro" => ModelPricing::new(1.25, 0.125, 10.00),
+                "gemini-2.5-flash" => ModelPricing::new(0.30, 0.03, 2.50),
+                "gemini-2.5-flash-lite" => ModelPricing::new(0.10, 0.01, 0.40),
+                "gemini-2.0-flash" => ModelPricing::new(0.10, 0.025, 0.40),
+                _ => return None,
+            },
+            ProviderId::Anthropic => match model {
+                "claude-opus-4-5" => ModelPricing::new(5.00, 0.50, 25.00),
+                "claude-sonnet-4-5" => ModelPricing::new(3.00, 0.30, 15.00),
+                "claude-haiku-4-5" => ModelPricing::new(1.00, 0.10, 5.00),
+                _ => return None,
+            },
+            ProviderId::Modal | ProviderId::OpenAIChatGPT | ProviderId::Custom => return None,
+        };
+        Some(pricing)
+    }
+
     /// Get the default model for a provider.
     pub fn default_model_for_provider(provider: ProviderId) -> String {
         match provider {
@@ -368,6 +494,63 @@ impl ModelCatalog {
             ProviderId::Modal => "zai-org/GLM-5-FP8".to_string(),
             ProviderId::OpenAIChatGPT => "gpt-5.3-codex".to_string(),
             ProviderId::Gemini => "gemini-3-flash-preview".to_string(),
+            ProviderId::Anthropic => "claude-sonnet-4-5".to_string(),
+            // Custom endpoints have no built-in models; see `custom_provider_entry`.
+            ProviderId::Custom => String::new(),
         }
     }
+
+    /// Catalog entry for the user-configured custom provider.
+    pub fn custom_provider_entry(settings: &CustomProviderSettings) -> ProviderEntry {
+        ProviderEntry {
+            provider: ProviderId::Custom.as_str().to_string(),
+            models: settings
+                .models
+                .iter()
+                .map(|model| ModelInfo {
+                    name: model.name.clone(),
+                    context_window: model.context_window,
+                    output_limit: model.output_limit,
+                    description: match settings.display_name.as_deref() {
+                        Some(label) => format!("{} model", label),
+                        None => "Custom endpoint model".to_string(),
+                    },
+                    deprecated: false,
+                    deprecation_reason: None,
+                    suggested_alternative: None,
+                    capabilities: if settings.supports_tools {
+                        vec!["text".to_string(), "function_calling".to_string()]
+                    } else {
+                        vec!["text".to_string()]
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pricing_bills_cached_prompt_tokens_at_cached_rate() {
+        let pricing = ModelCatalog::pricing_for(ProviderId::Kimi, "kimi-k2.5").expect("pricing");
+        let usage = TokenUsage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 100_000,
+            reasoning_tokens: 20_000,
+            cached_tokens: 500_000,
+        };
+
+        let cost = pricing.cost_usd(&usage);
+        // 0.5M * 0.60 + 0.5M * 0.10 + 0.1M * 3.00
+        assert!((cost - 0.65).abs() < 1e-9, "unexpected cost {cost}");
+    }
+
+    #[test]
+    fn pricing_is_unknown_for_subscription_and_unlisted_models() {
+        assert!(ModelCatalog::pricing_for(ProviderId::OpenAIChatGPT, "gpt-5.3-codex").is_none());
+        assert!(ModelCatalog::pricing_for(ProviderId::MiniMax, "not-a-model").is_none());
+    }
 }
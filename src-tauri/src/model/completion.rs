@@ -0,0 +1,229 @@
+//! Provider-agnostic plain completion for auxiliary LLM jobs.
+
+use crate::model::cassette::{self, ReplayClient};
+use crate::model::structured::OutputSchema;
+use crate::model::{
+    AgentModelClient, AnthropicClient, ChatGPTClient, CompletionClient, CustomClient, GeminiClient,
+    GlmClient, KimiClient, MiniMaxClient, ModalClient, ModelError,
+};
+use crate::policy::redaction;
+
+/// A configured provider client used only for plain completions. Secrets in
+/// the prompt are masked before it is sent or recorded to a cassette.
+pub enum CompletionModel {
+    MiniMax(MiniMaxClient),
+    Kimi(KimiClient),
+    Glm(GlmClient),
+    Modal(ModalClient),
+    Gemini(GeminiClient),
+    ChatGPT(ChatGPTClient),
+    Anthropic(AnthropicClient),
+    Custom(CustomClient),
+    Replay(ReplayClient),
+}
+
+impl CompletionModel {
+    /// Build the client for a provider id, with the same aliases and MiniMax
+    /// default as the worker. For ChatGPT and custom endpoints `api_key` is the
+    /// JSON payload produced by `load_provider_config`.
+    pub fn from_provider(
+        provider: &str,
+        api_key: &str,
+        model: Option<&str>,
+        base_url: Option<&str>,
+    ) -> Self {
+        let api_key = api_key.to_string();
+        let model = model.map(String::from);
+        let base_url = base_url.map(String::from);
+        match provider {
+            "kimi" => Self::Kimi(KimiClient::new(api_key, model, base_url)),
+            "zhipu" | "glm" => Self::Glm(GlmClient::new(api_key, model, base_url)),
+            "modal" => Self::Modal(ModalClient::new(api_key, model, base_url)),
+            "gemini" => Self::Gemini(GeminiClient::new(api_key, model, base_url)),
+            "openai-chatgpt" | "chatgpt" => {
+                Self::ChatGPT(ChatGPTClient::from_api_key_payload(api_key, model))
+            }
+            "anthropic" | "claude" => {
+                Self::Anthropic(AnthropicClient::new(api_key, model, base_url))
+            }
+            "custom" => Self::Custom(CustomClient::from_api_key_payload(api_key, model, base_url)),
+            cassette::REPLAY_PROVIDER => Self::Replay(ReplayClient::open(api_key, model)),
+            _ => Self::MiniMax(MiniMaxClient::new_with_base_url(api_key, model, base_url)),
+        }
+    }
+
+    fn model_id(&self) -> String {
+        match self {
+            Self::MiniMax(client) => client.model_id(),
+            Self::Kimi(client) => client.model_id(),
+            Self::Glm(client) => client.model_id(),
+            Self::Modal(client) => client.model_id(),
+            Self::Gemini(client) => client.model_id(),
+            Self::ChatGPT(client) => client.model_id(),
+            Self::Anthropic(client) => client.model_id(),
+            Self::Custom(client) => client.model_id(),
+            Self::Replay(client) => client.model_id(),
+        }
+    }
+
+    /// Append a live completion to the active cassette when recording.
+    fn record(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: Option<&OutputSchema>,
+        result: &Result<String, ModelError>,
+    ) {
+        if matches!(self, Self::Replay(_)) {
+            return;
+        }
+        if let Some(recorder) = cassette::active_recorder() {
+            recorder.record_completion(&self.model_id(), system, conversation, schema, result);
+        }
+    }
+}
+
+fn redact_prompt(system: &str, conversation: &str) -> (String, String) {
+    (
+        redaction::redact_text(system).0,
+        redaction::redact_text(conversation).0,
+    )
+}
+
+impl CompletionClient for CompletionModel {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        let (system, conversation) = redact_prompt(system, conversation);
+        let (system, conversation) = (system.as_str(), conversation.as_str());
+        let result = match self {
+            Self::MiniMax(client) => client.complete(system, conversation, max_tokens).await,
+            Self::Kimi(client) => client.complete(system, conversation, max_tokens).await,
+            Self::Glm(client) => client.complete(system, conversation, max_tokens).await,
+            Self::Modal(client) => client.complete(system, conversation, max_tokens).await,
+            Self::Gemini(client) => client.complete(system, conversation, max_tokens).await,
+            Self::ChatGPT(client) => client.complete(system, conversation, max_tokens).await,
+            Self::Anthropic(client) => client.complete(system, conversation, max_tokens).await,
+            Self::Custom(client) => client.complete(system, conversation, max_tokens).await,
+            Self::Replay(client) => client.complete(system, conversation, max_tokens).await,
+        };
+        self.record(system, conversation, None, &result);
+        result
+    }
+
+    async fn complete_json(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: &OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        let (system, conversation) = redact_prompt(system, conversation);
+        let (system, conversation) = (system.as_str(), conversation.as_str());
+        let result = match self {
+            Self::MiniMax(client) => {
+                CompletionClient::complete_json(client, system, conversation, schema, max_tokens)
+                    .await
+            }
+            Self::Kimi(client) => {
+                CompletionClient::complete_json(client, system, conversation, schema, max_tokens)
+                    .await
+            }
+            Self::Glm(client) => {
+                CompletionClient::complete_json(client, system, conversation, schema, max_tokens)
+                    .await
+            }
+            Self::Modal(client) => {
+                CompletionClient::complete_json(client, system, conversation, schema, max_tokens)
+                    .await
+            }
+            Self::Gemini(client) => {
+                CompletionClient::complete_json(client, system, conversation, schema, max_tokens)
+                    .await
+            }
+            Self::ChatGPT(client) => {
+                CompletionClient::complete_json(client, system, conversation, schema, max_tokens)
+                    .await
+            }
+            Self::Anthropic(client) => {
+                CompletionClient::complete_json(client, system, conversation, schema, max_tokens)
+                    .await
+            }
+            Self::Custom(client) => {
+                CompletionClient::complete_json(client, system, conversation, schema, max_tokens)
+                    .await
+            }
+            Self::Replay(client) => {
+                CompletionClient::complete_json(client, system, conversation, schema, max_tokens)
+                    .await
+            }
+        };
+        self.record(system, conversation, Some(schema), &result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn provider_aliases_select_matching_client() {
+        let cases = [
+            ("kimi", "Kimi"),
+            ("glm", "Glm"),
+            ("zhipu", "Glm"),
+            ("chatgpt", "ChatGPT"),
+            ("openai-chatgpt", "ChatGPT"),
+            ("claude", "Anthropic"),
+            ("custom", "Custom"),
+            ("replay", "Replay"),
+            ("unknown", "MiniMax"),
+        ];
+        for (provider, expected) in cases {
+            let variant = match CompletionModel::from_provider(provider, "key", None, None) {
+                CompletionModel::MiniMax(_) => "MiniMax",
+                CompletionModel::Kimi(_) => "Kimi",
+                CompletionModel::Glm(_) => "Glm",
+                CompletionModel::Modal(_) => "Modal",
+                CompletionModel::Gemini(_) => "Gemini",
+                CompletionModel::ChatGPT(_) => "ChatGPT",
+                CompletionModel::Anthropic(_) => "Anthropic",
+                CompletionModel::Custom(_) => "Custom",
+                CompletionModel::Replay(_) => "Replay",
+            };
+            assert_eq!(variant, expected, "provider {provider}");
+        }
+    }
+
+    #[tokio::test]
+    async fn completes_through_provider_agnostic_client() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v1/chat/completions")
+                .body_contains("Summarize this");
+            then.status(200).json_body(json!({
+                "choices": [{"message": {"content": "A short summary."}}]
+            }));
+        });
+
+        let model = CompletionModel::from_provider(
+            "kimi",
+            "sk-test",
+            Some("kimi-k2.5"),
+            Some(&format!("{}/v1", server.base_url())),
+        );
+        let text = CompletionClient::complete(&model, "Summarize this", "conversation", 256)
+            .await
+            .expect("completion");
+
+        mock.assert();
+        assert_eq!(text, "A short summary.");
+    }
+}
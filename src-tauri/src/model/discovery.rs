@@ -0,0 +1,375 @@
+//! Model discovery from provider model-listing endpoints.
+//!
+//! The static `ModelCatalog` knows context windows, output limits and
+//! capabilities, but not which models a vendor currently serves. Providers
+//! with a listing endpoint (`GET /models` for OpenAI-compatible APIs, Gemini
+//! and Anthropic) are queried here. The listing is cached per provider and
+//! merged into the static entries: listed-but-unknown models are added with
+//! conservative limits, and known models the provider no longer lists are
+//! flagged as deprecated.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::catalog::{ModelInfo, ProviderEntry};
+use crate::model::{
+    AnthropicClient, CustomClient, GeminiClient, GlmClient, KimiClient, ModalClient, ModelError,
+    ProviderId,
+};
+
+/// How long a cached model listing is trusted before it is fetched again.
+pub const MODEL_LIST_TTL_HOURS: i64 = 24;
+
+/// Limits assumed for listed models the static catalog knows nothing about.
+const UNKNOWN_CONTEXT_WINDOW: u32 = 32_768;
+const UNKNOWN_OUTPUT_LIMIT: u32 = 8_192;
+
+/// One model as reported by a provider's listing endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveredModel {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_limit: Option<u32>,
+}
+
+/// A provider's model listing as stored in the `settings` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedModelList {
+    pub fetched_at: String,
+    pub models: Vec<DiscoveredModel>,
+}
+
+impl CachedModelList {
+    pub fn new(models: Vec<DiscoveredModel>) -> Self {
+        Self {
+            fetched_at: Utc::now().to_rfc3339(),
+            models,
+        }
+    }
+
+    /// Whether the listing is younger than `MODEL_LIST_TTL_HOURS`.
+    pub fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        DateTime::parse_from_rfc3339(&self.fetched_at)
+            .map(|at| now - at.with_timezone(&Utc) < Duration::hours(MODEL_LIST_TTL_HOURS))
+            .unwrap_or(false)
+    }
+}
+
+/// Query a provider's model listing.
+///
+/// Returns `Ok(None)` for providers without a usable listing endpoint
+/// (MiniMax, and the ChatGPT subscription backend); their static entries are
+/// used as-is.
+pub async fn discover_models(
+    provider: ProviderId,
+    api_key: &str,
+    base_url: Option<&str>,
+) -> Result<Option<Vec<DiscoveredModel>>, ModelError> {
+    let api_key = api_key.to_string();
+    let base_url = base_url.map(String::from);
+    let models = match provider {
+        ProviderId::Kimi => {
+            KimiClient::new(api_key, None, base_url)
+                .list_models()
+                .await?
+        }
+        ProviderId::Zhipu => {
+            GlmClient::new(api_key, None, base_url)
+                .list_models()
+                .await?
+        }
+        ProviderId::Modal => {
+            ModalClient::new(api_key, None, base_url)
+                .list_models()
+                .await?
+        }
+        ProviderId::Gemini => {
+            GeminiClient::new(api_key, None, base_url)
+                .list_models()
+                .await?
+        }
+        ProviderId::Anthropic => {
+            AnthropicClient::new(api_key, None, base_url)
+                .list_models()
+                .await?
+        }
+        ProviderId::Custom => {
+            CustomClient::from_api_key_payload(api_key, None, base_url)
+                .list_models()
+                .await?
+        }
+        ProviderId::MiniMax | ProviderId::OpenAIChatGPT => return Ok(None),
+    };
+    Ok(Some(models))
+}
+
+/// `id` without a trailing `-YYYYMMDD` snapshot date. Anthropic lists only
+/// dated IDs, while the catalog uses the undated aliases.
+fn undated(id: &str) -> &str {
+    match id.rsplit_once('-') {
+        Some((alias, date)) if date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()) => alias,
+        _ => id,
+    }
+}
+
+/// Merge a provider listing into its static catalog entry.
+///
+/// Static metadata wins for models it knows, also when they are listed under
+/// a dated snapshot ID. Static models missing from the listing are marked
+/// deprecated, pointing at `default_model` when the provider still lists it.
+/// An empty listing is treated as unreliable and leaves the entry untouched.
+pub fn merge_discovered(
+    mut entry: ProviderEntry,
+    discovered: &[DiscoveredModel],
+    default_model: &str,
+) -> ProviderEntry {
+    if discovered.is_empty() {
+        return entry;
+    }
+
+    let listed = |name: &str| {
+        discovered
+            .iter()
+            .any(|model| model.id == name || undated(&model.id) == name)
+    };
+    let alternative = listed(default_model).then(|| default_model.to_string());
+
+    for model in &mut entry.models {
+        if !model.deprecated && !listed(&model.name) {
+            model.deprecated = true;
+            model.deprecation_reason = Some(format!("No longer listed by {}", entry.provider));
+            model.suggested_alternative = alternative
+                .clone()
+                .filter(|alternative| *alternative != model.name);
+        }
+    }
+
+    for model in discovered {
+        if entry
+            .models
+            .iter()
+            .any(|known| known.name == model.id || known.name == undated(&model.id))
+        {
+            continue;
+        }
+        entry.models.push(ModelInfo {
+            name: model.id.clone(),
+            context_window: model.context_window.unwrap_or(UNKNOWN_CONTEXT_WINDOW),
+            output_limit: model.output_limit.unwrap_or(UNKNOWN_OUTPUT_LIMIT),
+            description: model
+                .display_name
+                .clone()
+                .unwrap_or_else(|| format!("Discovered from {}", entry.provider)),
+            deprecated: false,
+            deprecation_reason: None,
+            suggested_alternative: None,
+            capabilities: vec!["text".to_string()],
+        });
+    }
+
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    fn static_model(name: &str) -> ModelInfo {
+        ModelInfo {
+            name: name.to_string(),
+            context_window: 256_000,
+            output_limit: 65_536,
+            description: format!("{name} model"),
+            deprecated: false,
+            deprecation_reason: None,
+            suggested_alternative: None,
+            capabilities: vec!["text".to_string(), "function_calling".to_string()],
+        }
+    }
+
+    fn listed(id: &str, context_window: Option<u32>) -> DiscoveredModel {
+        DiscoveredModel {
+            id: id.to_string(),
+            display_name: None,
+            context_window,
+            output_limit: None,
+        }
+    }
+
+    #[test]
+    fn merge_keeps_static_metadata_adds_new_and_flags_missing() {
+        let entry = ProviderEntry {
+            provider: "kimi".to_string(),
+            models: vec![static_model("kimi-k2.5"), static_model("kimi-k2-thinking")],
+        };
+        let discovered = vec![
+            listed("kimi-k2.5", Some(128_000)),
+            listed("kimi-k3", Some(512_000)),
+        ];
+
+        let merged = merge_discovered(entry, &discovered, "kimi-k2.5");
+
+        let current = &merged.models[0];
+        assert_eq!(current.context_window, 256_000);
+        assert!(!current.deprecated);
+
+        let retired = &merged.models[1];
+        assert!(retired.deprecated);
+        assert_eq!(retired.suggested_alternative.as_deref(), Some("kimi-k2.5"));
+
+        let added = &merged.models[2];
+        assert_eq!(added.name, "kimi-k3");
+        assert_eq!(added.context_window, 512_000);
+        assert_eq!(added.output_limit, UNKNOWN_OUTPUT_LIMIT);
+        assert_eq!(added.capabilities, vec!["text".to_string()]);
+    }
+
+    #[test]
+    fn merge_matches_aliases_against_dated_ids() {
+        let entry = ProviderEntry {
+            provider: "anthropic".to_string(),
+            models: vec![
+                static_model("claude-sonnet-4-5"),
+                static_model("claude-haiku-4-5"),
+                static_model("claude-opus-4-1"),
+            ],
+        };
+        let discovered = vec![
+            listed("claude-sonnet-4-5-20250929", None),
+            listed("claude-haiku-4-5-20251001", None),
+        ];
+
+        let merged = merge_discovered(entry, &discovered, "claude-sonnet-4-5");
+
+        assert_eq!(merged.models.len(), 3);
+        assert!(!merged.models[0].deprecated);
+        assert!(!merged.models[1].deprecated);
+        assert!(merged.models[2].deprecated);
+        assert_eq!(
+            merged.models[2].suggested_alternative.as_deref(),
+            Some("claude-sonnet-4-5")
+        );
+    }
+
+    #[test]
+    fn empty_listing_leaves_entry_untouched() {
+        let entry = ProviderEntry {
+            provider: "zhipu".to_string(),
+            models: vec![static_model("glm-5")],
+        };
+        let merged = merge_discovered(entry, &[], "glm-5");
+        assert_eq!(merged.models.len(), 1);
+        assert!(!merged.models[0].deprecated);
+    }
+
+    #[test]
+    fn cached_listing_expires_after_ttl() {
+        let cached = CachedModelList::new(vec![listed("glm-5", None)]);
+        let now = Utc::now();
+        assert!(cached.is_fresh(now));
+        assert!(!cached.is_fresh(now + Duration::hours(MODEL_LIST_TTL_HOURS + 1)));
+
+        let corrupt = CachedModelList {
+            fetched_at: "yesterday".to_string(),
+            models: Vec::new(),
+        };
+        assert!(!corrupt.is_fresh(now));
+    }
+
+    #[tokio::test]
+    async fn lists_openai_compatible_models_with_context_hints() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v1/models")
+                .header("Authorization", "Bearer sk-test");
+            then.status(200).json_body(json!({
+                "object": "list",
+                "data": [
+                    {"id": "qwen3-coder", "object": "model", "max_model_len": 262144},
+                    {"id": "llama3.1", "object": "model"}
+                ]
+            }));
+        });
+
+        let models = discover_models(
+            ProviderId::Custom,
+            "sk-test",
+            Some(&format!("{}/v1", server.base_url())),
+        )
+        .await
+        .expect("listing")
+        .expect("custom endpoints support listing");
+
+        mock.assert();
+        assert_eq!(
+            models,
+            vec![
+                listed("qwen3-coder", Some(262_144)),
+                listed("llama3.1", None)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn follows_anthropic_pagination() {
+        let server = MockServer::start();
+        let first = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v1/models")
+                .header("x-api-key", "sk-ant-test")
+                .matches(|req| {
+                    !req.query_params
+                        .as_ref()
+                        .is_some_and(|params| params.iter().any(|(key, _)| key == "after_id"))
+                });
+            then.status(200).json_body(json!({
+                "data": [{"id": "claude-sonnet-4-5-20250929", "display_name": "Claude Sonnet 4.5"}],
+                "has_more": true,
+                "last_id": "claude-sonnet-4-5-20250929"
+            }));
+        });
+        let second = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v1/models")
+                .query_param("after_id", "claude-sonnet-4-5-20250929");
+            then.status(200).json_body(json!({
+                "data": [{"id": "claude-haiku-4-5-20251001"}],
+                "has_more": false,
+                "last_id": "claude-haiku-4-5-20251001"
+            }));
+        });
+
+        let models = discover_models(
+            ProviderId::Anthropic,
+            "sk-ant-test",
+            Some(&format!("{}/v1", server.base_url())),
+        )
+        .await
+        .expect("listing")
+        .expect("anthropic supports listing");
+
+        first.assert();
+        second.assert();
+        let ids: Vec<&str> = models.iter().map(|model| model.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["claude-sonnet-4-5-20250929", "claude-haiku-4-5-20251001"]
+        );
+        assert_eq!(models[0].display_name.as_deref(), Some("Claude Sonnet 4.5"));
+    }
+
+    #[tokio::test]
+    async fn providers_without_listing_report_none() {
+        let models = discover_models(ProviderId::MiniMax, "key", None)
+            .await
+            .expect("no request is made");
+        assert!(models.is_none());
+    }
+}
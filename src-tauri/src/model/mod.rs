@@ -5,9 +5,14 @@
 //! ## Structure
 //!
 //! - `types`: Core types (WorkerAction, WorkerDecision, etc.)
-//! - `traits`: Client trait definitions (AgentModelClient)
+//! - `traits`: Client trait definitions (AgentModelClient, CompletionClient)
 //! - `provider`: Provider ID enum and parsing
 //! - `catalog`: Model metadata and defaults
+//! - `cassette`: Record/replay of model transcripts for offline tests
+//! - `completion`: Provider-agnostic plain completion (summaries, suggestions)
+//! - `discovery`: Model listing from provider `/models` endpoints
+//! - `retry`: Shared retry/backoff policy for provider requests
+//! - `structured`: JSON-schema constrained output with validation and repair
 //! - `factory`: Client construction
 //! - `prompts`: System prompt builders
 //! - `sanitize`: Output text utilities
@@ -17,8 +22,13 @@
 // Core types and traits
 mod shared;
 
+pub mod cassette;
 pub mod catalog;
+pub mod completion;
+pub mod discovery;
 pub mod provider;
+pub mod retry;
+pub mod structured;
 pub mod traits;
 pub mod types;
 
@@ -27,15 +37,19 @@ pub mod providers;
 
 // Re-export commonly used types
 pub use catalog::ModelCatalog;
+pub use completion::CompletionModel;
 pub use provider::ProviderId;
 pub use shared::strip_tool_call_markup;
-pub use traits::AgentModelClient;
+pub use traits::{AgentModelClient, CompletionClient};
 pub use types::{
-    ModelError, StreamDelta, WorkerAction, WorkerActionRequest, WorkerDecision, WorkerToolCall,
+    ModelError, StreamDelta, TokenUsage, WorkerAction, WorkerActionRequest, WorkerDecision,
+    WorkerToolCall,
 };
 
 // Re-export provider clients for convenience
+pub use providers::anthropic::AnthropicClient;
 pub use providers::chatgpt::ChatGPTClient;
+pub use providers::custom::CustomClient;
 pub use providers::gemini::GeminiClient;
 pub use providers::glm::GlmClient;
 pub use providers::kimi::KimiClient;
@@ -12,6 +12,9 @@ pub enum ProviderId {
     Modal,
     OpenAIChatGPT,
     Gemini,
+    Anthropic,
+    /// User-configured OpenAI-compatible endpoint (Ollama, vLLM, gateways).
+    Custom,
 }
 
 impl ProviderId {
@@ -23,6 +26,8 @@ impl ProviderId {
             ProviderId::Modal => "modal",
             ProviderId::OpenAIChatGPT => "openai-chatgpt",
             ProviderId::Gemini => "gemini",
+            ProviderId::Anthropic => "anthropic",
+            ProviderId::Custom => "custom",
         }
     }
 
@@ -34,6 +39,8 @@ impl ProviderId {
             ProviderId::Modal,
             ProviderId::OpenAIChatGPT,
             ProviderId::Gemini,
+            ProviderId::Anthropic,
+            ProviderId::Custom,
         ]
     }
 }
@@ -55,6 +62,8 @@ impl FromStr for ProviderId {
             "modal" => Ok(ProviderId::Modal),
             "openai-chatgpt" | "chatgpt" | "openai" => Ok(ProviderId::OpenAIChatGPT),
             "gemini" => Ok(ProviderId::Gemini),
+            "anthropic" | "claude" => Ok(ProviderId::Anthropic),
+            "custom" => Ok(ProviderId::Custom),
             _ => Err(format!("unknown provider: {}", s)),
         }
     }
@@ -125,6 +134,15 @@ mod tests {
         );
         assert_eq!(ProviderId::from_str("gemini").unwrap(), ProviderId::Gemini);
         assert_eq!(ProviderId::from_str("GEMINI").unwrap(), ProviderId::Gemini);
+        assert_eq!(
+            ProviderId::from_str("anthropic").unwrap(),
+            ProviderId::Anthropic
+        );
+        assert_eq!(
+            ProviderId::from_str("claude").unwrap(),
+            ProviderId::Anthropic
+        );
+        assert_eq!(ProviderId::from_str("custom").unwrap(), ProviderId::Custom);
         assert!(ProviderId::from_str("unknown").is_err());
     }
 
@@ -138,6 +156,14 @@ mod tests {
         assert_eq!(provider, Some(ProviderId::MiniMax));
         assert_eq!(model, "MiniMax-M2.1");
 
+        let (provider, model) = parse_model_override("anthropic/claude-sonnet-4-5");
+        assert_eq!(provider, Some(ProviderId::Anthropic));
+        assert_eq!(model, "claude-sonnet-4-5");
+
+        let (provider, model) = parse_model_override("custom/qwen3:32b");
+        assert_eq!(provider, Some(ProviderId::Custom));
+        assert_eq!(model, "qwen3:32b");
+
         let (provider, model) = parse_model_override("MiniMax-M2.1");
         assert_eq!(provider, None);
         assert_eq!(model, "MiniMax-M2.1");
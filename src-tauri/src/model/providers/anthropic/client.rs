@@ -0,0 +1,938 @@
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::core::tool::ToolDescriptor;
+use crate::model::catalog::ModelCatalog;
+use crate::model::discovery::DiscoveredModel;
+use crate::model::retry::{self, send_with_retry, RetryPolicy};
+use crate::model::shared::{
+    completion_summary_from_content_or_reasoning, parse_token_usage, preferred_response_text,
+    worker_prompt_from_request,
+};
+use crate::model::structured::OutputSchema;
+use crate::model::{
+    AgentModelClient, CompletionClient, ModelError, ProviderId, StreamDelta, TokenUsage,
+    WorkerAction, WorkerActionRequest, WorkerDecision, WorkerToolCall,
+};
+use crate::runtime::plan_mode_settings::WORKER_MAX_TOKENS;
+
+const DEFAULT_ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com/v1";
+const DEFAULT_ANTHROPIC_MODEL: &str = "claude-sonnet-4-5";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Output cap for models missing from the catalog; current Claude models accept 64k.
+const DEFAULT_OUTPUT_LIMIT: u32 = 64_000;
+/// Extended-thinking budget for worker decisions. The API requires at least
+/// 1024 tokens and strictly less than `max_tokens`.
+const THINKING_BUDGET_TOKENS: u32 = 4_096;
+const MIN_THINKING_BUDGET_TOKENS: u32 = 1_024;
+
+/// Anthropic tool names must match `^[a-zA-Z0-9_-]{1,128}$`; dots and MCP
+/// separators are replaced with underscores.
+fn tool_name_to_anthropic(name: &str) -> String {
+    name.chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '_' || ch == '-' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Resolve a sanitised tool name back to its canonical form via the descriptor list.
+fn tool_name_from_anthropic(name: &str, descriptors: &[ToolDescriptor]) -> String {
+    descriptors
+        .iter()
+        .find(|d| tool_name_to_anthropic(&d.name) == name)
+        .map(|d| d.name.clone())
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Thinking budget for a request, or `None` when `max_tokens` leaves no room for it.
+fn thinking_budget_for(max_tokens: u32) -> Option<u32> {
+    let budget = THINKING_BUDGET_TOKENS.min(max_tokens / 2);
+    (budget >= MIN_THINKING_BUDGET_TOKENS).then_some(budget)
+}
+
+#[derive(Debug, Clone)]
+pub struct AnthropicClient {
+    api_key: String,
+    model: String,
+    base_url: String,
+    client: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl AnthropicClient {
+    pub fn new(api_key: String, model: Option<String>, base_url: Option<String>) -> Self {
+        Self {
+            api_key,
+            model: model.unwrap_or_else(|| DEFAULT_ANTHROPIC_MODEL.to_string()),
+            base_url: base_url.unwrap_or_else(|| DEFAULT_ANTHROPIC_BASE_URL.to_string()),
+            client: reqwest::Client::new(),
+            retry: retry::policy_for(ProviderId::Anthropic),
+        }
+    }
+
+    pub fn model_id(&self) -> String {
+        self.model.clone()
+    }
+
+    /// Lists available models, following `has_more` pagination.
+    pub async fn list_models(&self) -> Result<Vec<DiscoveredModel>, ModelError> {
+        let endpoint = format!("{}/models", self.base_url.trim_end_matches('/'));
+        let mut models = Vec::new();
+        let mut after_id: Option<String> = None;
+
+        loop {
+            let mut request = self
+                .client
+                .get(&endpoint)
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION)
+                .query(&[("limit", "1000")]);
+            if let Some(after) = after_id.as_deref() {
+                request = request.query(&[("after_id", after)]);
+            }
+
+            let response = send_with_retry(&self.retry, "Anthropic", request).await?;
+            let status = response.status();
+            let text = response
+                .text()
+                .await
+                .map_err(|e| ModelError::Request(e.to_string()))?;
+            if status.as_u16() == 401 || status.as_u16() == 403 {
+                return Err(ModelError::Auth(format!(
+                    "Anthropic auth failed ({status}) listing models. Check ANTHROPIC_API_KEY."
+                )));
+            }
+            if !status.is_success() {
+                return Err(ModelError::Request(format!(
+                    "Anthropic model listing error {status}: {text}"
+                )));
+            }
+
+            let page: AnthropicModelList = serde_json::from_str(&text).map_err(|e| {
+                ModelError::InvalidResponse(format!("Anthropic model listing parse failed: {e}"))
+            })?;
+            models.extend(page.data.into_iter().map(|model| DiscoveredModel {
+                id: model.id,
+                display_name: model.display_name,
+                context_window: None,
+                output_limit: None,
+            }));
+
+            match page.last_id.filter(|_| page.has_more) {
+                Some(last_id) => after_id = Some(last_id),
+                None => return Ok(models),
+            }
+        }
+    }
+
+    pub async fn complete(
+        &self,
+        system: &str,
+        user: &str,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        let response = self
+            .run_messages(system, user, max_tokens, &[], false, None)
+            .await?;
+        Ok(preferred_response_text(response.content, response.thinking))
+    }
+
+    /// Structured output by forcing a call to a single tool whose input schema
+    /// is the requested schema; the tool input is the result.
+    pub async fn complete_json(
+        &self,
+        system: &str,
+        user: &str,
+        schema: &OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        let tool = ToolDescriptor {
+            name: schema.name.clone(),
+            description: schema.description.clone(),
+            input_schema: schema.schema.clone(),
+            output_schema: None,
+        };
+        let max_tokens = self.output_tokens(max_tokens);
+        let mut body = self.build_request(system, user, max_tokens, &[tool], false, None);
+        body.tool_choice = Some(serde_json::json!({
+            "type": "tool",
+            "name": tool_name_to_anthropic(&schema.name),
+        }));
+
+        let response = self.send_messages(&body, None).await?;
+        match response.tool_calls.into_iter().next() {
+            Some(call) => Ok(call.input.to_string()),
+            // Fall back to whatever text came back; the caller validates it.
+            None => Ok(response.content.unwrap_or_default()),
+        }
+    }
+
+    /// `requested` capped at the model's output limit; the API rejects a
+    /// larger `max_tokens` outright.
+    fn output_tokens(&self, requested: u32) -> u32 {
+        let limit = ModelCatalog::output_limit_for(ProviderId::Anthropic, &self.model)
+            .unwrap_or(DEFAULT_OUTPUT_LIMIT);
+        requested.min(limit)
+    }
+
+    fn build_request(
+        &self,
+        system: &str,
+        user: &str,
+        max_tokens: u32,
+        tools: &[ToolDescriptor],
+        stream: bool,
+        thinking_budget: Option<u32>,
+    ) -> AnthropicMessagesRequest {
+        let tools = if tools.is_empty() {
+            None
+        } else {
+            Some(
+                tools
+                    .iter()
+                    .map(|d| AnthropicTool {
+                        name: tool_name_to_anthropic(&d.name),
+                        description: d.description.clone(),
+                        input_schema: d.input_schema.clone(),
+                    })
+                    .collect(),
+            )
+        };
+
+        AnthropicMessagesRequest {
+            model: self.model.clone(),
+            max_tokens,
+            system: if system.is_empty() {
+                None
+            } else {
+                Some(system.to_string())
+            },
+            messages: vec![AnthropicMessage {
+                role: "user".to_string(),
+                content: user.to_string(),
+            }],
+            tools,
+            tool_choice: None,
+            stream,
+            // Extended thinking only accepts the default temperature.
+            temperature: if thinking_budget.is_some() {
+                None
+            } else {
+                Some(0.1)
+            },
+            thinking: thinking_budget.map(|budget_tokens| AnthropicThinkingConfig {
+                type_: "enabled".to_string(),
+                budget_tokens,
+            }),
+        }
+    }
+
+    async fn run_messages(
+        &self,
+        system: &str,
+        user: &str,
+        max_tokens: u32,
+        tools: &[ToolDescriptor],
+        stream: bool,
+        on_delta: Option<&mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send)>,
+    ) -> Result<AnthropicResponseMessage, ModelError> {
+        let max_tokens = self.output_tokens(max_tokens);
+        let thinking_budget = if stream {
+            thinking_budget_for(max_tokens)
+        } else {
+            None
+        };
+        let body = self.build_request(system, user, max_tokens, tools, stream, thinking_budget);
+        self.send_messages(&body, on_delta).await
+    }
+
+    async fn send_messages(
+        &self,
+        body: &AnthropicMessagesRequest,
+        on_delta: Option<&mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send)>,
+    ) -> Result<AnthropicResponseMessage, ModelError> {
+        let endpoint = format!("{}/messages", self.base_url.trim_end_matches('/'));
+        let stream = body.stream;
+        let mut request = self
+            .client
+            .post(&endpoint)
+            .header("Content-Type", "application/json")
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(body);
+
+        if stream {
+            request = request.header("Accept", "text/event-stream");
+        }
+
+        let response = send_with_retry(&self.retry, "Anthropic", request).await?;
+
+        let status = response.status();
+
+        if status.as_u16() == 401 || status.as_u16() == 403 {
+            let text = response.text().await.unwrap_or_default();
+            return Err(ModelError::Auth(format!(
+                "Anthropic auth failed ({status}). Check ANTHROPIC_API_KEY and account access. Response: {text}"
+            )));
+        }
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(ModelError::Request(format!(
+                "Anthropic error {status}: {text}"
+            )));
+        }
+
+        if stream {
+            self.handle_streaming_response(response, on_delta).await
+        } else {
+            let text = response
+                .text()
+                .await
+                .map_err(|e| ModelError::Request(e.to_string()))?;
+
+            tracing::debug!("Anthropic API response: status={}, body={}", status, text);
+
+            let parsed: AnthropicMessagesResponse = serde_json::from_str(&text)
+                .map_err(|e| ModelError::InvalidResponse(format!("Anthropic parse failed: {e}")))?;
+
+            Ok(parse_response(parsed))
+        }
+    }
+
+    async fn handle_streaming_response(
+        &self,
+        response: reqwest::Response,
+        mut on_delta: Option<&mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send)>,
+    ) -> Result<AnthropicResponseMessage, ModelError> {
+        let mut state = AnthropicStreamState::default();
+        let mut stream = response.bytes_stream();
+        let mut buffer = String::new();
+
+        'outer: while let Some(chunk) = stream.next().await {
+            let bytes = chunk.map_err(|e| ModelError::Request(e.to_string()))?;
+            buffer.push_str(&String::from_utf8_lossy(&bytes));
+
+            while let Some(newline_idx) = buffer.find('\n') {
+                let line = buffer[..newline_idx].trim_end_matches('\r').to_string();
+                buffer.drain(..=newline_idx);
+
+                if state.process_line(&line, &mut on_delta)? {
+                    break 'outer;
+                }
+            }
+        }
+
+        if !buffer.trim().is_empty() {
+            state.process_line(buffer.trim_end_matches('\r'), &mut on_delta)?;
+        }
+
+        Ok(state.finish())
+    }
+
+    pub async fn decide_action_streaming<F>(
+        &self,
+        req: WorkerActionRequest,
+        mut on_delta: F,
+    ) -> Result<WorkerDecision, ModelError>
+    where
+        F: FnMut(StreamDelta) -> Result<(), String> + Send,
+    {
+        let (system, user) = worker_prompt_from_request(&req)?;
+        let max_tokens = req.max_tokens.unwrap_or(WORKER_MAX_TOKENS);
+        let on_delta: &mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send) = &mut on_delta;
+
+        let response = self
+            .run_messages(
+                &system,
+                &user,
+                max_tokens,
+                &req.tool_descriptors,
+                true,
+                Some(on_delta),
+            )
+            .await?;
+
+        tracing::debug!(
+            "Anthropic worker response - content: {:?}, tool_calls: {:?}, stop_reason: {:?}",
+            response.content,
+            response.tool_calls,
+            response.stop_reason
+        );
+
+        Ok(anthropic_response_to_worker_decision(
+            response,
+            &req.tool_descriptors,
+        ))
+    }
+}
+
+impl AgentModelClient for AnthropicClient {
+    fn model_id(&self) -> String {
+        self.model.clone()
+    }
+
+    async fn decide_action(&self, req: WorkerActionRequest) -> Result<WorkerDecision, ModelError> {
+        let noop = |_delta: StreamDelta| Ok::<(), String>(());
+        self.decide_action_streaming(req, noop).await
+    }
+}
+
+impl CompletionClient for AnthropicClient {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.complete(system, conversation, max_tokens).await
+    }
+
+    async fn complete_json(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: &OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.complete_json(system, conversation, schema, max_tokens)
+            .await
+    }
+}
+
+fn parse_response(response: AnthropicMessagesResponse) -> AnthropicResponseMessage {
+    let mut content = String::new();
+    let mut thinking = String::new();
+    let mut tool_calls = Vec::new();
+
+    for block in response.content {
+        match block {
+            AnthropicContentBlock::Text { text } => content.push_str(&text),
+            AnthropicContentBlock::Thinking { thinking: text } => thinking.push_str(&text),
+            AnthropicContentBlock::ToolUse { id, name, input } => {
+                tool_calls.push(AnthropicToolCall { id, name, input })
+            }
+            AnthropicContentBlock::Other => {}
+        }
+    }
+
+    AnthropicResponseMessage {
+        content: (!content.is_empty()).then_some(content),
+        thinking: (!thinking.is_empty()).then_some(thinking),
+        tool_calls,
+        stop_reason: response.stop_reason,
+        usage: response.usage.as_ref().and_then(parse_token_usage),
+    }
+}
+
+fn anthropic_response_to_worker_decision(
+    response: AnthropicResponseMessage,
+    tool_descriptors: &[ToolDescriptor],
+) -> WorkerDecision {
+    let raw_response = serde_json::to_string(&response).ok();
+
+    if !response.tool_calls.is_empty() {
+        let calls = response
+            .tool_calls
+            .into_iter()
+            .map(|call| WorkerToolCall {
+                tool_name: tool_name_from_anthropic(&call.name, tool_descriptors),
+                tool_args: call.input,
+                rationale: None,
+            })
+            .collect();
+
+        return WorkerDecision {
+            action: WorkerAction::ToolCalls { calls },
+            reasoning: response.thinking,
+            raw_response,
+            usage: response.usage,
+        };
+    }
+
+    WorkerDecision {
+        action: WorkerAction::Complete {
+            summary: completion_summary_from_content_or_reasoning(
+                response.content,
+                response.thinking,
+            ),
+        },
+        reasoning: None,
+        raw_response,
+        usage: response.usage,
+    }
+}
+
+/// A content block being assembled from `content_block_*` stream events.
+#[derive(Debug, Default)]
+struct AnthropicBlockAccumulator {
+    kind: String,
+    tool_id: String,
+    tool_name: String,
+    input_json: String,
+}
+
+/// Accumulates a Messages API SSE stream into a single response.
+#[derive(Debug, Default)]
+struct AnthropicStreamState {
+    content: String,
+    thinking: String,
+    blocks: Vec<AnthropicBlockAccumulator>,
+    stop_reason: Option<String>,
+    usage: Option<TokenUsage>,
+}
+
+impl AnthropicStreamState {
+    /// Handle one SSE line. Returns `Ok(true)` once `message_stop` arrives.
+    fn process_line(
+        &mut self,
+        line: &str,
+        on_delta: &mut Option<&mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send)>,
+    ) -> Result<bool, ModelError> {
+        let trimmed = line.trim();
+        let Some(payload) = trimmed.strip_prefix("data:").map(str::trim) else {
+            // `event:` lines duplicate the `type` field of the data payload.
+            return Ok(false);
+        };
+        if payload.is_empty() {
+            return Ok(false);
+        }
+
+        let event: serde_json::Value = match serde_json::from_str(payload) {
+            Ok(v) => v,
+            Err(_) => return Ok(false),
+        };
+
+        let mut emit = |delta: StreamDelta| -> Result<(), ModelError> {
+            match on_delta.as_mut() {
+                Some(on_delta) => on_delta(delta).map_err(ModelError::Request),
+                None => Ok(()),
+            }
+        };
+
+        match event.get("type").and_then(|t| t.as_str()).unwrap_or("") {
+            "message_start" => {
+                self.usage = event
+                    .get("message")
+                    .and_then(|m| m.get("usage"))
+                    .and_then(parse_token_usage);
+            }
+            "content_block_start" => {
+                let index = event.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as usize;
+                if self.blocks.len() <= index {
+                    self.blocks
+                        .resize_with(index + 1, AnthropicBlockAccumulator::default);
+                }
+                let block = event.get("content_block");
+                let field = |key: &str| {
+                    block
+                        .and_then(|b| b.get(key))
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string()
+                };
+                let entry = &mut self.blocks[index];
+                entry.kind = field("type");
+                entry.tool_id = field("id");
+                entry.tool_name = field("name");
+
+                // Blocks may arrive pre-filled rather than through deltas.
+                match entry.kind.as_str() {
+                    "text" => {
+                        let text = field("text");
+                        if !text.is_empty() {
+                            self.content.push_str(&text);
+                            emit(StreamDelta::Content(text))?;
+                        }
+                    }
+                    "thinking" => {
+                        let text = field("thinking");
+                        if !text.is_empty() {
+                            self.thinking.push_str(&text);
+                            emit(StreamDelta::Reasoning(text))?;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_delta" => {
+                let index = event.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as usize;
+                let Some(delta) = event.get("delta") else {
+                    return Ok(false);
+                };
+                let text_of = |key: &str| {
+                    delta
+                        .get(key)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string()
+                };
+                match delta.get("type").and_then(|t| t.as_str()).unwrap_or("") {
+                    "text_delta" => {
+                        let text = text_of("text");
+                        if !text.is_empty() {
+                            self.content.push_str(&text);
+                            emit(StreamDelta::Content(text))?;
+                        }
+                    }
+                    "thinking_delta" => {
+                        let text = text_of("thinking");
+                        if !text.is_empty() {
+                            self.thinking.push_str(&text);
+                            emit(StreamDelta::Reasoning(text))?;
+                        }
+                    }
+                    "input_json_delta" => {
+                        if let Some(entry) = self.blocks.get_mut(index) {
+                            entry.input_json.push_str(&text_of("partial_json"));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "message_delta" => {
+                if let Some(reason) = event
+                    .get("delta")
+                    .and_then(|d| d.get("stop_reason"))
+                    .and_then(|r| r.as_str())
+                {
+                    self.stop_reason = Some(reason.to_string());
+                }
+                // `output_tokens` here is cumulative; input counts came with message_start.
+                if let Some(output) = event
+                    .get("usage")
+                    .and_then(|u| u.get("output_tokens"))
+                    .and_then(|v| v.as_u64())
+                {
+                    let usage = self.usage.get_or_insert_with(TokenUsage::default);
+                    usage.completion_tokens = output;
+                }
+            }
+            "message_stop" => return Ok(true),
+            "error" => {
+                let message = event
+                    .get("error")
+                    .and_then(|e| e.get("message"))
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("unknown stream error");
+                return Err(ModelError::Request(format!(
+                    "Anthropic stream error: {message}"
+                )));
+            }
+            _ => {}
+        }
+
+        Ok(false)
+    }
+
+    fn finish(self) -> AnthropicResponseMessage {
+        let tool_calls = self
+            .blocks
+            .into_iter()
+            .filter(|b| b.kind == "tool_use" && !b.tool_name.is_empty())
+            .map(|b| AnthropicToolCall {
+                id: b.tool_id,
+                name: b.tool_name,
+                input: if b.input_json.trim().is_empty() {
+                    serde_json::json!({})
+                } else {
+                    serde_json::from_str(&b.input_json).unwrap_or_else(|_| serde_json::json!({}))
+                },
+            })
+            .collect();
+
+        AnthropicResponseMessage {
+            content: (!self.content.is_empty()).then_some(self.content),
+            thinking: (!self.thinking.is_empty()).then_some(self.thinking),
+            tool_calls,
+            stop_reason: self.stop_reason,
+            usage: self.usage,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicMessagesRequest {
+    model: String,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<AnthropicMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<AnthropicTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<AnthropicThinkingConfig>,
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicTool {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicThinkingConfig {
+    #[serde(rename = "type")]
+    type_: String,
+    budget_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicModelList {
+    #[serde(default)]
+    data: Vec<AnthropicModelEntry>,
+    #[serde(default)]
+    has_more: bool,
+    #[serde(default)]
+    last_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicModelEntry {
+    id: String,
+    #[serde(default)]
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicMessagesResponse {
+    #[serde(default)]
+    content: Vec<AnthropicContentBlock>,
+    #[serde(default)]
+    stop_reason: Option<String>,
+    #[serde(default)]
+    usage: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicContentBlock {
+    Text {
+        text: String,
+    },
+    Thinking {
+        thinking: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        #[serde(default)]
+        input: serde_json::Value,
+    },
+    /// `redacted_thinking`, `server_tool_use` and future block types.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct AnthropicResponseMessage {
+    content: Option<String>,
+    thinking: Option<String>,
+    tool_calls: Vec<AnthropicToolCall>,
+    stop_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AnthropicToolCall {
+    id: String,
+    name: String,
+    input: serde_json::Value,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    fn descriptors() -> Vec<ToolDescriptor> {
+        vec![ToolDescriptor {
+            name: "fs.read".to_string(),
+            description: "Read a file".to_string(),
+            input_schema: json!({"type": "object", "properties": {"path": {"type": "string"}}}),
+            output_schema: None,
+        }]
+    }
+
+    fn sse(events: &[serde_json::Value]) -> String {
+        events
+            .iter()
+            .map(|event| {
+                format!(
+                    "event: {}\ndata: {}\n\n",
+                    event["type"].as_str().unwrap_or(""),
+                    event
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tool_names_round_trip_through_descriptor_lookup() {
+        let tools = vec![ToolDescriptor {
+            name: "mcp.github.search_issues".to_string(),
+            description: String::new(),
+            input_schema: json!({}),
+            output_schema: None,
+        }];
+        let wire = tool_name_to_anthropic(&tools[0].name);
+        assert_eq!(wire, "mcp_github_search_issues");
+        assert_eq!(
+            tool_name_from_anthropic(&wire, &tools),
+            "mcp.github.search_issues"
+        );
+    }
+
+    #[test]
+    fn thinking_budget_requires_room_in_max_tokens() {
+        assert_eq!(thinking_budget_for(32_000), Some(THINKING_BUDGET_TOKENS));
+        assert_eq!(thinking_budget_for(3_000), Some(1_500));
+        assert_eq!(thinking_budget_for(1_500), None);
+    }
+
+    #[tokio::test]
+    async fn streams_thinking_and_tool_use_blocks() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v1/messages")
+                .header("x-api-key", "sk-ant-test")
+                .header("anthropic-version", ANTHROPIC_VERSION)
+                .body_contains("\"name\":\"fs_read\"")
+                // The worker default is clamped to the model's output limit
+                .body_contains("\"max_tokens\":64000")
+                .body_contains("\"thinking\":{\"type\":\"enabled\"");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(sse(&[
+                    json!({"type": "message_start", "message": {"usage": {
+                        "input_tokens": 100, "cache_read_input_tokens": 400, "output_tokens": 1
+                    }}}),
+                    json!({"type": "content_block_start", "index": 0,
+                        "content_block": {"type": "thinking", "thinking": ""}}),
+                    json!({"type": "content_block_delta", "index": 0,
+                        "delta": {"type": "thinking_delta", "thinking": "Need the file."}}),
+                    json!({"type": "content_block_stop", "index": 0}),
+                    json!({"type": "content_block_start", "index": 1,
+                        "content_block": {"type": "tool_use", "id": "toolu_1", "name": "fs_read", "input": {}}}),
+                    json!({"type": "content_block_delta", "index": 1,
+                        "delta": {"type": "input_json_delta", "partial_json": "{\"path\":"}}),
+                    json!({"type": "content_block_delta", "index": 1,
+                        "delta": {"type": "input_json_delta", "partial_json": "\"README.md\"}"}}),
+                    json!({"type": "content_block_stop", "index": 1}),
+                    json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"},
+                        "usage": {"output_tokens": 42}}),
+                    json!({"type": "message_stop"}),
+                ]));
+        });
+
+        let client = AnthropicClient::new(
+            "sk-ant-test".to_string(),
+            None,
+            Some(format!("{}/v1", server.base_url())),
+        );
+        let req = WorkerActionRequest {
+            task_prompt: "Summarize the README".to_string(),
+            goal_summary: "Read README".to_string(),
+            context: String::new(),
+            available_tools: vec!["fs.read".to_string()],
+            tool_descriptors: descriptors(),
+            prior_observations: Vec::new(),
+            max_tokens: None,
+        };
+
+        let mut reasoning = String::new();
+        let decision = client
+            .decide_action_streaming(req, |delta| {
+                if let StreamDelta::Reasoning(text) = delta {
+                    reasoning.push_str(&text);
+                }
+                Ok(())
+            })
+            .await
+            .expect("decision");
+
+        mock.assert();
+        assert_eq!(reasoning, "Need the file.");
+        assert_eq!(decision.reasoning.as_deref(), Some("Need the file."));
+        match decision.action {
+            WorkerAction::ToolCalls { calls } => {
+                assert_eq!(calls.len(), 1);
+                assert_eq!(calls[0].tool_name, "fs.read");
+                assert_eq!(calls[0].tool_args, json!({"path": "README.md"}));
+            }
+            other => panic!("expected tool calls, got {other:?}"),
+        }
+        let usage = decision.usage.expect("usage");
+        assert_eq!(usage.prompt_tokens, 500);
+        assert_eq!(usage.cached_tokens, 400);
+        assert_eq!(usage.completion_tokens, 42);
+    }
+
+    #[tokio::test]
+    async fn complete_reads_text_blocks() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v1/messages")
+                .body_contains("\"stream\":false")
+                .body_contains("\"system\":\"Summarize.\"");
+            then.status(200).json_body(json!({
+                "content": [
+                    {"type": "text", "text": "Short summary."}
+                ],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 12, "output_tokens": 4}
+            }));
+        });
+
+        let client = AnthropicClient::new(
+            "sk-ant-test".to_string(),
+            Some("claude-haiku-4-5".to_string()),
+            Some(format!("{}/v1", server.base_url())),
+        );
+        let text = client
+            .complete("Summarize.", "Long conversation", 512)
+            .await
+            .expect("text");
+
+        mock.assert();
+        assert_eq!(text, "Short summary.");
+    }
+
+    #[tokio::test]
+    async fn auth_failures_map_to_auth_error() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/v1/messages");
+            then.status(401)
+                .json_body(json!({"type": "error", "error": {"type": "authentication_error"}}));
+        });
+
+        let client = AnthropicClient::new(
+            "bad".to_string(),
+            None,
+            Some(format!("{}/v1", server.base_url())),
+        );
+        let err = client.complete("s", "u", 64).await.unwrap_err();
+        assert!(matches!(err, ModelError::Auth(_)), "unexpected {err:?}");
+    }
+}
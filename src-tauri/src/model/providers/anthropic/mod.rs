@@ -0,0 +1,5 @@
+//! Anthropic Messages API client implementation.
+
+pub mod client;
+
+pub use client::AnthropicClient;
@@ -94,9 +94,14 @@ fn tool_name_from_wire(wire: &str, descriptors: &[ToolDescriptor]) -> String {
         .map(|d| d.name.clone())
         .unwrap_or_else(|| wire.to_string())
 }
-use crate::model::shared::{preferred_response_text, worker_prompt_from_request};
+use crate::model::retry::{self, send_with_retry, RetryPolicy};
+use crate::model::shared::{
+    parse_token_usage, preferred_response_text, worker_prompt_from_request,
+};
+use crate::model::structured::OutputSchema;
 use crate::model::{
-    AgentModelClient, ModelError, StreamDelta, WorkerActionRequest, WorkerDecision,
+    AgentModelClient, CompletionClient, ModelError, ProviderId, StreamDelta, TokenUsage,
+    WorkerActionRequest, WorkerDecision,
 };
 use crate::runtime::plan_mode_settings::WORKER_MAX_TOKENS;
 
@@ -129,6 +134,7 @@ pub struct ChatGPTClient {
     auth: Arc<RwLock<ChatGPTAuth>>,
     model: String,
     client: reqwest::Client,
+    retry: RetryPolicy,
 }
 
 impl ChatGPTClient {
@@ -149,6 +155,7 @@ impl ChatGPTClient {
             })),
             model: model.unwrap_or_else(|| "gpt-5.2-codex".to_string()),
             client: reqwest::Client::new(),
+            retry: retry::policy_for(ProviderId::OpenAIChatGPT),
         }
     }
 
@@ -186,6 +193,7 @@ impl ChatGPTClient {
         _max_tokens: u32,
         stream: bool,
         tools: Option<Vec<ToolDescriptor>>,
+        text_format: Option<&serde_json::Value>,
     ) -> Result<serde_json::Value, ModelError> {
         let tool_values = tools
             .unwrap_or_default()
@@ -200,7 +208,7 @@ impl ChatGPTClient {
             })
             .collect::<Vec<_>>();
 
-        Ok(serde_json::json!({
+        let mut body = serde_json::json!({
             "model": self.model,
             "instructions": system,
             "input": [
@@ -219,20 +227,28 @@ impl ChatGPTClient {
             "parallel_tool_calls": true,
             "store": false,
             "stream": stream,
-        }))
+        });
+        if let Some(format) = text_format {
+            body["text"] = serde_json::json!({ "format": format });
+        }
+        Ok(body)
     }
 
     fn parse_response_value(
         value: &serde_json::Value,
     ) -> Result<OpenAiResponseMessage, ModelError> {
+        let usage = value.get("usage").and_then(parse_token_usage);
+
         if let Some(message) = value
             .get("choices")
             .and_then(|v| v.as_array())
             .and_then(|choices| choices.first())
             .and_then(|choice| choice.get("message"))
         {
-            return serde_json::from_value::<OpenAiResponseMessage>(message.clone())
-                .map_err(|e| ModelError::InvalidResponse(format!("ChatGPT parse failed: {}", e)));
+            let mut parsed = serde_json::from_value::<OpenAiResponseMessage>(message.clone())
+                .map_err(|e| ModelError::InvalidResponse(format!("ChatGPT parse failed: {}", e)))?;
+            parsed.usage = usage;
+            return Ok(parsed);
         }
 
         let output = value
@@ -291,6 +307,7 @@ impl ChatGPTClient {
             } else {
                 Some(tool_calls)
             },
+            usage,
         })
     }
 
@@ -327,21 +344,46 @@ impl ChatGPTClient {
         }
     }
 
-    /// Simple text completion without tools
-    #[allow(dead_code)]
+    /// Simple text completion without tools. Goes through the streaming endpoint,
+    /// which is what the Codex backend serves for subscription accounts.
     pub async fn complete(
         &self,
         system: &str,
         user: &str,
         max_tokens: u32,
     ) -> Result<String, ModelError> {
-        let response = self.run_chat(system, user, max_tokens, None).await?;
+        let response = self
+            .run_chat_streaming(system, user, max_tokens, None, None, &mut |_| Ok(()))
+            .await?;
         Ok(preferred_response_text(
             response.content,
             response.reasoning_content,
         ))
     }
 
+    /// Structured output through the Responses API `text.format` JSON schema.
+    pub async fn complete_json(
+        &self,
+        system: &str,
+        user: &str,
+        schema: &OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        let format = serde_json::json!({
+            "type": "json_schema",
+            "name": schema.name,
+            "description": schema.description,
+            "schema": normalize_schema(&schema.schema),
+            "strict": false,
+        });
+        let response = self
+            .run_chat_streaming(system, user, max_tokens, None, Some(&format), &mut |_| {
+                Ok(())
+            })
+            .await?;
+        Ok(response.content.unwrap_or_default())
+    }
+
     /// Run chat completion with optional tools
     #[allow(dead_code)]
     async fn run_chat(
@@ -353,7 +395,7 @@ impl ChatGPTClient {
     ) -> Result<OpenAiResponseMessage, ModelError> {
         let auth = self.get_auth().await?;
 
-        let body = self.build_request_body(system, user, max_tokens, false, tools)?;
+        let body = self.build_request_body(system, user, max_tokens, false, tools, None)?;
 
         let mut request = self
             .client
@@ -368,10 +410,7 @@ impl ChatGPTClient {
             request = request.header("ChatGPT-Account-Id", account_id);
         }
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| ModelError::Request(e.to_string()))?;
+        let response = send_with_retry(&self.retry, "ChatGPT", request).await?;
 
         let status = response.status();
         let text = response
@@ -407,11 +446,12 @@ impl ChatGPTClient {
         user: &str,
         max_tokens: u32,
         tools: Option<Vec<ToolDescriptor>>,
+        text_format: Option<&serde_json::Value>,
         on_delta: &mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send),
     ) -> Result<OpenAiResponseMessage, ModelError> {
         let auth = self.get_auth().await?;
 
-        let body = self.build_request_body(system, user, max_tokens, true, tools)?;
+        let body = self.build_request_body(system, user, max_tokens, true, tools, text_format)?;
 
         let mut request = self
             .client
@@ -425,10 +465,7 @@ impl ChatGPTClient {
             request = request.header("ChatGPT-Account-Id", account_id);
         }
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| ModelError::Request(e.to_string()))?;
+        let response = send_with_retry(&self.retry, "ChatGPT", request).await?;
 
         let status = response.status();
 
@@ -454,6 +491,7 @@ impl ChatGPTClient {
         let mut tool_call_accumulators: Vec<OpenAiToolCallAccumulator> = Vec::new();
         let mut full_tool_calls: Vec<OpenAiToolCall> = Vec::new();
         let mut saw_content_delta = false;
+        let mut usage: Option<TokenUsage> = None;
         let mut completed_response: Option<serde_json::Value> = None;
 
         use futures::StreamExt;
@@ -511,6 +549,7 @@ impl ChatGPTClient {
                     &mut tool_call_accumulators,
                     &mut full_tool_calls,
                     &mut saw_content_delta,
+                    &mut usage,
                     on_delta,
                 )? {
                     done = true;
@@ -535,6 +574,7 @@ impl ChatGPTClient {
                 &mut tool_call_accumulators,
                 &mut full_tool_calls,
                 &mut saw_content_delta,
+                &mut usage,
                 on_delta,
             )?;
         }
@@ -579,6 +619,7 @@ impl ChatGPTClient {
                 Some(reasoning)
             },
             tool_calls,
+            usage,
         })
     }
 
@@ -600,7 +641,7 @@ impl ChatGPTClient {
         let max_tokens = req.max_tokens.unwrap_or(WORKER_MAX_TOKENS);
 
         let response = self
-            .run_chat_streaming(&system, &user, max_tokens, tools_arg, &mut on_delta)
+            .run_chat_streaming(&system, &user, max_tokens, tools_arg, None, &mut on_delta)
             .await?;
 
         tracing::debug!(
@@ -627,3 +668,25 @@ impl AgentModelClient for ChatGPTClient {
         self.decide_action_streaming(req, noop).await
     }
 }
+
+impl CompletionClient for ChatGPTClient {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.complete(system, conversation, max_tokens).await
+    }
+
+    async fn complete_json(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: &OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.complete_json(system, conversation, schema, max_tokens)
+            .await
+    }
+}
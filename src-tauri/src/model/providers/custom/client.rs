@@ -0,0 +1,523 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::discovery::DiscoveredModel;
+use crate::model::providers::openai_compat::{
+    OpenAiCompatClient, OpenAiCompatClientConfig, ResponseFormatSupport,
+};
+use crate::model::retry;
+use crate::model::ProviderId;
+
+/// Ollama's OpenAI-compatible endpoint, used when no base URL is configured.
+pub const DEFAULT_CUSTOM_BASE_URL: &str = "http://localhost:11434/v1";
+const DEFAULT_CONTEXT_WINDOW: u32 = 32_768;
+const DEFAULT_OUTPUT_LIMIT: u32 = 8_192;
+
+/// A model exposed by the custom endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomModelEntry {
+    pub name: String,
+    #[serde(default = "default_context_window")]
+    pub context_window: u32,
+    #[serde(default = "default_output_limit")]
+    pub output_limit: u32,
+}
+
+fn default_context_window() -> u32 {
+    DEFAULT_CONTEXT_WINDOW
+}
+
+fn default_output_limit() -> u32 {
+    DEFAULT_OUTPUT_LIMIT
+}
+
+/// Endpoint description for the `custom` provider.
+///
+/// Base URL, API key and default model live in the regular provider config;
+/// this holds everything the hosted providers hard-code in their clients.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomProviderSettings {
+    /// Label shown in the UI (e.g. "Ollama", "Internal gateway").
+    pub display_name: Option<String>,
+    /// Extra headers sent with every request.
+    pub headers: BTreeMap<String, String>,
+    /// Models offered in the catalog; the first one is the default.
+    pub models: Vec<CustomModelEntry>,
+    /// Whether the endpoint accepts native `tools` / `tool_calls`.
+    pub supports_tools: bool,
+    /// Whether to send `parallel_tool_calls: true` alongside tools.
+    pub parallel_tool_calls: bool,
+    /// Response field carrying reasoning text, when not `reasoning_content`.
+    pub reasoning_field: Option<String>,
+    /// Whether the endpoint understands `stream_options.include_usage`.
+    pub stream_usage: bool,
+    /// Upper bound applied to every request's `max_tokens`.
+    pub max_output_tokens: Option<u32>,
+    /// Which `response_format` the endpoint accepts for structured output.
+    pub response_format: ResponseFormatSupport,
+}
+
+impl Default for CustomProviderSettings {
+    fn default() -> Self {
+        Self {
+            display_name: None,
+            headers: BTreeMap::new(),
+            models: Vec::new(),
+            supports_tools: true,
+            parallel_tool_calls: false,
+            reasoning_field: None,
+            stream_usage: false,
+            max_output_tokens: None,
+            response_format: ResponseFormatSupport::None,
+        }
+    }
+}
+
+impl CustomProviderSettings {
+    pub fn default_model(&self) -> Option<String> {
+        self.models.first().map(|m| m.name.clone())
+    }
+}
+
+/// Wire format of the `api_key` slot for the custom provider: the runtime only
+/// threads `(provider, api_key, model, base_url)`, so the settings ride along.
+#[derive(Serialize, Deserialize)]
+struct CustomApiKeyPayload {
+    api_key: String,
+    settings: CustomProviderSettings,
+}
+
+pub struct CustomClient(OpenAiCompatClient);
+
+impl CustomClient {
+    pub fn new(
+        api_key: String,
+        model: Option<String>,
+        base_url: Option<String>,
+        settings: CustomProviderSettings,
+    ) -> Self {
+        let resolved_model = model
+            .filter(|m| !m.trim().is_empty())
+            .or_else(|| settings.default_model())
+            .unwrap_or_default();
+        let resolved_base_url = base_url
+            .filter(|u| !u.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_CUSTOM_BASE_URL.to_string());
+
+        let config = OpenAiCompatClientConfig {
+            extra_headers: settings.headers.into_iter().collect(),
+            parallel_tool_calls: settings.parallel_tool_calls,
+            max_tokens_cap: settings.max_output_tokens,
+            stream_usage: settings.stream_usage,
+            supports_tools: settings.supports_tools,
+            reasoning_field: settings.reasoning_field.filter(|f| !f.trim().is_empty()),
+            retry_policy: retry::policy_for(ProviderId::Custom),
+            response_format: settings.response_format,
+            ..OpenAiCompatClientConfig::default()
+        };
+
+        Self(OpenAiCompatClient::new(
+            api_key,
+            Some(resolved_model),
+            Some(resolved_base_url),
+            "Custom",
+            "",
+            config,
+        ))
+    }
+
+    /// Packs the API key and endpoint settings into the `api_key` slot of a provider config.
+    pub fn encode_api_key_payload(api_key: &str, settings: &CustomProviderSettings) -> String {
+        serde_json::to_string(&CustomApiKeyPayload {
+            api_key: api_key.to_string(),
+            settings: settings.clone(),
+        })
+        .unwrap_or_else(|_| api_key.to_string())
+    }
+
+    /// Builds a client from a payload produced by [`Self::encode_api_key_payload`].
+    /// A plain key (e.g. from `CUSTOM_API_KEY`) is accepted with default settings.
+    pub fn from_api_key_payload(
+        payload: String,
+        model: Option<String>,
+        base_url: Option<String>,
+    ) -> Self {
+        match serde_json::from_str::<CustomApiKeyPayload>(&payload) {
+            Ok(decoded) => Self::new(decoded.api_key, model, base_url, decoded.settings),
+            Err(_) => Self::new(payload, model, base_url, CustomProviderSettings::default()),
+        }
+    }
+
+    pub fn model_id(&self) -> String {
+        self.0.model_id()
+    }
+
+    /// Lists the models served by the endpoint, for catalog discovery.
+    pub async fn list_models(&self) -> Result<Vec<DiscoveredModel>, crate::model::ModelError> {
+        self.0.list_models().await
+    }
+
+    #[allow(dead_code)]
+    pub async fn complete(
+        &self,
+        system: &str,
+        user: &str,
+        max_tokens: u32,
+    ) -> Result<String, crate::model::ModelError> {
+        self.0.complete(system, user, max_tokens).await
+    }
+
+    pub async fn decide_action_streaming<F>(
+        &self,
+        req: crate::model::WorkerActionRequest,
+        on_delta: F,
+    ) -> Result<crate::model::WorkerDecision, crate::model::ModelError>
+    where
+        F: FnMut(crate::model::StreamDelta) -> Result<(), String> + Send,
+    {
+        self.0.decide_action_streaming(req, on_delta).await
+    }
+
+    #[allow(dead_code)]
+    pub async fn generate_plan_markdown(
+        &self,
+        task_prompt: &str,
+        prior_markdown_context: &str,
+        tool_descriptors: Vec<crate::core::tool::ToolDescriptor>,
+    ) -> Result<String, crate::model::ModelError> {
+        self.0
+            .generate_plan_markdown(task_prompt, prior_markdown_context, tool_descriptors)
+            .await
+    }
+}
+
+impl crate::model::AgentModelClient for CustomClient {
+    fn model_id(&self) -> String {
+        self.model_id()
+    }
+
+    async fn decide_action(
+        &self,
+        req: crate::model::WorkerActionRequest,
+    ) -> Result<crate::model::WorkerDecision, crate::model::ModelError> {
+        let noop = |_delta: crate::model::StreamDelta| Ok::<(), String>(());
+        self.decide_action_streaming(req, noop).await
+    }
+}
+
+impl crate::model::CompletionClient for CustomClient {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, crate::model::ModelError> {
+        self.complete(system, conversation, max_tokens).await
+    }
+
+    async fn complete_json(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: &crate::model::structured::OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, crate::model::ModelError> {
+        self.0
+            .complete_json(system, conversation, schema, max_tokens)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tool::ToolDescriptor;
+    use crate::model::{StreamDelta, WorkerAction, WorkerActionRequest};
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    fn request_with_tools() -> WorkerActionRequest {
+        WorkerActionRequest {
+            task_prompt: "List the workspace".to_string(),
+            goal_summary: "List files".to_string(),
+            context: String::new(),
+            available_tools: vec!["fs.list".to_string()],
+            tool_descriptors: vec![ToolDescriptor {
+                name: "fs.list".to_string(),
+                description: "List files".to_string(),
+                input_schema: json!({"type": "object", "properties": {"path": {"type": "string"}}}),
+                output_schema: None,
+            }],
+            prior_observations: Vec::new(),
+            max_tokens: None,
+        }
+    }
+
+    fn sse(chunks: &[serde_json::Value]) -> String {
+        let mut body = String::new();
+        for chunk in chunks {
+            body.push_str(&format!("data: {}\n\n", chunk));
+        }
+        body.push_str("data: [DONE]\n\n");
+        body
+    }
+
+    fn settings_with_model(name: &str) -> CustomProviderSettings {
+        CustomProviderSettings {
+            models: vec![CustomModelEntry {
+                name: name.to_string(),
+                context_window: 131_072,
+                output_limit: 8_192,
+            }],
+            ..CustomProviderSettings::default()
+        }
+    }
+
+    #[test]
+    fn payload_roundtrip_keeps_settings_and_defaults_model() {
+        let mut settings = settings_with_model("qwen3:32b");
+        settings
+            .headers
+            .insert("X-Team".to_string(), "platform".to_string());
+        let payload = CustomClient::encode_api_key_payload("sk-local", &settings);
+
+        let client = CustomClient::from_api_key_payload(payload, None, None);
+        assert_eq!(client.model_id(), "qwen3:32b");
+        assert_eq!(client.0.api_key, "sk-local");
+        assert_eq!(client.0.base_url, DEFAULT_CUSTOM_BASE_URL);
+
+        let raw = CustomClient::from_api_key_payload(
+            "plain-key".to_string(),
+            Some("llama3".to_string()),
+            Some("http://gateway/v1".to_string()),
+        );
+        assert_eq!(raw.0.api_key, "plain-key");
+        assert_eq!(raw.model_id(), "llama3");
+    }
+
+    #[tokio::test]
+    async fn sends_tools_and_custom_headers_and_parses_tool_call() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v1/chat/completions")
+                .header("authorization", "Bearer sk-gateway")
+                .header("x-team", "platform")
+                .body_contains("\"model\":\"internal-coder\"")
+                .body_contains("\"tools\"");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(sse(&[json!({
+                    "choices": [{"delta": {"tool_calls": [{
+                        "index": 0,
+                        "type": "function",
+                        "function": {"name": "fs.list", "arguments": "{\"path\":\".\"}"}
+                    }]}}]
+                })]));
+        });
+
+        let mut settings = settings_with_model("internal-coder");
+        settings
+            .headers
+            .insert("X-Team".to_string(), "platform".to_string());
+        let client = CustomClient::new(
+            "sk-gateway".to_string(),
+            None,
+            Some(format!("{}/v1", server.base_url())),
+            settings,
+        );
+
+        let decision = client
+            .decide_action_streaming(request_with_tools(), |_| Ok(()))
+            .await
+            .expect("decision");
+
+        mock.assert();
+        match decision.action {
+            WorkerAction::ToolCalls { calls } => {
+                assert_eq!(calls.len(), 1);
+                assert_eq!(calls[0].tool_name, "fs.list");
+                assert_eq!(calls[0].tool_args, json!({"path": "."}));
+            }
+            other => panic!("expected tool calls, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn keyless_endpoint_without_tools_maps_reasoning_field() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v1/chat/completions")
+                .matches(|req| {
+                    let has_auth = req
+                        .headers
+                        .iter()
+                        .flatten()
+                        .any(|(name, _)| name.eq_ignore_ascii_case("authorization"));
+                    let body = req.body.as_deref().unwrap_or_default();
+                    !has_auth && !String::from_utf8_lossy(body).contains("\"tools\"")
+                });
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(sse(&[
+                    json!({"choices": [{"delta": {"reasoning": "Checking the tree."}}]}),
+                    json!({"choices": [{"delta": {"content": "Nothing to do."}}]}),
+                ]));
+        });
+
+        let settings = CustomProviderSettings {
+            supports_tools: false,
+            reasoning_field: Some("reasoning".to_string()),
+            ..settings_with_model("llama3.1:8b")
+        };
+        let client = CustomClient::new(
+            String::new(),
+            None,
+            Some(format!("{}/v1", server.base_url())),
+            settings,
+        );
+
+        let mut reasoning = String::new();
+        let decision = client
+            .decide_action_streaming(request_with_tools(), |delta| {
+                if let StreamDelta::Reasoning(text) = delta {
+                    reasoning.push_str(&text);
+                }
+                Ok(())
+            })
+            .await
+            .expect("decision");
+
+        mock.assert();
+        assert_eq!(reasoning, "Checking the tree.");
+        match decision.action {
+            WorkerAction::Complete { summary } => assert_eq!(summary, "Nothing to do."),
+            other => panic!("expected completion, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn complete_reads_renamed_reasoning_when_content_empty() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v1/chat/completions")
+                .body_contains("\"stream\":false");
+            then.status(200).json_body(json!({
+                "choices": [{"message": {"content": "", "reasoning": "Summary text"}}]
+            }));
+        });
+
+        let settings = CustomProviderSettings {
+            reasoning_field: Some("reasoning".to_string()),
+            ..settings_with_model("qwen3:32b")
+        };
+        let client = CustomClient::new(
+            String::new(),
+            None,
+            Some(format!("{}/v1", server.base_url())),
+            settings,
+        );
+
+        let text = client.complete("system", "user", 256).await.expect("text");
+        mock.assert();
+        assert_eq!(text, "Summary text");
+    }
+
+    #[tokio::test]
+    async fn streaming_retries_503_before_any_delta() {
+        let server = MockServer::start();
+        let unavailable = server.mock(|when, then| {
+            when.method(POST).path("/v1/chat/completions");
+            then.status(503)
+                .header("Retry-After", "1")
+                .body("upstream overloaded");
+        });
+
+        let client = CustomClient::new(
+            String::new(),
+            None,
+            Some(format!("{}/v1", server.base_url())),
+            settings_with_model("qwen3:32b"),
+        );
+        let pending = tokio::spawn(async move {
+            let mut deltas = Vec::new();
+            let decision = client
+                .decide_action_streaming(request_with_tools(), |delta| {
+                    deltas.push(delta);
+                    Ok(())
+                })
+                .await;
+            (decision, deltas)
+        });
+
+        while unavailable.hits_async().await == 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        unavailable.delete_async().await;
+        let ok = server.mock(|when, then| {
+            when.method(POST).path("/v1/chat/completions");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(sse(&[
+                    json!({"choices": [{"delta": {"content": "All done."}}]}),
+                ]));
+        });
+
+        let (decision, deltas) = pending.await.unwrap();
+        let decision = decision.expect("decision after retry");
+        ok.assert_hits(1);
+        assert_eq!(
+            deltas
+                .iter()
+                .filter(|delta| matches!(delta, StreamDelta::Content(_)))
+                .count(),
+            1
+        );
+        match decision.action {
+            WorkerAction::Complete { summary } => assert_eq!(summary, "All done."),
+            other => panic!("expected completion, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn structured_completion_sends_json_schema_response_format() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v1/chat/completions")
+                .body_contains("\"type\":\"json_schema\"")
+                .body_contains("\"name\":\"verdict\"");
+            then.status(200).json_body(json!({
+                "choices": [{"message": {"content": "{\"ok\":true}"}}]
+            }));
+        });
+
+        let settings = CustomProviderSettings {
+            response_format: ResponseFormatSupport::JsonSchema,
+            ..settings_with_model("qwen3:32b")
+        };
+        let client = CustomClient::new(
+            String::new(),
+            None,
+            Some(format!("{}/v1", server.base_url())),
+            settings,
+        );
+        let schema = crate::model::structured::OutputSchema::new(
+            "verdict",
+            "review verdict",
+            json!({"type": "object", "properties": {"ok": {"type": "boolean"}}}),
+        );
+
+        let verdict: serde_json::Value =
+            crate::model::structured::complete_structured(&client, "system", "user", &schema, 256)
+                .await
+                .expect("structured output");
+
+        mock.assert();
+        assert_eq!(verdict, json!({"ok": true}));
+    }
+}
@@ -0,0 +1,5 @@
+//! User-configured OpenAI-compatible provider (Ollama, llama.cpp, vLLM, internal gateways).
+
+pub mod client;
+
+pub use client::{CustomClient, CustomModelEntry, CustomProviderSettings};
@@ -2,13 +2,16 @@ use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 
 use crate::core::tool::ToolDescriptor;
+use crate::model::discovery::DiscoveredModel;
+use crate::model::retry::{self, send_with_retry, RetryPolicy};
 use crate::model::shared::{
-    completion_summary_from_content_or_reasoning, preferred_response_text,
+    completion_summary_from_content_or_reasoning, parse_token_usage, preferred_response_text,
     worker_prompt_from_request,
 };
+use crate::model::structured::OutputSchema;
 use crate::model::{
-    AgentModelClient, ModelError, StreamDelta, WorkerAction, WorkerActionRequest, WorkerDecision,
-    WorkerToolCall,
+    AgentModelClient, CompletionClient, ModelError, ProviderId, StreamDelta, TokenUsage,
+    WorkerAction, WorkerActionRequest, WorkerDecision, WorkerToolCall,
 };
 use crate::runtime::plan_mode_settings::WORKER_MAX_TOKENS;
 
@@ -77,6 +80,7 @@ pub struct GeminiClient {
     model: String,
     base_url: String,
     client: reqwest::Client,
+    retry: RetryPolicy,
 }
 
 impl GeminiClient {
@@ -86,6 +90,7 @@ impl GeminiClient {
             model: model.unwrap_or_else(|| "gemini-3-flash-preview".to_string()),
             base_url: base_url.unwrap_or_else(|| DEFAULT_GEMINI_BASE_URL.to_string()),
             client: reqwest::Client::new(),
+            retry: retry::policy_for(ProviderId::Gemini),
         }
     }
 
@@ -93,6 +98,65 @@ impl GeminiClient {
         self.model.clone()
     }
 
+    /// Lists models that support `generateContent`, following page tokens.
+    pub async fn list_models(&self) -> Result<Vec<DiscoveredModel>, ModelError> {
+        let endpoint = format!("{}/models", self.base_url.trim_end_matches('/'));
+        let mut models = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut request = self
+                .client
+                .get(&endpoint)
+                .query(&[("key", self.api_key.as_str()), ("pageSize", "1000")]);
+            if let Some(token) = page_token.as_deref() {
+                request = request.query(&[("pageToken", token)]);
+            }
+
+            let response = send_with_retry(&self.retry, "Gemini", request).await?;
+            let status = response.status();
+            let text = response
+                .text()
+                .await
+                .map_err(|e| ModelError::Request(e.to_string()))?;
+            if status.as_u16() == 401 || status.as_u16() == 403 {
+                return Err(ModelError::Auth(format!(
+                    "Gemini auth failed ({status}) listing models"
+                )));
+            }
+            if !status.is_success() {
+                return Err(ModelError::Request(format!(
+                    "Gemini model listing error {status}: {text}"
+                )));
+            }
+
+            let page: GeminiModelList = serde_json::from_str(&text).map_err(|e| {
+                ModelError::InvalidResponse(format!("Gemini model listing parse failed: {e}"))
+            })?;
+            models.extend(
+                page.models
+                    .into_iter()
+                    .filter(|model| {
+                        model
+                            .supported_generation_methods
+                            .iter()
+                            .any(|method| method == "generateContent")
+                    })
+                    .map(|model| DiscoveredModel {
+                        id: model.name.trim_start_matches("models/").to_string(),
+                        display_name: model.display_name,
+                        context_window: model.input_token_limit,
+                        output_limit: model.output_token_limit,
+                    }),
+            );
+
+            match page.next_page_token.filter(|token| !token.is_empty()) {
+                Some(token) => page_token = Some(token),
+                None => return Ok(models),
+            }
+        }
+    }
+
     pub async fn complete(
         &self,
         system: &str,
@@ -100,7 +164,7 @@ impl GeminiClient {
         max_tokens: u32,
     ) -> Result<String, ModelError> {
         let response = self
-            .run_chat(system, user, max_tokens, None, false, None)
+            .run_chat(system, user, max_tokens, None, None, false, None)
             .await?;
         Ok(preferred_response_text(
             response.content,
@@ -108,12 +172,35 @@ impl GeminiClient {
         ))
     }
 
+    /// Structured output through `responseMimeType` and `responseSchema`.
+    pub async fn complete_json(
+        &self,
+        system: &str,
+        user: &str,
+        schema: &OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        let response = self
+            .run_chat(
+                system,
+                user,
+                max_tokens,
+                None,
+                Some(&schema.schema),
+                false,
+                None,
+            )
+            .await?;
+        Ok(response.content.unwrap_or_default())
+    }
+
     async fn run_chat(
         &self,
         system: &str,
         user: &str,
         max_tokens: u32,
         tools: Option<Vec<ToolDescriptor>>,
+        response_schema: Option<&serde_json::Value>,
         stream: bool,
         on_delta: Option<&mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send)>,
     ) -> Result<GeminiResponseMessage, ModelError> {
@@ -182,6 +269,8 @@ impl GeminiClient {
             generation_config: GeminiGenerationConfig {
                 temperature: 0.1,
                 max_output_tokens: max_tokens as i32,
+                response_mime_type: response_schema.map(|_| "application/json".to_string()),
+                response_schema: response_schema.map(sanitize_schema_for_gemini),
             },
         };
 
@@ -196,10 +285,7 @@ impl GeminiClient {
             request = request.header("Accept", "text/event-stream");
         }
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| ModelError::Request(e.to_string()))?;
+        let response = send_with_retry(&self.retry, "Gemini", request).await?;
 
         let status = response.status();
 
@@ -241,6 +327,7 @@ impl GeminiClient {
         &self,
         response: GeminiGenerateResponse,
     ) -> Result<GeminiResponseMessage, ModelError> {
+        let usage = response.usage_metadata.as_ref().and_then(parse_token_usage);
         let candidate = response.candidates.into_iter().next().ok_or_else(|| {
             ModelError::InvalidResponse("No candidates in Gemini response".to_string())
         })?;
@@ -283,6 +370,7 @@ impl GeminiClient {
             } else {
                 Some(tool_calls)
             },
+            usage,
         })
     }
 
@@ -293,6 +381,7 @@ impl GeminiClient {
     ) -> Result<GeminiResponseMessage, ModelError> {
         let mut content = String::new();
         let reasoning = String::new();
+        let mut usage: Option<TokenUsage> = None;
 
         let mut stream = response.bytes_stream();
         let mut buffer = String::new();
@@ -315,6 +404,12 @@ impl GeminiClient {
 
                 // Parse the JSON and extract content
                 if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(line) {
+                    // Each chunk repeats cumulative usageMetadata; keep the latest.
+                    if let Some(chunk_usage) =
+                        parsed.get("usageMetadata").and_then(parse_token_usage)
+                    {
+                        usage = Some(chunk_usage);
+                    }
                     if let Some(candidates) = parsed.get("candidates").and_then(|c| c.as_array()) {
                         for candidate in candidates {
                             if let Some(content_obj) = candidate
@@ -350,6 +445,7 @@ impl GeminiClient {
                 Some(reasoning)
             },
             tool_calls: None,
+            usage,
         })
     }
 
@@ -371,7 +467,7 @@ impl GeminiClient {
 
         // Use non-streaming for decide_action to get complete response reliably
         let response = self
-            .run_chat(&system, &user, max_tokens, tools_arg, false, None)
+            .run_chat(&system, &user, max_tokens, tools_arg, None, false, None)
             .await?;
 
         tracing::debug!(
@@ -398,6 +494,7 @@ impl GeminiClient {
                 action: WorkerAction::ToolCalls { calls },
                 reasoning: None,
                 raw_response,
+                usage: response.usage,
             });
         }
 
@@ -410,6 +507,7 @@ impl GeminiClient {
             },
             reasoning: None,
             raw_response,
+            usage: response.usage,
         })
     }
 }
@@ -425,6 +523,28 @@ impl AgentModelClient for GeminiClient {
     }
 }
 
+impl CompletionClient for GeminiClient {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.complete(system, conversation, max_tokens).await
+    }
+
+    async fn complete_json(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: &OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.complete_json(system, conversation, schema, max_tokens)
+            .await
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct GeminiGenerateRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -441,6 +561,10 @@ struct GeminiGenerationConfig {
     temperature: f32,
     #[serde(rename = "maxOutputTokens")]
     max_output_tokens: i32,
+    #[serde(rename = "responseMimeType", skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<String>,
+    #[serde(rename = "responseSchema", skip_serializing_if = "Option::is_none")]
+    response_schema: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -473,6 +597,29 @@ struct GeminiFunctionCall {
     args: serde_json::Value,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiModelList {
+    #[serde(default)]
+    models: Vec<GeminiModelEntry>,
+    #[serde(default)]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiModelEntry {
+    name: String,
+    #[serde(default)]
+    display_name: Option<String>,
+    #[serde(default)]
+    input_token_limit: Option<u32>,
+    #[serde(default)]
+    output_token_limit: Option<u32>,
+    #[serde(default)]
+    supported_generation_methods: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct GeminiGenerateResponse {
@@ -481,6 +628,8 @@ struct GeminiGenerateResponse {
     #[serde(default)]
     #[allow(dead_code)]
     prompt_feedback: Option<serde_json::Value>,
+    #[serde(default, rename = "usageMetadata")]
+    usage_metadata: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -513,6 +662,8 @@ struct GeminiResponseMessage {
     content: Option<String>,
     reasoning_content: Option<String>,
     tool_calls: Option<Vec<GeminiToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<TokenUsage>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -1,9 +1,14 @@
 use std::sync::Arc;
 
 use crate::core::tool::ToolDescriptor;
-use crate::model::providers::openai_compat::{OpenAiCompatClient, OpenAiCompatClientConfig};
+use crate::model::discovery::DiscoveredModel;
+use crate::model::providers::openai_compat::{
+    OpenAiCompatClient, OpenAiCompatClientConfig, ResponseFormatSupport,
+};
+use crate::model::retry;
 use crate::model::{
-    AgentModelClient, ModelError, StreamDelta, WorkerActionRequest, WorkerDecision,
+    AgentModelClient, CompletionClient, ModelError, ProviderId, StreamDelta, WorkerActionRequest,
+    WorkerDecision,
 };
 
 const DEFAULT_GLM_BASE_URL: &str = "https://api.z.ai/api/coding/paas/v4";
@@ -155,10 +160,14 @@ impl GlmClient {
             schema_filter,
             extra_headers: Vec::new(),
             parallel_tool_calls: false, // GLM doesn't support parallel_tool_calls
-            retry_on_rate_limit: true,
+            retry_policy: retry::policy_for(ProviderId::Zhipu),
             retry_on_invalid_param_1210: true,
             fallback_model,
             max_tokens_cap: Some(GLM_MAX_OUTPUT_TOKENS),
+            stream_usage: true,
+            supports_tools: true,
+            reasoning_field: None,
+            response_format: ResponseFormatSupport::JsonObject,
         };
 
         Self(OpenAiCompatClient::new(
@@ -175,6 +184,11 @@ impl GlmClient {
         self.0.model_id()
     }
 
+    /// Lists the models served by the endpoint, for catalog discovery.
+    pub async fn list_models(&self) -> Result<Vec<DiscoveredModel>, ModelError> {
+        self.0.list_models().await
+    }
+
     #[allow(dead_code)]
     pub async fn complete(
         &self,
@@ -220,6 +234,29 @@ impl AgentModelClient for GlmClient {
     }
 }
 
+impl CompletionClient for GlmClient {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.complete(system, conversation, max_tokens).await
+    }
+
+    async fn complete_json(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: &crate::model::structured::OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.0
+            .complete_json(system, conversation, schema, max_tokens)
+            .await
+    }
+}
+
 #[cfg(test)]
 mod unit_tests {
     use super::*;
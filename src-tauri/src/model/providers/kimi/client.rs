@@ -1,9 +1,14 @@
 use std::sync::Arc;
 
 use crate::core::tool::ToolDescriptor;
-use crate::model::providers::openai_compat::{OpenAiCompatClient, OpenAiCompatClientConfig};
+use crate::model::discovery::DiscoveredModel;
+use crate::model::providers::openai_compat::{
+    OpenAiCompatClient, OpenAiCompatClientConfig, ResponseFormatSupport,
+};
+use crate::model::retry;
 use crate::model::{
-    AgentModelClient, ModelError, StreamDelta, WorkerActionRequest, WorkerDecision,
+    AgentModelClient, CompletionClient, ModelError, ProviderId, StreamDelta, WorkerActionRequest,
+    WorkerDecision,
 };
 
 const DEFAULT_KIMI_BASE_URL: &str = "https://api.kimi.com/coding/v1";
@@ -66,12 +71,16 @@ impl KimiClient {
             tool_name_to_wire,
             tool_name_from_wire,
             schema_filter: Arc::new(|schema| schema.clone()), // No schema filtering needed
-            extra_headers: vec![("User-Agent", "KimiCLI/0.77".to_string())],
+            extra_headers: vec![("User-Agent".to_string(), "KimiCLI/0.77".to_string())],
             parallel_tool_calls: true,
-            retry_on_rate_limit: false, // Kimi doesn't have the same rate limit issues
+            retry_policy: retry::policy_for(ProviderId::Kimi),
             retry_on_invalid_param_1210: false,
             fallback_model: None,
             max_tokens_cap: None,
+            stream_usage: true,
+            supports_tools: true,
+            reasoning_field: None,
+            response_format: ResponseFormatSupport::JsonObject,
         };
 
         Self(OpenAiCompatClient::new(
@@ -88,6 +97,11 @@ impl KimiClient {
         self.0.model_id()
     }
 
+    /// Lists the models served by the endpoint, for catalog discovery.
+    pub async fn list_models(&self) -> Result<Vec<DiscoveredModel>, ModelError> {
+        self.0.list_models().await
+    }
+
     /// Simple text completion without tools - useful for summarization and other single-turn tasks.
     #[allow(dead_code)]
     pub async fn complete(
@@ -136,6 +150,29 @@ impl AgentModelClient for KimiClient {
     }
 }
 
+impl CompletionClient for KimiClient {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.complete(system, conversation, max_tokens).await
+    }
+
+    async fn complete_json(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: &crate::model::structured::OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.0
+            .complete_json(system, conversation, schema, max_tokens)
+            .await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,13 +2,14 @@ use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 
 use crate::core::tool::ToolDescriptor;
+use crate::model::retry::{self, send_with_retry, RetryPolicy};
 use crate::model::shared::{
-    completion_summary_from_content_or_reasoning, plan_markdown_system_prompt,
+    completion_summary_from_content_or_reasoning, parse_token_usage, plan_markdown_system_prompt,
     preferred_response_text, strip_tool_call_markup, worker_system_prompt, worker_user_prompt,
 };
 use crate::model::{
-    AgentModelClient, ModelError, StreamDelta, WorkerAction, WorkerActionRequest, WorkerDecision,
-    WorkerToolCall,
+    AgentModelClient, CompletionClient, ModelError, ProviderId, StreamDelta, TokenUsage,
+    WorkerAction, WorkerActionRequest, WorkerDecision, WorkerToolCall,
 };
 use crate::runtime::plan_mode_settings::{DEFAULT_PLAN_MODE_MAX_TOKENS, WORKER_MAX_TOKENS};
 
@@ -22,6 +23,7 @@ pub struct MiniMaxClient {
     model: String,
     base_url: String,
     client: reqwest::Client,
+    retry: RetryPolicy,
 }
 
 impl MiniMaxClient {
@@ -44,6 +46,7 @@ impl MiniMaxClient {
             model: model.unwrap_or_else(|| "MiniMax-M2.1".to_string()),
             base_url: base_url.unwrap_or_else(|| DEFAULT_MINIMAX_BASE_URL.to_string()),
             client: reqwest::Client::new(),
+            retry: retry::policy_for(ProviderId::MiniMax),
         }
     }
 
@@ -373,12 +376,14 @@ impl MiniMaxClient {
                         action: WorkerAction::ToolCalls { calls },
                         reasoning,
                         raw_response,
+                        usage: response.usage,
                     });
                 }
             }
         }
 
         let raw_response = serde_json::to_string(&response).ok();
+        let usage = response.usage;
         let summary = completion_summary_from_content_or_reasoning(
             response.content,
             response.reasoning_content,
@@ -390,6 +395,7 @@ impl MiniMaxClient {
             action: WorkerAction::Complete { summary },
             reasoning,
             raw_response,
+            usage,
         })
     }
 }
@@ -405,6 +411,17 @@ impl AgentModelClient for MiniMaxClient {
     }
 }
 
+impl CompletionClient for MiniMaxClient {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        self.complete(system, conversation, max_tokens).await
+    }
+}
+
 impl MiniMaxClient {
     /// Simple text completion without tools - useful for summarization and other single-turn tasks.
     pub async fn complete(
@@ -473,15 +490,13 @@ impl MiniMaxClient {
             body.tools.as_ref().map(|t| t.len())
         );
 
-        let response = self
+        let request = self
             .client
             .post(&endpoint)
             .header("Content-Type", "application/json")
             .bearer_auth(&self.api_key)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| ModelError::Request(e.to_string()))?;
+            .json(&body);
+        let response = send_with_retry(&self.retry, "MiniMax", request).await?;
 
         let status = response.status();
         let text = response
@@ -503,11 +518,15 @@ impl MiniMaxClient {
         let parsed: MiniMaxChatResponse = serde_json::from_str(&text)
             .map_err(|e| ModelError::InvalidResponse(format!("MiniMax parse failed: {e}")))?;
 
+        let usage = parsed.usage.as_ref().and_then(parse_token_usage);
         parsed
             .choices
             .into_iter()
             .next()
-            .map(|choice| choice.message)
+            .map(|choice| MiniMaxResponseMessage {
+                usage,
+                ..choice.message
+            })
             .ok_or_else(|| {
                 ModelError::InvalidResponse(
                     "missing choices[0].message from MiniMax response".to_string(),
@@ -550,15 +569,13 @@ impl MiniMaxClient {
             body.tools.as_ref().map(|t| t.len())
         );
 
-        let response = self
+        let request = self
             .client
             .post(&endpoint)
             .header("Content-Type", "application/json")
             .bearer_auth(&self.api_key)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| ModelError::Request(e.to_string()))?;
+            .json(&body);
+        let response = send_with_retry(&self.retry, "MiniMax", request).await?;
 
         let status = response.status();
         if status.as_u16() == 401 || status.as_u16() == 403 {
@@ -579,6 +596,7 @@ impl MiniMaxClient {
         let mut tool_call_accumulators: Vec<MiniMaxToolCallAccumulator> = Vec::new();
         let mut full_tool_calls: Vec<MiniMaxToolCall> = Vec::new();
         let mut saw_content_delta = false;
+        let mut usage: Option<TokenUsage> = None;
 
         let mut stream = response.bytes_stream();
         let mut buffer = String::new();
@@ -602,6 +620,7 @@ impl MiniMaxClient {
                     &mut tool_call_accumulators,
                     &mut full_tool_calls,
                     &mut saw_content_delta,
+                    &mut usage,
                     on_delta,
                 )? {
                     done = true;
@@ -622,6 +641,7 @@ impl MiniMaxClient {
                 &mut tool_call_accumulators,
                 &mut full_tool_calls,
                 &mut saw_content_delta,
+                &mut usage,
                 on_delta,
             )?;
         }
@@ -671,6 +691,7 @@ impl MiniMaxClient {
                 Some(reasoning)
             },
             tool_calls,
+            usage,
         })
     }
 }
@@ -712,6 +733,8 @@ struct MiniMaxChatRequest {
 #[derive(Debug, Deserialize)]
 struct MiniMaxChatResponse {
     choices: Vec<MiniMaxChoice>,
+    #[serde(default)]
+    usage: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -723,6 +746,8 @@ struct MiniMaxChoice {
 struct MiniMaxStreamChunk {
     #[serde(default)]
     choices: Vec<MiniMaxStreamChoice>,
+    #[serde(default)]
+    usage: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -778,6 +803,8 @@ struct MiniMaxResponseMessage {
     reasoning_content: Option<String>,
     #[serde(default)]
     tool_calls: Option<Vec<MiniMaxToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    usage: Option<TokenUsage>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -816,6 +843,7 @@ fn process_minimax_stream_line(
     tool_call_accumulators: &mut Vec<MiniMaxToolCallAccumulator>,
     full_tool_calls: &mut Vec<MiniMaxToolCall>,
     saw_content_delta: &mut bool,
+    usage: &mut Option<TokenUsage>,
     on_delta: &mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send),
 ) -> Result<bool, ModelError> {
     let trimmed = line.trim();
@@ -847,6 +875,10 @@ fn process_minimax_stream_line(
         }
     };
 
+    if let Some(chunk_usage) = chunk.usage.as_ref().and_then(parse_token_usage) {
+        *usage = Some(chunk_usage);
+    }
+
     for choice in chunk.choices {
         if let Some(delta) = choice.delta {
             if let Some(delta_content) = delta.content {
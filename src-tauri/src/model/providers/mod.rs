@@ -1,6 +1,8 @@
 //! Model client implementations.
 
+pub mod anthropic;
 pub mod chatgpt;
+pub mod custom;
 pub mod gemini;
 pub mod glm;
 pub mod kimi;
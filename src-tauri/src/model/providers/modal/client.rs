@@ -1,4 +1,9 @@
-use crate::model::providers::openai_compat::{OpenAiCompatClient, OpenAiCompatClientConfig};
+use crate::model::discovery::DiscoveredModel;
+use crate::model::providers::openai_compat::{
+    OpenAiCompatClient, OpenAiCompatClientConfig, ResponseFormatSupport,
+};
+use crate::model::retry;
+use crate::model::ProviderId;
 
 const DEFAULT_MODAL_BASE_URL: &str = "https://api.us-west-2.modal.direct/v1";
 const DEFAULT_MODAL_MODEL: &str = "zai-org/GLM-5-FP8";
@@ -13,7 +18,12 @@ impl ModalClient {
             base_url.or_else(|| Some(DEFAULT_MODAL_BASE_URL.to_string())),
             "Modal",
             DEFAULT_MODAL_MODEL,
-            OpenAiCompatClientConfig::default(),
+            OpenAiCompatClientConfig {
+                retry_policy: retry::policy_for(ProviderId::Modal),
+                // vLLM supports guided decoding from a JSON schema
+                response_format: ResponseFormatSupport::JsonSchema,
+                ..OpenAiCompatClientConfig::default()
+            },
         ))
     }
 
@@ -21,6 +31,11 @@ impl ModalClient {
         self.0.model_id()
     }
 
+    /// Lists the models served by the endpoint, for catalog discovery.
+    pub async fn list_models(&self) -> Result<Vec<DiscoveredModel>, crate::model::ModelError> {
+        self.0.list_models().await
+    }
+
     #[allow(dead_code)]
     pub async fn complete(
         &self,
@@ -68,3 +83,26 @@ impl crate::model::AgentModelClient for ModalClient {
         self.decide_action_streaming(req, noop).await
     }
 }
+
+impl crate::model::CompletionClient for ModalClient {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, crate::model::ModelError> {
+        self.complete(system, conversation, max_tokens).await
+    }
+
+    async fn complete_json(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: &crate::model::structured::OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, crate::model::ModelError> {
+        self.0
+            .complete_json(system, conversation, schema, max_tokens)
+            .await
+    }
+}
@@ -3,13 +3,16 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::core::tool::ToolDescriptor;
+use crate::model::discovery::DiscoveredModel;
+use crate::model::retry::{send_with_retry, RetryPolicy};
 use crate::model::shared::{
-    completion_summary_from_content_or_reasoning, plan_markdown_system_prompt,
+    completion_summary_from_content_or_reasoning, parse_token_usage, plan_markdown_system_prompt,
     preferred_response_text, strip_tool_call_markup, worker_prompt_from_request,
 };
+use crate::model::structured::OutputSchema;
 use crate::model::{
-    AgentModelClient, ModelError, StreamDelta, WorkerAction, WorkerActionRequest, WorkerDecision,
-    WorkerToolCall,
+    AgentModelClient, ModelError, StreamDelta, TokenUsage, WorkerAction, WorkerActionRequest,
+    WorkerDecision, WorkerToolCall,
 };
 use crate::runtime::plan_mode_settings::{DEFAULT_PLAN_MODE_MAX_TOKENS, WORKER_MAX_TOKENS};
 
@@ -23,17 +26,39 @@ pub struct OpenAiCompatClientConfig {
     /// Function to filter/transform JSON schemas for provider compatibility
     pub schema_filter: Arc<dyn Fn(&serde_json::Value) -> serde_json::Value + Send + Sync>,
     /// Extra headers to add to requests (e.g., User-Agent, custom auth)
-    pub extra_headers: Vec<(&'static str, String)>,
+    pub extra_headers: Vec<(String, String)>,
     /// Whether to send parallel_tool_calls field (GLM doesn't support it)
     pub parallel_tool_calls: bool,
-    /// Whether to retry on 429 rate limit errors
-    pub retry_on_rate_limit: bool,
+    /// Retry/backoff for transient failures (429, 5xx, timeouts)
+    pub retry_policy: RetryPolicy,
     /// Whether to retry on GLM error 1210 (invalid parameter)
     pub retry_on_invalid_param_1210: bool,
     /// Fallback model for error 1210 retries
     pub fallback_model: Option<String>,
     /// Maximum tokens cap (if provider has a limit)
     pub max_tokens_cap: Option<u32>,
+    /// Whether to request a final usage chunk on streaming calls (`stream_options.include_usage`)
+    pub stream_usage: bool,
+    /// Whether the endpoint accepts `tools`; when false, tool descriptors are never sent
+    pub supports_tools: bool,
+    /// Response field carrying reasoning text when it is not `reasoning_content`
+    /// (e.g. `reasoning` on Ollama and some vLLM builds)
+    pub reasoning_field: Option<String>,
+    /// Which `response_format` the endpoint accepts for structured output
+    pub response_format: ResponseFormatSupport,
+}
+
+/// `response_format` support of an OpenAI-compatible endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormatSupport {
+    /// `{"type": "json_schema", ...}`: the schema itself constrains decoding.
+    JsonSchema,
+    /// `{"type": "json_object"}`: valid JSON only; the schema goes in the prompt.
+    JsonObject,
+    /// No `response_format`; JSON is requested in the prompt alone.
+    #[default]
+    None,
 }
 
 impl Default for OpenAiCompatClientConfig {
@@ -44,10 +69,14 @@ impl Default for OpenAiCompatClientConfig {
             schema_filter: Arc::new(|schema| schema.clone()),
             extra_headers: Vec::new(),
             parallel_tool_calls: true,
-            retry_on_rate_limit: false,
+            retry_policy: RetryPolicy::default(),
             retry_on_invalid_param_1210: false,
             fallback_model: None,
             max_tokens_cap: None,
+            stream_usage: true,
+            supports_tools: true,
+            reasoning_field: None,
+            response_format: ResponseFormatSupport::None,
         }
     }
 }
@@ -90,14 +119,6 @@ impl OpenAiCompatClient {
         }
     }
 
-    fn is_rate_limit_error(&self, status: reqwest::StatusCode, text: &str) -> bool {
-        status.as_u16() == 429
-            || text.contains("rate limit")
-            || text.contains("Rate limit")
-            || (self.provider_name == "GLM"
-                && (text.contains("\"code\":\"1302\"") || text.contains("\"code\":1302")))
-    }
-
     fn is_invalid_parameter_1210(&self, status: reqwest::StatusCode, text: &str) -> bool {
         if status.as_u16() != 400 {
             return false;
@@ -108,6 +129,89 @@ impl OpenAiCompatClient {
             || text.contains("\"code\" : 1210")
     }
 
+    /// Starts a JSON POST with auth and provider-specific headers applied.
+    /// The bearer header is omitted for keyless endpoints such as a local Ollama.
+    fn post(&self, endpoint: &str) -> reqwest::RequestBuilder {
+        let mut request = self
+            .client
+            .post(endpoint)
+            .header("Content-Type", "application/json");
+        if !self.api_key.is_empty() {
+            request = request.header("Authorization", format!("Bearer {}", self.api_key));
+        }
+        for (key, value) in &self.config.extra_headers {
+            request = request.header(key.as_str(), value.clone());
+        }
+        request
+    }
+
+    /// Lists the models served at `{base_url}/models`.
+    pub async fn list_models(&self) -> Result<Vec<DiscoveredModel>, ModelError> {
+        let endpoint = format!("{}/models", self.base_url.trim_end_matches('/'));
+        let mut request = self.client.get(&endpoint);
+        if !self.api_key.is_empty() {
+            request = request.header("Authorization", format!("Bearer {}", self.api_key));
+        }
+        for (key, value) in &self.config.extra_headers {
+            request = request.header(key.as_str(), value.clone());
+        }
+
+        let response = self.send(request).await?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| ModelError::Request(e.to_string()))?;
+
+        if status.as_u16() == 401 || status.as_u16() == 403 {
+            return Err(ModelError::Auth(format!(
+                "{} auth failed ({}). Check API key and account access.",
+                self.provider_name, status
+            )));
+        }
+        if !status.is_success() {
+            return Err(ModelError::Request(format!(
+                "{} model listing error {}: {}",
+                self.provider_name, status, text
+            )));
+        }
+
+        let parsed: OpenAiModelList = serde_json::from_str(&text).map_err(|e| {
+            ModelError::InvalidResponse(format!(
+                "{} model listing parse failed: {}",
+                self.provider_name, e
+            ))
+        })?;
+        Ok(parsed
+            .data
+            .into_iter()
+            .map(|model| DiscoveredModel {
+                id: model.id,
+                display_name: None,
+                context_window: model
+                    .context_length
+                    .or(model.context_window)
+                    .or(model.max_model_len),
+                output_limit: None,
+            })
+            .collect())
+    }
+
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, ModelError> {
+        send_with_retry(&self.config.retry_policy, self.provider_name, request).await
+    }
+
+    fn tools_for_request<'a>(&self, tools: &'a [ToolDescriptor]) -> Option<&'a [ToolDescriptor]> {
+        if tools.is_empty() || !self.config.supports_tools {
+            None
+        } else {
+            Some(tools)
+        }
+    }
+
     pub fn model_id(&self) -> String {
         self.model.clone()
     }
@@ -119,19 +223,55 @@ impl OpenAiCompatClient {
         user: &str,
         max_tokens: u32,
     ) -> Result<String, ModelError> {
-        let response = self.run_chat(system, user, max_tokens, None).await?;
+        let response = self.run_chat(system, user, max_tokens, None, None).await?;
         Ok(preferred_response_text(
             response.content,
             response.reasoning_content,
         ))
     }
 
+    /// Completion constrained by `response_format` where the endpoint supports it.
+    pub async fn complete_json(
+        &self,
+        system: &str,
+        user: &str,
+        schema: &OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        let (system, response_format) = match self.config.response_format {
+            ResponseFormatSupport::JsonSchema => (
+                system.to_string(),
+                Some(serde_json::json!({
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": schema.name,
+                        "description": schema.description,
+                        "schema": schema.schema,
+                    },
+                })),
+            ),
+            ResponseFormatSupport::JsonObject => (
+                format!("{system}\n\n{}", schema.prompt_instructions()),
+                Some(serde_json::json!({ "type": "json_object" })),
+            ),
+            ResponseFormatSupport::None => (
+                format!("{system}\n\n{}", schema.prompt_instructions()),
+                None,
+            ),
+        };
+        let response = self
+            .run_chat(&system, user, max_tokens, None, response_format.as_ref())
+            .await?;
+        Ok(response.content.unwrap_or_default())
+    }
+
     async fn run_chat(
         &self,
         system: &str,
         user: &str,
         max_tokens: u32,
         tools: Option<&[ToolDescriptor]>,
+        response_format: Option<&serde_json::Value>,
     ) -> Result<OpenAiResponseMessage, ModelError> {
         let max_tokens = self.normalize_max_tokens(max_tokens);
         let endpoint = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
@@ -170,6 +310,7 @@ impl OpenAiCompatClient {
                 temperature: 0.1,
                 max_tokens,
                 stream: false,
+                stream_options: None,
                 tools: openai_tools,
                 tool_choice: if has_tools {
                     Some("auto".to_string())
@@ -181,27 +322,13 @@ impl OpenAiCompatClient {
                 } else {
                     None
                 },
+                response_format: response_format.cloned(),
             }
         };
 
         let body = make_body(self.model.clone());
 
-        // Build request with extra headers
-        let mut request = self
-            .client
-            .post(&endpoint)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&body);
-
-        for (key, value) in &self.config.extra_headers {
-            request = request.header(*key, value.clone());
-        }
-
-        let mut response = request
-            .send()
-            .await
-            .map_err(|e| ModelError::Request(e.to_string()))?;
+        let mut response = self.send(self.post(&endpoint).json(&body)).await?;
 
         let mut status = response.status();
         let mut text = response
@@ -211,33 +338,6 @@ impl OpenAiCompatClient {
 
         tracing::debug!("{} API response: status={}", self.provider_name, status);
 
-        // Handle rate limiting with retry
-        if self.config.retry_on_rate_limit && self.is_rate_limit_error(status, &text) {
-            let delay_ms = 2000;
-            tracing::warn!(
-                "{} rate limit hit ({}), waiting {}ms before retry",
-                self.provider_name,
-                status,
-                delay_ms
-            );
-            tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-
-            response = self
-                .client
-                .post(&endpoint)
-                .header("Content-Type", "application/json")
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .json(&body)
-                .send()
-                .await
-                .map_err(|e| ModelError::Request(e.to_string()))?;
-            status = response.status();
-            text = response
-                .text()
-                .await
-                .map_err(|e| ModelError::Request(e.to_string()))?;
-        }
-
         // Handle 1210 with model fallback
         if self.config.retry_on_invalid_param_1210 && self.is_invalid_parameter_1210(status, &text)
         {
@@ -250,15 +350,7 @@ impl OpenAiCompatClient {
                         fallback_model
                     );
                     let retry_body = make_body(fallback_model.clone());
-                    response = self
-                        .client
-                        .post(&endpoint)
-                        .header("Content-Type", "application/json")
-                        .header("Authorization", format!("Bearer {}", self.api_key))
-                        .json(&retry_body)
-                        .send()
-                        .await
-                        .map_err(|e| ModelError::Request(e.to_string()))?;
+                    response = self.send(self.post(&endpoint).json(&retry_body)).await?;
                     status = response.status();
                     text = response
                         .text()
@@ -281,15 +373,25 @@ impl OpenAiCompatClient {
             )));
         }
 
-        let parsed: OpenAiChatResponse = serde_json::from_str(&text).map_err(|e| {
+        let parse_error = |e: serde_json::Error| {
             ModelError::InvalidResponse(format!("{} parse failed: {}", self.provider_name, e))
-        })?;
+        };
+        let parsed: OpenAiChatResponse = match self.config.reasoning_field.as_deref() {
+            Some(field) => {
+                let mut value: serde_json::Value =
+                    serde_json::from_str(&text).map_err(parse_error)?;
+                rename_reasoning_field(&mut value, field);
+                serde_json::from_value(value).map_err(parse_error)?
+            }
+            None => serde_json::from_str(&text).map_err(parse_error)?,
+        };
 
+        let usage = parsed.usage.as_ref().and_then(parse_token_usage);
         parsed
             .choices
             .into_iter()
             .next()
-            .map(|c| c.message)
+            .map(|c| OpenAiResponseMessage { usage, ..c.message })
             .ok_or_else(|| {
                 ModelError::InvalidResponse(format!(
                     "missing choices[0].message from {} response",
@@ -343,6 +445,13 @@ impl OpenAiCompatClient {
                 temperature: 0.1,
                 max_tokens,
                 stream: true,
+                stream_options: if self.config.stream_usage {
+                    Some(OpenAiStreamOptions {
+                        include_usage: true,
+                    })
+                } else {
+                    None
+                },
                 tools: openai_tools,
                 tool_choice: if has_tools {
                     Some("auto".to_string())
@@ -354,63 +463,18 @@ impl OpenAiCompatClient {
                 } else {
                     None
                 },
+                response_format: None,
             }
         };
 
         let body = make_body(self.model.clone());
 
-        let mut request = self
-            .client
-            .post(&endpoint)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&body);
-
-        for (key, value) in &self.config.extra_headers {
-            request = request.header(*key, value.clone());
-        }
-
-        let response = request
-            .send()
-            .await
-            .map_err(|e| ModelError::Request(e.to_string()))?;
+        let response = self.send(self.post(&endpoint).json(&body)).await?;
 
         let initial_status = response.status();
 
-        // Handle rate limiting with retry
-        let response = if self.config.retry_on_rate_limit
-            && self.is_rate_limit_error(initial_status, "")
-        {
-            let delay_ms = 2000;
-            tracing::warn!(
-                "{} streaming rate limit hit ({}), waiting {}ms before retry",
-                self.provider_name,
-                initial_status,
-                delay_ms
-            );
-            tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-
-            let retry_response = self
-                .client
-                .post(&endpoint)
-                .header("Content-Type", "application/json")
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .json(&body)
-                .send()
-                .await
-                .map_err(|e| ModelError::Request(e.to_string()))?;
-
-            if retry_response.status().is_success() {
-                retry_response
-            } else {
-                let retry_status = retry_response.status();
-                let retry_text = retry_response.text().await.unwrap_or_default();
-                return Err(ModelError::Request(format!(
-                    "{} error {} after rate limit retry: {}",
-                    self.provider_name, retry_status, retry_text
-                )));
-            }
-        } else if self.config.retry_on_invalid_param_1210
+        // Handle 1210 with model fallback
+        let response = if self.config.retry_on_invalid_param_1210
             && self.is_invalid_parameter_1210(initial_status, "")
         {
             if let Some(ref fallback_model) = self.config.fallback_model {
@@ -422,15 +486,7 @@ impl OpenAiCompatClient {
                         fallback_model
                     );
                     let retry_body = make_body(fallback_model.clone());
-                    let retry_response = self
-                        .client
-                        .post(&endpoint)
-                        .header("Content-Type", "application/json")
-                        .header("Authorization", format!("Bearer {}", self.api_key))
-                        .json(&retry_body)
-                        .send()
-                        .await
-                        .map_err(|e| ModelError::Request(e.to_string()))?;
+                    let retry_response = self.send(self.post(&endpoint).json(&retry_body)).await?;
 
                     if retry_response.status().is_success() {
                         retry_response
@@ -480,6 +536,7 @@ impl OpenAiCompatClient {
         let mut tool_call_accumulators: Vec<OpenAiToolCallAccumulator> = Vec::new();
         let mut full_tool_calls: Vec<OpenAiToolCall> = Vec::new();
         let mut saw_content_delta = false;
+        let mut usage: Option<TokenUsage> = None;
 
         let mut stream = response.bytes_stream();
         let mut buffer = String::new();
@@ -495,6 +552,9 @@ impl OpenAiCompatClient {
                     line.pop();
                 }
                 buffer.drain(..=newline_idx);
+                if let Some(field) = self.config.reasoning_field.as_deref() {
+                    line = rename_reasoning_field_in_line(&line, field);
+                }
 
                 if process_openai_stream_line(
                     &line,
@@ -503,6 +563,7 @@ impl OpenAiCompatClient {
                     &mut tool_call_accumulators,
                     &mut full_tool_calls,
                     &mut saw_content_delta,
+                    &mut usage,
                     on_delta,
                 )? {
                     done = true;
@@ -516,6 +577,9 @@ impl OpenAiCompatClient {
         }
 
         if !done && !buffer.trim().is_empty() {
+            if let Some(field) = self.config.reasoning_field.as_deref() {
+                buffer = rename_reasoning_field_in_line(&buffer, field);
+            }
             let _ = process_openai_stream_line(
                 buffer.trim_end_matches('\r'),
                 &mut content,
@@ -523,6 +587,7 @@ impl OpenAiCompatClient {
                 &mut tool_call_accumulators,
                 &mut full_tool_calls,
                 &mut saw_content_delta,
+                &mut usage,
                 on_delta,
             )?;
         }
@@ -567,6 +632,7 @@ impl OpenAiCompatClient {
                 Some(reasoning)
             },
             tool_calls,
+            usage,
         })
     }
 
@@ -579,11 +645,7 @@ impl OpenAiCompatClient {
         F: FnMut(StreamDelta) -> Result<(), String> + Send,
     {
         let (system, user) = worker_prompt_from_request(&req)?;
-        let tools_arg = if req.tool_descriptors.is_empty() {
-            None
-        } else {
-            Some(req.tool_descriptors.as_slice())
-        };
+        let tools_arg = self.tools_for_request(&req.tool_descriptors);
         let max_tokens = req.max_tokens.unwrap_or(WORKER_MAX_TOKENS);
 
         // Handle GLM-specific fallback for plan mode (no tools retry)
@@ -609,9 +671,11 @@ impl OpenAiCompatClient {
                         "{} plan-mode fallback: retrying without tools",
                         self.provider_name
                     );
-                    self.run_chat(&system, &user, max_tokens, None).await?
+                    self.run_chat(&system, &user, max_tokens, None, None)
+                        .await?
                 } else {
-                    self.run_chat(&system, &user, max_tokens, tools_arg).await?
+                    self.run_chat(&system, &user, max_tokens, tools_arg, None)
+                        .await?
                 }
             }
             Err(err) => return Err(err),
@@ -648,17 +712,14 @@ impl OpenAiCompatClient {
             }
         );
 
-        let tools_arg = if tool_descriptors.is_empty() {
-            None
-        } else {
-            Some(tool_descriptors.as_slice())
-        };
+        let tools_arg = self.tools_for_request(&tool_descriptors);
         let response = self
             .run_chat(
                 &plan_markdown_system_prompt(),
                 &user,
                 DEFAULT_PLAN_MODE_MAX_TOKENS,
                 tools_arg,
+                None,
             )
             .await?;
 
@@ -727,6 +788,7 @@ pub fn process_openai_stream_line(
     tool_call_accumulators: &mut Vec<OpenAiToolCallAccumulator>,
     full_tool_calls: &mut Vec<OpenAiToolCall>,
     saw_content_delta: &mut bool,
+    usage: &mut Option<TokenUsage>,
     on_delta: &mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send),
 ) -> Result<bool, ModelError> {
     let trimmed = line.trim();
@@ -748,6 +810,11 @@ pub fn process_openai_stream_line(
         Err(_) => return Ok(false),
     };
 
+    // With `include_usage`, the final chunk carries the usage block and no choices.
+    if let Some(chunk_usage) = chunk.usage.as_ref().and_then(parse_token_usage) {
+        *usage = Some(chunk_usage);
+    }
+
     for choice in chunk.choices {
         if let Some(delta) = choice.delta {
             if let Some(delta_content) = delta.content {
@@ -827,6 +894,47 @@ pub fn process_openai_stream_line(
     Ok(false)
 }
 
+/// Moves a provider-specific reasoning field (e.g. `reasoning`) onto `reasoning_content`
+/// in every `choices[].delta` / `choices[].message` of a chat-completions payload.
+pub fn rename_reasoning_field(payload: &mut serde_json::Value, field: &str) {
+    if field == "reasoning_content" {
+        return;
+    }
+    let Some(choices) = payload.get_mut("choices").and_then(|c| c.as_array_mut()) else {
+        return;
+    };
+    for choice in choices {
+        for key in ["delta", "message"] {
+            let Some(obj) = choice.get_mut(key).and_then(|v| v.as_object_mut()) else {
+                continue;
+            };
+            if let Some(value) = obj.remove(field) {
+                if !obj.contains_key("reasoning_content") && value.is_string() {
+                    obj.insert("reasoning_content".to_string(), value);
+                }
+            }
+        }
+    }
+}
+
+/// Applies [`rename_reasoning_field`] to a single SSE `data:` line, passing other lines through.
+fn rename_reasoning_field_in_line(line: &str, field: &str) -> String {
+    let trimmed = line.trim();
+    let Some(payload) = trimmed.strip_prefix("data:").map(str::trim) else {
+        return line.to_string();
+    };
+    if !payload.contains(field) {
+        return line.to_string();
+    }
+    match serde_json::from_str::<serde_json::Value>(payload) {
+        Ok(mut value) => {
+            rename_reasoning_field(&mut value, field);
+            format!("data: {}", value)
+        }
+        Err(_) => line.to_string(),
+    }
+}
+
 pub fn openai_response_to_worker_decision(
     response: OpenAiResponseMessage,
     tool_descriptors: &[ToolDescriptor],
@@ -854,6 +962,7 @@ pub fn openai_response_to_worker_decision(
                     action: WorkerAction::ToolCalls { calls },
                     reasoning: response.reasoning_content,
                     raw_response,
+                    usage: response.usage,
                 };
             }
         }
@@ -869,9 +978,30 @@ pub fn openai_response_to_worker_decision(
         },
         reasoning: None,
         raw_response,
+        usage: response.usage,
     }
 }
 
+/// `GET /models` response. Servers that report a context size use one of
+/// several non-standard fields (`context_length` on OpenRouter, `max_model_len`
+/// on vLLM).
+#[derive(Debug, Deserialize)]
+struct OpenAiModelList {
+    #[serde(default)]
+    data: Vec<OpenAiModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiModelEntry {
+    id: String,
+    #[serde(default)]
+    context_length: Option<u32>,
+    #[serde(default)]
+    context_window: Option<u32>,
+    #[serde(default)]
+    max_model_len: Option<u32>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct OpenAiChatRequest {
     pub model: String,
@@ -880,11 +1010,20 @@ pub struct OpenAiChatRequest {
     pub max_tokens: u32,
     pub stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<OpenAiStreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<OpenAiTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpenAiStreamOptions {
+    pub include_usage: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -915,12 +1054,17 @@ pub struct OpenAiResponseMessage {
     pub reasoning_content: Option<String>,
     #[serde(default)]
     pub tool_calls: Option<Vec<OpenAiToolCall>>,
+    /// Filled from the response-level `usage` block; never present on the wire message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
 }
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 pub struct OpenAiChatResponse {
     pub choices: Vec<OpenAiChoice>,
+    #[serde(default)]
+    pub usage: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -933,6 +1077,8 @@ pub struct OpenAiChoice {
 pub struct OpenAiStreamChunk {
     #[serde(default)]
     pub choices: Vec<OpenAiStreamChoice>,
+    #[serde(default)]
+    pub usage: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
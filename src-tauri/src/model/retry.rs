@@ -0,0 +1,354 @@
+//! Shared retry and backoff for provider HTTP requests.
+//!
+//! Every model client sends its requests through `send_with_retry`. Transient
+//! failures (connect errors, timeouts, 408, 429, 5xx and Anthropic's 529) are
+//! retried up to `RetryPolicy::max_attempts`. A `Retry-After` header takes
+//! precedence over the computed delay; otherwise the delay doubles from
+//! `initial_backoff_ms` with random jitter, capped at `max_backoff_ms`.
+//!
+//! Retries only happen before a response body is read, so a streaming call
+//! never replays deltas it has already delivered. Policies are configured per
+//! provider through `set_provider_config` and cached in this module.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{ModelError, ProviderId};
+
+const MAX_ATTEMPTS_LIMIT: u32 = 10;
+
+/// How a provider's requests are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts including the first request; `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry when the server gives no `Retry-After`.
+    pub initial_backoff_ms: u64,
+    /// Upper bound for the exponential backoff.
+    pub max_backoff_ms: u64,
+    /// Fraction of each backoff that is randomised, from 0.0 to 1.0.
+    pub jitter: f64,
+    /// Longest `Retry-After` worth waiting for. Longer hints (exhausted daily
+    /// quota, for example) fail immediately so fallback models can take over.
+    pub max_retry_after_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 1_000,
+            max_backoff_ms: 30_000,
+            jitter: 0.2,
+            max_retry_after_ms: 60_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_attempts == 0 || self.max_attempts > MAX_ATTEMPTS_LIMIT {
+            return Err(format!(
+                "max_attempts must be between 1 and {MAX_ATTEMPTS_LIMIT}"
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err("jitter must be between 0.0 and 1.0".to_string());
+        }
+        if self.initial_backoff_ms > self.max_backoff_ms {
+            return Err("initial_backoff_ms cannot exceed max_backoff_ms".to_string());
+        }
+        Ok(())
+    }
+
+    /// Backoff before retry number `retry` (1 for the first retry).
+    fn backoff_delay(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(16);
+        let base = self
+            .initial_backoff_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_backoff_ms) as f64;
+        let factor = if self.jitter > 0.0 {
+            rand::thread_rng().gen_range(1.0 - self.jitter..=1.0 + self.jitter)
+        } else {
+            1.0
+        };
+        let delay_ms = (base * factor).round() as u64;
+        Duration::from_millis(delay_ms.min(self.max_backoff_ms))
+    }
+}
+
+/// Statuses worth retrying: the request may succeed unchanged a moment later.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 408 | 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+/// Parse `Retry-After` as delay-seconds or an HTTP date.
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        at.with_timezone(&Utc)
+            .signed_duration_since(now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, Utc::now())
+}
+
+static PROVIDER_POLICIES: OnceLock<DashMap<ProviderId, RetryPolicy>> = OnceLock::new();
+
+fn policies() -> &'static DashMap<ProviderId, RetryPolicy> {
+    PROVIDER_POLICIES.get_or_init(DashMap::new)
+}
+
+/// Retry policy for a provider: its configured policy, or the default.
+pub fn policy_for(provider: ProviderId) -> RetryPolicy {
+    policies()
+        .get(&provider)
+        .map(|policy| *policy)
+        .unwrap_or_default()
+}
+
+/// Replace (or with `None`, reset) the cached policy for a provider.
+pub fn set_provider_policy(provider: ProviderId, policy: Option<RetryPolicy>) {
+    match policy {
+        Some(policy) => {
+            policies().insert(provider, policy);
+        }
+        None => {
+            policies().remove(&provider);
+        }
+    }
+}
+
+/// Send `request`, retrying transient failures according to `policy`.
+///
+/// The final response is returned whatever its status so callers keep their
+/// provider-specific error mapping. Requests whose body cannot be cloned are
+/// sent once.
+pub async fn send_with_retry(
+    policy: &RetryPolicy,
+    provider_name: &str,
+    request: RequestBuilder,
+) -> Result<Response, ModelError> {
+    let max_attempts = policy.max_attempts.clamp(1, MAX_ATTEMPTS_LIMIT);
+    let mut attempt: u32 = 1;
+
+    loop {
+        let retryable = if attempt < max_attempts {
+            request.try_clone()
+        } else {
+            None
+        };
+        let Some(current) = retryable else {
+            return request
+                .send()
+                .await
+                .map_err(|e| ModelError::Request(e.to_string()));
+        };
+
+        let delay = match current.send().await {
+            Ok(response) if is_retryable_status(response.status()) => {
+                let status = response.status();
+                let delay = match retry_after(response.headers()) {
+                    Some(hint) if hint > Duration::from_millis(policy.max_retry_after_ms) => {
+                        tracing::warn!(
+                            "{provider_name} returned {status} with Retry-After {}s; not retrying",
+                            hint.as_secs()
+                        );
+                        return Ok(response);
+                    }
+                    Some(hint) => hint,
+                    None => policy.backoff_delay(attempt),
+                };
+                tracing::warn!(
+                    "{provider_name} returned {status} (attempt {attempt}/{max_attempts}), retrying in {}ms",
+                    delay.as_millis()
+                );
+                delay
+            }
+            Ok(response) => return Ok(response),
+            Err(error) if error.is_timeout() || error.is_connect() => {
+                let delay = policy.backoff_delay(attempt);
+                tracing::warn!(
+                    "{provider_name} request failed (attempt {attempt}/{max_attempts}): {error}; retrying in {}ms",
+                    delay.as_millis()
+                );
+                delay
+            }
+            Err(error) => return Err(ModelError::Request(error.to_string())),
+        };
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 40,
+            jitter: 0.0,
+            max_retry_after_ms: 5_000,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        let policy = fast_policy(5);
+        assert_eq!(policy.backoff_delay(1), Duration::from_millis(10));
+        assert_eq!(policy.backoff_delay(2), Duration::from_millis(20));
+        assert_eq!(policy.backoff_delay(3), Duration::from_millis(40));
+        assert_eq!(policy.backoff_delay(9), Duration::from_millis(40));
+
+        let jittered = RetryPolicy {
+            jitter: 0.5,
+            max_backoff_ms: 10_000,
+            initial_backoff_ms: 1_000,
+            ..fast_policy(3)
+        };
+        for _ in 0..50 {
+            let delay = jittered.backoff_delay(1).as_millis();
+            assert!((500..=1_500).contains(&delay), "delay {delay} out of range");
+        }
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_and_dates() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2026 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_retry_after("7", now), Some(Duration::from_secs(7)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_values() {
+        assert!(RetryPolicy::default().validate().is_ok());
+        assert!(fast_policy(0).validate().is_err());
+        assert!(fast_policy(MAX_ATTEMPTS_LIMIT + 1).validate().is_err());
+        assert!(RetryPolicy {
+            jitter: 1.5,
+            ..RetryPolicy::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts_on_503() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/chat");
+            then.status(503).body("overloaded");
+        });
+
+        let request = reqwest::Client::new()
+            .post(server.url("/chat"))
+            .json(&serde_json::json!({"ping": true}));
+        let response = send_with_retry(&fast_policy(3), "Test", request)
+            .await
+            .expect("final response");
+
+        assert_eq!(response.status().as_u16(), 503);
+        mock.assert_hits(3);
+    }
+
+    #[tokio::test]
+    async fn honors_retry_after_then_succeeds() {
+        let server = MockServer::start();
+        let limited = server.mock(|when, then| {
+            when.method(POST).path("/chat");
+            then.status(429)
+                .header("Retry-After", "1")
+                .body("rate limited");
+        });
+
+        let request = reqwest::Client::new()
+            .post(server.url("/chat"))
+            .json(&serde_json::json!({"ping": true}));
+        let started = std::time::Instant::now();
+        let pending =
+            tokio::spawn(async move { send_with_retry(&fast_policy(3), "Test", request).await });
+
+        while limited.hits_async().await == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        limited.delete_async().await;
+        let ok = server.mock(|when, then| {
+            when.method(POST).path("/chat");
+            then.status(200).body("ok");
+        });
+
+        let response = pending.await.unwrap().expect("retried response");
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        ok.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn long_retry_after_is_returned_without_waiting() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/chat");
+            then.status(429)
+                .header("Retry-After", "3600")
+                .body("quota exhausted");
+        });
+
+        let request = reqwest::Client::new().post(server.url("/chat"));
+        let response = send_with_retry(&fast_policy(3), "Test", request)
+            .await
+            .expect("response");
+
+        assert_eq!(response.status().as_u16(), 429);
+        mock.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/chat");
+            then.status(400).body("bad request");
+        });
+
+        let request = reqwest::Client::new().post(server.url("/chat"));
+        let response = send_with_retry(&fast_policy(3), "Test", request)
+            .await
+            .expect("response");
+
+        assert_eq!(response.status().as_u16(), 400);
+        mock.assert_hits(1);
+    }
+}
@@ -110,7 +110,9 @@ You have access to these read-only and planning tools:
 - `fs.list` - List directory contents
 - `search.rg` - Search file contents (ripgrep). Use `json_output: true` for structured results.
 - `search.files` - Fuzzy file name search. Quickly find files by partial name.
-- `git.status`, `git.diff`, `git.log` - Git operations (read-only)
+- `git.status`, `git.diff`, `git.log`, `git.blame`, `git.show`, `git.conflicts` - Git operations (read-only)
+- `code.definition`, `code.references`, `code.hover`, `code.diagnostics` - Language-server code navigation and diagnostics
+- `code.outline`, `code.symbols` - Outline a file's definitions or find symbols across the workspace (tree-sitter, no build needed)
 - `skills.list_installed`, `skills.search`, `skills.load` - Discover and load skills on demand
 - `memory.list`, `memory.read` - Inspect durable auto-memory context
 - `agent.ask_user` - Ask preference/clarification multiple-choice questions when needed
@@ -118,7 +120,7 @@ You have access to these read-only and planning tools:
 - `agent.create_artifact` - **CREATE your planning artifacts here**
 - `agent.request_build_mode` - Request switch to BUILD mode
 
-**BLOCKED in PLAN mode**: `fs.write`, `fs.patch`, `cmd.exec`, `subagent.spawn`
+**BLOCKED in PLAN mode**: `fs.write`, `fs.patch`, `code.rename`, `cmd.exec`, `subagent.spawn`
 
 ## Plan Structure (Suggested)
 
@@ -427,6 +429,63 @@ pub fn completion_summary_from_content_or_reasoning(
     }
 }
 
+// ---------------------------------------------------------------------------
+// Token usage parsing
+// ---------------------------------------------------------------------------
+
+/// Parses a provider `usage` block into [`TokenUsage`].
+///
+/// Understands the chat-completions shape (`prompt_tokens`/`completion_tokens`),
+/// the Responses API shape (`input_tokens`/`output_tokens`) and Gemini's
+/// `usageMetadata` (`promptTokenCount`/`candidatesTokenCount`). Returns `None`
+/// when the value carries no recognizable counts.
+pub fn parse_token_usage(value: &serde_json::Value) -> Option<crate::model::types::TokenUsage> {
+    let obj = value.as_object()?;
+    let count = |key: &str| obj.get(key).and_then(|v| v.as_u64());
+    let nested = |outer: &str, inner: &str| {
+        obj.get(outer)
+            .and_then(|v| v.get(inner))
+            .and_then(|v| v.as_u64())
+    };
+
+    if let Some(prompt) = count("promptTokenCount") {
+        // Gemini reports thinking tokens separately from candidate tokens.
+        let reasoning = count("thoughtsTokenCount").unwrap_or(0);
+        return Some(crate::model::types::TokenUsage {
+            prompt_tokens: prompt,
+            completion_tokens: count("candidatesTokenCount").unwrap_or(0) + reasoning,
+            reasoning_tokens: reasoning,
+            cached_tokens: count("cachedContentTokenCount").unwrap_or(0),
+        });
+    }
+
+    let prompt = count("prompt_tokens").or_else(|| count("input_tokens"));
+    let completion = count("completion_tokens").or_else(|| count("output_tokens"));
+    if prompt.is_none() && completion.is_none() {
+        return None;
+    }
+
+    let reasoning = nested("completion_tokens_details", "reasoning_tokens")
+        .or_else(|| nested("output_tokens_details", "reasoning_tokens"))
+        .unwrap_or(0);
+    // Anthropic reports cache reads and writes outside `input_tokens`.
+    let cache_read = count("cache_read_input_tokens");
+    let cache_write = count("cache_creation_input_tokens").unwrap_or(0);
+    let cached = nested("prompt_tokens_details", "cached_tokens")
+        .or_else(|| nested("input_tokens_details", "cached_tokens"))
+        .or_else(|| count("cached_tokens"))
+        .or_else(|| count("prompt_cache_hit_tokens"))
+        .or(cache_read)
+        .unwrap_or(0);
+
+    Some(crate::model::types::TokenUsage {
+        prompt_tokens: prompt.unwrap_or(0) + cache_read.unwrap_or(0) + cache_write,
+        completion_tokens: completion.unwrap_or(0),
+        reasoning_tokens: reasoning,
+        cached_tokens: cached,
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Strip tool-call markup from model output
 // ---------------------------------------------------------------------------
@@ -549,7 +608,7 @@ pub fn strip_tool_call_markup(text: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{preferred_response_text, strip_tool_call_markup};
+    use super::{parse_token_usage, preferred_response_text, strip_tool_call_markup};
 
     #[test]
     fn preferred_response_text_falls_back_to_reasoning_when_content_empty() {
@@ -571,6 +630,64 @@ mod tests {
         assert_eq!(text, "{\"quarter_strategies\":[\"premium_focus\"]}");
     }
 
+    #[test]
+    fn parse_token_usage_reads_chat_completions_shape() {
+        let usage = parse_token_usage(&serde_json::json!({
+            "prompt_tokens": 1200,
+            "completion_tokens": 300,
+            "total_tokens": 1500,
+            "prompt_tokens_details": {"cached_tokens": 1024},
+            "completion_tokens_details": {"reasoning_tokens": 120}
+        }))
+        .expect("usage");
+
+        assert_eq!(usage.prompt_tokens, 1200);
+        assert_eq!(usage.completion_tokens, 300);
+        assert_eq!(usage.reasoning_tokens, 120);
+        assert_eq!(usage.cached_tokens, 1024);
+        assert_eq!(usage.total_tokens(), 1500);
+    }
+
+    #[test]
+    fn parse_token_usage_reads_responses_and_gemini_shapes() {
+        let responses = parse_token_usage(&serde_json::json!({
+            "input_tokens": 50,
+            "input_tokens_details": {"cached_tokens": 10},
+            "output_tokens": 20,
+            "output_tokens_details": {"reasoning_tokens": 5}
+        }))
+        .expect("responses usage");
+        assert_eq!(responses.prompt_tokens, 50);
+        assert_eq!(responses.cached_tokens, 10);
+        assert_eq!(responses.reasoning_tokens, 5);
+
+        let gemini = parse_token_usage(&serde_json::json!({
+            "promptTokenCount": 40,
+            "candidatesTokenCount": 12,
+            "thoughtsTokenCount": 8,
+            "totalTokenCount": 60
+        }))
+        .expect("gemini usage");
+        assert_eq!(gemini.completion_tokens, 20);
+        assert_eq!(gemini.reasoning_tokens, 8);
+
+        assert!(parse_token_usage(&serde_json::json!({"total_tokens": 3})).is_none());
+    }
+
+    #[test]
+    fn parse_token_usage_folds_anthropic_cache_counts_into_prompt() {
+        let usage = parse_token_usage(&serde_json::json!({
+            "input_tokens": 30,
+            "cache_creation_input_tokens": 200,
+            "cache_read_input_tokens": 1000,
+            "output_tokens": 45
+        }))
+        .expect("anthropic usage");
+        assert_eq!(usage.prompt_tokens, 1230);
+        assert_eq!(usage.cached_tokens, 1000);
+        assert_eq!(usage.completion_tokens, 45);
+    }
+
     #[test]
     fn strip_tool_call_markup_removes_minimax_tool_call_syntax() {
         // Exact pattern that can leak from MiniMax into reasoning_content or content
@@ -0,0 +1,352 @@
+//! Structured (JSON-schema) output for auxiliary model calls.
+//!
+//! `complete_structured` asks a model for a value matching an `OutputSchema`
+//! and returns it as a typed struct. Providers constrain the response
+//! natively where they can (OpenAI-style `response_format`, a forced tool
+//! call on Anthropic, `responseSchema` on Gemini, `text.format` on ChatGPT);
+//! the rest are asked for JSON in the prompt. Every response is validated
+//! against the schema either way, and invalid output is sent back to the model
+//! with the validation error for a bounded number of repair attempts.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::model::{CompletionClient, ModelError};
+
+/// Repair round-trips after the first invalid response.
+const MAX_REPAIR_ATTEMPTS: usize = 2;
+
+/// A named JSON schema the response must conform to.
+#[derive(Debug, Clone)]
+pub struct OutputSchema {
+    /// Identifier used as the `response_format` / tool name (`[a-zA-Z0-9_-]`).
+    pub name: String,
+    pub description: String,
+    pub schema: Value,
+}
+
+impl OutputSchema {
+    pub fn new(name: &str, description: &str, schema: Value) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            schema,
+        }
+    }
+
+    /// Instructions appended to the system prompt when the provider cannot
+    /// carry the schema itself.
+    pub fn prompt_instructions(&self) -> String {
+        format!(
+            "Respond with a single JSON object and nothing else (no prose, no code fences). \
+             It must conform to this JSON schema ({}):\n{}",
+            self.description,
+            serde_json::to_string_pretty(&self.schema).unwrap_or_default()
+        )
+    }
+}
+
+/// Ask `client` for a value conforming to `schema` and deserialize it as `T`.
+pub async fn complete_structured<T, C>(
+    client: &C,
+    system: &str,
+    conversation: &str,
+    schema: &OutputSchema,
+    max_tokens: u32,
+) -> Result<T, ModelError>
+where
+    T: DeserializeOwned,
+    C: CompletionClient,
+{
+    let mut raw = client
+        .complete_json(system, conversation, schema, max_tokens)
+        .await?;
+    let mut attempt = 0;
+
+    loop {
+        let error = match parse_and_validate(&raw, &schema.schema) {
+            Ok(value) => match serde_json::from_value::<T>(value) {
+                Ok(typed) => return Ok(typed),
+                Err(e) => e.to_string(),
+            },
+            Err(e) => e,
+        };
+
+        if attempt >= MAX_REPAIR_ATTEMPTS {
+            return Err(ModelError::InvalidResponse(format!(
+                "{} output did not match its schema after {} repair attempts: {}",
+                schema.name, MAX_REPAIR_ATTEMPTS, error
+            )));
+        }
+        attempt += 1;
+        tracing::debug!(
+            "{} output invalid ({error}); repair attempt {attempt}/{MAX_REPAIR_ATTEMPTS}",
+            schema.name
+        );
+
+        let repair = format!(
+            "{conversation}\n\n---\nYour previous response was not valid: {error}\n\n\
+             Previous response:\n{raw}\n\n\
+             Return the corrected JSON object only."
+        );
+        raw = client
+            .complete_json(system, &repair, schema, max_tokens)
+            .await?;
+    }
+}
+
+/// Extract the JSON value from a model response and check it against `schema`.
+pub fn parse_and_validate(raw: &str, schema: &Value) -> Result<Value, String> {
+    let value = extract_json(raw).ok_or_else(|| "response contains no JSON object".to_string())?;
+    validate(&value, schema, "$")?;
+    Ok(value)
+}
+
+/// Parse a JSON object from model text, tolerating code fences and prose
+/// around it.
+fn extract_json(raw: &str) -> Option<Value> {
+    let trimmed = raw.trim();
+    if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+        return Some(value);
+    }
+    let start = trimmed.find('{')?;
+    let end = trimmed.rfind('}')?;
+    if end <= start {
+        return None;
+    }
+    serde_json::from_str(&trimmed[start..=end]).ok()
+}
+
+/// Validate `value` against the subset of JSON Schema used for structured
+/// output: `type`, `properties`, `required`, `additionalProperties: false`,
+/// `items`, `minItems` and `enum`.
+fn validate(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|name| matches_type(value, name)) {
+            return Err(format!(
+                "{path} should be {} but is {}",
+                types.join(" or "),
+                type_name(value)
+            ));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return Err(format!(
+                "{path} must be one of {}",
+                Value::Array(allowed.clone())
+            ));
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(key) {
+                        return Err(format!("{path} is missing required field `{key}`"));
+                    }
+                }
+            }
+            for (key, field) in object {
+                match properties.and_then(|props| props.get(key)) {
+                    Some(field_schema) => validate(field, field_schema, &format!("{path}.{key}"))?,
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        return Err(format!("{path} has unexpected field `{key}`"));
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    return Err(format!("{path} needs at least {min} item(s)"));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (idx, item) in items.iter().enumerate() {
+                    validate(item, item_schema, &format!("{path}[{idx}]"))?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn matches_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    fn step_schema() -> OutputSchema {
+        OutputSchema::new(
+            "steps",
+            "ordered steps",
+            json!({
+                "type": "object",
+                "properties": {
+                    "steps": {
+                        "type": "array",
+                        "minItems": 1,
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "title": {"type": "string"},
+                                "kind": {"type": "string", "enum": ["edit", "test"]}
+                            },
+                            "required": ["title"],
+                            "additionalProperties": false
+                        }
+                    }
+                },
+                "required": ["steps"]
+            }),
+        )
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Steps {
+        steps: Vec<Step>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Step {
+        title: String,
+    }
+
+    /// Replays canned responses and records the conversations it was sent.
+    struct ScriptedClient {
+        responses: Mutex<Vec<&'static str>>,
+        seen: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedClient {
+        fn new(mut responses: Vec<&'static str>) -> Self {
+            responses.reverse();
+            Self {
+                responses: Mutex::new(responses),
+                seen: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl CompletionClient for ScriptedClient {
+        async fn complete(
+            &self,
+            _system: &str,
+            conversation: &str,
+            _max_tokens: u32,
+        ) -> Result<String, ModelError> {
+            self.seen.lock().unwrap().push(conversation.to_string());
+            self.responses
+                .lock()
+                .unwrap()
+                .pop()
+                .map(str::to_string)
+                .ok_or_else(|| ModelError::Request("script exhausted".to_string()))
+        }
+    }
+
+    #[test]
+    fn validation_reports_the_offending_path() {
+        let schema = step_schema().schema;
+        assert!(parse_and_validate(r#"{"steps":[{"title":"a","kind":"edit"}]}"#, &schema).is_ok());
+
+        let missing = parse_and_validate(r#"{"steps":[{"kind":"edit"}]}"#, &schema).unwrap_err();
+        assert!(
+            missing.contains("$.steps[0]") && missing.contains("title"),
+            "{missing}"
+        );
+
+        let bad_enum = parse_and_validate(r#"{"steps":[{"title":"a","kind":"deploy"}]}"#, &schema)
+            .unwrap_err();
+        assert!(bad_enum.contains("$.steps[0].kind"), "{bad_enum}");
+
+        let empty = parse_and_validate(r#"{"steps":[]}"#, &schema).unwrap_err();
+        assert!(empty.contains("at least 1"), "{empty}");
+
+        let extra =
+            parse_and_validate(r#"{"steps":[{"title":"a","owner":"me"}]}"#, &schema).unwrap_err();
+        assert!(extra.contains("owner"), "{extra}");
+    }
+
+    #[test]
+    fn extracts_json_from_fenced_or_chatty_responses() {
+        let schema = step_schema().schema;
+        let fenced = "```json\n{\"steps\":[{\"title\":\"a\"}]}\n```";
+        assert!(parse_and_validate(fenced, &schema).is_ok());
+        let chatty = "Here is the plan: {\"steps\":[{\"title\":\"a\"}]} Hope it helps.";
+        assert!(parse_and_validate(chatty, &schema).is_ok());
+        assert!(parse_and_validate("no json here", &schema).is_err());
+    }
+
+    #[tokio::test]
+    async fn repairs_invalid_output_with_validation_feedback() {
+        let client = ScriptedClient::new(vec![
+            r#"{"steps":[{"name":"wrong field"}]}"#,
+            r#"{"steps":[{"title":"Write tests"}]}"#,
+        ]);
+
+        let steps: Steps =
+            complete_structured(&client, "system", "make a plan", &step_schema(), 512)
+                .await
+                .expect("repaired output");
+
+        assert_eq!(
+            steps,
+            Steps {
+                steps: vec![Step {
+                    title: "Write tests".to_string()
+                }]
+            }
+        );
+        let seen = client.seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert!(seen[1].contains("missing required field `title`"));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_bounded_repairs() {
+        let client = ScriptedClient::new(vec!["nope", "still nope", "never"]);
+        let result: Result<Steps, _> =
+            complete_structured(&client, "system", "make a plan", &step_schema(), 512).await;
+
+        assert!(matches!(result, Err(ModelError::InvalidResponse(_))));
+        assert_eq!(client.seen.lock().unwrap().len(), 1 + MAX_REPAIR_ATTEMPTS);
+    }
+}
@@ -1,5 +1,6 @@
 //! Traits for model clients.
 
+use crate::model::structured::OutputSchema;
 use crate::model::types::{ModelError, WorkerActionRequest, WorkerDecision};
 
 /// Core trait for agent model clients.
@@ -10,3 +11,34 @@ pub trait AgentModelClient: Send + Sync {
     fn model_id(&self) -> String;
     async fn decide_action(&self, req: WorkerActionRequest) -> Result<WorkerDecision, ModelError>;
 }
+
+/// Plain text completion: system prompt and conversation in, text out.
+///
+/// Every provider implements this so auxiliary jobs (summaries, prompt
+/// suggestions, titles, commit messages) work with whichever provider is
+/// configured. No tools and no worker protocol are involved.
+#[allow(async_fn_in_trait)]
+pub trait CompletionClient: Send + Sync {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, ModelError>;
+
+    /// Completion whose text should be a JSON value matching `schema`.
+    ///
+    /// Providers override this with their native mechanism; the default only
+    /// describes the schema in the system prompt. The result is raw text and
+    /// must still be validated, see `structured::complete_structured`.
+    async fn complete_json(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: &OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        let system = format!("{system}\n\n{}", schema.prompt_instructions());
+        self.complete(&system, conversation, max_tokens).await
+    }
+}
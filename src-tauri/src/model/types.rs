@@ -3,7 +3,8 @@
 use crate::core::tool::ToolDescriptor;
 
 /// Delta type for streaming callbacks.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", content = "text", rename_all = "snake_case")]
 pub enum StreamDelta {
     /// Content/message text delta
     Content(String),
@@ -52,16 +53,43 @@ pub enum WorkerAction {
     },
 }
 
+/// Token counts reported by a provider for a single model call.
+///
+/// `completion_tokens` includes any reasoning tokens and `prompt_tokens` includes
+/// any cached tokens, so the totals match what providers bill for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub reasoning_tokens: u64,
+    pub cached_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.reasoning_tokens += other.reasoning_tokens;
+        self.cached_tokens += other.cached_tokens;
+    }
+}
+
 /// Wraps a `WorkerAction` together with optional model reasoning/thinking content.
 /// The reasoning comes from the model's chain-of-thought (e.g. MiniMax `reasoning_content`)
 /// and should be forwarded to the UI separately from the action itself.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct WorkerDecision {
     pub action: WorkerAction,
     /// Model's chain-of-thought reasoning, if the provider returned it.
     pub reasoning: Option<String>,
     /// Raw response from the provider for debugging purposes.
     pub raw_response: Option<String>,
+    /// Token usage for the call that produced this decision, if the provider reported it.
+    pub usage: Option<TokenUsage>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -0,0 +1,854 @@
+//! Argument-aware command rules.
+//!
+//! A command is matched against declarative rules on its binary, its
+//! arguments, its working directory and whether it reads from a pipe. Every
+//! matching rule contributes its decision and the most restrictive one wins
+//! (deny over needs-approval over allow), so a workspace can allow extra
+//! toolchains without being able to lift the built-in denies. Commands no rule
+//! matches are denied.
+//!
+//! Shell strings are split into pipeline stages and `&&` / `||` / `;` chains
+//! and every stage is checked. Constructs whose effect cannot be known without
+//! running them (command substitution, subshells, here-documents, a command
+//! name taken from a variable) need approval.
+
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::policy::config::{evaluate_url, NetworkRules};
+
+/// Programs that fetch the URLs they are given.
+const NETWORK_BINARIES: &[&str] = &["curl", "wget"];
+
+/// Binaries whose path arguments must stay inside the workspace.
+const DELETE_BINARIES: &[&str] = &["rm", "rmdir", "del", "rd", "unlink"];
+
+/// Outcome of a rule, ordered from least to most restrictive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleDecision {
+    Allow,
+    NeedsApproval,
+    Deny,
+}
+
+/// One declarative command rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandRule {
+    /// Binary names or glob patterns (`git`, `python*`). Matched without
+    /// directory or `.exe`, case-insensitively.
+    pub binaries: Vec<String>,
+    /// Glob patterns that must each match at least one argument.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Glob for the working directory relative to the workspace root, with
+    /// `/` separators and `.` for the root itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// When set, only match commands that do (or do not) read the output of
+    /// an earlier pipeline stage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub piped: Option<bool>,
+    pub decision: RuleDecision,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl CommandRule {
+    fn matches(&self, invocation: &CommandInvocation, relative_cwd: &str) -> bool {
+        let binary = invocation.binary_name();
+        if !self
+            .binaries
+            .iter()
+            .any(|pattern| glob_match(&pattern.to_ascii_lowercase(), &binary))
+        {
+            return false;
+        }
+        if !self
+            .args
+            .iter()
+            .all(|pattern| invocation.args.iter().any(|arg| glob_match(pattern, arg)))
+        {
+            return false;
+        }
+        if let Some(pattern) = &self.cwd {
+            if !glob_match(pattern, relative_cwd) {
+                return false;
+            }
+        }
+        if let Some(piped) = self.piped {
+            if piped != invocation.piped {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// A single command as it will be executed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandInvocation {
+    pub binary: String,
+    pub args: Vec<String>,
+    pub cwd: PathBuf,
+    /// Whether stdin is the output of an earlier pipeline stage.
+    pub piped: bool,
+    /// Files its output is redirected to (`>`, `>>`, `&>`).
+    pub redirects: Vec<PathBuf>,
+}
+
+impl CommandInvocation {
+    /// Binary without directory, `.exe` suffix or case.
+    fn binary_name(&self) -> String {
+        let name = self
+            .binary
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(&self.binary);
+        let name = name.to_ascii_lowercase();
+        name.strip_suffix(".exe").map(String::from).unwrap_or(name)
+    }
+
+    fn display(&self) -> String {
+        std::iter::once(self.binary.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Decision for a command or shell string, with the reason behind it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandVerdict {
+    pub decision: RuleDecision,
+    pub reason: String,
+}
+
+/// Effective rule set: configured rules followed by the built-in defaults.
+#[derive(Debug, Clone)]
+pub struct CommandPolicy {
+    rules: Vec<CommandRule>,
+    network: NetworkRules,
+}
+
+impl CommandPolicy {
+    pub fn new(configured: Vec<CommandRule>, network: NetworkRules) -> Self {
+        let mut rules = configured;
+        rules.extend(default_rules());
+        Self { rules, network }
+    }
+
+    pub fn evaluate(
+        &self,
+        invocation: &CommandInvocation,
+        workspace_root: &Path,
+    ) -> CommandVerdict {
+        // A `command <name> ...` wrapper runs <name>; check that instead.
+        if invocation.binary_name() == "command" {
+            if let Some((inner, rest)) = invocation.args.split_first() {
+                if !inner.starts_with('-') {
+                    return self.evaluate(
+                        &CommandInvocation {
+                            binary: inner.clone(),
+                            args: rest.to_vec(),
+                            ..invocation.clone()
+                        },
+                        workspace_root,
+                    );
+                }
+            }
+        }
+
+        let relative_cwd = workspace_relative(&invocation.cwd, workspace_root);
+        let strictest = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches(invocation, &relative_cwd))
+            .max_by_key(|rule| rule.decision);
+
+        let verdict = match strictest {
+            Some(rule) => CommandVerdict {
+                decision: rule.decision,
+                reason: match (&rule.reason, rule.decision) {
+                    (Some(reason), _) => format!("{reason}: {}", invocation.display()),
+                    (None, RuleDecision::Allow) => format!("allowed: {}", invocation.display()),
+                    (None, RuleDecision::NeedsApproval) => {
+                        format!("command requires approval: {}", invocation.display())
+                    }
+                    (None, RuleDecision::Deny) => {
+                        format!("command not allowed: {}", invocation.display())
+                    }
+                },
+            },
+            None => CommandVerdict {
+                decision: RuleDecision::Deny,
+                reason: format!("command not allowed: {}", invocation.display()),
+            },
+        };
+        if verdict.decision != RuleDecision::Deny
+            && DELETE_BINARIES.contains(&invocation.binary_name().as_str())
+            && deletes_outside(invocation, workspace_root)
+        {
+            return CommandVerdict {
+                decision: RuleDecision::Deny,
+                reason: format!(
+                    "refusing to delete outside the workspace: {}",
+                    invocation.display()
+                ),
+            };
+        }
+        if verdict.decision == RuleDecision::Deny
+            || !NETWORK_BINARIES.contains(&invocation.binary_name().as_str())
+        {
+            return verdict;
+        }
+        self.network_verdict(invocation).unwrap_or(verdict)
+    }
+
+    /// Network rules for a `curl` / `wget` invocation, when they forbid it.
+    fn network_verdict(&self, invocation: &CommandInvocation) -> Option<CommandVerdict> {
+        if self.network.enabled == Some(false) {
+            return Some(CommandVerdict {
+                decision: RuleDecision::Deny,
+                reason: format!("network access is disabled: {}", invocation.display()),
+            });
+        }
+        invocation
+            .args
+            .iter()
+            .filter(|arg| !arg.starts_with('-'))
+            .find_map(|arg| evaluate_url(&self.network, arg, false).err())
+            .map(|reason| CommandVerdict {
+                decision: RuleDecision::Deny,
+                reason,
+            })
+    }
+
+    /// Check every stage of a shell command; the strictest stage decides.
+    pub fn evaluate_shell(
+        &self,
+        command: &str,
+        cwd: &Path,
+        workspace_root: &Path,
+    ) -> CommandVerdict {
+        let invocations = match parse_shell_command(command, cwd) {
+            Ok(invocations) => invocations,
+            Err(reason) => {
+                return CommandVerdict {
+                    decision: RuleDecision::NeedsApproval,
+                    reason: format!("{reason}: {command}"),
+                }
+            }
+        };
+        if invocations.is_empty() {
+            return CommandVerdict {
+                decision: RuleDecision::Deny,
+                reason: "empty command".to_string(),
+            };
+        }
+
+        invocations
+            .iter()
+            .map(|invocation| self.evaluate(invocation, workspace_root))
+            .reduce(|strictest, verdict| {
+                if verdict.decision > strictest.decision {
+                    verdict
+                } else {
+                    strictest
+                }
+            })
+            .expect("at least one invocation")
+    }
+}
+
+fn rule(
+    binaries: &[&str],
+    args: &[&str],
+    piped: Option<bool>,
+    decision: RuleDecision,
+    reason: Option<&str>,
+) -> CommandRule {
+    CommandRule {
+        binaries: binaries.iter().map(|value| value.to_string()).collect(),
+        args: args.iter().map(|value| value.to_string()).collect(),
+        cwd: None,
+        piped,
+        decision,
+        reason: reason.map(String::from),
+    }
+}
+
+/// Binaries allowed with any arguments unless a stricter rule matches.
+const DEFAULT_ALLOWED_BINARIES: &[&str] = &[
+    // Version control
+    "git",
+    // Search
+    "rg",
+    // Rust toolchain
+    "cargo",
+    "rustc",
+    "rustup",
+    // JavaScript / Node toolchain
+    "bun",
+    "bunx",
+    "node",
+    "npx",
+    "npm",
+    "deno",
+    // Python
+    "python",
+    "python3",
+    "pip",
+    "pip3",
+    "uv",
+    // File operations (needed for project scaffolding)
+    "mkdir",
+    "cp",
+    "mv",
+    "rm",
+    "ls",
+    "cat",
+    "touch",
+    "cd",
+    // Windows equivalents
+    "xcopy",
+    "robocopy",
+    "dir",
+    "del",
+    "copy",
+    "move",
+    "type",
+    "rmdir",
+    // Shell built-ins (used for command availability checks)
+    "command",
+    // Common dev tools
+    "echo",
+    "tar",
+    "unzip",
+    "zip",
+    "curl",
+    "wget",
+    "which",
+    "make",
+    "cmake",
+    "docker",
+    "docker-compose",
+    // Testing / linting
+    "jest",
+    "vitest",
+    "eslint",
+    "prettier",
+    "tsc",
+];
+
+/// Programs that execute whatever they read on stdin.
+const INTERPRETERS: &[&str] = &[
+    "sh",
+    "bash",
+    "zsh",
+    "dash",
+    "fish",
+    "python",
+    "python3",
+    "node",
+    "deno",
+    "bun",
+    "perl",
+    "ruby",
+    "php",
+    "cmd",
+    "powershell",
+    "pwsh",
+];
+
+/// Global defaults applied after any workspace rules.
+pub fn default_rules() -> Vec<CommandRule> {
+    use RuleDecision::{Deny, NeedsApproval};
+
+    let mut rules = vec![rule(
+        DEFAULT_ALLOWED_BINARIES,
+        &[],
+        None,
+        RuleDecision::Allow,
+        None,
+    )];
+
+    // Drive roots do not resolve as paths off Windows, so the workspace
+    // check in `evaluate` misses them; `?` stands for a single drive letter.
+    for target in ["?:", "?:\\", "?:/"] {
+        rules.push(rule(
+            &["rm", "rmdir", "del"],
+            &[target],
+            None,
+            Deny,
+            Some("refusing to delete a filesystem or home root"),
+        ));
+    }
+    rules.push(rule(
+        INTERPRETERS,
+        &[],
+        Some(true),
+        Deny,
+        Some("piping into an interpreter runs unreviewed code"),
+    ));
+    rules.push(rule(
+        &["sudo", "su", "doas", "runas"],
+        &[],
+        None,
+        Deny,
+        Some("privilege escalation is not allowed"),
+    ));
+
+    for flag in ["--force", "--force-with-lease*", "-f", "+*"] {
+        rules.push(rule(
+            &["git"],
+            &["push", flag],
+            None,
+            NeedsApproval,
+            Some("force push rewrites remote history"),
+        ));
+    }
+    rules.push(rule(
+        &["git"],
+        &["reset", "--hard"],
+        None,
+        NeedsApproval,
+        Some("hard reset discards uncommitted work"),
+    ));
+    rules.push(rule(
+        &["git"],
+        &["clean", "-*f*"],
+        None,
+        NeedsApproval,
+        Some("git clean deletes untracked files"),
+    ));
+    rules.push(rule(
+        &["npm", "bun", "cargo", "uv"],
+        &["publish"],
+        None,
+        NeedsApproval,
+        Some("publishing a package is outward-facing"),
+    ));
+    rules.push(rule(
+        &["docker"],
+        &["--privileged"],
+        None,
+        NeedsApproval,
+        Some("privileged containers can modify the host"),
+    ));
+    for flag in ["/c", "/C", "/k", "/K"] {
+        rules.push(rule(
+            &["cmd"],
+            &[flag],
+            None,
+            NeedsApproval,
+            Some("nested shell commands cannot be checked"),
+        ));
+    }
+    for flag in ["-c*", "-C*", "-e*", "-E*"] {
+        rules.push(rule(
+            &["powershell", "pwsh"],
+            &[flag],
+            None,
+            NeedsApproval,
+            Some("nested shell commands cannot be checked"),
+        ));
+    }
+
+    rules
+}
+
+/// Path relative to the workspace root, `/`-separated, `.` for the root.
+/// Paths outside the workspace are returned as-is.
+pub(crate) fn workspace_relative(cwd: &Path, workspace_root: &Path) -> String {
+    let relative = cwd
+        .strip_prefix(workspace_root)
+        .map(Path::to_path_buf)
+        .or_else(|_| {
+            // One side may be canonicalized (`\\?\` prefixes, symlinked temp dirs)
+            let cwd = cwd.canonicalize().map_err(|_| ())?;
+            let root = workspace_root.canonicalize().map_err(|_| ())?;
+            cwd.strip_prefix(root)
+                .map(Path::to_path_buf)
+                .map_err(|_| ())
+        })
+        .unwrap_or_else(|_| cwd.to_path_buf());
+    let text = relative.to_string_lossy().replace('\\', "/");
+    let text = text.trim_matches('/');
+    if text.is_empty() {
+        ".".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Glob match where `*` matches any run of characters and `?` one character.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Pipe,
+    Sequence,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    /// Target of an output redirection.
+    Redirect(String),
+    Operator(Operator),
+}
+
+/// What the next word is when it follows a redirection operator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RedirectKind {
+    Input,
+    Output,
+}
+
+/// Split a POSIX-style shell command into the commands it runs.
+///
+/// Quotes and escapes are honoured, output redirection targets are kept
+/// with the command they belong to, leading `NAME=value` assignments are
+/// skipped and `cd` changes the working directory of later commands. Returns
+/// an error for constructs that cannot be checked statically.
+pub fn parse_shell_command(command: &str, cwd: &Path) -> Result<Vec<CommandInvocation>, String> {
+    let mut invocations = Vec::new();
+    let mut cwd = cwd.to_path_buf();
+    let mut words: Vec<String> = Vec::new();
+    let mut redirects: Vec<String> = Vec::new();
+    let mut piped = false;
+
+    let mut finish = |words: &mut Vec<String>,
+                      redirects: &mut Vec<String>,
+                      piped: bool|
+     -> Result<(), String> {
+        let redirects = std::mem::take(redirects)
+            .into_iter()
+            .filter(|target| !is_null_device(target))
+            .map(|target| shell_path(&cwd, &target))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut parts = std::mem::take(words)
+            .into_iter()
+            .skip_while(|word| is_assignment(word));
+        let Some(binary) = parts.next() else {
+            if redirects.is_empty() {
+                return Ok(());
+            }
+            return Err("redirection without a command cannot be checked".to_string());
+        };
+        if binary.starts_with('$') || binary.contains("${") {
+            return Err("command name comes from a variable".to_string());
+        }
+        let args: Vec<String> = parts.collect();
+        let invocation = CommandInvocation {
+            binary,
+            args,
+            cwd: cwd.clone(),
+            piped,
+            redirects,
+        };
+        if invocation.binary_name() == "cd" {
+            let target = invocation.args.iter().find(|arg| {
+                *arg == "-" || !(arg.starts_with('-') || arg.eq_ignore_ascii_case("/d"))
+            });
+            cwd = match target {
+                Some(target) if target == "-" => return Err("`cd -` cannot be checked".to_string()),
+                Some(target) => shell_path(&cwd, target)?,
+                None => home_dir().ok_or("home directory is unknown")?,
+            };
+        }
+        invocations.push(invocation);
+        Ok(())
+    };
+
+    for token in tokenize(command)? {
+        match token {
+            Token::Word(word) => words.push(word),
+            Token::Redirect(target) => redirects.push(target),
+            Token::Operator(operator) => {
+                finish(&mut words, &mut redirects, piped)?;
+                piped = operator == Operator::Pipe;
+            }
+        }
+    }
+    finish(&mut words, &mut redirects, piped)?;
+
+    Ok(invocations)
+}
+
+/// Resolve a `cd` or redirection target the way the shell would, expanding
+/// `~` and `$HOME`. Other variables cannot be resolved statically.
+fn shell_path(cwd: &Path, raw: &str) -> Result<PathBuf, String> {
+    let home_relative = ["~", "$HOME", "${HOME}"].iter().find_map(|prefix| {
+        let rest = raw.strip_prefix(prefix)?;
+        (rest.is_empty() || rest.starts_with('/')).then(|| rest.trim_start_matches('/'))
+    });
+    if let Some(rest) = home_relative {
+        let home = home_dir().ok_or("home directory is unknown")?;
+        return Ok(if rest.is_empty() {
+            home
+        } else {
+            home.join(rest)
+        });
+    }
+    if raw.contains('$') {
+        return Err(format!("path {raw} comes from a variable"));
+    }
+    Ok(cwd.join(raw))
+}
+
+/// Whether a delete command names a path that is not strictly inside the
+/// workspace. Globs are taken literally, so `/*` resolves to `/` plus a
+/// child and is outside; paths from variables cannot be checked and count
+/// as outside.
+fn deletes_outside(invocation: &CommandInvocation, workspace_root: &Path) -> bool {
+    let windows_switches = matches!(invocation.binary_name().as_str(), "del" | "rd");
+    let roots: Vec<PathBuf> = std::iter::once(normalize_lexically(workspace_root))
+        .chain(workspace_root.canonicalize().ok())
+        .collect();
+    let mut options_done = false;
+    invocation.args.iter().any(|arg| {
+        if !options_done {
+            if arg == "--" {
+                options_done = true;
+                return false;
+            }
+            if arg.len() > 1 && arg.starts_with('-') {
+                return false;
+            }
+            if windows_switches && arg.len() == 2 && arg.starts_with('/') {
+                return false;
+            }
+        }
+        let Ok(target) = shell_path(&invocation.cwd, arg) else {
+            return true;
+        };
+        let target = normalize_lexically(&target);
+        !roots
+            .iter()
+            .any(|root| target != *root && target.starts_with(root))
+    })
+}
+
+/// Resolve `.` and `..` components without touching the filesystem.
+pub(crate) fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+fn is_null_device(target: &str) -> bool {
+    matches!(target, "/dev/null" | "/dev/stdout" | "/dev/stderr")
+        || target.eq_ignore_ascii_case("nul")
+}
+
+fn is_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+fn tokenize(command: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = command.chars().collect();
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    // The next word is a redirection target, not an argument
+    let mut redirect: Option<RedirectKind> = None;
+    let mut i = 0;
+
+    fn flush(
+        tokens: &mut Vec<Token>,
+        word: &mut String,
+        in_word: &mut bool,
+        redirect: &mut Option<RedirectKind>,
+    ) {
+        if *in_word {
+            match redirect.take() {
+                Some(RedirectKind::Output) => tokens.push(Token::Redirect(word.clone())),
+                Some(RedirectKind::Input) => {}
+                None => tokens.push(Token::Word(word.clone())),
+            }
+        }
+        word.clear();
+        *in_word = false;
+    }
+
+    let substitution = || Err("command substitution cannot be checked".to_string());
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\'' => {
+                in_word = true;
+                i += 1;
+                while i < chars.len() && chars[i] != '\'' {
+                    word.push(chars[i]);
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err("unterminated single quote".to_string());
+                }
+            }
+            '"' => {
+                in_word = true;
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err("unterminated double quote".to_string()),
+                        Some('"') => break,
+                        Some('\\') if matches!(chars.get(i + 1), Some('"' | '\\' | '$' | '`')) => {
+                            word.push(chars[i + 1]);
+                            i += 1;
+                        }
+                        Some('`') => return substitution(),
+                        Some('$') if chars.get(i + 1) == Some(&'(') => return substitution(),
+                        Some(&ch) => word.push(ch),
+                    }
+                    i += 1;
+                }
+            }
+            '\\' => {
+                match next {
+                    Some('\n') => {}
+                    Some(escaped) => {
+                        word.push(escaped);
+                        in_word = true;
+                    }
+                    None => {}
+                }
+                i += 1;
+            }
+            '`' => return substitution(),
+            '$' if next == Some('(') => return substitution(),
+            '(' | ')' => return Err("subshells cannot be checked".to_string()),
+            '#' if !in_word => break,
+            ' ' | '\t' | '\r' => flush(&mut tokens, &mut word, &mut in_word, &mut redirect),
+            '\n' | ';' => {
+                flush(&mut tokens, &mut word, &mut in_word, &mut redirect);
+                tokens.push(Token::Operator(Operator::Sequence));
+            }
+            '|' => {
+                flush(&mut tokens, &mut word, &mut in_word, &mut redirect);
+                match next {
+                    Some('|') => {
+                        tokens.push(Token::Operator(Operator::Sequence));
+                        i += 1;
+                    }
+                    Some('&') => {
+                        tokens.push(Token::Operator(Operator::Pipe));
+                        i += 1;
+                    }
+                    _ => tokens.push(Token::Operator(Operator::Pipe)),
+                }
+            }
+            '&' if next == Some('>') => {
+                flush(&mut tokens, &mut word, &mut in_word, &mut redirect);
+                i += 1;
+                if chars.get(i + 1) == Some(&'>') {
+                    i += 1;
+                }
+                redirect = Some(RedirectKind::Output);
+            }
+            '&' => {
+                flush(&mut tokens, &mut word, &mut in_word, &mut redirect);
+                if next == Some('&') {
+                    i += 1;
+                }
+                tokens.push(Token::Operator(Operator::Sequence));
+            }
+            '>' | '<' => {
+                // A file descriptor number directly before the operator (`2>`)
+                if in_word && !word.is_empty() && word.chars().all(|ch| ch.is_ascii_digit()) {
+                    word.clear();
+                    in_word = false;
+                } else {
+                    flush(&mut tokens, &mut word, &mut in_word, &mut redirect);
+                }
+                if c == '<' && next == Some('<') {
+                    return Err("here-documents cannot be checked".to_string());
+                }
+                if next == Some('(') {
+                    return Err("process substitution cannot be checked".to_string());
+                }
+                if next == Some(c) {
+                    i += 1;
+                }
+                if chars.get(i + 1) == Some(&'&') {
+                    // Descriptor duplication (`2>&1`, `>&2`) has no file target
+                    i += 1;
+                    while matches!(chars.get(i + 1), Some(ch) if ch.is_ascii_digit() || *ch == '-')
+                    {
+                        i += 1;
+                    }
+                } else if c == '>' {
+                    redirect = Some(RedirectKind::Output);
+                } else {
+                    redirect = Some(RedirectKind::Input);
+                }
+            }
+            _ => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+        i += 1;
+    }
+    flush(&mut tokens, &mut word, &mut in_word, &mut redirect);
+
+    if redirect.is_some() {
+        return Err("redirection without a target".to_string());
+    }
+    Ok(tokens)
+}
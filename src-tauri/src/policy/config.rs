@@ -0,0 +1,426 @@
+//! Policy configuration files.
+//!
+//! Policy is layered: built-in defaults, then the user-global file in the
+//! Orchestrix data directory, then the workspace's `.orchestrix/policy.toml`
+//! (or `policy.json`). Workspace command rules may allow extra binaries, but
+//! everything else a workspace sets can only tighten what the user allows:
+//! denied paths and hosts are unioned, network access is off if either layer
+//! turns it off, and approval requirements are combined.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::policy::commands::{glob_match, CommandRule, RuleDecision};
+use crate::policy::paths::SecretReadMode;
+
+/// Policy file names, in lookup order, inside a `.orchestrix` directory.
+const POLICY_FILE_NAMES: &[&str] = &["policy.toml", "policy.json"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyConfig {
+    /// Extra command rules, evaluated together with the built-in defaults.
+    #[serde(default)]
+    pub commands: Vec<CommandRule>,
+    #[serde(default)]
+    pub paths: PathRules,
+    #[serde(default)]
+    pub network: NetworkRules,
+    #[serde(default)]
+    pub approvals: ApprovalRules,
+    #[serde(default)]
+    pub exec: ExecRules,
+    #[serde(default)]
+    pub sandbox: SandboxRules,
+}
+
+/// Glob patterns relative to the workspace root (`/`-separated). A pattern
+/// ending in `/` covers everything below that directory. The class lists
+/// extend the built-in patterns in [`crate::policy::paths`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathRules {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny_write: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny_read: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protected: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secret: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub generated: Vec<String>,
+    /// `redact` (default) or `block`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_reads: Option<SecretReadMode>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkRules {
+    /// `false` blocks `curl`/`wget` and every non-local URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Host globs (`*.example.com`) the agent may reach. When set, `curl` and
+    /// `wget` are limited to them; `web.snapshot` may visit them in addition
+    /// to local addresses.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_hosts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApprovalRules {
+    /// Tool name globs (`git.commit`, `mcp.*`) that always need approval.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    /// Decision for paths outside the workspace; defaults to `needs_approval`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outside_workspace: Option<RuleDecision>,
+}
+
+/// Limits for `cmd.exec`. Both layers can only shorten them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecRules {
+    /// Timeout for calls that do not pass `timeout_secs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Upper bound for a per-call `timeout_secs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_timeout_secs: Option<u64>,
+}
+
+/// OS-level confinement of `cmd.exec` (Linux only, see
+/// [`crate::tools::sandbox`]). The stricter mode, the smaller limits, and a
+/// disabled network win when layers are merged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SandboxRules {
+    /// `off` (default), `auto`, or `required`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<SandboxMode>,
+    /// `false` runs commands without network access. Also off when
+    /// `network.enabled` is `false`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<bool>,
+    /// CPU time limit per command, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_secs: Option<u64>,
+    /// Address space limit per command, in MiB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u64>,
+    /// Absolute paths outside the worktree that stay writable (`~/.cargo`).
+    /// A workspace can only list paths inside ones the user listed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub writable: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxMode {
+    /// Commands run with the user's privileges.
+    #[default]
+    Off,
+    /// Confine commands when a backend is available, otherwise run them
+    /// unconfined and say so in the result.
+    Auto,
+    /// Refuse to run commands that cannot be confined.
+    Required,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyFormat {
+    Toml,
+    Json,
+}
+
+impl PolicyFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+}
+
+impl PolicyConfig {
+    pub fn parse(content: &str, format: PolicyFormat) -> Result<Self, String> {
+        match format {
+            PolicyFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            PolicyFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Combine the user-global layer with a workspace layer (see module docs).
+    pub fn merge(global: PolicyConfig, workspace: PolicyConfig) -> PolicyConfig {
+        let mut commands = workspace.commands;
+        commands.extend(global.commands);
+
+        let enabled = match (global.network.enabled, workspace.network.enabled) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (global, workspace) => workspace.or(global),
+        };
+        // A workspace allowlist can only narrow the user's allowlist.
+        let allowed_hosts = match (
+            global.network.allowed_hosts.is_empty(),
+            workspace.network.allowed_hosts.is_empty(),
+        ) {
+            (_, true) => global.network.allowed_hosts,
+            (true, false) => workspace.network.allowed_hosts,
+            (false, false) => workspace
+                .network
+                .allowed_hosts
+                .into_iter()
+                .filter(|host| host_matches_any(host, &global.network.allowed_hosts))
+                .collect(),
+        };
+
+        // Only the user can lower the outside-workspace decision below approval.
+        let user_floor = global
+            .approvals
+            .outside_workspace
+            .unwrap_or(RuleDecision::NeedsApproval);
+        let outside_workspace = match workspace.approvals.outside_workspace {
+            Some(decision) => Some(decision.max(user_floor)),
+            None => global.approvals.outside_workspace,
+        };
+
+        // Writable sandbox paths only narrow what the user made writable.
+        let writable = workspace
+            .sandbox
+            .writable
+            .into_iter()
+            .filter(|path| {
+                global
+                    .sandbox
+                    .writable
+                    .iter()
+                    .any(|allowed| Path::new(path).starts_with(allowed))
+            })
+            .collect();
+
+        PolicyConfig {
+            commands,
+            paths: PathRules {
+                deny_write: union(global.paths.deny_write, workspace.paths.deny_write),
+                deny_read: union(global.paths.deny_read, workspace.paths.deny_read),
+                protected: union(global.paths.protected, workspace.paths.protected),
+                secret: union(global.paths.secret, workspace.paths.secret),
+                generated: union(global.paths.generated, workspace.paths.generated),
+                secret_reads: global.paths.secret_reads.max(workspace.paths.secret_reads),
+            },
+            network: NetworkRules {
+                enabled,
+                allowed_hosts,
+                denied_hosts: union(global.network.denied_hosts, workspace.network.denied_hosts),
+            },
+            approvals: ApprovalRules {
+                tools: union(global.approvals.tools, workspace.approvals.tools),
+                outside_workspace,
+            },
+            exec: ExecRules {
+                timeout_secs: min_set(global.exec.timeout_secs, workspace.exec.timeout_secs),
+                max_timeout_secs: min_set(
+                    global.exec.max_timeout_secs,
+                    workspace.exec.max_timeout_secs,
+                ),
+            },
+            sandbox: SandboxRules {
+                mode: global.sandbox.mode.max(workspace.sandbox.mode),
+                network: match (global.sandbox.network, workspace.sandbox.network) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (global, workspace) => workspace.or(global),
+                },
+                cpu_secs: min_set(global.sandbox.cpu_secs, workspace.sandbox.cpu_secs),
+                memory_mb: min_set(global.sandbox.memory_mb, workspace.sandbox.memory_mb),
+                writable: union(global.sandbox.writable, writable),
+            },
+        }
+    }
+}
+
+/// The smaller of two optional limits; an unset limit does not count.
+fn min_set(first: Option<u64>, second: Option<u64>) -> Option<u64> {
+    match (first, second) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn union(mut first: Vec<String>, second: Vec<String>) -> Vec<String> {
+    for item in second {
+        if !first.contains(&item) {
+            first.push(item);
+        }
+    }
+    first
+}
+
+/// Whether `host` is covered by one of the host `patterns`.
+pub(crate) fn host_matches_any(host: &str, patterns: &[String]) -> bool {
+    let host = host.to_ascii_lowercase();
+    patterns
+        .iter()
+        .any(|pattern| glob_match(&pattern.to_ascii_lowercase(), &host))
+}
+
+/// First pattern covering a workspace-relative path. Patterns without a `/`
+/// match any path component (`.env`, `*.pem`); a trailing `/` covers the
+/// whole directory; other patterns match the full relative path.
+pub(crate) fn matching_path_pattern<'a>(patterns: &'a [String], relative: &str) -> Option<&'a str> {
+    patterns
+        .iter()
+        .find(|pattern| {
+            if let Some(dir) = pattern.strip_suffix('/') {
+                glob_match(dir, relative) || glob_match(&format!("{dir}/*"), relative)
+            } else if pattern.contains('/') {
+                glob_match(pattern, relative)
+            } else {
+                relative
+                    .split('/')
+                    .any(|component| glob_match(pattern, component))
+            }
+        })
+        .map(String::as_str)
+}
+
+/// Check a URL against the network rules. Strings without a scheme are not
+/// URLs and pass. With `local_by_default`, loopback and private addresses
+/// are always reachable and any other host must be in `allowed_hosts`.
+pub(crate) fn evaluate_url(
+    network: &NetworkRules,
+    url: &str,
+    local_by_default: bool,
+) -> Result<(), String> {
+    let Some(host) = url_host(url) else {
+        return Ok(());
+    };
+    if host_matches_any(&host, &network.denied_hosts) {
+        return Err(format!("host is denied by policy: {host}"));
+    }
+    if local_by_default && is_local_host(&host) {
+        return Ok(());
+    }
+    if network.enabled == Some(false) {
+        return Err(format!("network access is disabled: {url}"));
+    }
+    if !network.allowed_hosts.is_empty() {
+        if host_matches_any(&host, &network.allowed_hosts) {
+            return Ok(());
+        }
+        return Err(format!("host is not in the allowed hosts: {host}"));
+    }
+    if local_by_default {
+        return Err("Only localhost and local network URLs are allowed".to_string());
+    }
+    Ok(())
+}
+
+/// Lowercased host of a `scheme://` URL, without credentials or port.
+fn url_host(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let authority = authority.rsplit('@').next().unwrap_or(authority);
+    let host = if let Some(bracketed) = authority.strip_prefix('[') {
+        bracketed.split(']').next().unwrap_or(bracketed)
+    } else {
+        authority.split(':').next().unwrap_or(authority)
+    };
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+fn is_local_host(host: &str) -> bool {
+    if host == "localhost" || host.ends_with(".localhost") {
+        return true;
+    }
+    match host.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_unspecified(),
+        Ok(std::net::IpAddr::V6(ip)) => ip.is_loopback() || ip.is_unspecified(),
+        Err(_) => false,
+    }
+}
+
+/// Where a policy layer was read from and whether it parsed.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicySource {
+    /// `global` or `workspace`.
+    pub scope: String,
+    pub path: String,
+    pub exists: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The merged configuration and the files it came from.
+#[derive(Debug, Clone)]
+pub struct LoadedPolicy {
+    pub config: PolicyConfig,
+    pub sources: Vec<PolicySource>,
+}
+
+impl LoadedPolicy {
+    /// Load and merge the global and workspace layers. Files that fail to
+    /// parse are skipped and reported in `sources`.
+    pub fn load(workspace_root: &Path) -> Self {
+        let global_dir = crate::orchestrix_data_dir();
+        let workspace_dir = policy_root(workspace_root).join(".orchestrix");
+
+        let (global, global_source) = load_layer("global", &global_dir);
+        let (workspace, workspace_source) = load_layer("workspace", &workspace_dir);
+        for source in [&global_source, &workspace_source] {
+            if let Some(error) = &source.error {
+                tracing::warn!("ignoring invalid policy file {}: {error}", source.path);
+            }
+        }
+
+        Self {
+            config: PolicyConfig::merge(global, workspace),
+            sources: vec![global_source, workspace_source],
+        }
+    }
+}
+
+fn load_layer(scope: &str, dir: &Path) -> (PolicyConfig, PolicySource) {
+    let path = POLICY_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| dir.join(POLICY_FILE_NAMES[0]));
+    let mut source = PolicySource {
+        scope: scope.to_string(),
+        path: path.to_string_lossy().to_string(),
+        exists: path.is_file(),
+        error: None,
+    };
+    if !source.exists {
+        return (PolicyConfig::default(), source);
+    }
+
+    let parsed = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|raw| PolicyConfig::parse(&raw, PolicyFormat::from_path(&path)));
+    match parsed {
+        Ok(config) => (config, source),
+        Err(error) => {
+            source.error = Some(error);
+            (PolicyConfig::default(), source)
+        }
+    }
+}
+
+/// Sub-agent worktrees live under `<workspace>/.orchestrix/worktrees/`; they
+/// follow the policy of the workspace that owns them.
+fn policy_root(workspace_root: &Path) -> PathBuf {
+    let parts: Vec<_> = workspace_root.components().collect();
+    for idx in 0..parts.len().saturating_sub(1) {
+        if parts[idx].as_os_str() == ".orchestrix" && parts[idx + 1].as_os_str() == "worktrees" {
+            return parts[..idx].iter().collect();
+        }
+    }
+    workspace_root.to_path_buf()
+}
@@ -1,9 +1,35 @@
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+pub mod commands;
+pub mod config;
+pub mod paths;
+pub mod redaction;
+
+use commands::{CommandInvocation, CommandPolicy, CommandVerdict, RuleDecision};
+use config::{LoadedPolicy, PolicyConfig, SandboxRules};
+use paths::{PathClass, SecretReadMode};
+
+/// Approval scope prefix for a command that needs approval; the rest of the
+/// scope is the exact command text, so approving it does not cover variants.
+const COMMAND_SCOPE_PREFIX: &str = "command:";
+
+/// Approval scope prefix for writing one protected or secret file.
+const WRITE_SCOPE_PREFIX: &str = "write:";
+
+/// Approval scope prefix for a tool that the policy file puts behind approval.
+const TOOL_SCOPE_PREFIX: &str = "tool:";
+
+/// `cmd.exec` timeout when neither the call nor the policy sets one.
+const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 120;
+
+/// Longest `cmd.exec` timeout unless the policy sets `exec.max_timeout_secs`.
+const MAX_COMMAND_TIMEOUT_SECS: u64 = 30 * 60;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PolicyDecision {
     Allow,
@@ -15,23 +41,29 @@ pub enum PolicyDecision {
 pub struct PolicyEngine {
     workspace_root: PathBuf,
     approved_scopes: Arc<Mutex<HashSet<String>>>,
+    config: Arc<PolicyConfig>,
+    commands: Arc<CommandPolicy>,
 }
 
 impl PolicyEngine {
     pub fn new(workspace_root: PathBuf) -> Self {
-        Self {
-            workspace_root,
-            approved_scopes: Arc::new(Mutex::new(HashSet::new())),
-        }
+        Self::with_approved_scopes(workspace_root, Arc::new(Mutex::new(HashSet::new())))
     }
 
     pub fn with_approved_scopes(
         workspace_root: PathBuf,
         approved_scopes: Arc<Mutex<HashSet<String>>>,
     ) -> Self {
+        let config = LoadedPolicy::load(&workspace_root).config;
+        let commands = Arc::new(CommandPolicy::new(
+            config.commands.clone(),
+            config.network.clone(),
+        ));
         Self {
             workspace_root,
             approved_scopes,
+            config: Arc::new(config),
+            commands,
         }
     }
 
@@ -41,6 +73,14 @@ impl PolicyEngine {
         }
     }
 
+    /// Exact-match check for non-path scopes (`command:…`, `tool:…`).
+    fn is_exact_scope_allowed(&self, scope: &str) -> bool {
+        self.approved_scopes
+            .lock()
+            .map(|guard| guard.contains(scope))
+            .unwrap_or(false)
+    }
+
     fn is_scope_allowed(&self, candidate: &Path) -> bool {
         let candidate_str = normalize_path_text(candidate.to_string_lossy().as_ref());
         let Ok(guard) = self.approved_scopes.lock() else {
@@ -95,93 +135,246 @@ impl PolicyEngine {
         }
 
         let scope = normalize_path_text(candidate.to_string_lossy().as_ref());
+        match self
+            .config
+            .approvals
+            .outside_workspace
+            .unwrap_or(RuleDecision::NeedsApproval)
+        {
+            RuleDecision::Allow => PolicyDecision::Allow,
+            RuleDecision::Deny => PolicyDecision::Deny(format!("path outside workspace: {scope}")),
+            RuleDecision::NeedsApproval => PolicyDecision::NeedsApproval {
+                scope: scope.clone(),
+                reason: format!("path outside workspace: {scope}"),
+            },
+        }
+    }
+
+    /// `evaluate_path` plus `paths.deny_read`; secret files are blocked when
+    /// `paths.secret_reads` is `block` (callers redact them otherwise).
+    pub fn evaluate_read(&self, candidate: &Path) -> PolicyDecision {
+        let decision =
+            self.evaluate_path_access(candidate, &self.config.paths.deny_read, "reading");
+        if !matches!(decision, PolicyDecision::Allow)
+            || self.secret_read_mode() != SecretReadMode::Block
+            || self.path_class(candidate) != Some(PathClass::Secret)
+        {
+            return decision;
+        }
+        let relative = self.relative(candidate);
+        PolicyDecision::Deny(format!(
+            "reading secret file {relative} is blocked by policy"
+        ))
+    }
+
+    /// `evaluate_path` plus `paths.deny_write`; protected and secret files
+    /// need approval per file.
+    pub fn evaluate_write(&self, candidate: &Path) -> PolicyDecision {
+        let decision =
+            self.evaluate_path_access(candidate, &self.config.paths.deny_write, "writing");
+        if !matches!(decision, PolicyDecision::Allow) {
+            return decision;
+        }
+        let class = match self.path_class(candidate) {
+            Some(class @ (PathClass::Protected | PathClass::Secret)) => class,
+            _ => return decision,
+        };
+        let relative = self.relative(candidate);
+        let scope = format!("{WRITE_SCOPE_PREFIX}{relative}");
+        if self.is_exact_scope_allowed(&scope) {
+            return PolicyDecision::Allow;
+        }
+        let class = match class {
+            PathClass::Secret => "secret",
+            _ => "protected",
+        };
         PolicyDecision::NeedsApproval {
-            scope: scope.clone(),
-            reason: format!("path outside workspace: {scope}"),
-        }
-    }
-
-    pub fn evaluate_command(&self, cmd: &str) -> PolicyDecision {
-        // Extract the binary name from compound commands (e.g. "mkdir -p foo" -> "mkdir")
-        let binary = cmd.split_whitespace().next().unwrap_or(cmd);
-        // Also strip any path prefix (e.g. "/usr/bin/git" -> "git")
-        let binary = binary.rsplit('/').next().unwrap_or(binary);
-        let binary = binary.rsplit('\\').next().unwrap_or(binary);
-        // Strip .exe suffix on Windows
-        let binary = binary.strip_suffix(".exe").unwrap_or(binary);
-
-        const ALLOWLIST: &[&str] = &[
-            // Version control
-            "git",
-            // Search
-            "rg",
-            // Rust toolchain
-            "cargo",
-            "rustc",
-            "rustup",
-            // JavaScript / Node toolchain
-            "bun",
-            "bunx",
-            "node",
-            "npx",
-            "npm",
-            "deno",
-            // Python
-            "python",
-            "python3",
-            "pip",
-            "pip3",
-            "uv",
-            // File operations (needed for project scaffolding)
-            "mkdir",
-            "cp",
-            "mv",
-            "rm",
-            "ls",
-            "cat",
-            "touch",
-            "cd",
-            // Windows equivalents
-            "cmd",
-            "powershell",
-            "pwsh",
-            "xcopy",
-            "robocopy",
-            "dir",
-            "del",
-            "copy",
-            "move",
-            "type",
-            "rmdir",
-            // Shell built-ins (used for command availability checks)
-            "command",
-            "type",
-            // Common dev tools
-            "echo",
-            "tar",
-            "unzip",
-            "zip",
-            "curl",
-            "wget",
-            "which",
-            "make",
-            "cmake",
-            "docker",
-            "docker-compose",
-            // Testing / linting
-            "jest",
-            "vitest",
-            "eslint",
-            "prettier",
-            "tsc",
-        ];
-
-        if ALLOWLIST.contains(&binary) {
-            PolicyDecision::Allow
-        } else {
-            PolicyDecision::Deny(format!("command not allowed: {cmd}"))
+            scope,
+            reason: format!("writing {class} path {relative} requires approval"),
         }
     }
+
+    /// Class of a path, if it is protected, secret or generated.
+    pub fn path_class(&self, candidate: &Path) -> Option<PathClass> {
+        paths::classify(&self.config.paths, &self.relative(candidate))
+    }
+
+    pub fn secret_read_mode(&self) -> SecretReadMode {
+        self.config.paths.secret_reads.unwrap_or_default()
+    }
+
+    /// Patterns content search must skip (secret and read-denied files).
+    pub fn search_exclusions(&self) -> Vec<String> {
+        paths::search_exclusions(&self.config.paths)
+    }
+
+    /// `{"path_classes": {path: class}}` for the classified paths a tool
+    /// touched, for the tool output and the `tool_calls` policy record.
+    pub fn path_classes_record(&self, candidates: &[PathBuf]) -> Option<serde_json::Value> {
+        let classes: serde_json::Map<String, serde_json::Value> = candidates
+            .iter()
+            .filter_map(|candidate| {
+                let class = self.path_class(candidate)?;
+                Some((self.relative(candidate), serde_json::json!(class)))
+            })
+            .collect();
+        (!classes.is_empty()).then(|| serde_json::json!({ "path_classes": classes }))
+    }
+
+    fn relative(&self, candidate: &Path) -> String {
+        commands::workspace_relative(candidate, &self.workspace_root)
+    }
+
+    fn evaluate_path_access(
+        &self,
+        candidate: &Path,
+        denied: &[String],
+        access: &str,
+    ) -> PolicyDecision {
+        let decision = self.evaluate_path(candidate);
+        if matches!(decision, PolicyDecision::Deny(_)) {
+            return decision;
+        }
+        let relative = self.relative(candidate);
+        match config::matching_path_pattern(denied, &relative) {
+            Some(pattern) => PolicyDecision::Deny(format!(
+                "{access} {relative} is denied by policy pattern `{pattern}`"
+            )),
+            None => decision,
+        }
+    }
+
+    /// Tools listed under `approvals.tools` need approval before each run
+    /// until the user approves that tool.
+    pub fn evaluate_tool(&self, tool_name: &str) -> PolicyDecision {
+        let required = self
+            .config
+            .approvals
+            .tools
+            .iter()
+            .any(|pattern| commands::glob_match(pattern, tool_name));
+        let scope = format!("{TOOL_SCOPE_PREFIX}{tool_name}");
+        if !required || self.is_exact_scope_allowed(&scope) {
+            return PolicyDecision::Allow;
+        }
+        PolicyDecision::NeedsApproval {
+            scope,
+            reason: format!("policy requires approval for {tool_name}"),
+        }
+    }
+
+    /// Check a URL the agent wants to open in a browser. Local addresses are
+    /// always allowed; other hosts must be in `network.allowed_hosts`.
+    pub fn evaluate_browser_url(&self, url: &str) -> PolicyDecision {
+        match config::evaluate_url(&self.config.network, url, true) {
+            Ok(()) => PolicyDecision::Allow,
+            Err(reason) => PolicyDecision::Deny(reason),
+        }
+    }
+
+    /// Timeout for a `cmd.exec` call: the requested one or the policy
+    /// default, capped by the policy maximum.
+    pub fn command_timeout(&self, requested: Option<u64>) -> Duration {
+        let max = self
+            .config
+            .exec
+            .max_timeout_secs
+            .unwrap_or(MAX_COMMAND_TIMEOUT_SECS);
+        let secs = requested
+            .or(self.config.exec.timeout_secs)
+            .unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECS);
+        Duration::from_secs(secs.clamp(1, max.max(1)))
+    }
+
+    /// Sandbox settings for `cmd.exec`. Network is off inside the sandbox
+    /// whenever the network rules turn it off.
+    pub fn command_sandbox(&self) -> SandboxRules {
+        let mut rules = self.config.sandbox.clone();
+        if self.config.network.enabled == Some(false) {
+            rules.network = Some(false);
+        }
+        rules
+    }
+
+    /// Check a program and its arguments against the command rules.
+    pub fn evaluate_command(&self, binary: &str, args: &[String], cwd: &Path) -> PolicyDecision {
+        let invocation = CommandInvocation {
+            binary: binary.to_string(),
+            args: args.to_vec(),
+            cwd: cwd.to_path_buf(),
+            piped: false,
+            redirects: Vec::new(),
+        };
+        let verdict = self.commands.evaluate(&invocation, &self.workspace_root);
+        let text = std::iter::once(binary)
+            .chain(args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.command_decision(&text, verdict)
+    }
+
+    /// Check every command of a shell string (pipelines, `&&` / `||` / `;`
+    /// chains) against the command rules, the directory each one runs in
+    /// after earlier `cd`s, and the files its output is redirected to.
+    pub fn evaluate_shell_command(&self, command: &str, cwd: &Path) -> PolicyDecision {
+        let verdict = self
+            .commands
+            .evaluate_shell(command, cwd, &self.workspace_root);
+        let decision = self.command_decision(command.trim(), verdict);
+        if matches!(decision, PolicyDecision::Deny(_)) {
+            return decision;
+        }
+        // Commands that cannot be parsed already need approval as a whole
+        let Ok(invocations) = commands::parse_shell_command(command, cwd) else {
+            return decision;
+        };
+        let locations = invocations
+            .iter()
+            .map(|invocation| self.evaluate_path(&invocation.cwd));
+        let writes = invocations
+            .iter()
+            .flat_map(|invocation| &invocation.redirects)
+            .map(|target| self.evaluate_write(target));
+        std::iter::once(decision)
+            .chain(locations)
+            .chain(writes)
+            .reduce(stricter)
+            .expect("at least the command decision")
+    }
+
+    fn command_decision(&self, text: &str, verdict: CommandVerdict) -> PolicyDecision {
+        match verdict.decision {
+            RuleDecision::Allow => PolicyDecision::Allow,
+            RuleDecision::Deny => PolicyDecision::Deny(verdict.reason),
+            RuleDecision::NeedsApproval => {
+                let scope = format!("{COMMAND_SCOPE_PREFIX}{text}");
+                if self.is_exact_scope_allowed(&scope) {
+                    PolicyDecision::Allow
+                } else {
+                    PolicyDecision::NeedsApproval {
+                        scope,
+                        reason: verdict.reason,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The more restrictive of two decisions; the earlier one on a tie.
+fn stricter(current: PolicyDecision, next: PolicyDecision) -> PolicyDecision {
+    fn rank(decision: &PolicyDecision) -> u8 {
+        match decision {
+            PolicyDecision::Allow => 0,
+            PolicyDecision::NeedsApproval { .. } => 1,
+            PolicyDecision::Deny(_) => 2,
+        }
+    }
+    if rank(&next) > rank(&current) {
+        next
+    } else {
+        current
+    }
 }
 
 fn normalize_path_text(raw: &str) -> String {
@@ -0,0 +1,629 @@
+//! Token, step and wall-clock budgets for task execution.
+//!
+//! A task may declare a `TaskBudget`. While one of its runs executes, a
+//! `BudgetTracker` registered under the run id accumulates the usage reported by
+//! worker model calls. `Orchestrator::execute_plan` and `execute_step_with_tools`
+//! call `enforce_budget` before doing more work; when a limit is about to be
+//! exceeded the run pauses, emits `task.budget_exhausted`, and asks the user
+//! whether to extend the budget or stop.
+
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::time::timeout;
+use uuid::Uuid;
+
+use crate::bus::{
+    EventBus, CATEGORY_AGENT, CATEGORY_TASK, EVENT_AGENT_QUESTION_ANSWERED,
+    EVENT_AGENT_QUESTION_REQUIRED,
+};
+use crate::db::{queries, Database};
+use crate::model::TokenUsage;
+use crate::runtime::planner::emit_and_record;
+use crate::runtime::questions::{UserQuestionGate, UserQuestionOption};
+
+pub const EVENT_TASK_BUDGET_EXHAUSTED: &str = "task.budget_exhausted";
+pub const EVENT_TASK_BUDGET_EXTENDED: &str = "task.budget_extended";
+pub const EVENT_TASK_BUDGET_UPDATED: &str = "task.budget_updated";
+
+const BUDGET_OPTION_EXTEND: &str = "extend";
+const BUDGET_OPTION_STOP: &str = "stop";
+const BUDGET_PROMPT_TIMEOUT_SECS: u64 = 600;
+
+/// Limits a task may declare. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskBudget {
+    /// Prompt plus completion tokens across every worker model call.
+    pub max_tokens: Option<u64>,
+    /// Worker decision turns across all steps and sub-agents.
+    pub max_steps: Option<u64>,
+    /// Wall-clock time since the run started executing.
+    pub max_wall_clock_secs: Option<u64>,
+}
+
+impl TaskBudget {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_tokens.is_none() && self.max_steps.is_none() && self.max_wall_clock_secs.is_none()
+    }
+
+    pub fn from_row(row: &queries::TaskBudgetRow) -> Self {
+        Self {
+            max_tokens: row.max_tokens.map(|v| v.max(0) as u64),
+            max_steps: row.max_steps.map(|v| v.max(0) as u64),
+            max_wall_clock_secs: row.max_wall_clock_secs.map(|v| v.max(0) as u64),
+        }
+    }
+
+    pub fn to_row(&self, task_id: &str) -> queries::TaskBudgetRow {
+        queries::TaskBudgetRow {
+            task_id: task_id.to_string(),
+            max_tokens: self.max_tokens.map(|v| v as i64),
+            max_steps: self.max_steps.map(|v| v as i64),
+            max_wall_clock_secs: self.max_wall_clock_secs.map(|v| v as i64),
+            extended_tokens: 0,
+            extended_steps: 0,
+            updated_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Limits granted on top of the declared budget by extending it. Token and
+/// step limits cover every run of a task, so their extensions are stored with
+/// the budget; a wall-clock extension only lasts for the run it was granted in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BudgetExtensions {
+    pub tokens: u64,
+    pub steps: u64,
+}
+
+impl BudgetExtensions {
+    pub fn from_row(row: &queries::TaskBudgetRow) -> Self {
+        Self {
+            tokens: row.extended_tokens.max(0) as u64,
+            steps: row.extended_steps.max(0) as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    Tokens,
+    Steps,
+    WallClock,
+}
+
+impl BudgetKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tokens => "tokens",
+            Self::Steps => "steps",
+            Self::WallClock => "wall_clock",
+        }
+    }
+}
+
+/// A limit that the next unit of work would cross.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BudgetExhaustion {
+    pub kind: BudgetKind,
+    pub limit: u64,
+    pub used: u64,
+}
+
+impl BudgetExhaustion {
+    pub fn describe(&self) -> String {
+        match self.kind {
+            BudgetKind::Tokens => format!(
+                "token budget exhausted ({} of {} tokens used)",
+                self.used, self.limit
+            ),
+            BudgetKind::Steps => format!(
+                "step budget exhausted ({} of {} steps used)",
+                self.used, self.limit
+            ),
+            BudgetKind::WallClock => format!(
+                "wall-clock budget exhausted ({}s of {}s elapsed)",
+                self.used, self.limit
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    budget: TaskBudget,
+    /// The limits as originally declared; each extension grants this much again.
+    increment: TaskBudget,
+    usage: TokenUsage,
+    steps_used: u64,
+    last_request_tokens: u64,
+}
+
+/// Usage accounting for one executing run.
+#[derive(Debug)]
+pub struct BudgetTracker {
+    state: Mutex<BudgetState>,
+    started_at: Instant,
+    /// Serializes extension prompts so parallel sub-agents ask only once.
+    prompt_lock: tokio::sync::Mutex<()>,
+}
+
+impl BudgetTracker {
+    pub fn new(
+        budget: TaskBudget,
+        extensions: BudgetExtensions,
+        usage: TokenUsage,
+        steps_used: u64,
+    ) -> Self {
+        Self {
+            state: Mutex::new(BudgetState {
+                budget: TaskBudget {
+                    max_tokens: budget.max_tokens.map(|limit| limit + extensions.tokens),
+                    max_steps: budget.max_steps.map(|limit| limit + extensions.steps),
+                    ..budget
+                },
+                increment: budget,
+                usage,
+                steps_used,
+                last_request_tokens: 0,
+            }),
+            started_at: Instant::now(),
+            prompt_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Replace the limits, e.g. after the user edits the task budget mid-run.
+    /// Earlier extensions are dropped along with the old limits.
+    pub fn set_budget(&self, budget: TaskBudget) {
+        let mut state = self.state.lock().expect("budget mutex poisoned");
+        state.budget = budget;
+        state.increment = budget;
+    }
+
+    pub fn record_usage(&self, usage: &TokenUsage) {
+        let mut state = self.state.lock().expect("budget mutex poisoned");
+        state.usage.add(usage);
+        state.last_request_tokens = usage.total_tokens();
+    }
+
+    pub fn record_step(&self) {
+        let mut state = self.state.lock().expect("budget mutex poisoned");
+        state.steps_used += 1;
+    }
+
+    /// Check whether the next model call would cross a limit.
+    ///
+    /// Tokens are projected using the size of the previous request, so the run
+    /// pauses before a call that would overshoot rather than after it.
+    pub fn check(&self) -> Option<BudgetExhaustion> {
+        let state = self.state.lock().expect("budget mutex poisoned");
+        if let Some(limit) = state.budget.max_steps {
+            if state.steps_used >= limit {
+                return Some(BudgetExhaustion {
+                    kind: BudgetKind::Steps,
+                    limit,
+                    used: state.steps_used,
+                });
+            }
+        }
+        if let Some(limit) = state.budget.max_tokens {
+            let used = state.usage.total_tokens();
+            if used + state.last_request_tokens > limit {
+                return Some(BudgetExhaustion {
+                    kind: BudgetKind::Tokens,
+                    limit,
+                    used,
+                });
+            }
+        }
+        if let Some(limit) = state.budget.max_wall_clock_secs {
+            let elapsed = self.started_at.elapsed().as_secs();
+            if elapsed >= limit {
+                return Some(BudgetExhaustion {
+                    kind: BudgetKind::WallClock,
+                    limit,
+                    used: elapsed,
+                });
+            }
+        }
+        None
+    }
+
+    /// How much [`extend`](Self::extend) grants for `kind`.
+    pub fn increment(&self, kind: BudgetKind) -> u64 {
+        let state = self.state.lock().expect("budget mutex poisoned");
+        let increment = match kind {
+            BudgetKind::Tokens => state.increment.max_tokens,
+            BudgetKind::Steps => state.increment.max_steps,
+            BudgetKind::WallClock => state.increment.max_wall_clock_secs,
+        };
+        increment.unwrap_or(0).max(1)
+    }
+
+    /// Grant another increment of the exhausted limit and return the new budget.
+    pub fn extend(&self, kind: BudgetKind) -> TaskBudget {
+        let mut guard = self.state.lock().expect("budget mutex poisoned");
+        let state = &mut *guard;
+        let elapsed = self.started_at.elapsed().as_secs();
+        let (limit, increment, used) = match kind {
+            BudgetKind::Tokens => (
+                &mut state.budget.max_tokens,
+                state.increment.max_tokens,
+                state.usage.total_tokens() + state.last_request_tokens,
+            ),
+            BudgetKind::Steps => (
+                &mut state.budget.max_steps,
+                state.increment.max_steps,
+                state.steps_used,
+            ),
+            BudgetKind::WallClock => (
+                &mut state.budget.max_wall_clock_secs,
+                state.increment.max_wall_clock_secs,
+                elapsed,
+            ),
+        };
+        if let (Some(current), Some(increment)) = (limit.as_mut(), increment) {
+            *current = (*current).max(used) + increment.max(1);
+        }
+        state.budget
+    }
+
+    /// Token and step limits granted so far beyond the declared budget.
+    pub fn extensions(&self) -> BudgetExtensions {
+        let state = self.state.lock().expect("budget mutex poisoned");
+        let granted = |limit: Option<u64>, declared: Option<u64>| {
+            limit.unwrap_or(0).saturating_sub(declared.unwrap_or(0))
+        };
+        BudgetExtensions {
+            tokens: granted(state.budget.max_tokens, state.increment.max_tokens),
+            steps: granted(state.budget.max_steps, state.increment.max_steps),
+        }
+    }
+
+    pub fn snapshot(&self) -> serde_json::Value {
+        let state = self.state.lock().expect("budget mutex poisoned");
+        serde_json::json!({
+            "budget": state.budget,
+            "usage": state.usage,
+            "steps_used": state.steps_used,
+            "elapsed_secs": self.started_at.elapsed().as_secs(),
+        })
+    }
+}
+
+static RUN_BUDGETS: OnceLock<DashMap<String, Arc<BudgetTracker>>> = OnceLock::new();
+
+fn run_budget_registry() -> &'static DashMap<String, Arc<BudgetTracker>> {
+    RUN_BUDGETS.get_or_init(DashMap::new)
+}
+
+/// Register a tracker for a run from the task's stored budget.
+///
+/// Returns `None` when the task has no budget. Prior usage for the task is
+/// counted so that resumed and continued runs honour the same limits.
+pub fn register_run_budget(
+    db: &Database,
+    run_id: &str,
+    task_id: &str,
+) -> Result<Option<Arc<BudgetTracker>>, String> {
+    let Some(row) = queries::get_task_budget(db, task_id).map_err(|e| e.to_string())? else {
+        run_budget_registry().remove(run_id);
+        return Ok(None);
+    };
+    let budget = TaskBudget::from_row(&row);
+    let extensions = BudgetExtensions::from_row(&row);
+    if budget.is_unlimited() {
+        run_budget_registry().remove(run_id);
+        return Ok(None);
+    }
+
+    let totals = queries::get_task_reported_usage_totals(db, task_id).map_err(|e| e.to_string())?;
+    let usage = TokenUsage {
+        prompt_tokens: totals.tokens_in.max(0) as u64,
+        completion_tokens: totals.tokens_out.max(0) as u64,
+        reasoning_tokens: totals.tokens_reasoning.max(0) as u64,
+        cached_tokens: totals.tokens_cached.max(0) as u64,
+    };
+    let steps_used = queries::count_task_worker_requests(db, task_id).map_err(|e| e.to_string())?;
+    let tracker = Arc::new(BudgetTracker::new(
+        budget,
+        extensions,
+        usage,
+        steps_used.max(0) as u64,
+    ));
+    run_budget_registry().insert(run_id.to_string(), tracker.clone());
+    Ok(Some(tracker))
+}
+
+pub fn run_budget(run_id: &str) -> Option<Arc<BudgetTracker>> {
+    run_budget_registry()
+        .get(run_id)
+        .map(|entry| entry.value().clone())
+}
+
+pub fn release_run_budget(run_id: &str) {
+    run_budget_registry().remove(run_id);
+}
+
+/// Apply an updated task budget to any run of the task that is executing.
+pub fn update_active_budgets(db: &Database, task_id: &str, budget: TaskBudget) {
+    let Ok(runs) = queries::list_runs_for_task(db, task_id) else {
+        return;
+    };
+    for run in runs.into_iter().filter(|run| run.status == "executing") {
+        match run_budget(&run.id) {
+            Some(tracker) => tracker.set_budget(budget),
+            None => {
+                if let Err(error) = register_run_budget(db, &run.id, task_id) {
+                    tracing::warn!("failed to register budget for run {}: {error}", run.id);
+                }
+            }
+        }
+    }
+}
+
+/// Pause until the run is within budget, asking the user to extend if needed.
+///
+/// Returns `Ok(())` when there is no budget, the run is within it, or the user
+/// chose to extend it. Returns an error describing the exhausted limit when the
+/// user declines or the prompt times out.
+pub async fn enforce_budget(
+    db: &Database,
+    bus: &EventBus,
+    question_gate: &UserQuestionGate,
+    run_id: &str,
+    task_id: &str,
+    sub_agent_id: Option<&str>,
+) -> Result<(), String> {
+    let Some(tracker) = run_budget(run_id) else {
+        return Ok(());
+    };
+    if tracker.check().is_none() {
+        return Ok(());
+    }
+
+    let _prompt = tracker.prompt_lock.lock().await;
+    // Another sub-agent may have extended the budget while we waited.
+    while let Some(exhaustion) = tracker.check() {
+        let prompt_id = format!("budget-{}", Uuid::new_v4());
+        let sub_agent = sub_agent_id.unwrap_or("");
+
+        let _ = emit_and_record(
+            db,
+            bus,
+            CATEGORY_TASK,
+            EVENT_TASK_BUDGET_EXHAUSTED,
+            Some(run_id.to_string()),
+            serde_json::json!({
+                "task_id": task_id,
+                "run_id": run_id,
+                "sub_agent_id": sub_agent_id,
+                "kind": exhaustion.kind,
+                "limit": exhaustion.limit,
+                "used": exhaustion.used,
+                "usage": tracker.snapshot(),
+            }),
+        );
+
+        let (request, receiver) = question_gate.request(
+            task_id,
+            run_id,
+            sub_agent,
+            &prompt_id,
+            format!(
+                "The {}. Extend the budget and continue?",
+                exhaustion.describe()
+            ),
+            vec![
+                UserQuestionOption {
+                    id: BUDGET_OPTION_EXTEND.to_string(),
+                    label: "Extend budget".to_string(),
+                    description: Some(format!(
+                        "Grant another {} of {} and keep going",
+                        tracker.increment(exhaustion.kind),
+                        exhaustion.kind.as_str()
+                    )),
+                },
+                UserQuestionOption {
+                    id: BUDGET_OPTION_STOP.to_string(),
+                    label: "Stop".to_string(),
+                    description: Some("Halt the run here".to_string()),
+                },
+            ],
+            false,
+            false,
+            Some(BUDGET_PROMPT_TIMEOUT_SECS),
+            Some(BUDGET_OPTION_STOP.to_string()),
+        );
+
+        let fallback_payload = serde_json::json!({
+            "task_id": task_id,
+            "run_id": run_id,
+            "tool_call_id": prompt_id,
+            "question": request.question,
+        });
+        let _ = emit_and_record(
+            db,
+            bus,
+            CATEGORY_AGENT,
+            EVENT_AGENT_QUESTION_REQUIRED,
+            Some(run_id.to_string()),
+            serde_json::to_value(&request).unwrap_or(fallback_payload),
+        );
+
+        let answer = match timeout(Duration::from_secs(BUDGET_PROMPT_TIMEOUT_SECS), receiver).await
+        {
+            Ok(Ok(answer)) => Some(answer),
+            _ => None,
+        };
+
+        let _ = emit_and_record(
+            db,
+            bus,
+            CATEGORY_AGENT,
+            EVENT_AGENT_QUESTION_ANSWERED,
+            Some(run_id.to_string()),
+            serde_json::json!({
+                "task_id": task_id,
+                "run_id": run_id,
+                "tool_call_id": prompt_id,
+                "question_id": request.id,
+                "answer": answer,
+            }),
+        );
+
+        let extend = answer
+            .as_ref()
+            .map(|value| {
+                value
+                    .selected_option_ids
+                    .iter()
+                    .any(|id| id == BUDGET_OPTION_EXTEND)
+            })
+            .unwrap_or(false);
+        if !extend {
+            return Err(exhaustion.describe());
+        }
+
+        // Only the grant is stored; the declared limits stay what the user set
+        let budget = tracker.extend(exhaustion.kind);
+        let extensions = tracker.extensions();
+        queries::set_task_budget_extensions(
+            db,
+            task_id,
+            extensions.tokens as i64,
+            extensions.steps as i64,
+        )
+        .map_err(|e| e.to_string())?;
+        let _ = emit_and_record(
+            db,
+            bus,
+            CATEGORY_TASK,
+            EVENT_TASK_BUDGET_EXTENDED,
+            Some(run_id.to_string()),
+            serde_json::json!({
+                "task_id": task_id,
+                "run_id": run_id,
+                "kind": exhaustion.kind,
+                "budget": budget,
+            }),
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt: u64, completion: u64) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            ..TokenUsage::default()
+        }
+    }
+
+    #[test]
+    fn token_budget_projects_next_request() {
+        let tracker = BudgetTracker::new(
+            TaskBudget {
+                max_tokens: Some(1_000),
+                ..TaskBudget::default()
+            },
+            BudgetExtensions::default(),
+            TokenUsage::default(),
+            0,
+        );
+        assert!(tracker.check().is_none());
+
+        tracker.record_usage(&usage(400, 100));
+        assert!(tracker.check().is_none());
+
+        // 500 used + 500 projected is still within the limit; the next is not.
+        tracker.record_usage(&usage(400, 150));
+        let exhaustion = tracker.check().expect("token budget should be exhausted");
+        assert_eq!(exhaustion.kind, BudgetKind::Tokens);
+        assert_eq!(exhaustion.used, 1_050);
+
+        let extended = tracker.extend(BudgetKind::Tokens);
+        assert_eq!(extended.max_tokens, Some(2_600));
+        assert!(tracker.check().is_none());
+        // Each extension grants the declared limit again, not the current one
+        assert_eq!(tracker.increment(BudgetKind::Tokens), 1_000);
+        assert_eq!(tracker.extensions().tokens, 1_600);
+    }
+
+    #[test]
+    fn later_runs_keep_grants_apart_from_the_declared_budget() {
+        let declared = TaskBudget {
+            max_tokens: Some(1_000),
+            max_wall_clock_secs: Some(0),
+            ..TaskBudget::default()
+        };
+        let tracker = BudgetTracker::new(
+            declared,
+            BudgetExtensions {
+                tokens: 1_600,
+                steps: 0,
+            },
+            usage(1_500, 0),
+            0,
+        );
+        assert_eq!(
+            tracker.check().map(|exhaustion| exhaustion.kind),
+            Some(BudgetKind::WallClock)
+        );
+        tracker.extend(BudgetKind::WallClock);
+        assert!(tracker.check().is_none());
+        // The wall-clock grant is not stored for later runs
+        assert_eq!(
+            tracker.extensions(),
+            BudgetExtensions {
+                tokens: 1_600,
+                steps: 0,
+            }
+        );
+        // Extending again still grants the declared amount, not the extended one
+        assert_eq!(tracker.increment(BudgetKind::Tokens), 1_000);
+    }
+
+    #[test]
+    fn step_budget_counts_turns() {
+        let tracker = BudgetTracker::new(
+            TaskBudget {
+                max_steps: Some(2),
+                ..TaskBudget::default()
+            },
+            BudgetExtensions::default(),
+            TokenUsage::default(),
+            0,
+        );
+        tracker.record_step();
+        assert!(tracker.check().is_none());
+        tracker.record_step();
+        let exhaustion = tracker.check().expect("step budget should be exhausted");
+        assert_eq!(exhaustion.kind, BudgetKind::Steps);
+        assert_eq!(exhaustion.limit, 2);
+
+        assert_eq!(tracker.extend(BudgetKind::Steps).max_steps, Some(4));
+        assert!(tracker.check().is_none());
+    }
+
+    #[test]
+    fn unlimited_budget_never_exhausts() {
+        let tracker = BudgetTracker::new(
+            TaskBudget::default(),
+            BudgetExtensions::default(),
+            usage(10_000_000, 0),
+            10_000,
+        );
+        assert!(TaskBudget::default().is_unlimited());
+        assert!(tracker.check().is_none());
+    }
+}
@@ -0,0 +1,524 @@
+//! Per-workspace check command, run after a worker turn edits files.
+//!
+//! A workspace may configure a command such as `cargo check
+//! --message-format=json` or `tsc --noEmit`. When a turn's tool calls change
+//! files, the command runs in the step's worktree through `cmd.exec`, so the
+//! usual command policy, sandbox and timeout apply. The compiler output is
+//! parsed into a list of diagnostics that is attached to the observation of
+//! the turn's last edit, so the model sees what it broke right away.
+//!
+//! Understood output formats: cargo/rustc JSON messages, rustc's
+//! `error[E0308]: …` / `--> file:line:col` blocks, `tsc` (both
+//! `file(line,col): error TS…` and `file:line:col - error TS…`), and the
+//! common `file:line:col: error: message` form used by gcc, clang, eslint's
+//! unix formatter and many others.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{queries, Database};
+use crate::policy::{redaction, PolicyEngine};
+use crate::runtime::tool_calling::invoke_internal_tool;
+use crate::tools::{ToolCallInput, ToolError, ToolRegistry};
+
+/// Tools whose successful calls trigger the check.
+pub const MUTATING_TOOLS: &[&str] = &[
+    "fs.write",
+    "fs.patch",
+    "git.apply_patch",
+    "code.rename",
+    "git.branch",
+    "git.stash",
+];
+
+/// Diagnostics beyond this many are counted but not listed.
+const MAX_DIAGNOSTICS: usize = 50;
+
+/// How much of the end of a long run's full log is parsed; compilers print
+/// the errors that matter last.
+const MAX_LOG_TAIL_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Output kept in the report when the check fails without diagnostics.
+const MAX_OUTPUT_TAIL_CHARS: usize = 2000;
+
+/// The check command configured for a workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckCommandSettings {
+    /// Shell command run in the worktree root.
+    pub command: String,
+    /// Timeout in seconds; the policy's `cmd.exec` default when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+fn setting_key(workspace_root: &Path) -> String {
+    format!("check_command:{}", workspace_root.to_string_lossy())
+}
+
+/// Load the check command of `workspace_root`, if one is configured.
+pub fn load_check_command(
+    db: &Database,
+    workspace_root: &Path,
+) -> Result<Option<CheckCommandSettings>, String> {
+    match queries::get_setting(db, &setting_key(workspace_root)) {
+        Ok(Some(json)) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse check command settings: {e}")),
+        Ok(None) => Ok(None),
+        Err(e) => Err(format!("Failed to load check command settings: {e}")),
+    }
+}
+
+/// Save the check command of `workspace_root`; `None` or a blank command
+/// removes it.
+pub fn save_check_command(
+    db: &Database,
+    workspace_root: &Path,
+    settings: Option<&CheckCommandSettings>,
+) -> Result<(), String> {
+    let key = setting_key(workspace_root);
+    let Some(settings) = settings.filter(|s| !s.command.trim().is_empty()) else {
+        return queries::delete_setting(db, &key)
+            .map_err(|e| format!("Failed to remove check command settings: {e}"));
+    };
+    let json = serde_json::to_string(settings)
+        .map_err(|e| format!("Failed to serialize check command settings: {e}"))?;
+    queries::upsert_setting(db, &key, &json, &chrono::Utc::now().to_rfc3339())
+        .map_err(|e| format!("Failed to save check command settings: {e}"))
+}
+
+/// One compiler message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompilerDiagnostic {
+    /// `error` or `warning`.
+    pub severity: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// Outcome of one check run, attached to the observation as `check`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckReport {
+    pub command: String,
+    pub passed: bool,
+    pub exit_code: Option<i32>,
+    pub error_count: usize,
+    pub warning_count: usize,
+    /// Errors first, at most [`MAX_DIAGNOSTICS`].
+    pub diagnostics: Vec<CompilerDiagnostic>,
+    pub truncated: bool,
+    /// End of the output, when the check failed without parseable errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_tail: Option<String>,
+    /// Why the check did not run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+impl CheckReport {
+    fn skipped(command: &str, reason: String) -> Self {
+        Self {
+            command: command.to_string(),
+            passed: false,
+            exit_code: None,
+            error_count: 0,
+            warning_count: 0,
+            diagnostics: Vec::new(),
+            truncated: false,
+            output_tail: None,
+            skipped: Some(reason),
+        }
+    }
+}
+
+/// Run `settings.command` in `worktree_path` and collect its diagnostics.
+///
+/// Never asks for approval: a command the policy does not allow outright is
+/// skipped and the report says why.
+pub async fn run_check_command(
+    tool_registry: &ToolRegistry,
+    policy: &PolicyEngine,
+    task_id: &str,
+    worktree_path: &Path,
+    settings: &CheckCommandSettings,
+) -> CheckReport {
+    let call = ToolCallInput {
+        name: "cmd.exec".to_string(),
+        args: serde_json::json!({
+            "command": settings.command,
+            "timeout_secs": settings.timeout_secs,
+        }),
+    };
+    let output =
+        match invoke_internal_tool(task_id, tool_registry, policy, worktree_path, call).await {
+            Ok(output) => output,
+            Err(ToolError::ApprovalRequired { reason, .. }) => {
+                return CheckReport::skipped(
+                    &settings.command,
+                    format!("check command needs approval: {reason}"),
+                )
+            }
+            Err(error) => return CheckReport::skipped(&settings.command, error.to_string()),
+        };
+    if let Some(error) = output.error.as_deref() {
+        // Timed out or cancelled; partial output is not a verdict
+        return CheckReport::skipped(&settings.command, error.to_string());
+    }
+
+    let stdout = output.data["stdout"].as_str().unwrap_or_default();
+    let stderr = output.data["stderr"].as_str().unwrap_or_default();
+    // Inline output is cut in the middle for long runs; the full log is not
+    let full_log = match output
+        .data
+        .pointer("/full_log/path")
+        .and_then(|p| p.as_str())
+    {
+        Some(path) => {
+            let path = path.to_string();
+            tokio::task::spawn_blocking(move || read_log_tail(Path::new(&path)))
+                .await
+                .ok()
+                .flatten()
+        }
+        None => None,
+    };
+    let text = full_log.unwrap_or_else(|| format!("{stdout}\n{stderr}"));
+    // The report goes into the model's observations
+    let (text, _) = redaction::redact_text(&text);
+
+    let mut diagnostics = parse_diagnostics(&text, worktree_path);
+    let error_count = diagnostics.iter().filter(|d| d.severity == "error").count();
+    let warning_count = diagnostics.len() - error_count;
+    // Stable sort keeps compiler order within each severity
+    diagnostics.sort_by_key(|d| d.severity != "error");
+    let truncated = diagnostics.len() > MAX_DIAGNOSTICS;
+    diagnostics.truncate(MAX_DIAGNOSTICS);
+
+    let output_tail = (!output.ok && error_count == 0).then(|| {
+        let (combined, _) = redaction::redact_text(&format!("{stdout}\n{stderr}"));
+        let combined = combined.trim();
+        let skip = combined
+            .chars()
+            .count()
+            .saturating_sub(MAX_OUTPUT_TAIL_CHARS);
+        combined.chars().skip(skip).collect()
+    });
+
+    CheckReport {
+        command: settings.command.clone(),
+        passed: output.ok,
+        exit_code: output.data["code"].as_i64().map(|code| code as i32),
+        error_count,
+        warning_count,
+        diagnostics,
+        truncated,
+        output_tail,
+        skipped: None,
+    }
+}
+
+/// The last `MAX_LOG_TAIL_BYTES` of a log, from the first whole line.
+fn read_log_tail(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let start = len.saturating_sub(MAX_LOG_TAIL_BYTES);
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    let text = String::from_utf8_lossy(&bytes);
+    if start == 0 {
+        return Some(text.into_owned());
+    }
+    let first_line_end = text.find('\n').map_or(text.len(), |i| i + 1);
+    Some(text[first_line_end..].to_string())
+}
+
+struct Patterns {
+    /// `error[E0308]: mismatched types` (rustc human output)
+    rustc_header: Regex,
+    /// `  --> src/lib.rs:3:5`
+    rustc_location: Regex,
+    /// `src/app.ts(12,5): error TS2322: …`
+    tsc_parens: Regex,
+    /// `src/app.ts:12:5 - error TS2322: …` (`tsc --pretty`)
+    tsc_pretty: Regex,
+    /// `src/main.c:3:5: error: …`
+    gcc: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        rustc_header: Regex::new(r"^(error|warning)(?:\[(\w+)\])?: (.+)$")
+            .expect("valid rustc header pattern"),
+        rustc_location: Regex::new(r"^\s*--> (.+?):(\d+):(\d+)$")
+            .expect("valid rustc location pattern"),
+        tsc_parens: Regex::new(r"^(.+?)\((\d+),(\d+)\): (error|warning) (TS\d+): (.+)$")
+            .expect("valid tsc pattern"),
+        tsc_pretty: Regex::new(r"^(.+?):(\d+):(\d+) - (error|warning) (TS\d+): (.+)$")
+            .expect("valid tsc pretty pattern"),
+        gcc: Regex::new(r"^(.+?):(\d+):(\d+): (?:fatal )?(error|warning)(?:\[([\w-]+)\])?: (.+)$")
+            .expect("valid gcc pattern"),
+    })
+}
+
+/// Summary lines rustc prints as diagnostics of their own.
+fn is_rustc_summary(message: &str) -> bool {
+    message.starts_with("aborting due to")
+        || message.ends_with("warning emitted")
+        || message.ends_with("warnings emitted")
+        || message.starts_with("could not compile")
+}
+
+/// Diagnostics in a check command's output, deduplicated, in output order.
+/// Paths under `cwd` are made relative to it.
+pub fn parse_diagnostics(output: &str, cwd: &Path) -> Vec<CompilerDiagnostic> {
+    let patterns = patterns();
+    let relative = |path: &str| -> String {
+        let path = path.trim();
+        Path::new(path)
+            .strip_prefix(cwd)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| path.to_string())
+    };
+    let number = |text: &str| text.parse::<u32>().ok();
+
+    let mut diagnostics: Vec<CompilerDiagnostic> = Vec::new();
+    // A rustc header waiting for its `-->` line
+    let mut pending: Option<CompilerDiagnostic> = None;
+
+    for line in output.lines() {
+        let line = line.trim_end();
+
+        if line.starts_with('{') {
+            if let Some(diagnostic) = parse_cargo_message(line, &relative) {
+                diagnostics.push(diagnostic);
+            }
+            continue;
+        }
+
+        if let Some(caps) = patterns.rustc_location.captures(line) {
+            if let Some(mut diagnostic) = pending.take() {
+                diagnostic.path = Some(relative(&caps[1]));
+                diagnostic.line = number(&caps[2]);
+                diagnostic.column = number(&caps[3]);
+                diagnostics.push(diagnostic);
+            }
+            continue;
+        }
+
+        let parsed = if let Some(caps) = patterns.tsc_parens.captures(line) {
+            Some(CompilerDiagnostic {
+                severity: caps[4].to_string(),
+                message: caps[6].to_string(),
+                path: Some(relative(&caps[1])),
+                line: number(&caps[2]),
+                column: number(&caps[3]),
+                code: Some(caps[5].to_string()),
+            })
+        } else if let Some(caps) = patterns.tsc_pretty.captures(line) {
+            Some(CompilerDiagnostic {
+                severity: caps[4].to_string(),
+                message: caps[6].to_string(),
+                path: Some(relative(&caps[1])),
+                line: number(&caps[2]),
+                column: number(&caps[3]),
+                code: Some(caps[5].to_string()),
+            })
+        } else if let Some(caps) = patterns.gcc.captures(line) {
+            Some(CompilerDiagnostic {
+                severity: caps[4].to_string(),
+                message: caps[6].to_string(),
+                path: Some(relative(&caps[1])),
+                line: number(&caps[2]),
+                column: number(&caps[3]),
+                code: caps.get(5).map(|m| m.as_str().to_string()),
+            })
+        } else {
+            None
+        };
+        if let Some(diagnostic) = parsed {
+            diagnostics.extend(pending.take());
+            diagnostics.push(diagnostic);
+            continue;
+        }
+
+        if let Some(caps) = patterns.rustc_header.captures(line) {
+            diagnostics.extend(pending.take());
+            if !is_rustc_summary(&caps[3]) {
+                pending = Some(CompilerDiagnostic {
+                    severity: caps[1].to_string(),
+                    message: caps[3].to_string(),
+                    path: None,
+                    line: None,
+                    column: None,
+                    code: caps.get(2).map(|m| m.as_str().to_string()),
+                });
+            }
+        }
+    }
+    diagnostics.extend(pending);
+
+    let mut unique: Vec<CompilerDiagnostic> = Vec::with_capacity(diagnostics.len());
+    for diagnostic in diagnostics {
+        if !unique.contains(&diagnostic) {
+            unique.push(diagnostic);
+        }
+    }
+    unique
+}
+
+/// A cargo `compiler-message` line, or `None` for other JSON output.
+fn parse_cargo_message(
+    line: &str,
+    relative: &dyn Fn(&str) -> String,
+) -> Option<CompilerDiagnostic> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    if value.get("reason").and_then(|r| r.as_str()) != Some("compiler-message") {
+        return None;
+    }
+    let message = value.get("message")?;
+    let severity = match message.get("level")?.as_str()? {
+        "error" | "error: internal compiler error" => "error",
+        "warning" => "warning",
+        _ => return None,
+    };
+    let text = message.get("message")?.as_str()?;
+    if is_rustc_summary(text) {
+        return None;
+    }
+    let primary = message
+        .get("spans")
+        .and_then(|spans| spans.as_array())
+        .and_then(|spans| {
+            spans
+                .iter()
+                .find(|span| span.get("is_primary").and_then(|p| p.as_bool()) == Some(true))
+        });
+    let span_number = |field: &str| {
+        primary
+            .and_then(|span| span.get(field))
+            .and_then(|n| n.as_u64())
+            .map(|n| n as u32)
+    };
+    Some(CompilerDiagnostic {
+        severity: severity.to_string(),
+        message: text.to_string(),
+        path: primary
+            .and_then(|span| span.get("file_name"))
+            .and_then(|f| f.as_str())
+            .map(relative),
+        line: span_number("line_start"),
+        column: span_number("column_start"),
+        code: message
+            .pointer("/code/code")
+            .and_then(|c| c.as_str())
+            .map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cargo_rustc_and_tsc_output() {
+        let cwd = Path::new("/work/tree");
+        let output = concat!(
+            r#"{"reason":"compiler-artifact","target":{"name":"dep"}}"#,
+            "\n",
+            r#"{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","code":{"code":"E0308"},"spans":[{"file_name":"src/lib.rs","line_start":3,"column_start":5,"is_primary":true}]}}"#,
+            "\n",
+            r#"{"reason":"compiler-message","message":{"level":"error","message":"aborting due to 1 previous error","code":null,"spans":[]}}"#,
+            "\n",
+            "warning: unused variable: `x`\n",
+            "  --> /work/tree/src/main.rs:7:9\n",
+            "   |\n",
+            "web/app.ts(12,5): error TS2322: Type 'string' is not assignable to type 'number'.\n",
+            "web/util.ts:4:1 - warning TS6133: 'y' is declared but its value is never read.\n",
+            "lib/c/main.c:3:5: error: expected ';' before '}' token\n",
+            "error: could not compile `demo` (lib) due to 1 previous error\n",
+        );
+
+        let diagnostics = parse_diagnostics(output, cwd);
+        let summary: Vec<(&str, Option<&str>, Option<u32>, Option<&str>)> = diagnostics
+            .iter()
+            .map(|d| {
+                (
+                    d.severity.as_str(),
+                    d.path.as_deref(),
+                    d.line,
+                    d.code.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("error", Some("src/lib.rs"), Some(3), Some("E0308")),
+                ("warning", Some("src/main.rs"), Some(7), None),
+                ("error", Some("web/app.ts"), Some(12), Some("TS2322")),
+                ("warning", Some("web/util.ts"), Some(4), Some("TS6133")),
+                ("error", Some("lib/c/main.c"), Some(3), None),
+            ]
+        );
+        assert_eq!(diagnostics[1].message, "unused variable: `x`");
+        assert_eq!(diagnostics[0].column, Some(5));
+    }
+
+    #[test]
+    fn check_command_is_stored_per_workspace() {
+        let db = Database::open_in_memory().expect("in-memory DB");
+        let first = Path::new("/work/one");
+        let settings = CheckCommandSettings {
+            command: "cargo check --message-format=json".to_string(),
+            timeout_secs: Some(300),
+        };
+
+        save_check_command(&db, first, Some(&settings)).unwrap();
+        assert_eq!(load_check_command(&db, first).unwrap(), Some(settings));
+        assert_eq!(
+            load_check_command(&db, Path::new("/work/two")).unwrap(),
+            None
+        );
+
+        let blank = CheckCommandSettings {
+            command: "  ".to_string(),
+            timeout_secs: None,
+        };
+        save_check_command(&db, first, Some(&blank)).unwrap();
+        assert_eq!(load_check_command(&db, first).unwrap(), None);
+    }
+
+    #[test]
+    fn long_logs_are_read_from_the_tail() {
+        let dir = crate::tests::temp_workspace();
+        let path = dir.join("check.log");
+        let line = "note: building dependency\n";
+        let mut log = line.repeat(MAX_LOG_TAIL_BYTES as usize / line.len() + 10);
+        log.push_str("src/main.c:3:5: error: expected ';'\n");
+        std::fs::write(&path, &log).unwrap();
+
+        let tail = read_log_tail(&path).unwrap();
+        assert!(tail.len() as u64 <= MAX_LOG_TAIL_BYTES);
+        assert!(tail.starts_with("note: building dependency\n"));
+        assert_eq!(parse_diagnostics(&tail, &dir).len(), 1);
+
+        std::fs::write(&path, "short\n").unwrap();
+        assert_eq!(read_log_tail(&path).unwrap(), "short\n");
+
+        crate::tests::cleanup(&dir);
+    }
+}
@@ -0,0 +1,258 @@
+//! Agent-assisted resolution of sub-agent merges that conflict.
+//!
+//! By default a conflicted merge is aborted and its files reported, leaving
+//! the merge to the user. In [`ConflictResolutionMode::Agent`] the worker
+//! hands the conflict to a resolver sub-agent instead. The resolver works in
+//! a temporary worktree where the merge of the agent branch into the base
+//! branch's tip has stopped at its conflicts, with a brief of what both sides
+//! set out to do and what they changed. The resolved merge is committed, the
+//! workspace check command runs on it, and the base branch is fast-forwarded
+//! only once the user approves.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::{queries, Database};
+use crate::runtime::worktree::ConflictContext;
+
+/// Tools the resolver sub-agent may use. It edits files but leaves
+/// committing and branch handling to the runtime, so it gets no `cmd.exec`
+/// that could abort or reset the merge.
+pub const RESOLVER_TOOLS: &[&str] = &[
+    "fs.read",
+    "fs.write",
+    "fs.patch",
+    "fs.list",
+    "search.rg",
+    "search.files",
+    "git.status",
+    "git.diff",
+    "git.log",
+    "git.show",
+    "git.blame",
+    "git.conflicts",
+    "code.definition",
+    "code.references",
+    "code.hover",
+    "code.diagnostics",
+    "code.outline",
+    "code.symbols",
+    "agent.complete",
+];
+
+/// Diffs in the brief are cut at this many characters; the resolver can read
+/// the rest with `git.diff`.
+const MAX_BRIEF_DIFF_CHARS: usize = 12_000;
+
+/// What happens when a sub-agent's branch conflicts with the base branch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolutionMode {
+    /// Abort the merge and report the conflicted files.
+    #[default]
+    Manual,
+    /// Resolve in a temporary worktree with a resolver sub-agent, then ask
+    /// before fast-forwarding the base branch.
+    Agent,
+}
+
+fn setting_key(workspace_root: &Path) -> String {
+    format!("conflict_resolution:{}", workspace_root.to_string_lossy())
+}
+
+/// Load the conflict resolution mode of `workspace_root`.
+pub fn load_conflict_resolution_mode(
+    db: &Database,
+    workspace_root: &Path,
+) -> Result<ConflictResolutionMode, String> {
+    match queries::get_setting(db, &setting_key(workspace_root)) {
+        Ok(Some(json)) => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse conflict resolution mode: {e}")),
+        Ok(None) => Ok(ConflictResolutionMode::default()),
+        Err(e) => Err(format!("Failed to load conflict resolution mode: {e}")),
+    }
+}
+
+/// Save the conflict resolution mode of `workspace_root`; the default mode
+/// removes the setting.
+pub fn save_conflict_resolution_mode(
+    db: &Database,
+    workspace_root: &Path,
+    mode: ConflictResolutionMode,
+) -> Result<(), String> {
+    let key = setting_key(workspace_root);
+    if mode == ConflictResolutionMode::default() {
+        return queries::delete_setting(db, &key)
+            .map_err(|e| format!("Failed to remove conflict resolution mode: {e}"));
+    }
+    let json = serde_json::to_string(&mode)
+        .map_err(|e| format!("Failed to serialize conflict resolution mode: {e}"))?;
+    queries::upsert_setting(db, &key, &json, &chrono::Utc::now().to_rfc3339())
+        .map_err(|e| format!("Failed to save conflict resolution mode: {e}"))
+}
+
+/// Objectives of `run_id`'s sub-agents whose branches already merged into
+/// the base branch, oldest first.
+pub fn merged_sub_agent_objectives(db: &Database, run_id: &str) -> Vec<String> {
+    let merged: Vec<String> = queries::list_worktree_logs_for_run(db, run_id)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|log| {
+            log.merge_success == Some(true) && log.merge_strategy.as_deref() != Some("skipped")
+        })
+        .map(|log| log.sub_agent_id)
+        .collect();
+    let agents = queries::list_sub_agents_for_run(db, run_id).unwrap_or_default();
+    merged
+        .iter()
+        .filter_map(|id| {
+            let agent = agents.iter().find(|agent| &agent.id == id)?;
+            let context: serde_json::Value =
+                serde_json::from_str(agent.context_json.as_deref()?).ok()?;
+            context
+                .pointer("/step/description")
+                .and_then(|d| d.as_str())
+                .map(str::to_string)
+        })
+        .collect()
+}
+
+/// What the two sides of a conflicted merge set out to do and changed.
+#[derive(Debug, Clone)]
+pub struct ResolverBrief<'a> {
+    pub task_prompt: &'a str,
+    /// Objective of the sub-agent whose branch conflicted.
+    pub agent_objective: &'a str,
+    /// Objectives of the run's sub-agents already merged into the base branch.
+    pub merged_objectives: &'a [String],
+    pub conflicted_files: &'a [String],
+    pub context: &'a ConflictContext,
+}
+
+impl ResolverBrief<'_> {
+    /// Step description for the resolver sub-agent.
+    pub fn step_description(&self) -> String {
+        let branch = &self.context.branch;
+        let or_none = |text: &str| {
+            if text.trim().is_empty() {
+                "(none)".to_string()
+            } else {
+                text.trim_end().to_string()
+            }
+        };
+        let files: Vec<String> = self
+            .conflicted_files
+            .iter()
+            .map(|file| format!("- {file}"))
+            .collect();
+        let merged: Vec<String> = self
+            .merged_objectives
+            .iter()
+            .map(|objective| format!("- {}", objective.trim()))
+            .collect();
+
+        format!(
+            "Resolve the merge conflicts between the base branch and `{branch}`, a sub-agent's branch.\n\n\
+             The merge of `{branch}` is in progress in this worktree and stopped at conflicts in:\n{files}\n\n\
+             ## Overall task\n{task}\n\n\
+             ## What the sub-agent on `{branch}` was doing\n{objective}\n\n\
+             Its commits:\n{branch_log}\n\n\
+             ## What the base branch was doing\n\
+             Sub-agents already merged into it in this run:\n{merged}\n\n\
+             Its commits since the branches forked:\n{base_log}\n\n\
+             ## Base branch changes to the conflicted files\n```diff\n{base_diff}\n```\n\n\
+             ## `{branch}` changes to the conflicted files\n```diff\n{branch_diff}\n```\n\n\
+             ## How to resolve\n\
+             1. Call git.conflicts for each hunk's line range and its ours (base branch), base and theirs (`{branch}`) content.\n\
+             2. Edit the files so the result keeps the intent of both sides, and remove every conflict marker.\n\
+             3. Check the files you changed with code.diagnostics; the workspace check command runs on the merge afterwards.\n\
+             4. Do not commit, switch branches or abort the merge; the merge is committed for you.\n\
+             5. Call agent.complete with how you resolved each conflict.",
+            files = files.join("\n"),
+            task = or_none(self.task_prompt),
+            objective = or_none(self.agent_objective),
+            branch_log = or_none(&self.context.branch_log),
+            merged = if merged.is_empty() {
+                "(none)".to_string()
+            } else {
+                merged.join("\n")
+            },
+            base_log = or_none(&self.context.base_log),
+            base_diff = clip(self.context.base_diff.trim_end(), MAX_BRIEF_DIFF_CHARS),
+            branch_diff = clip(self.context.branch_diff.trim_end(), MAX_BRIEF_DIFF_CHARS),
+        )
+    }
+}
+
+fn clip(text: &str, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if total <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars).collect();
+    format!(
+        "{kept}\n... ({} more characters; use git.diff for the rest)",
+        total - max_chars
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_defaults_to_manual_and_round_trips() {
+        let db = Database::open_in_memory().unwrap();
+        let root = Path::new("/tmp/project");
+
+        assert_eq!(
+            load_conflict_resolution_mode(&db, root).unwrap(),
+            ConflictResolutionMode::Manual
+        );
+        save_conflict_resolution_mode(&db, root, ConflictResolutionMode::Agent).unwrap();
+        assert_eq!(
+            load_conflict_resolution_mode(&db, root).unwrap(),
+            ConflictResolutionMode::Agent
+        );
+        assert_eq!(
+            load_conflict_resolution_mode(&db, Path::new("/tmp/other")).unwrap(),
+            ConflictResolutionMode::Manual
+        );
+        save_conflict_resolution_mode(&db, root, ConflictResolutionMode::Manual).unwrap();
+        assert!(queries::get_setting(&db, &setting_key(root))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn brief_carries_both_sides_intent_and_clips_diffs() {
+        let context = ConflictContext {
+            branch: "orchestrix/run/agent".to_string(),
+            fork_point: "abc123".to_string(),
+            base_log: "1111111 add login form\n".to_string(),
+            base_diff: "-old\n+base side\n".to_string(),
+            branch_log: String::new(),
+            branch_diff: "+".repeat(MAX_BRIEF_DIFF_CHARS + 10),
+        };
+        let files = vec!["src/app.ts".to_string()];
+        let merged = vec!["Build the login form".to_string()];
+        let brief = ResolverBrief {
+            task_prompt: "Add authentication",
+            agent_objective: "Add the session store",
+            merged_objectives: &merged,
+            conflicted_files: &files,
+            context: &context,
+        };
+
+        let description = brief.step_description();
+        assert!(description.contains("- src/app.ts"));
+        assert!(description.contains("Add authentication"));
+        assert!(description.contains("Add the session store"));
+        assert!(description.contains("- Build the login form"));
+        assert!(description.contains("1111111 add login form"));
+        assert!(description.contains("+base side"));
+        assert!(description.contains("Its commits:\n(none)"));
+        assert!(description.contains("(10 more characters; use git.diff for the rest)"));
+    }
+}
@@ -0,0 +1,296 @@
+//! Provider fallback chains and per-provider circuit breakers.
+//!
+//! A task, or the agent preset its prompt invokes, may declare an ordered list
+//! of fallback models as `<provider>/<model>` strings (for example
+//! `minimax/MiniMax-M2.5 -> kimi/kimi-k2.5 -> zhipu/glm-5`). `execute_plan`
+//! resolves the chain into `RuntimeModelConfig::fallbacks`, and the worker's
+//! `WorkerModelClient` moves down the chain when a provider returns
+//! `ModelError::Request`. Providers that keep failing trip a circuit breaker so
+//! later calls skip them until a cooldown elapses.
+
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use dashmap::DashMap;
+
+use crate::core::agent_presets;
+use crate::db::{queries, Database};
+use crate::model::{ModelError, ProviderId};
+use crate::runtime::orchestrator::RuntimeModelConfig;
+
+pub const EVENT_MODEL_FAILOVER: &str = "model.failover";
+
+/// Consecutive request failures that open a provider's breaker.
+const BREAKER_FAILURE_THRESHOLD: u32 = 3;
+/// How long an open breaker rejects calls before letting a probe through.
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Failure tracking for one provider.
+///
+/// Closed until `BREAKER_FAILURE_THRESHOLD` consecutive failures, then open for
+/// `BREAKER_COOLDOWN`. After the cooldown it is half-open: one probe call is
+/// admitted while others are still rejected. A success closes the breaker, a
+/// failure reopens it immediately, and a probe that never reports lets the
+/// next one through after another cooldown.
+#[derive(Debug, Clone, Default)]
+struct ProviderBreaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl ProviderBreaker {
+    /// Whether a call may go ahead; admitting the half-open probe keeps the
+    /// breaker open for everyone else.
+    fn admit(&mut self, now: Instant) -> bool {
+        match self.open_until {
+            None => true,
+            Some(until) if now >= until => {
+                self.open_until = Some(now + BREAKER_COOLDOWN);
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= BREAKER_FAILURE_THRESHOLD {
+            self.open_until = Some(now + BREAKER_COOLDOWN);
+        }
+    }
+}
+
+static PROVIDER_BREAKERS: OnceLock<DashMap<String, ProviderBreaker>> = OnceLock::new();
+
+fn breakers() -> &'static DashMap<String, ProviderBreaker> {
+    PROVIDER_BREAKERS.get_or_init(DashMap::new)
+}
+
+/// Canonical breaker key so aliases such as `glm` and `zhipu` share state.
+fn breaker_key(provider: &str) -> String {
+    ProviderId::from_str(provider)
+        .map(|id| id.as_str().to_string())
+        .unwrap_or_else(|_| provider.to_ascii_lowercase())
+}
+
+/// Whether a call to `provider` should be attempted right now. A `true` for
+/// a half-open breaker makes the caller its probe, so the call must follow.
+pub fn breaker_allows(provider: &str) -> bool {
+    breakers()
+        .get_mut(&breaker_key(provider))
+        .map_or(true, |mut breaker| breaker.admit(Instant::now()))
+}
+
+pub fn record_provider_success(provider: &str) {
+    breakers().remove(&breaker_key(provider));
+}
+
+pub fn record_provider_failure(provider: &str) {
+    breakers()
+        .entry(breaker_key(provider))
+        .or_default()
+        .record_failure(Instant::now());
+}
+
+/// Only transport-level failures (5xx, timeouts, exhausted quota) move to the
+/// next model. Auth and malformed-response errors would repeat on any retry
+/// of the same request shape and are surfaced as-is.
+pub fn is_failover_error(error: &ModelError) -> bool {
+    matches!(error, ModelError::Request(_))
+}
+
+/// Check that every chain entry names a model and, when prefixed, a known provider.
+pub fn validate_fallback_chain(chain: &[String]) -> Result<(), String> {
+    for entry in chain {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            return Err("fallback model entries cannot be empty".to_string());
+        }
+        if let Some((provider, _)) = entry.split_once('/') {
+            if ProviderId::from_str(provider.trim()).is_err() {
+                return Err(format!(
+                    "unknown provider in fallback model '{entry}'; use <provider>/<model>"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Resolve a chain entry to `(provider, model)`. Entries without a provider
+/// prefix use the primary provider.
+fn parse_chain_entry(entry: &str, primary_provider: &str) -> Option<(String, String)> {
+    let (provider, model) = agent_presets::parse_model_override(entry);
+    if model.trim().is_empty() {
+        return None;
+    }
+    let provider = provider.unwrap_or_else(|| breaker_key(primary_provider));
+    Some((provider, model))
+}
+
+/// Fallback chain for a task: the task's own chain if set, otherwise the
+/// `fallback_models` of the agent preset referenced by its prompt.
+pub fn load_fallback_chain(
+    db: &Database,
+    task_id: &str,
+    task_prompt: &str,
+    workspace_root: &std::path::Path,
+) -> Vec<String> {
+    match queries::get_task_model_fallbacks(db, task_id) {
+        Ok(Some(chain)) if !chain.is_empty() => return chain,
+        Ok(_) => {}
+        Err(error) => tracing::warn!("failed to load task model fallbacks: {error}"),
+    }
+
+    agent_presets::resolve_agent_preset_from_prompt(task_prompt, workspace_root)
+        .map(|preset| preset.fallback_models)
+        .unwrap_or_default()
+}
+
+/// Turn chain entries into runtime configs using each provider's stored
+/// credentials. Entries for unconfigured providers, and entries identical to
+/// the primary model, are skipped.
+pub fn resolve_fallback_configs(
+    db: &Database,
+    primary: &RuntimeModelConfig,
+    chain: &[String],
+) -> Vec<RuntimeModelConfig> {
+    let primary_provider = breaker_key(&primary.provider);
+    let mut configs: Vec<RuntimeModelConfig> = Vec::new();
+
+    for entry in chain {
+        let Some((provider, model)) = parse_chain_entry(entry, &primary.provider) else {
+            continue;
+        };
+        if provider == primary_provider && primary.model.as_deref() == Some(model.as_str()) {
+            continue;
+        }
+        if configs
+            .iter()
+            .any(|c| c.provider == provider && c.model.as_deref() == Some(model.as_str()))
+        {
+            continue;
+        }
+
+        match crate::load_provider_config(db, &provider) {
+            Ok(Some(config)) => configs.push(RuntimeModelConfig {
+                provider,
+                api_key: config.api_key,
+                model: Some(model),
+                base_url: config.base_url,
+                fallbacks: Vec::new(),
+            }),
+            Ok(None) => {
+                tracing::warn!(
+                    "skipping fallback model {entry}: provider {provider} is not configured"
+                )
+            }
+            Err(error) => tracing::warn!("skipping fallback model {entry}: {error}"),
+        }
+    }
+
+    configs
+}
+
+/// Attach the task's fallback chain to its primary model config, unless the
+/// caller already supplied one.
+pub fn attach_fallback_chain(
+    db: &Database,
+    task_id: &str,
+    task_prompt: &str,
+    workspace_root: &std::path::Path,
+    mut config: RuntimeModelConfig,
+) -> RuntimeModelConfig {
+    if config.fallbacks.is_empty() {
+        let chain = load_fallback_chain(db, task_id, task_prompt, workspace_root);
+        config.fallbacks = resolve_fallback_configs(db, &config, &chain);
+    }
+    config
+}
+
+/// Persist or clear a task's fallback chain.
+pub fn store_task_fallback_chain(
+    db: &Database,
+    task_id: &str,
+    chain: &[String],
+) -> Result<(), String> {
+    validate_fallback_chain(chain)?;
+    if chain.is_empty() {
+        queries::delete_task_model_fallbacks(db, task_id).map_err(|e| e.to_string())
+    } else {
+        let chain: Vec<String> = chain.iter().map(|entry| entry.trim().to_string()).collect();
+        queries::upsert_task_model_fallbacks(db, task_id, &chain, &Utc::now().to_rfc3339())
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaker_opens_after_threshold_and_half_opens_after_cooldown() {
+        let start = Instant::now();
+        let mut breaker = ProviderBreaker::default();
+
+        for _ in 0..BREAKER_FAILURE_THRESHOLD - 1 {
+            breaker.record_failure(start);
+        }
+        assert!(breaker.admit(start));
+
+        breaker.record_failure(start);
+        assert!(!breaker.admit(start));
+        assert!(!breaker.admit(start + BREAKER_COOLDOWN / 2));
+
+        // Half-open: one probe is admitted, concurrent calls are not, and
+        // one more failure reopens it.
+        let probe = start + BREAKER_COOLDOWN;
+        assert!(breaker.admit(probe));
+        assert!(!breaker.admit(probe));
+        breaker.record_failure(probe);
+        assert!(!breaker.admit(probe + Duration::from_secs(1)));
+
+        // A probe that never reports lets another through after a cooldown
+        let second = probe + BREAKER_COOLDOWN;
+        assert!(breaker.admit(second));
+        assert!(!breaker.admit(second + Duration::from_secs(1)));
+        assert!(breaker.admit(second + BREAKER_COOLDOWN));
+    }
+
+    #[test]
+    fn success_closes_breaker_for_provider_aliases() {
+        for _ in 0..BREAKER_FAILURE_THRESHOLD {
+            record_provider_failure("glm");
+        }
+        assert!(!breaker_allows("zhipu"));
+
+        record_provider_success("zhipu");
+        assert!(breaker_allows("glm"));
+    }
+
+    #[test]
+    fn chain_entries_default_to_primary_provider() {
+        assert_eq!(
+            parse_chain_entry("kimi/kimi-k2.5", "minimax"),
+            Some(("kimi".to_string(), "kimi-k2.5".to_string()))
+        );
+        assert_eq!(
+            parse_chain_entry("glm-5", "glm"),
+            Some(("zhipu".to_string(), "glm-5".to_string()))
+        );
+        assert_eq!(
+            parse_chain_entry("modal/zai-org/GLM-5-FP8", "minimax"),
+            Some(("modal".to_string(), "zai-org/GLM-5-FP8".to_string()))
+        );
+    }
+
+    #[test]
+    fn validation_rejects_unknown_providers() {
+        assert!(validate_fallback_chain(&["kimi/kimi-k2.5".to_string()]).is_ok());
+        assert!(validate_fallback_chain(&["glm-5".to_string()]).is_ok());
+        assert!(validate_fallback_chain(&["acme/model".to_string()]).is_err());
+        assert!(validate_fallback_chain(&["  ".to_string()]).is_err());
+    }
+}
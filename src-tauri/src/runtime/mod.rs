@@ -1,13 +1,19 @@
 pub mod approval;
 pub mod artifacts;
+pub mod budget;
+pub mod check_command;
+pub mod conflict_resolution;
+pub mod failover;
 pub mod orchestrator;
 pub mod plan_mode_settings;
 pub mod planner;
 pub mod prompt_suggestion;
 pub mod questions;
 pub mod recovery;
+pub mod snapshots;
 pub mod summarization;
 pub mod tool_calling;
+pub mod usage;
 pub mod worktree;
 
 #[cfg(test)]
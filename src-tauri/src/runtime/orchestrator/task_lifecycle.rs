@@ -2,6 +2,9 @@ use super::*;
 use crate::core::prompt_references::expand_prompt_references;
 use crate::embeddings;
 use crate::runtime::artifacts::collect_markdown_artifact_bundle;
+use crate::runtime::budget::{enforce_budget, register_run_budget, release_run_budget};
+use crate::runtime::failover::attach_fallback_chain;
+use crate::runtime::planner::load_structured_plan;
 
 impl Orchestrator {
     /// Legacy: unified plan+build entry. Current flow uses run_plan_mode then run_build_mode separately.
@@ -50,10 +53,18 @@ impl Orchestrator {
     pub fn cancel_task(&self, task_id: &str) {
         self.approval_gate.reject_all_for_task(task_id);
         self.question_gate.reject_all_for_task(task_id);
+        // Aborting the task does not stop tool work on the blocking pool;
+        // cancelling the tool calls does.
+        crate::runtime::tool_calling::cancel_task_tool_calls(task_id);
         let mut guard = self.active.lock().expect("orchestrator mutex poisoned");
         if let Some(handle) = guard.remove(task_id) {
             handle.abort();
         }
+        if let Ok(runs) = queries::list_runs_for_task(&self.db, task_id) {
+            for run in runs {
+                release_run_budget(&run.id);
+            }
+        }
     }
 
     pub fn approve_plan(
@@ -109,31 +120,34 @@ impl Orchestrator {
         let has_artifacts = !artifact_bundle.trim().is_empty();
 
         let run_uuid = Uuid::parse_str(&run.id).map_err(|e| format!("invalid run id: {e}"))?;
-        let plan = Plan {
-            id: Uuid::new_v4(),
-            run_id: run_uuid,
-            goal_summary: "Implement task using reviewed markdown artifacts".to_string(),
-            steps: vec![PlanStep {
-                idx: 0,
-                title: "Implement from artifacts".to_string(),
-                description: format!(
-                    "Implement the task using the user prompt and all markdown artifacts as source-of-truth context when available.\n\nTask prompt:\n{}\n\nMarkdown artifacts:{}",
-                    task.prompt,
-                    if has_artifacts {
-                        artifact_bundle
-                    } else {
-                        "\n(none found; implement directly from prompt)".to_string()
-                    }
-                ),
-                tool_intent: None,
-                status: StepStatus::Pending,
-                max_retries: 1,
-                result: None,
-            }],
-            completion_criteria: vec![
-                "Implementation matches the markdown plan artifacts".to_string(),
-                "Changes are applied and validated where possible".to_string(),
-            ],
+        let context = format!(
+            "Task prompt:\n{}\n\nMarkdown artifacts:{}",
+            task.prompt,
+            if has_artifacts {
+                artifact_bundle
+            } else {
+                "\n(none found; implement directly from prompt)".to_string()
+            }
+        );
+        let plan = match load_structured_plan(&self.db, &run.id) {
+            Some(reviewed) => reviewed_plan_for_run(reviewed, run_uuid, &context),
+            None => Plan {
+                id: Uuid::new_v4(),
+                run_id: run_uuid,
+                goal_summary: "Implement task using reviewed markdown artifacts".to_string(),
+                steps: vec![PlanStep {
+                    idx: 0,
+                    title: "Implement from artifacts".to_string(),
+                    description: format!(
+                        "Implement the task using the user prompt and all markdown artifacts as source-of-truth context when available.\n\n{context}"
+                    ),
+                    tool_intent: None,
+                    status: StepStatus::Pending,
+                    max_retries: 1,
+                    result: None,
+                }],
+                completion_criteria: default_build_criteria(),
+            },
         };
 
         let task_id = task.id.clone();
@@ -163,6 +177,7 @@ impl Orchestrator {
                         api_key,
                         model,
                         base_url,
+                        fallbacks: Vec::new(),
                     }),
                 )
                 .await;
@@ -213,6 +228,10 @@ impl Orchestrator {
         let artifact_bundle = collect_markdown_artifact_bundle(&self.db, &task.id);
         let has_artifacts = !artifact_bundle.trim().is_empty();
 
+        let reviewed_steps = load_structured_plan(&self.db, &run.id)
+            .map(|reviewed| plan_outline(&reviewed))
+            .unwrap_or_else(|| "(none)".to_string());
+
         let run_uuid = Uuid::parse_str(&run.id).map_err(|e| format!("invalid run id: {e}"))?;
         let plan = Plan {
             id: Uuid::new_v4(),
@@ -222,8 +241,9 @@ impl Orchestrator {
                 idx: 0,
                 title: "Process follow-up request".to_string(),
                 description: format!(
-                    "Continue working on the task with the new follow-up message. Review previous work and artifacts if available.\n\nContinue prompt:\n{}\n\nPrevious artifacts:{}",
+                    "Continue working on the task with the new follow-up message. Review previous work and artifacts if available.\n\nContinue prompt:\n{}\n\nReviewed plan:\n{}\n\nPrevious artifacts:{}",
                     continue_prompt,
+                    reviewed_steps,
                     if has_artifacts {
                         artifact_bundle
                     } else {
@@ -267,6 +287,7 @@ impl Orchestrator {
                         api_key,
                         model,
                         base_url,
+                        fallbacks: Vec::new(),
                     }),
                 )
                 .await;
@@ -368,6 +389,9 @@ impl Orchestrator {
         model: Option<String>,
         base_url: Option<String>,
     ) -> Result<(), String> {
+        let reviewed = queries::get_latest_run_for_task(&self.db, &task.id)
+            .map_err(|e| e.to_string())?
+            .and_then(|latest| load_structured_plan(&self.db, &latest.id));
         let run = queries::RunRow {
             id: Uuid::new_v4().to_string(),
             task_id: task.id.clone(),
@@ -380,20 +404,27 @@ impl Orchestrator {
         queries::insert_run(&self.db, &run).map_err(|e| e.to_string())?;
 
         let run_uuid = Uuid::parse_str(&run.id).map_err(|e| format!("invalid run id: {e}"))?;
-        let plan = Plan {
-            id: Uuid::new_v4(),
-            run_id: run_uuid,
-            goal_summary: "Autonomous conversational execution".to_string(),
-            steps: vec![PlanStep {
-                idx: 0,
-                title: "Autonomous execution".to_string(),
-                description: task.prompt.clone(),
-                tool_intent: None,
-                status: StepStatus::Pending,
-                max_retries: 0,
-                result: None,
-            }],
-            completion_criteria: vec!["Worker completes autonomously".to_string()],
+        let plan = match reviewed {
+            Some(reviewed) => reviewed_plan_for_run(
+                reviewed,
+                run_uuid,
+                &format!("Task prompt:\n{}", task.prompt),
+            ),
+            None => Plan {
+                id: Uuid::new_v4(),
+                run_id: run_uuid,
+                goal_summary: "Autonomous conversational execution".to_string(),
+                steps: vec![PlanStep {
+                    idx: 0,
+                    title: "Autonomous execution".to_string(),
+                    description: task.prompt.clone(),
+                    tool_intent: None,
+                    status: StepStatus::Pending,
+                    max_retries: 0,
+                    result: None,
+                }],
+                completion_criteria: vec!["Worker completes autonomously".to_string()],
+            },
         };
 
         self.execute_plan(
@@ -406,6 +437,7 @@ impl Orchestrator {
                 api_key,
                 model,
                 base_url,
+                fallbacks: Vec::new(),
             }),
         )
         .await
@@ -433,6 +465,9 @@ impl Orchestrator {
 
         let workspace_root = self.current_workspace_root();
         let resolved_task_prompt = expand_prompt_references(&task_prompt, &workspace_root);
+        let model_config = model_config.map(|config| {
+            attach_fallback_chain(&self.db, &task_id, &task_prompt, &workspace_root, config)
+        });
         let policy = PolicyEngine::with_approved_scopes(
             workspace_root.clone(),
             self.approval_gate.approved_scopes_handle(),
@@ -446,6 +481,8 @@ impl Orchestrator {
         let checkpoint = queries::get_checkpoint(&self.db, &run_id).map_err(|e| e.to_string())?;
         let mut failed: Vec<SubAgentResult> = Vec::new();
 
+        register_run_budget(&self.db, &run_id, &task_id)?;
+
         for step in &plan.steps {
             if let Some(cp) = checkpoint.as_ref() {
                 if step.idx as i64 <= cp.last_step_idx {
@@ -453,6 +490,26 @@ impl Orchestrator {
                 }
             }
 
+            if let Err(error) = enforce_budget(
+                &self.db,
+                &self.bus,
+                &self.question_gate,
+                &run_id,
+                &task_id,
+                None,
+            )
+            .await
+            {
+                failed.push(SubAgentResult {
+                    sub_agent_id: format!("parent-{}-step-{}", run_id, step.idx),
+                    success: false,
+                    output_path: None,
+                    error: Some(error),
+                    merge_message: None,
+                });
+                break;
+            }
+
             let virtual_parent = queries::SubAgentRow {
                 id: format!("parent-{}-step-{}", run_id, step.idx),
                 run_id: run_id.clone(),
@@ -540,6 +597,8 @@ impl Orchestrator {
             }
         }
 
+        release_run_budget(&run_id);
+
         if failed.is_empty() {
             queries::update_run_status(
                 &self.db,
@@ -599,3 +658,42 @@ impl Orchestrator {
         Err(failure_reason)
     }
 }
+
+fn default_build_criteria() -> Vec<String> {
+    vec![
+        "Implementation matches the markdown plan artifacts".to_string(),
+        "Changes are applied and validated where possible".to_string(),
+    ]
+}
+
+/// The reviewed plan's steps, to run in `run_id`. Each step runs as its own
+/// sub-agent, so each description carries `context` as well.
+fn reviewed_plan_for_run(mut plan: Plan, run_id: Uuid, context: &str) -> Plan {
+    let total = plan.steps.len();
+    plan.id = Uuid::new_v4();
+    plan.run_id = run_id;
+    for step in &mut plan.steps {
+        step.description = format!(
+            "{}\n\nThis is step {} of {} of the reviewed plan.\n\n{}",
+            step.description,
+            step.idx + 1,
+            total,
+            context
+        );
+        step.status = StepStatus::Pending;
+        step.result = None;
+    }
+    if plan.completion_criteria.is_empty() {
+        plan.completion_criteria = default_build_criteria();
+    }
+    plan
+}
+
+/// Numbered step titles and descriptions of a plan.
+fn plan_outline(plan: &Plan) -> String {
+    plan.steps
+        .iter()
+        .map(|step| format!("{}. {}: {}", step.idx + 1, step.title, step.description))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
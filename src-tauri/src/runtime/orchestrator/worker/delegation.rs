@@ -6,15 +6,33 @@
 use std::path::Path;
 
 use chrono::Utc;
+use tokio::time::{timeout, Duration};
 use uuid::Uuid;
 
+use crate::bus::{
+    CATEGORY_AGENT, CATEGORY_TOOL, EVENT_AGENT_CONFLICT_RESOLUTION_FINISHED,
+    EVENT_AGENT_CONFLICT_RESOLUTION_STARTED, EVENT_AGENT_SUBAGENT_CLOSED,
+    EVENT_TOOL_APPROVAL_REQUIRED, EVENT_TOOL_APPROVAL_RESOLVED,
+};
 use crate::core::agent_presets;
 use crate::core::plan::{PlanStep, StepStatus};
 use crate::db::{queries, Database};
+use crate::policy::PolicyEngine;
+use crate::runtime::check_command::{load_check_command, run_check_command, CheckReport};
+use crate::runtime::conflict_resolution::{
+    load_conflict_resolution_mode, merged_sub_agent_objectives, ConflictResolutionMode,
+    ResolverBrief, RESOLVER_TOOLS,
+};
 use crate::runtime::planner::emit_and_record;
-use crate::runtime::worktree::WorktreeManager;
+use crate::runtime::worktree::{MergeResult, MergeStrategy, WorktreeManager};
 use crate::tools::ToolRegistry;
 
+/// How long a resolved merge waits for the user's approval.
+const MERGE_APPROVAL_TIMEOUT_SECS: u64 = 300;
+
+/// Attempt timeout of a conflict resolver sub-agent.
+const RESOLVER_ATTEMPT_TIMEOUT_SECS: u64 = 600;
+
 /// Result of a sub-agent execution.
 pub struct SubAgentExecutionResult {
     pub success: bool,
@@ -259,7 +277,39 @@ pub async fn spawn_and_execute_delegated_sub_agent(
 
     // Merge worktree if successful
     if child_result.success {
-        match worktree_manager.merge_worktree(workspace_root, &child_result.sub_agent_id) {
+        let merged = match worktree_manager
+            .merge_worktree(workspace_root, &child_result.sub_agent_id)
+        {
+            Ok(conflict)
+                if conflict.strategy == MergeStrategy::Conflict
+                    && load_conflict_resolution_mode(db, workspace_root).unwrap_or_default()
+                        == ConflictResolutionMode::Agent =>
+            {
+                Ok(resolve_conflicts_with_agent(
+                    db,
+                    bus,
+                    workspace_root,
+                    tool_registry,
+                    worktree_manager,
+                    approval_gate,
+                    question_gate,
+                    run_id,
+                    task_id,
+                    step_idx,
+                    turn,
+                    &child_result.sub_agent_id,
+                    objective,
+                    task_prompt,
+                    goal_summary,
+                    skills_context,
+                    model_config,
+                    conflict,
+                )
+                .await)
+            }
+            other => other,
+        };
+        match merged {
             Ok(merge_result) => {
                 let _ = emit_and_record(
                     db,
@@ -415,6 +465,348 @@ pub async fn spawn_and_execute_delegated_sub_agent(
     }
 }
 
+/// Resolve a conflicted merge of `child_sub_agent_id`'s branch with a
+/// resolver sub-agent in a temporary worktree, run the check command on the
+/// result and, once the user approves, fast-forward the base branch to it.
+///
+/// Returns `conflict` with the reason appended when the resolution fails or
+/// is not approved, leaving the base branch as it was.
+async fn resolve_conflicts_with_agent(
+    db: &Database,
+    bus: &crate::bus::EventBus,
+    workspace_root: &Path,
+    tool_registry: &ToolRegistry,
+    worktree_manager: &WorktreeManager,
+    approval_gate: &crate::runtime::approval::ApprovalGate,
+    question_gate: &crate::runtime::questions::UserQuestionGate,
+    run_id: &str,
+    task_id: &str,
+    step_idx: u32,
+    turn: usize,
+    child_sub_agent_id: &str,
+    objective: &str,
+    task_prompt: &str,
+    goal_summary: &str,
+    skills_context: &str,
+    model_config: Option<&super::model::RuntimeModelConfig>,
+    conflict: MergeResult,
+) -> MergeResult {
+    let unresolved = |reason: String| MergeResult {
+        message: format!("{}; agent resolution failed: {reason}", conflict.message),
+        ..conflict.clone()
+    };
+
+    let context = match worktree_manager.conflict_context(
+        workspace_root,
+        child_sub_agent_id,
+        &conflict.conflicted_files,
+    ) {
+        Ok(context) => context,
+        Err(error) => return unresolved(error.to_string()),
+    };
+    let merged_objectives = merged_sub_agent_objectives(db, run_id);
+    let brief = ResolverBrief {
+        task_prompt,
+        agent_objective: objective,
+        merged_objectives: &merged_objectives,
+        conflicted_files: &conflict.conflicted_files,
+        context: &context,
+    };
+
+    let resolver = queries::SubAgentRow {
+        id: Uuid::new_v4().to_string(),
+        run_id: run_id.to_string(),
+        step_idx: step_idx as i64,
+        name: format!("resolver-{}", turn),
+        status: "created".to_string(),
+        worktree_path: None,
+        context_json: Some(
+            serde_json::json!({
+                "task_prompt": task_prompt,
+                "goal_summary": goal_summary,
+                "step": {
+                    "title": format!("Resolve merge conflicts of {}", context.branch),
+                    "description": format!("Resolve merge conflicts of {}", context.branch),
+                },
+                "conflict": {
+                    "sub_agent_id": child_sub_agent_id,
+                    "branch": context.branch,
+                    "conflicted_files": conflict.conflicted_files,
+                },
+                "contract": {
+                    "permissions": {
+                        "allowed_tools": RESOLVER_TOOLS,
+                        "can_spawn_children": false,
+                        "max_delegation_depth": 0,
+                    },
+                    "execution": {
+                        "attempt_timeout_ms": RESOLVER_ATTEMPT_TIMEOUT_SECS * 1000,
+                        "close_on_completion": true,
+                    }
+                }
+            })
+            .to_string(),
+        ),
+        started_at: None,
+        finished_at: None,
+        error: None,
+    };
+    if let Err(error) = queries::insert_sub_agent(db, &resolver) {
+        return unresolved(format!("failed to insert resolver sub-agent: {error}"));
+    }
+
+    let _ = emit_and_record(
+        db,
+        bus,
+        CATEGORY_AGENT,
+        EVENT_AGENT_CONFLICT_RESOLUTION_STARTED,
+        Some(run_id.to_string()),
+        serde_json::json!({
+            "task_id": task_id,
+            "sub_agent_id": child_sub_agent_id,
+            "resolver_sub_agent_id": resolver.id,
+            "step_idx": step_idx,
+            "branch": context.branch,
+            "conflicted_files": conflict.conflicted_files,
+        }),
+    );
+
+    let outcome = match worktree_manager.create_conflict_worktree(
+        workspace_root,
+        run_id,
+        &resolver.id,
+        &context.branch,
+    ) {
+        Ok(worktree) => {
+            let step = PlanStep {
+                idx: step_idx,
+                title: format!("Resolve merge conflicts of {}", context.branch),
+                description: brief.step_description(),
+                tool_intent: None,
+                status: StepStatus::Pending,
+                max_retries: 0,
+                result: None,
+            };
+            let result = Box::pin(super::super::sub_agent::execute_sub_agent(
+                db,
+                bus,
+                workspace_root,
+                tool_registry,
+                worktree_manager,
+                approval_gate,
+                question_gate,
+                run_id.to_string(),
+                task_id.to_string(),
+                resolver.clone(),
+                step,
+                model_config.cloned(),
+                goal_summary.to_string(),
+                task_prompt.to_string(),
+                skills_context.to_string(),
+            ))
+            .await;
+
+            if result.success {
+                let policy = PolicyEngine::with_approved_scopes(
+                    worktree.path.clone(),
+                    approval_gate.approved_scopes_handle(),
+                );
+                approve_resolved_merge(
+                    db,
+                    bus,
+                    workspace_root,
+                    tool_registry,
+                    worktree_manager,
+                    approval_gate,
+                    &policy,
+                    run_id,
+                    task_id,
+                    &resolver.id,
+                    &worktree.path,
+                    &context.branch,
+                    &conflict.conflicted_files,
+                )
+                .await
+            } else {
+                Err(result
+                    .error
+                    .unwrap_or_else(|| "resolver sub-agent failed".to_string()))
+            }
+        }
+        Err(error) => Err(error.to_string()),
+    };
+
+    let merge_result = match outcome {
+        Ok(result) if result.success => result,
+        Ok(result) => MergeResult {
+            conflicted_files: conflict.conflicted_files.clone(),
+            ..result
+        },
+        Err(reason) => unresolved(reason),
+    };
+
+    let (final_status, close_reason) = if merge_result.success {
+        ("completed", "conflicts_resolved")
+    } else {
+        ("failed", "conflicts_unresolved")
+    };
+    let now = Utc::now().to_rfc3339();
+    let _ = queries::update_worktree_log_merge(
+        db,
+        &resolver.id,
+        &merge_result.strategy.to_string(),
+        merge_result.success,
+        &merge_result.message,
+        None,
+        &now,
+    );
+    let _ = queries::update_sub_agent_status(
+        db,
+        &resolver.id,
+        "closed",
+        None,
+        Some(&now),
+        (!merge_result.success).then_some(merge_result.message.as_str()),
+    );
+    let _ = emit_and_record(
+        db,
+        bus,
+        CATEGORY_AGENT,
+        EVENT_AGENT_SUBAGENT_CLOSED,
+        Some(run_id.to_string()),
+        serde_json::json!({
+            "task_id": task_id,
+            "sub_agent_id": resolver.id,
+            "step_idx": step_idx,
+            "final_status": final_status,
+            "close_reason": close_reason,
+        }),
+    );
+    let _ = worktree_manager.remove_worktree(workspace_root, &resolver.id);
+    let _ = queries::update_worktree_log_cleaned(db, &resolver.id, &Utc::now().to_rfc3339());
+
+    let _ = emit_and_record(
+        db,
+        bus,
+        CATEGORY_AGENT,
+        EVENT_AGENT_CONFLICT_RESOLUTION_FINISHED,
+        Some(run_id.to_string()),
+        serde_json::json!({
+            "task_id": task_id,
+            "sub_agent_id": child_sub_agent_id,
+            "resolver_sub_agent_id": resolver.id,
+            "step_idx": step_idx,
+            "success": merge_result.success,
+            "message": merge_result.message,
+        }),
+    );
+
+    merge_result
+}
+
+/// Commit the resolver's merge, run the check command on it and ask the user
+/// before fast-forwarding the base branch.
+async fn approve_resolved_merge(
+    db: &Database,
+    bus: &crate::bus::EventBus,
+    workspace_root: &Path,
+    tool_registry: &ToolRegistry,
+    worktree_manager: &WorktreeManager,
+    approval_gate: &crate::runtime::approval::ApprovalGate,
+    policy: &PolicyEngine,
+    run_id: &str,
+    task_id: &str,
+    resolver_id: &str,
+    worktree_path: &Path,
+    branch: &str,
+    conflicted_files: &[String],
+) -> Result<MergeResult, String> {
+    let diffstat = worktree_manager
+        .commit_conflict_resolution(resolver_id, branch, conflicted_files)
+        .map_err(|e| e.to_string())?;
+
+    let check = match load_check_command(db, workspace_root)? {
+        Some(settings) => {
+            Some(run_check_command(tool_registry, policy, task_id, worktree_path, &settings).await)
+        }
+        None => None,
+    };
+    let check_summary = match &check {
+        None => "No check command is configured.".to_string(),
+        Some(CheckReport {
+            command,
+            skipped: Some(reason),
+            ..
+        }) => format!("Check `{command}` did not run: {reason}"),
+        Some(report) if report.passed => format!("Check `{}` passed.", report.command),
+        Some(report) => format!(
+            "Check `{}` FAILED with {} error(s).",
+            report.command, report.error_count
+        ),
+    };
+
+    let tool_name = "worktree.merge";
+    let tool_call_id = format!("merge-{resolver_id}");
+    let scope = format!("merge:{branch}");
+    let reason = format!(
+        "Fast-forward the base branch to the agent-resolved merge of {branch}. {check_summary}\n{}",
+        diffstat.trim_end()
+    );
+    let (request, receiver) = approval_gate.request(
+        task_id,
+        run_id,
+        resolver_id,
+        &tool_call_id,
+        tool_name,
+        &scope,
+        &reason,
+    );
+    let _ = emit_and_record(
+        db,
+        bus,
+        CATEGORY_TOOL,
+        EVENT_TOOL_APPROVAL_REQUIRED,
+        Some(run_id.to_string()),
+        serde_json::json!({
+            "task_id": task_id,
+            "tool_call_id": tool_call_id,
+            "approval_id": request.id,
+            "tool_name": tool_name,
+            "scope": scope,
+            "reason": reason,
+            "sub_agent_id": resolver_id,
+            "diffstat": diffstat,
+            "check": check,
+        }),
+    );
+
+    let approved = matches!(
+        timeout(Duration::from_secs(MERGE_APPROVAL_TIMEOUT_SECS), receiver).await,
+        Ok(Ok(true))
+    );
+    let _ = emit_and_record(
+        db,
+        bus,
+        CATEGORY_TOOL,
+        EVENT_TOOL_APPROVAL_RESOLVED,
+        Some(run_id.to_string()),
+        serde_json::json!({
+            "task_id": task_id,
+            "tool_call_id": tool_call_id,
+            "approval_id": request.id,
+            "approved": approved,
+            "sub_agent_id": resolver_id,
+        }),
+    );
+    if !approved {
+        return Err("the resolved merge was not approved".to_string());
+    }
+
+    worktree_manager
+        .fast_forward_to_resolution(workspace_root, resolver_id)
+        .map_err(|e| e.to_string())
+}
+
 fn persist_child_output_path(
     workspace_root: &Path,
     run_id: &str,
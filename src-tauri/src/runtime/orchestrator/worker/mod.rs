@@ -8,7 +8,7 @@
 //!
 //! # Sub-modules
 //!
-//! - `model`: Model client abstraction (MiniMax/Kimi)
+//! - `model`: Model client abstraction with provider failover
 //! - `tools`: Tool invocation and lifecycle management
 //! - `delegation`: Sub-agent spawning and worktree merging
 //! - `helpers`: Utility functions for observations and contracts
@@ -19,6 +19,8 @@ pub mod model;
 pub mod tools;
 
 use std::path::Path;
+
+use futures::StreamExt;
 use uuid::Uuid;
 
 use crate::bus::{
@@ -31,19 +33,37 @@ use crate::db::{queries, Database};
 use crate::model::{WorkerAction, WorkerActionRequest};
 use crate::policy::PolicyEngine;
 use crate::runtime::approval::ApprovalGate;
+use crate::runtime::budget::{enforce_budget, run_budget};
+use crate::runtime::check_command::load_check_command;
+use crate::runtime::failover::EVENT_MODEL_FAILOVER;
 use crate::runtime::planner::emit_and_record;
 use crate::runtime::questions::UserQuestionGate;
+use crate::runtime::usage::{record_model_usage, UsageScope};
 use crate::runtime::worktree::WorktreeManager;
 use crate::tools::{infer_tool_call, ToolRegistry};
 
 use crate::tools::dev_server::stop_all_dev_servers_for_run;
 use delegation::spawn_and_execute_delegated_sub_agent;
 use helpers::{open_tasks_in_latest_task_observation, parse_sub_agent_contract};
-use model::{RuntimeModelConfig, StreamDelta, WorkerModelClient};
-use tools::execute_tool_call;
-
-const STREAM_DELTA_FLUSH_CHARS: usize = 120;
-const THINKING_DELTA_FLUSH_CHARS: usize = 120;
+use model::{ModelFailover, RuntimeModelConfig, StreamDelta, WorkerModelClient};
+use tools::{attach_check_report, execute_tool_call, group_tool_calls, MAX_PARALLEL_TOOL_CALLS};
+
+/// A partial streamed line is emitted once it grows past this many bytes.
+const MAX_PENDING_LINE_BYTES: usize = 2_000;
+
+/// The whole lines of `pending`, leaving the partial last line behind so
+/// that a secret is never split across two deltas, which are masked one at
+/// a time.
+fn take_complete_lines(pending: &mut String) -> Option<String> {
+    match pending.rfind('\n') {
+        Some(end) => {
+            let rest = pending.split_off(end + 1);
+            Some(std::mem::replace(pending, rest))
+        }
+        None if pending.len() >= MAX_PENDING_LINE_BYTES => Some(std::mem::take(pending)),
+        None => None,
+    }
+}
 
 fn worker_tool_priority(name: &str) -> u8 {
     match name {
@@ -101,10 +121,10 @@ impl<'a> MessageStreamEmitter<'a> {
             self.start_stream()?;
         }
         self.pending.push_str(delta);
-        if self.pending.len() >= STREAM_DELTA_FLUSH_CHARS || self.pending.contains('\n') {
-            self.flush_pending()?;
+        match take_complete_lines(&mut self.pending) {
+            Some(lines) => self.emit_delta(lines),
+            None => Ok(()),
         }
-        Ok(())
     }
 
     fn append_thinking_delta(&mut self, delta: &str) -> Result<(), String> {
@@ -115,12 +135,10 @@ impl<'a> MessageStreamEmitter<'a> {
             self.start_thinking_stream()?;
         }
         self.thinking_pending.push_str(delta);
-        if self.thinking_pending.len() >= THINKING_DELTA_FLUSH_CHARS
-            || self.thinking_pending.contains('\n')
-        {
-            self.flush_thinking_pending()?;
+        match take_complete_lines(&mut self.thinking_pending) {
+            Some(lines) => self.emit_thinking_delta(lines),
+            None => Ok(()),
         }
-        Ok(())
     }
 
     fn complete(mut self) -> Result<(), String> {
@@ -239,6 +257,10 @@ impl<'a> MessageStreamEmitter<'a> {
             return Ok(());
         }
         let content = std::mem::take(&mut self.pending);
+        self.emit_delta(content)
+    }
+
+    fn emit_delta(&self, content: String) -> Result<(), String> {
         let stream_id = self.stream_id.clone().unwrap_or_default();
         let _ = emit_and_record(
             self.db,
@@ -286,6 +308,10 @@ impl<'a> MessageStreamEmitter<'a> {
             return Ok(());
         }
         let content = std::mem::take(&mut self.thinking_pending);
+        self.emit_thinking_delta(content)
+    }
+
+    fn emit_thinking_delta(&self, content: String) -> Result<(), String> {
         let stream_id = self.thinking_stream_id.clone().unwrap_or_default();
         let _ = emit_and_record(
             self.db,
@@ -472,6 +498,12 @@ pub async fn execute_step_with_tools(
     // Create model client if config provided
     let worker_model = model_config.as_ref().map(WorkerModelClient::from_config);
 
+    // Run after turns that edit files, so the model sees what it broke
+    let check_command = load_check_command(db, workspace_root).unwrap_or_else(|error| {
+        tracing::warn!("ignoring check command settings: {error}");
+        None
+    });
+
     let mut observations: Vec<serde_json::Value> = Vec::new();
     #[allow(unused_assignments)]
     let mut completion_summary: Option<String> = None;
@@ -480,6 +512,11 @@ pub async fn execute_step_with_tools(
     loop {
         turn += 1;
 
+        // Pause for an extension prompt if the next model call would exceed the task budget
+        if worker_model.is_some() {
+            enforce_budget(db, bus, question_gate, run_id, task_id, Some(&sub_agent.id)).await?;
+        }
+
         // Emit deciding event
         let _ = emit_and_record(
             db,
@@ -511,7 +548,8 @@ pub async fn execute_step_with_tools(
                 ""
             };
 
-            let decision = model
+            let request_started = std::time::Instant::now();
+            let (decision, answered_by) = model
                 .decide_streaming(
                     WorkerActionRequest {
                         task_prompt: task_prompt.clone(),
@@ -532,9 +570,65 @@ pub async fn execute_step_with_tools(
                         StreamDelta::Content(text) => stream_emitter.append_delta(&text),
                         StreamDelta::Reasoning(text) => stream_emitter.append_thinking_delta(&text),
                     },
+                    |failover: ModelFailover| {
+                        tracing::warn!(
+                            "model failover {}/{} -> {}/{}: {}",
+                            failover.from_provider,
+                            failover.from_model,
+                            failover.to_provider,
+                            failover.to_model,
+                            failover.reason
+                        );
+                        let _ = emit_and_record(
+                            db,
+                            bus,
+                            CATEGORY_AGENT,
+                            EVENT_MODEL_FAILOVER,
+                            Some(run_id.to_string()),
+                            serde_json::json!({
+                                "task_id": task_id,
+                                "run_id": run_id,
+                                "step_idx": step.idx,
+                                "sub_agent_id": sub_agent.id,
+                                "turn": turn,
+                                "from_provider": failover.from_provider,
+                                "from_model": failover.from_model,
+                                "to_provider": failover.to_provider,
+                                "to_model": failover.to_model,
+                                "reason": failover.reason,
+                            }),
+                        );
+                    },
                 )
                 .await?;
 
+            let budget = run_budget(run_id);
+            if let Some(tracker) = budget.as_ref() {
+                tracker.record_step();
+                if let Some(usage) = decision.usage.as_ref() {
+                    tracker.record_usage(usage);
+                }
+            }
+
+            if let Some(usage) = decision.usage.as_ref() {
+                if let Err(error) = record_model_usage(
+                    db,
+                    bus,
+                    &UsageScope {
+                        run_id,
+                        task_id,
+                        sub_agent_id: Some(&sub_agent.id),
+                        step_idx: Some(step.idx),
+                    },
+                    &answered_by.provider,
+                    &answered_by.model,
+                    usage,
+                    request_started.elapsed().as_millis() as u64,
+                ) {
+                    tracing::warn!("failed to record model usage: {error}");
+                }
+            }
+
             match &decision.action {
                 WorkerAction::Complete { .. } => {
                     stream_emitter.complete()?;
@@ -562,6 +656,7 @@ pub async fn execute_step_with_tools(
                 action: fallback_action,
                 reasoning: None,
                 raw_response: None,
+                usage: None,
             }
         };
 
@@ -673,128 +768,68 @@ pub async fn execute_step_with_tools(
                     }),
                 );
 
-                // Helper to check if a tool is read-only (safe for parallel execution)
-                let is_read_only_tool = |tool_name: &str| -> bool {
-                    matches!(
-                        tool_name,
-                        "fs.read"
-                            | "search.rg"
-                            | "search.embeddings"
-                            | "search.files"
-                            | "git.status"
-                            | "git.diff"
-                            | "git.log"
-                            | "git.show"
-                            | "git.branch"
-                            | "agent.ask_user"
-                            | "agent.memory_read"
-                            | "memory.list"
-                            | "memory.read"
-                            | "skills.list_installed"
-                            | "skills.search"
-                            | "session.recall"
-                            | "web_search"
-                            | "web_fetch"
-                    )
-                };
-
-                // Helper to check if tool might have side effects (need sequential execution)
-                let has_side_effects = |tool_name: &str| -> bool {
-                    matches!(
-                        tool_name,
-                        "fs.write"
-                            | "fs.patch"
-                            | "fs.delete"
-                            | "fs.mkdir"
-                            | "cmd.exec"
-                            | "subagent.spawn"
-                            | "agent.create_artifact"
-                            | "agent.ask_user"
-                            | "memory.upsert"
-                            | "memory.delete"
-                            | "skills.load"
-                            | "skills.remove"
-                    )
-                };
-
-                // Strategy: Parallelize read-only tools, sequential for side-effect tools
-                let read_only_calls: Vec<_> = calls
-                    .iter()
-                    .filter(|call| is_read_only_tool(&call.tool_name))
-                    .collect();
-                let side_effect_calls: Vec<_> = calls
-                    .iter()
-                    .filter(|call| has_side_effects(&call.tool_name))
-                    .collect();
-
-                // If we have multiple independent read-only calls, execute in parallel
-                let use_parallel = read_only_calls.len() > 1
-                    && side_effect_calls.is_empty()
-                    && calls.iter().all(|c| {
-                        c.tool_name == "subagent.spawn" || is_read_only_tool(&c.tool_name)
-                    });
-
-                if use_parallel {
-                    // Parallel execution for independent read-only tools
-                    // Clone values needed for the closures
-                    let task_prompt_clone = task_prompt.clone();
-                    let goal_summary_clone = goal_summary.clone();
-                    let skills_context_clone = skills_context.to_string();
-                    let available_tools_clone = available_tools.clone();
-                    let model_config_ref = model_config.as_ref();
-                    let step_idx_val = step.idx;
-
-                    let tool_call_futures: Vec<_> = calls
+                // Check if all calls are subagent spawns (for parallel delegation)
+                let all_subagent_spawns =
+                    calls.iter().all(|call| call.tool_name == "subagent.spawn");
+
+                if all_subagent_spawns && calls.len() > 1 {
+                    // If the model requested multiple sub-agent spawns in one turn,
+                    // execute them concurrently for real parallel delegation.
+                    let spawn_observations = futures::future::join_all(calls.iter().map(|call| {
+                        let objective = call
+                            .tool_args
+                            .get("objective")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .trim();
+                        let agent_preset_id = call
+                            .tool_args
+                            .get("agent_preset_id")
+                            .and_then(|v| v.as_str())
+                            .map(str::trim)
+                            .filter(|v| !v.is_empty());
+
+                        execute_subagent_spawn_observation(
+                            db,
+                            bus,
+                            workspace_root,
+                            tool_registry,
+                            worktree_manager,
+                            approval_gate,
+                            question_gate,
+                            run_id,
+                            task_id,
+                            &sub_agent.id,
+                            step.idx,
+                            turn,
+                            objective,
+                            agent_preset_id,
+                            &task_prompt,
+                            &goal_summary,
+                            skills_context,
+                            &available_tools,
+                            model_config.as_ref(),
+                            contract.permissions.can_spawn_children,
+                            contract.permissions.max_delegation_depth,
+                            delegation_depth,
+                        )
+                    }))
+                    .await;
+                    observations.extend(spawn_observations);
+                } else {
+                    // Runs of read-only calls execute concurrently, bounded; any
+                    // other call runs alone, in order. Observations keep the
+                    // order the calls were made in.
+                    let read_only: Vec<bool> = calls
                         .iter()
-                        .map(|call| {
-                            let tool_name = call.tool_name.clone();
-                            let tool_args = call.tool_args.clone();
-                            let rationale = call.rationale.clone();
-
-                            let task_prompt = task_prompt_clone.clone();
-                            let goal_summary = goal_summary_clone.clone();
-                            let skills_context = skills_context_clone.clone();
-                            let available_tools = available_tools_clone.clone();
-
-                            async move {
-                                if tool_name == "subagent.spawn" {
-                                    let objective = tool_args
-                                        .get("objective")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or("")
-                                        .trim();
-                                    let agent_preset_id = tool_args
-                                        .get("agent_preset_id")
-                                        .and_then(|v| v.as_str())
-                                        .map(str::trim)
-                                        .filter(|v| !v.is_empty());
-
-                                    execute_subagent_spawn_observation(
-                                        db,
-                                        bus,
-                                        workspace_root,
-                                        tool_registry,
-                                        worktree_manager,
-                                        approval_gate,
-                                        question_gate,
-                                        run_id,
-                                        task_id,
-                                        &sub_agent.id,
-                                        step_idx_val,
-                                        turn,
-                                        objective,
-                                        agent_preset_id,
-                                        &task_prompt,
-                                        &goal_summary,
-                                        &skills_context,
-                                        &available_tools,
-                                        model_config_ref,
-                                        contract.permissions.can_spawn_children,
-                                        contract.permissions.max_delegation_depth,
-                                        delegation_depth,
-                                    )
-                                    .await
-                                } else {
+                        .map(|call| tool_registry.is_read_only(&call.tool_name))
+                        .collect();
+                    let mut completion_requested = false;
+                    let turn_start = observations.len();
+                    for group in group_tool_calls(&read_only) {
+                        if group.len() > 1 {
+                            let group_observations: Vec<serde_json::Value> =
+                                futures::stream::iter(calls[group].iter().map(|call| {
                                     execute_tool_call(
                                         db,
                                         bus,
@@ -805,160 +840,113 @@ pub async fn execute_step_with_tools(
                                         run_id,
                                         task_id,
                                         &sub_agent.id,
-                                        step_idx_val as usize,
+                                        step.idx as usize,
                                         turn,
-                                        &tool_name,
-                                        &tool_args,
-                                        rationale.as_deref(),
+                                        &call.tool_name,
+                                        &call.tool_args,
+                                        call.rationale.as_deref(),
                                         worktree_path,
                                         &available_tools,
                                     )
-                                    .await
-                                }
-                            }
-                        })
-                        .collect();
-
-                    let results = futures::future::join_all(tool_call_futures).await;
-
-                    for result in results {
-                        if let Some(summary) = completion_summary_from_observation(&result) {
-                            completion_summary = Some(summary);
-                        }
-                        observations.push(result);
-                    }
-                } else {
-                    // Check if all calls are subagent spawns (for parallel delegation)
-                    let all_subagent_spawns =
-                        calls.iter().all(|call| call.tool_name == "subagent.spawn");
-
-                    if all_subagent_spawns && calls.len() > 1 {
-                        // If the model requested multiple sub-agent spawns in one turn,
-                        // execute them concurrently for real parallel delegation.
-                        let spawn_observations =
-                            futures::future::join_all(calls.iter().map(|call| {
-                                let objective = call
-                                    .tool_args
-                                    .get("objective")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or("")
-                                    .trim();
-                                let agent_preset_id = call
-                                    .tool_args
-                                    .get("agent_preset_id")
-                                    .and_then(|v| v.as_str())
-                                    .map(str::trim)
-                                    .filter(|v| !v.is_empty());
-
-                                execute_subagent_spawn_observation(
-                                    db,
-                                    bus,
-                                    workspace_root,
-                                    tool_registry,
-                                    worktree_manager,
-                                    approval_gate,
-                                    question_gate,
-                                    run_id,
-                                    task_id,
-                                    &sub_agent.id,
-                                    step.idx,
-                                    turn,
-                                    objective,
-                                    agent_preset_id,
-                                    &task_prompt,
-                                    &goal_summary,
-                                    skills_context,
-                                    &available_tools,
-                                    model_config.as_ref(),
-                                    contract.permissions.can_spawn_children,
-                                    contract.permissions.max_delegation_depth,
-                                    delegation_depth,
-                                )
-                            }))
-                            .await;
-                        observations.extend(spawn_observations);
-                    } else {
-                        let mut completion_requested = false;
-                        // Execute each tool call sequentially when there are mixed tool
-                        // dependencies in the same turn.
-                        for call in calls {
-                            if call.tool_name == "subagent.spawn" {
-                                let objective = call
-                                    .tool_args
-                                    .get("objective")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or("")
-                                    .trim();
-                                let agent_preset_id = call
-                                    .tool_args
-                                    .get("agent_preset_id")
-                                    .and_then(|v| v.as_str())
-                                    .map(str::trim)
-                                    .filter(|v| !v.is_empty());
-
-                                let observation = execute_subagent_spawn_observation(
-                                    db,
-                                    bus,
-                                    workspace_root,
-                                    tool_registry,
-                                    worktree_manager,
-                                    approval_gate,
-                                    question_gate,
-                                    run_id,
-                                    task_id,
-                                    &sub_agent.id,
-                                    step.idx,
-                                    turn,
-                                    objective,
-                                    agent_preset_id,
-                                    &task_prompt,
-                                    &goal_summary,
-                                    skills_context,
-                                    &available_tools,
-                                    model_config.as_ref(),
-                                    contract.permissions.can_spawn_children,
-                                    contract.permissions.max_delegation_depth,
-                                    delegation_depth,
-                                )
+                                }))
+                                .buffered(MAX_PARALLEL_TOOL_CALLS)
+                                .collect()
                                 .await;
-                                observations.push(observation);
-                                continue;
-                            }
+                            observations.extend(group_observations);
+                            continue;
+                        }
 
-                            let observation = execute_tool_call(
+                        let call = &calls[group.start];
+                        if call.tool_name == "subagent.spawn" {
+                            let objective = call
+                                .tool_args
+                                .get("objective")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("")
+                                .trim();
+                            let agent_preset_id = call
+                                .tool_args
+                                .get("agent_preset_id")
+                                .and_then(|v| v.as_str())
+                                .map(str::trim)
+                                .filter(|v| !v.is_empty());
+
+                            let observation = execute_subagent_spawn_observation(
                                 db,
                                 bus,
+                                workspace_root,
                                 tool_registry,
-                                policy,
+                                worktree_manager,
                                 approval_gate,
                                 question_gate,
                                 run_id,
                                 task_id,
                                 &sub_agent.id,
-                                step.idx as usize,
+                                step.idx,
                                 turn,
-                                &call.tool_name,
-                                &call.tool_args,
-                                call.rationale.as_deref(),
-                                worktree_path,
+                                objective,
+                                agent_preset_id,
+                                &task_prompt,
+                                &goal_summary,
+                                skills_context,
                                 &available_tools,
+                                model_config.as_ref(),
+                                contract.permissions.can_spawn_children,
+                                contract.permissions.max_delegation_depth,
+                                delegation_depth,
                             )
                             .await;
-                            if let Some(summary) = completion_summary_from_observation(&observation)
-                            {
-                                completion_summary = Some(summary);
-                                completion_requested = true;
-                            }
                             observations.push(observation);
-                            if completion_requested {
-                                break;
-                            }
+                            continue;
                         }
 
+                        let observation = execute_tool_call(
+                            db,
+                            bus,
+                            tool_registry,
+                            policy,
+                            approval_gate,
+                            question_gate,
+                            run_id,
+                            task_id,
+                            &sub_agent.id,
+                            step.idx as usize,
+                            turn,
+                            &call.tool_name,
+                            &call.tool_args,
+                            call.rationale.as_deref(),
+                            worktree_path,
+                            &available_tools,
+                        )
+                        .await;
+                        if let Some(summary) = completion_summary_from_observation(&observation) {
+                            completion_summary = Some(summary);
+                            completion_requested = true;
+                        }
+                        observations.push(observation);
                         if completion_requested {
                             break;
                         }
                     }
+
+                    if completion_requested {
+                        break;
+                    }
+                    attach_check_report(
+                        db,
+                        bus,
+                        tool_registry,
+                        policy,
+                        check_command.as_ref(),
+                        run_id,
+                        task_id,
+                        &sub_agent.id,
+                        step.idx as usize,
+                        turn,
+                        worktree_path,
+                        &mut observations[turn_start..],
+                    )
+                    .await;
                 }
             }
             WorkerAction::Delegate { .. } => {
@@ -1037,6 +1025,22 @@ pub async fn execute_step_with_tools(
                     break;
                 }
                 observations.push(observation);
+                let turn_start = observations.len() - 1;
+                attach_check_report(
+                    db,
+                    bus,
+                    tool_registry,
+                    policy,
+                    check_command.as_ref(),
+                    run_id,
+                    task_id,
+                    &sub_agent.id,
+                    step.idx as usize,
+                    turn,
+                    worktree_path,
+                    &mut observations[turn_start..],
+                )
+                .await;
             }
         }
     }
@@ -1,13 +1,17 @@
 //! Worker model client abstraction.
 //!
 //! Provides a unified interface for different LLM providers (MiniMax, Kimi, GLM)
-//! used by the worker during step execution.
+//! used by the worker during step execution. A client wraps the configured
+//! model plus its fallback chain and fails over between them on request errors.
 
+use crate::model::cassette::{self, ReplayClient};
 pub use crate::model::StreamDelta;
 use crate::model::{
-    ChatGPTClient, GeminiClient, GlmClient, KimiClient, MiniMaxClient, ModalClient,
-    WorkerActionRequest, WorkerDecision,
+    AnthropicClient, ChatGPTClient, CustomClient, GeminiClient, GlmClient, KimiClient,
+    MiniMaxClient, ModalClient, ModelError, WorkerActionRequest, WorkerDecision,
 };
+use crate::policy::redaction;
+use crate::runtime::failover;
 
 /// Runtime model configuration for worker execution.
 #[derive(Debug, Clone)]
@@ -16,21 +20,25 @@ pub struct RuntimeModelConfig {
     pub api_key: String,
     pub model: Option<String>,
     pub base_url: Option<String>,
+    /// Ordered models to fail over to when this one returns a request error.
+    pub fallbacks: Vec<RuntimeModelConfig>,
 }
 
-/// Unified model client for worker execution.
-pub enum WorkerModelClient {
+/// Client for a single provider/model pair.
+enum ProviderModelClient {
     MiniMax(MiniMaxClient),
     Kimi(KimiClient),
     Glm(GlmClient),
     Modal(ModalClient),
     Gemini(GeminiClient),
     ChatGPT(ChatGPTClient),
+    Anthropic(AnthropicClient),
+    Custom(CustomClient),
+    Replay(ReplayClient),
 }
 
-impl WorkerModelClient {
-    /// Create a new model client from runtime configuration.
-    pub fn from_config(config: &RuntimeModelConfig) -> Self {
+impl ProviderModelClient {
+    fn from_config(config: &RuntimeModelConfig) -> Self {
         match config.provider.as_str() {
             "kimi" => Self::Kimi(KimiClient::new(
                 config.api_key.clone(),
@@ -56,6 +64,21 @@ impl WorkerModelClient {
                 config.api_key.clone(),
                 config.model.clone(),
             )),
+            "anthropic" | "claude" => Self::Anthropic(AnthropicClient::new(
+                config.api_key.clone(),
+                config.model.clone(),
+                config.base_url.clone(),
+            )),
+            // api_key carries the JSON-encoded key and endpoint settings
+            "custom" => Self::Custom(CustomClient::from_api_key_payload(
+                config.api_key.clone(),
+                config.model.clone(),
+                config.base_url.clone(),
+            )),
+            // api_key is the path of the cassette to serve responses from
+            cassette::REPLAY_PROVIDER => {
+                Self::Replay(ReplayClient::open(&config.api_key, config.model.clone()))
+            }
             _ => Self::MiniMax(MiniMaxClient::new_with_base_url(
                 config.api_key.clone(),
                 config.model.clone(),
@@ -64,40 +87,188 @@ impl WorkerModelClient {
         }
     }
 
+    fn model_id(&self) -> String {
+        match self {
+            Self::MiniMax(model) => model.model_id(),
+            Self::Kimi(model) => model.model_id(),
+            Self::Glm(model) => model.model_id(),
+            Self::Modal(model) => model.model_id(),
+            Self::Gemini(model) => model.model_id(),
+            Self::ChatGPT(model) => model.model_id(),
+            Self::Anthropic(model) => model.model_id(),
+            Self::Custom(model) => model.model_id(),
+            Self::Replay(model) => model.model_id(),
+        }
+    }
+
+    /// Request a decision, appending it to the active cassette when recording.
+    async fn decide_streaming(
+        &self,
+        req: WorkerActionRequest,
+        on_delta: &mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send),
+    ) -> Result<WorkerDecision, ModelError> {
+        let recorder = match self {
+            Self::Replay(_) => None,
+            _ => cassette::active_recorder(),
+        };
+        let Some(recorder) = recorder else {
+            return self.decide_live(req, on_delta).await;
+        };
+
+        let mut deltas = Vec::new();
+        let result = {
+            let mut capture = |delta: StreamDelta| {
+                deltas.push(delta.clone());
+                on_delta(delta)
+            };
+            self.decide_live(req.clone(), &mut capture).await
+        };
+        recorder.record_decision(&self.model_id(), &req, deltas, &result);
+        result
+    }
+
+    async fn decide_live(
+        &self,
+        req: WorkerActionRequest,
+        on_delta: &mut (dyn FnMut(StreamDelta) -> Result<(), String> + Send),
+    ) -> Result<WorkerDecision, ModelError> {
+        match self {
+            Self::MiniMax(model) => model.decide_action_streaming(req, on_delta).await,
+            Self::Kimi(model) => model.decide_action_streaming(req, on_delta).await,
+            Self::Glm(model) => model.decide_action_streaming(req, on_delta).await,
+            Self::Modal(model) => model.decide_action_streaming(req, on_delta).await,
+            Self::Gemini(model) => model.decide_action_streaming(req, on_delta).await,
+            Self::ChatGPT(model) => model.decide_action_streaming(req, on_delta).await,
+            Self::Anthropic(model) => model.decide_action_streaming(req, on_delta).await,
+            Self::Custom(model) => model.decide_action_streaming(req, on_delta).await,
+            Self::Replay(model) => model.decide_action_streaming(req, on_delta).await,
+        }
+    }
+}
+
+struct ModelCandidate {
+    provider: String,
+    client: ProviderModelClient,
+}
+
+/// Provider and model that produced a decision.
+#[derive(Debug, Clone)]
+pub struct AnsweredBy {
+    pub provider: String,
+    pub model: String,
+}
+
+/// A hop from one candidate in the fallback chain to the next.
+#[derive(Debug, Clone)]
+pub struct ModelFailover {
+    pub from_provider: String,
+    pub from_model: String,
+    pub to_provider: String,
+    pub to_model: String,
+    pub reason: String,
+}
+
+/// Unified model client for worker execution.
+///
+/// Holds the configured model followed by its fallback chain. Candidates whose
+/// provider circuit breaker is open are skipped while a later candidate exists.
+pub struct WorkerModelClient {
+    candidates: Vec<ModelCandidate>,
+}
+
+impl WorkerModelClient {
+    /// Create a new model client from runtime configuration.
+    pub fn from_config(config: &RuntimeModelConfig) -> Self {
+        let candidates = std::iter::once(config)
+            .chain(config.fallbacks.iter())
+            .map(|candidate| ModelCandidate {
+                provider: candidate.provider.clone(),
+                client: ProviderModelClient::from_config(candidate),
+            })
+            .collect();
+        Self { candidates }
+    }
+
     /// Request a decision and stream text deltas as the provider responds.
-    pub async fn decide_streaming<F>(
+    ///
+    /// Fails over to the next candidate only on `ModelError::Request` and only
+    /// if the failed attempt had not streamed anything yet, so partial output is
+    /// never duplicated. Each hop is reported through `on_failover`. Secrets
+    /// in the request are masked before any candidate sees it.
+    pub async fn decide_streaming<F, G>(
         &self,
         req: WorkerActionRequest,
         mut on_delta: F,
-    ) -> Result<WorkerDecision, String>
+        mut on_failover: G,
+    ) -> Result<(WorkerDecision, AnsweredBy), String>
     where
         F: FnMut(StreamDelta) -> Result<(), String> + Send,
+        G: FnMut(ModelFailover) + Send,
     {
-        match self {
-            Self::MiniMax(model) => model
-                .decide_action_streaming(req, |delta| on_delta(delta))
-                .await
-                .map_err(|e| e.to_string()),
-            Self::Kimi(model) => model
-                .decide_action_streaming(req, |delta| on_delta(delta))
-                .await
-                .map_err(|e| e.to_string()),
-            Self::Glm(model) => model
-                .decide_action_streaming(req, |delta| on_delta(delta))
-                .await
-                .map_err(|e| e.to_string()),
-            Self::Modal(model) => model
-                .decide_action_streaming(req, |delta| on_delta(delta))
-                .await
-                .map_err(|e| e.to_string()),
-            Self::Gemini(model) => model
-                .decide_action_streaming(req, |delta| on_delta(delta))
-                .await
-                .map_err(|e| e.to_string()),
-            Self::ChatGPT(model) => model
-                .decide_action_streaming(req, |delta| on_delta(delta))
-                .await
-                .map_err(|e| e.to_string()),
+        let (req, _) = redaction::redact_worker_request(req);
+
+        // Candidate index and reason for a hop not yet reported
+        let mut pending: Option<(usize, String)> = None;
+
+        for (idx, candidate) in self.candidates.iter().enumerate() {
+            let has_next = idx + 1 < self.candidates.len();
+            if has_next && !failover::breaker_allows(&candidate.provider) {
+                if pending.is_none() {
+                    pending = Some((
+                        idx,
+                        format!("circuit breaker open for {}", candidate.provider),
+                    ));
+                }
+                continue;
+            }
+
+            if let Some((from_idx, reason)) = pending.take() {
+                let from = &self.candidates[from_idx];
+                on_failover(ModelFailover {
+                    from_provider: from.provider.clone(),
+                    from_model: from.client.model_id(),
+                    to_provider: candidate.provider.clone(),
+                    to_model: candidate.client.model_id(),
+                    reason,
+                });
+            }
+
+            let mut streamed = false;
+            let result = {
+                let mut forward = |delta: StreamDelta| {
+                    streamed = true;
+                    on_delta(delta)
+                };
+                candidate
+                    .client
+                    .decide_streaming(req.clone(), &mut forward)
+                    .await
+            };
+
+            match result {
+                Ok(decision) => {
+                    failover::record_provider_success(&candidate.provider);
+                    return Ok((
+                        decision,
+                        AnsweredBy {
+                            provider: candidate.provider.clone(),
+                            model: candidate.client.model_id(),
+                        },
+                    ));
+                }
+                Err(error) => {
+                    let retryable = failover::is_failover_error(&error);
+                    if retryable {
+                        failover::record_provider_failure(&candidate.provider);
+                    }
+                    if !retryable || streamed || !has_next {
+                        return Err(error.to_string());
+                    }
+                    pending = Some((idx, error.to_string()));
+                }
+            }
         }
+
+        Err("no model candidates configured".to_string())
     }
 }
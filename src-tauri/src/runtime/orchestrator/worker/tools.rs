@@ -5,14 +5,42 @@
 use chrono::Utc;
 use uuid::Uuid;
 
+use crate::bus::{CATEGORY_TOOL, EVENT_TOOL_CHECK_FINISHED};
 use crate::db::{queries, Database};
 use crate::policy::PolicyEngine;
 use crate::runtime::approval::ApprovalGate;
+use crate::runtime::check_command::{run_check_command, CheckCommandSettings, MUTATING_TOOLS};
 use crate::runtime::planner::emit_and_record;
 use crate::runtime::questions::UserQuestionGate;
-use crate::runtime::tool_calling::{invoke_tool_with_special_cases, resolve_human_gates};
+use crate::runtime::tool_calling::{
+    invoke_tool_with_special_cases, record_policy_outcome, redact_tool_result, resolve_human_gates,
+    ToolCallScope,
+};
 use crate::tools::{ToolError, ToolRegistry};
 
+/// Read-only tool calls of one turn allowed in flight at once.
+pub const MAX_PARALLEL_TOOL_CALLS: usize = 4;
+
+/// Split a turn's tool calls, given which of them are read-only, into groups
+/// that run one after another. Each run of consecutive read-only calls forms
+/// one group whose calls may run concurrently; every other call is a group
+/// of its own, so mutating calls keep their order relative to everything.
+pub fn group_tool_calls(read_only: &[bool]) -> Vec<std::ops::Range<usize>> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    while start < read_only.len() {
+        let mut end = start + 1;
+        if read_only[start] {
+            while end < read_only.len() && read_only[end] {
+                end += 1;
+            }
+        }
+        groups.push(start..end);
+        start = end;
+    }
+    groups
+}
+
 /// Execute a single tool call with full lifecycle management.
 ///
 /// This function handles:
@@ -65,6 +93,7 @@ pub async fn execute_tool_call(
             started_at: Some(started_at),
             finished_at: None,
             error: None,
+            policy_json: None,
         },
     );
 
@@ -88,6 +117,11 @@ pub async fn execute_tool_call(
     );
 
     // Invoke the tool
+    let call_scope = ToolCallScope {
+        run_id,
+        sub_agent_id: Some(sub_agent_id),
+        tool_call_id: &tool_call_id,
+    };
     let invocation = invoke_tool_with_special_cases(
         db,
         bus,
@@ -97,7 +131,9 @@ pub async fn execute_tool_call(
         worktree_path,
         tool_name,
         tool_args,
-    );
+        &call_scope,
+    )
+    .await;
 
     let invocation = match resolve_human_gates(
         db,
@@ -121,6 +157,7 @@ pub async fn execute_tool_call(
                 worktree_path,
                 tool_name,
                 tool_args,
+                &call_scope,
             )
         },
     )
@@ -129,6 +166,8 @@ pub async fn execute_tool_call(
         Ok(result) => result,
         Err(error) => Err(ToolError::Execution(error)),
     };
+    let (invocation, redactions) = redact_tool_result(invocation);
+    record_policy_outcome(db, &tool_call_id, &invocation);
 
     // Handle invocation result
     match invocation {
@@ -155,6 +194,7 @@ pub async fn execute_tool_call(
                     "tool_call_id": tool_call_id,
                     "status": if output.ok { "succeeded" } else { "failed" },
                     "output": output.data,
+                    "redactions": redactions,
                 }),
             );
 
@@ -164,38 +204,19 @@ pub async fn execute_tool_call(
                     output.data.get("path").and_then(|v| v.as_str()),
                     output.data.get("kind").and_then(|v| v.as_str()),
                 ) {
-                    let artifact = queries::ArtifactRow {
-                        id: Uuid::new_v4().to_string(),
-                        run_id: run_id.to_string(),
-                        kind: kind.to_string(),
-                        uri_or_content: path.to_string(),
-                        metadata_json: Some(
-                            serde_json::json!({
-                                "task_id": task_id,
-                                "source": "agent.create_artifact",
-                                "kind": kind,
-                            })
-                            .to_string(),
-                        ),
-                        created_at: Utc::now().to_rfc3339(),
-                    };
-                    let _ = queries::insert_artifact(db, &artifact);
-                    let _ = emit_and_record(
-                        db,
-                        bus,
-                        "artifact",
-                        "artifact.created",
-                        Some(run_id.to_string()),
-                        serde_json::json!({
-                            "task_id": task_id,
-                            "artifact_id": artifact.id,
-                            "kind": artifact.kind,
-                            "uri": artifact.uri_or_content,
-                        }),
-                    );
+                    record_artifact(db, bus, run_id, task_id, tool_name, kind, path);
                 }
             }
 
+            // Full logs of truncated command output
+            if let Some(path) = output
+                .data
+                .pointer("/full_log/path")
+                .and_then(|v| v.as_str())
+            {
+                record_artifact(db, bus, run_id, task_id, tool_name, "command_log", path);
+            }
+
             serde_json::json!({
                 "tool_name": tool_name,
                 "status": if output.ok { "succeeded" } else { "failed" },
@@ -224,6 +245,7 @@ pub async fn execute_tool_call(
                     "tool_call_id": tool_call_id,
                     "status": "denied",
                     "error": error.to_string(),
+                    "redactions": redactions,
                 }),
             );
 
@@ -235,3 +257,109 @@ pub async fn execute_tool_call(
         }
     }
 }
+
+/// Run the workspace check command when one of a turn's `observations` is a
+/// successful edit, and attach the report to the last such observation as
+/// `check`.
+pub async fn attach_check_report(
+    db: &Database,
+    bus: &crate::bus::EventBus,
+    tool_registry: &ToolRegistry,
+    policy: &PolicyEngine,
+    check_command: Option<&CheckCommandSettings>,
+    run_id: &str,
+    task_id: &str,
+    sub_agent_id: &str,
+    step_idx: usize,
+    turn: usize,
+    worktree_path: &std::path::Path,
+    observations: &mut [serde_json::Value],
+) {
+    let Some(check_command) = check_command else {
+        return;
+    };
+    let Some(edit) = observations.iter_mut().rev().find(|observation| {
+        observation["status"] == "succeeded"
+            && observation["tool_name"]
+                .as_str()
+                .is_some_and(|name| MUTATING_TOOLS.contains(&name))
+    }) else {
+        return;
+    };
+
+    let report =
+        run_check_command(tool_registry, policy, task_id, worktree_path, check_command).await;
+    let _ = emit_and_record(
+        db,
+        bus,
+        CATEGORY_TOOL,
+        EVENT_TOOL_CHECK_FINISHED,
+        Some(run_id.to_string()),
+        serde_json::json!({
+            "task_id": task_id,
+            "sub_agent_id": sub_agent_id,
+            "step_idx": step_idx,
+            "turn": turn,
+            "command": report.command,
+            "passed": report.passed,
+            "error_count": report.error_count,
+            "warning_count": report.warning_count,
+            "skipped": report.skipped,
+        }),
+    );
+    edit["check"] = serde_json::to_value(&report).unwrap_or_default();
+}
+
+fn record_artifact(
+    db: &Database,
+    bus: &crate::bus::EventBus,
+    run_id: &str,
+    task_id: &str,
+    source: &str,
+    kind: &str,
+    path: &str,
+) {
+    let artifact = queries::ArtifactRow {
+        id: Uuid::new_v4().to_string(),
+        run_id: run_id.to_string(),
+        kind: kind.to_string(),
+        uri_or_content: path.to_string(),
+        metadata_json: Some(
+            serde_json::json!({
+                "task_id": task_id,
+                "source": source,
+                "kind": kind,
+            })
+            .to_string(),
+        ),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    let _ = queries::insert_artifact(db, &artifact);
+    let _ = emit_and_record(
+        db,
+        bus,
+        "artifact",
+        "artifact.created",
+        Some(run_id.to_string()),
+        serde_json::json!({
+            "task_id": task_id,
+            "artifact_id": artifact.id,
+            "kind": artifact.kind,
+            "uri": artifact.uri_or_content,
+        }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_only_runs_are_grouped_between_mutating_calls() {
+        assert!(group_tool_calls(&[]).is_empty());
+        assert_eq!(
+            group_tool_calls(&[true, true, false, true, false, false, true, true, true]),
+            vec![0..2, 2..3, 3..4, 4..5, 5..6, 6..9]
+        );
+    }
+}
@@ -6,7 +6,7 @@
 //! - Model-based suggestion generation
 
 use crate::db::{queries, Database};
-use crate::model::{GeminiClient, GlmClient, KimiClient, MiniMaxClient, ModalClient};
+use crate::model::{CompletionClient, CompletionModel};
 use chrono::Utc;
 
 /// Default number of conversation turns to include in context.
@@ -120,159 +120,19 @@ pub async fn generate_suggestion(
     // Use specified model or fall back to current model
     let effective_model = settings.suggestion_model.as_deref().or(model);
 
-    // Generate suggestion based on provider
-    let suggestion = match provider {
-        "kimi" => {
-            suggest_with_kimi(api_key, effective_model, base_url, &system_prompt, &context).await
-        }
-        "zhipu" | "glm" => {
-            suggest_with_glm(api_key, effective_model, base_url, &system_prompt, &context).await
-        }
-        "modal" => {
-            suggest_with_modal(api_key, effective_model, base_url, &system_prompt, &context).await
-        }
-        "gemini" => {
-            suggest_with_gemini(api_key, effective_model, base_url, &system_prompt, &context).await
-        }
-        _ => {
-            suggest_with_minimax(api_key, effective_model, base_url, &system_prompt, &context).await
-        }
-    };
-
-    Ok(suggestion.trim().to_string())
-}
-
-/// Suggest using Kimi model with simple completion.
-async fn suggest_with_kimi(
-    api_key: &str,
-    model: Option<&str>,
-    base_url: Option<&str>,
-    system_prompt: &str,
-    conversation: &str,
-) -> String {
-    let client = KimiClient::new(
-        api_key.to_string(),
-        model.map(String::from),
-        base_url.map(String::from),
-    );
-
-    match client
-        .complete(system_prompt, conversation, SUGGESTION_MAX_TOKENS)
-        .await
-    {
-        Ok(suggestion) => suggestion,
-        Err(e) => {
-            tracing::warn!("Kimi suggestion failed: {}", e);
-            String::new()
-        }
-    }
-}
-
-/// Suggest using MiniMax model with simple completion.
-async fn suggest_with_minimax(
-    api_key: &str,
-    model: Option<&str>,
-    base_url: Option<&str>,
-    system_prompt: &str,
-    conversation: &str,
-) -> String {
-    let client = MiniMaxClient::new_with_base_url(
-        api_key.to_string(),
-        model.map(String::from),
-        base_url.map(String::from),
-    );
-
-    match client
-        .complete(system_prompt, conversation, SUGGESTION_MAX_TOKENS)
-        .await
-    {
-        Ok(suggestion) => suggestion,
-        Err(e) => {
-            tracing::warn!("MiniMax suggestion failed: {}", e);
-            String::new()
-        }
-    }
-}
-
-/// Suggest using GLM (Zhipu) model with simple completion.
-#[allow(dead_code)]
-async fn suggest_with_glm(
-    api_key: &str,
-    model: Option<&str>,
-    base_url: Option<&str>,
-    system_prompt: &str,
-    conversation: &str,
-) -> String {
-    let client = GlmClient::new(
-        api_key.to_string(),
-        model.map(String::from),
-        base_url.map(String::from),
-    );
-
-    match client
-        .complete(system_prompt, conversation, SUGGESTION_MAX_TOKENS)
-        .await
-    {
-        Ok(suggestion) => suggestion,
-        Err(e) => {
-            tracing::warn!("GLM suggestion failed: {}", e);
-            String::new()
-        }
-    }
-}
-
-/// Suggest using Modal model with simple completion.
-#[allow(dead_code)]
-async fn suggest_with_modal(
-    api_key: &str,
-    model: Option<&str>,
-    base_url: Option<&str>,
-    system_prompt: &str,
-    conversation: &str,
-) -> String {
-    let client = ModalClient::new(
-        api_key.to_string(),
-        model.map(String::from),
-        base_url.map(String::from),
-    );
-
-    match client
-        .complete(system_prompt, conversation, SUGGESTION_MAX_TOKENS)
+    let client = CompletionModel::from_provider(provider, api_key, effective_model, base_url);
+    let suggestion = match client
+        .complete(&system_prompt, &context, SUGGESTION_MAX_TOKENS)
         .await
     {
         Ok(suggestion) => suggestion,
         Err(e) => {
-            tracing::warn!("Modal suggestion failed: {}", e);
+            tracing::warn!("{} suggestion failed: {}", provider, e);
             String::new()
         }
-    }
-}
-
-/// Suggest using Gemini model with simple completion.
-#[allow(dead_code)]
-async fn suggest_with_gemini(
-    api_key: &str,
-    model: Option<&str>,
-    base_url: Option<&str>,
-    system_prompt: &str,
-    conversation: &str,
-) -> String {
-    let client = GeminiClient::new(
-        api_key.to_string(),
-        model.map(String::from),
-        base_url.map(String::from),
-    );
+    };
 
-    match client
-        .complete(system_prompt, conversation, SUGGESTION_MAX_TOKENS)
-        .await
-    {
-        Ok(suggestion) => suggestion,
-        Err(e) => {
-            tracing::warn!("Gemini suggestion failed: {}", e);
-            String::new()
-        }
-    }
+    Ok(suggestion.trim().to_string())
 }
 
 /// Default prompt for suggestion generation.
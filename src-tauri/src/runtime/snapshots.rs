@@ -0,0 +1,497 @@
+//! Undo for file changes made by tool calls.
+//!
+//! Before a tool that edits files runs (`fs.write`, `fs.patch`,
+//! `git.apply_patch`, `code.rename`, switching branches or applying a stash),
+//! the content of every file it may touch is captured. Afterwards the files
+//! that actually changed are stored in `file_snapshots` with their pre-image
+//! and a hash of what the call left behind; files over `MAX_SNAPSHOT_BYTES`
+//! are stored without a pre-image and reported as unrevertible. Reverting a
+//! tool call, a step or a run restores the oldest pre-image of each file, but
+//! only when the file still matches the newest post-image; a file changed
+//! since is reported as a conflict and nothing is touched unless the revert
+//! is forced.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::db::queries::{self, FileSnapshotRow, FileSnapshotScope};
+use crate::db::Database;
+use crate::lsp;
+use crate::policy::commands::normalize_lexically;
+use crate::tools::args::{
+    CodeRenameArgs, FsPatchArgs, FsWriteArgs, GitApplyPatchArgs, GitBranchArgs, GitStashArgs,
+};
+use crate::tools::file_search::walk_files;
+use crate::tools::git::{git_listed_paths, patch_target_paths};
+use crate::tools::patch::{parse_patch, Hunk};
+
+/// Files larger than this keep no pre-image; a change to one is recorded but
+/// cannot be reverted.
+const MAX_SNAPSHOT_BYTES: u64 = 8 * 1024 * 1024;
+
+/// A captured file as it was before the call.
+enum PreImage {
+    /// Its content, or `None` when it did not exist.
+    Kept(Option<Vec<u8>>),
+    /// The hash of a file over `MAX_SNAPSHOT_BYTES`.
+    TooLarge(String),
+}
+
+impl PreImage {
+    fn capture(path: &Path) -> Self {
+        match std::fs::metadata(path) {
+            Ok(meta) if meta.is_file() && meta.len() > MAX_SNAPSHOT_BYTES => {
+                match file_hash(path) {
+                    Some(hash) => Self::TooLarge(hash),
+                    None => Self::Kept(None),
+                }
+            }
+            _ => Self::Kept(std::fs::read(path).ok()),
+        }
+    }
+
+    fn hash(&self) -> Option<String> {
+        match self {
+            Self::Kept(content) => content.as_deref().map(content_hash),
+            Self::TooLarge(hash) => Some(hash.clone()),
+        }
+    }
+}
+
+/// Files a tool call is about to change, with their content beforehand.
+pub struct PendingSnapshot {
+    files: Vec<(PathBuf, PreImage)>,
+}
+
+impl PendingSnapshot {
+    /// Capture the files a call to `tool_name` may change, or `None` when
+    /// the tool does not write files.
+    pub fn capture(tool_name: &str, args: &serde_json::Value, cwd: &Path) -> Option<Self> {
+        let mut targets: Vec<PathBuf> = snapshot_targets(tool_name, args, cwd)
+            .iter()
+            // One entry per file, however its path was spelled
+            .map(|path| normalize_lexically(path))
+            .collect();
+        if targets.is_empty() {
+            return None;
+        }
+        targets.sort();
+        targets.dedup();
+        let files = targets
+            .into_iter()
+            .map(|path| {
+                let pre_image = PreImage::capture(&path);
+                (path, pre_image)
+            })
+            .collect();
+        Some(Self { files })
+    }
+
+    /// Store the pre-image of every captured file the call changed. Returns
+    /// how many were stored.
+    pub fn record(self, db: &Database, run_id: &str, tool_call_id: &str) -> Result<usize, String> {
+        let created_at = Utc::now().to_rfc3339();
+        let mut recorded = 0;
+        for (path, pre_image) in self.files {
+            let post_hash = file_hash(&path);
+            if post_hash == pre_image.hash() {
+                continue;
+            }
+            let (pre_content, revertible) = match pre_image {
+                PreImage::Kept(content) => (content, true),
+                PreImage::TooLarge(_) => (None, false),
+            };
+            queries::insert_file_snapshot(
+                db,
+                &FileSnapshotRow {
+                    id: 0,
+                    run_id: run_id.to_string(),
+                    tool_call_id: tool_call_id.to_string(),
+                    step_idx: None,
+                    path: path.to_string_lossy().to_string(),
+                    pre_content,
+                    revertible,
+                    post_hash,
+                    created_at: created_at.clone(),
+                    reverted_at: None,
+                },
+            )
+            .map_err(|e| e.to_string())?;
+            recorded += 1;
+        }
+        Ok(recorded)
+    }
+}
+
+/// Files `tool_name` may create, modify, move or delete, resolved like the
+/// tool resolves them. Unparseable arguments yield nothing; the tool will
+/// reject them too.
+fn snapshot_targets(tool_name: &str, args: &serde_json::Value, cwd: &Path) -> Vec<PathBuf> {
+    match tool_name {
+        "fs.write" => serde_json::from_value::<FsWriteArgs>(args.clone())
+            .map(|args| vec![cwd.join(args.path)])
+            .unwrap_or_default(),
+        "fs.patch" => {
+            let Ok(args) = serde_json::from_value::<FsPatchArgs>(args.clone()) else {
+                return Vec::new();
+            };
+            let Ok(hunks) = parse_patch(&args.patch) else {
+                return Vec::new();
+            };
+            let mut targets = Vec::new();
+            for hunk in &hunks {
+                targets.push(hunk.resolve_path(cwd));
+                if let Hunk::UpdateFile {
+                    move_path: Some(dest),
+                    ..
+                } = hunk
+                {
+                    targets.push(cwd.join(dest));
+                }
+            }
+            targets
+        }
+        "git.apply_patch" => serde_json::from_value::<GitApplyPatchArgs>(args.clone())
+            .map(|args| {
+                patch_target_paths(&args.patch)
+                    .into_iter()
+                    .map(|path| cwd.join(path))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        // The edited files are only known once the language server answers,
+        // so every workspace file the same server handles is captured
+        "code.rename" => {
+            let Ok(args) = serde_json::from_value::<CodeRenameArgs>(args.clone()) else {
+                return Vec::new();
+            };
+            let Some(server) = lsp::server_for_path(&cwd.join(&args.path)) else {
+                return Vec::new();
+            };
+            walk_files(cwd)
+                .filter(|path| {
+                    lsp::server_for_path(path).is_some_and(|other| other.name == server.name)
+                })
+                .collect()
+        }
+        "git.branch" => match serde_json::from_value::<GitBranchArgs>(args.clone()) {
+            Ok(GitBranchArgs {
+                action: Some(action),
+                name: Some(name),
+                ..
+            }) if action == "switch" && !name.starts_with('-') => {
+                git_listed_paths(cwd, &["diff", "--name-only", "HEAD", &name, "--"])
+            }
+            _ => Vec::new(),
+        },
+        "git.stash" => match serde_json::from_value::<GitStashArgs>(args.clone()) {
+            Ok(args) if matches!(args.action.as_deref(), Some("pop" | "apply")) => {
+                let entry = format!("stash@{{{}}}", args.index.unwrap_or(0));
+                git_listed_paths(
+                    cwd,
+                    &[
+                        "stash",
+                        "show",
+                        "--name-only",
+                        "--include-untracked",
+                        &entry,
+                    ],
+                )
+            }
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// A file that could not be reverted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RevertIssue {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RevertReport {
+    /// Files restored to their earlier content, or removed when the reverted
+    /// calls created them.
+    pub reverted: Vec<String>,
+    /// Files changed since the reverted calls ran. Unless forced, nothing is
+    /// reverted while there are any.
+    pub conflicts: Vec<RevertIssue>,
+    /// Files that could not be written back; they can be reverted again.
+    pub failed: Vec<RevertIssue>,
+    /// Files too large to snapshot before they were changed; they are left
+    /// as they are.
+    pub unrevertible: Vec<RevertIssue>,
+}
+
+/// Revert the file changes made by the tool calls in `scope`.
+///
+/// Refused while a run in scope is still planning or executing, since the
+/// agent would keep editing the files being restored.
+pub fn revert_file_changes(
+    db: &Database,
+    scope: &FileSnapshotScope,
+    force: bool,
+) -> Result<RevertReport, String> {
+    let snapshots = queries::list_file_snapshots(db, scope).map_err(|e| e.to_string())?;
+
+    let mut run_ids: Vec<&str> = snapshots.iter().map(|s| s.run_id.as_str()).collect();
+    run_ids.sort_unstable();
+    run_ids.dedup();
+    for run_id in run_ids {
+        let run = queries::get_run(db, run_id).map_err(|e| e.to_string())?;
+        if let Some(run) = run.filter(|run| matches!(run.status.as_str(), "planning" | "executing"))
+        {
+            return Err(format!(
+                "run {} is still {}; cancel it before reverting its changes",
+                run.id, run.status
+            ));
+        }
+    }
+
+    // Oldest pre-image and newest post-image of each file
+    let mut files: BTreeMap<&str, (&FileSnapshotRow, &FileSnapshotRow)> = BTreeMap::new();
+    for snapshot in &snapshots {
+        files
+            .entry(snapshot.path.as_str())
+            .and_modify(|(_, newest)| *newest = snapshot)
+            .or_insert((snapshot, snapshot));
+    }
+
+    let mut report = RevertReport::default();
+    for (path, (_, newest)) in &files {
+        let current = file_hash(Path::new(path));
+        if current == newest.post_hash {
+            continue;
+        }
+        let reason = match (&current, &newest.post_hash) {
+            (None, Some(_)) => "deleted since the change",
+            (Some(_), None) => "recreated since the change",
+            _ => "modified since the change",
+        };
+        report.conflicts.push(RevertIssue {
+            path: path.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+    if !report.conflicts.is_empty() && !force {
+        return Ok(report);
+    }
+
+    for (path, (oldest, _)) in &files {
+        if !oldest.revertible {
+            report.unrevertible.push(RevertIssue {
+                path: path.to_string(),
+                reason: format!(
+                    "larger than {} MiB when it was changed, so its earlier content was not kept",
+                    MAX_SNAPSHOT_BYTES / (1024 * 1024)
+                ),
+            });
+            continue;
+        }
+        match restore_file(Path::new(path), oldest.pre_content.as_deref()) {
+            Ok(()) => report.reverted.push(path.to_string()),
+            Err(error) => report.failed.push(RevertIssue {
+                path: path.to_string(),
+                reason: error.to_string(),
+            }),
+        }
+    }
+
+    let reverted: Vec<i64> = snapshots
+        .iter()
+        .filter(|snapshot| {
+            !report
+                .failed
+                .iter()
+                .chain(&report.unrevertible)
+                .any(|issue| issue.path == snapshot.path)
+        })
+        .map(|snapshot| snapshot.id)
+        .collect();
+    queries::mark_file_snapshots_reverted(db, &reverted, &Utc::now().to_rfc3339())
+        .map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+fn restore_file(path: &Path, content: Option<&[u8]>) -> std::io::Result<()> {
+    match content {
+        Some(content) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)
+        }
+        None => match std::fs::remove_file(path) {
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        },
+    }
+}
+
+fn content_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Hash of the file's content, or `None` when there is no readable file.
+fn file_hash(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{cleanup, temp_workspace};
+
+    fn insert_run(db: &Database, run_id: &str) {
+        let now = Utc::now().to_rfc3339();
+        queries::insert_task(
+            db,
+            &queries::TaskRow {
+                id: "task-1".to_string(),
+                prompt: "refactor".to_string(),
+                parent_task_id: None,
+                status: "completed".to_string(),
+                created_at: now.clone(),
+                updated_at: now.clone(),
+                workspace_root: None,
+            },
+        )
+        .unwrap();
+        queries::insert_run(
+            db,
+            &queries::RunRow {
+                id: run_id.to_string(),
+                task_id: "task-1".to_string(),
+                status: "completed".to_string(),
+                plan_json: None,
+                started_at: Some(now.clone()),
+                finished_at: Some(now),
+                failure_reason: None,
+            },
+        )
+        .unwrap();
+    }
+
+    /// Record an `fs.write` tool call the way the runtime does.
+    fn write_call(db: &Database, cwd: &Path, id: &str, step_idx: i64, path: &str, content: &str) {
+        let args = serde_json::json!({ "path": path, "content": content });
+        queries::insert_tool_call(
+            db,
+            &queries::ToolCallRow {
+                id: id.to_string(),
+                run_id: "run-1".to_string(),
+                step_idx: Some(step_idx),
+                tool_name: "fs.write".to_string(),
+                input_json: args.to_string(),
+                output_json: None,
+                status: "succeeded".to_string(),
+                started_at: None,
+                finished_at: None,
+                error: None,
+                policy_json: None,
+            },
+        )
+        .unwrap();
+        let snapshot = PendingSnapshot::capture("fs.write", &args, cwd).expect("fs.write");
+        std::fs::write(cwd.join(path), content).unwrap();
+        snapshot.record(db, "run-1", id).unwrap();
+    }
+
+    #[test]
+    fn reverts_steps_and_runs_with_conflict_detection() {
+        let db = Database::open_in_memory().expect("in-memory DB");
+        let cwd = temp_workspace();
+        insert_run(&db, "run-1");
+        std::fs::write(cwd.join("a.txt"), "v0").unwrap();
+
+        write_call(&db, &cwd, "call-1", 0, "a.txt", "v1");
+        write_call(&db, &cwd, "call-2", 1, "a.txt", "v2");
+        write_call(&db, &cwd, "call-3", 1, "new.txt", "created");
+        write_call(&db, &cwd, "call-4", 1, "new.txt", "created");
+
+        let step = FileSnapshotScope::Step {
+            run_id: "run-1".to_string(),
+            step_idx: 0,
+        };
+        let report = revert_file_changes(&db, &step, false).unwrap();
+        assert!(report.reverted.is_empty());
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].reason, "modified since the change");
+        assert_eq!(std::fs::read_to_string(cwd.join("a.txt")).unwrap(), "v2");
+
+        let run = FileSnapshotScope::Run("run-1".to_string());
+        assert_eq!(queries::list_file_snapshots(&db, &run).unwrap().len(), 3);
+        let report = revert_file_changes(&db, &run, false).unwrap();
+        assert!(report.conflicts.is_empty(), "{report:?}");
+        assert_eq!(report.reverted.len(), 2);
+        assert_eq!(std::fs::read_to_string(cwd.join("a.txt")).unwrap(), "v0");
+        assert!(!cwd.join("new.txt").exists());
+        assert!(queries::list_file_snapshots(&db, &run).unwrap().is_empty());
+
+        cleanup(&cwd);
+    }
+
+    #[test]
+    fn oversized_files_are_recorded_but_not_reverted() {
+        let db = Database::open_in_memory().expect("in-memory DB");
+        let cwd = temp_workspace();
+        insert_run(&db, "run-1");
+        std::fs::create_dir_all(cwd.join("sub")).unwrap();
+        let big = "x".repeat(MAX_SNAPSHOT_BYTES as usize + 1);
+        std::fs::write(cwd.join("big.txt"), big).unwrap();
+
+        write_call(&db, &cwd, "call-1", 0, "big.txt", "small");
+        write_call(&db, &cwd, "call-2", 0, "sub/../new.txt", "created");
+
+        let run = FileSnapshotScope::Run("run-1".to_string());
+        let snapshots = queries::list_file_snapshots(&db, &run).unwrap();
+        let paths: Vec<&str> = snapshots.iter().map(|s| s.path.as_str()).collect();
+        let big_path = cwd.join("big.txt").to_string_lossy().to_string();
+        let new_path = cwd.join("new.txt").to_string_lossy().to_string();
+        assert_eq!(paths, [big_path.as_str(), new_path.as_str()]);
+        assert!(!snapshots[0].revertible);
+        assert!(snapshots[0].pre_content.is_none());
+
+        let report = revert_file_changes(&db, &run, false).unwrap();
+        assert_eq!(report.reverted, vec![new_path]);
+        assert_eq!(report.unrevertible.len(), 1);
+        assert_eq!(report.unrevertible[0].path, big_path);
+        assert_eq!(
+            std::fs::read_to_string(cwd.join("big.txt")).unwrap(),
+            "small"
+        );
+        assert!(!cwd.join("new.txt").exists());
+
+        cleanup(&cwd);
+    }
+
+    #[test]
+    fn rename_captures_every_file_of_the_language() {
+        let cwd = temp_workspace();
+        std::fs::create_dir_all(cwd.join("src/nested")).unwrap();
+        std::fs::write(cwd.join("src/lib.rs"), "pub fn old() {}").unwrap();
+        std::fs::write(cwd.join("src/nested/use.rs"), "old();").unwrap();
+        std::fs::write(cwd.join("README.md"), "old").unwrap();
+
+        let args =
+            serde_json::json!({ "path": "src/lib.rs", "line": 1, "column": 8, "new_name": "new" });
+        let mut targets = snapshot_targets("code.rename", &args, &cwd);
+        targets.sort();
+        assert_eq!(
+            targets,
+            vec![cwd.join("src/lib.rs"), cwd.join("src/nested/use.rs")]
+        );
+
+        cleanup(&cwd);
+    }
+}
@@ -7,7 +7,7 @@
 //! - Persistent storage of conversation summaries
 
 use crate::db::{queries, Database};
-use crate::model::{GeminiClient, GlmClient, KimiClient, MiniMaxClient, ModalClient, ModelCatalog};
+use crate::model::{CompletionClient, CompletionModel, ModelCatalog};
 use chrono::Utc;
 use uuid::Uuid;
 
@@ -186,24 +186,20 @@ pub async fn generate_summary(
         .cloned()
         .unwrap_or_else(default_summary_prompt);
 
-    // Generate summary based on provider using simple completion (no agent loop)
-    let summary_text = match provider {
-        "kimi" => {
-            summarize_with_kimi(api_key, model, base_url, &system_prompt, &conversation_text).await
-        }
-        "zhipu" | "glm" => {
-            summarize_with_glm(api_key, model, base_url, &system_prompt, &conversation_text).await
-        }
-        "modal" => {
-            summarize_with_modal(api_key, model, base_url, &system_prompt, &conversation_text).await
-        }
-        "gemini" => {
-            summarize_with_gemini(api_key, model, base_url, &system_prompt, &conversation_text)
-                .await
-        }
-        _ => {
-            summarize_with_minimax(api_key, model, base_url, &system_prompt, &conversation_text)
-                .await
+    // Simple completion (no agent loop) with whichever provider is configured
+    let client = CompletionModel::from_provider(provider, api_key, model, base_url);
+    let summary_text = match client
+        .complete(&system_prompt, &conversation_text, SUMMARIZATION_MAX_TOKENS)
+        .await
+    {
+        Ok(summary) => summary,
+        Err(e) => {
+            tracing::warn!(
+                "{} summarization failed: {}, falling back to basic summary",
+                provider,
+                e
+            );
+            generate_fallback_summary(&conversation_text)
         }
     };
 
@@ -231,154 +227,6 @@ pub async fn generate_summary(
     Ok(summary)
 }
 
-/// Summarize using Kimi model with simple completion (no agent loop).
-async fn summarize_with_kimi(
-    api_key: &str,
-    model: Option<&str>,
-    base_url: Option<&str>,
-    system_prompt: &str,
-    conversation: &str,
-) -> String {
-    let planner = KimiClient::new(
-        api_key.to_string(),
-        model.map(String::from),
-        base_url.map(String::from),
-    );
-
-    match planner
-        .complete(system_prompt, conversation, SUMMARIZATION_MAX_TOKENS)
-        .await
-    {
-        Ok(summary) => summary,
-        Err(e) => {
-            tracing::warn!(
-                "Kimi summarization failed: {}, falling back to basic summary",
-                e
-            );
-            generate_fallback_summary(conversation)
-        }
-    }
-}
-
-/// Summarize using MiniMax model with simple completion (no agent loop).
-async fn summarize_with_minimax(
-    api_key: &str,
-    model: Option<&str>,
-    base_url: Option<&str>,
-    system_prompt: &str,
-    conversation: &str,
-) -> String {
-    let planner = MiniMaxClient::new_with_base_url(
-        api_key.to_string(),
-        model.map(String::from),
-        base_url.map(String::from),
-    );
-
-    match planner
-        .complete(system_prompt, conversation, SUMMARIZATION_MAX_TOKENS)
-        .await
-    {
-        Ok(summary) => summary,
-        Err(e) => {
-            tracing::warn!(
-                "MiniMax summarization failed: {}, falling back to basic summary",
-                e
-            );
-            generate_fallback_summary(conversation)
-        }
-    }
-}
-
-/// Summarize using GLM (Zhipu) model with simple completion (no agent loop).
-#[allow(dead_code)]
-async fn summarize_with_glm(
-    api_key: &str,
-    model: Option<&str>,
-    base_url: Option<&str>,
-    system_prompt: &str,
-    conversation: &str,
-) -> String {
-    let planner = GlmClient::new(
-        api_key.to_string(),
-        model.map(String::from),
-        base_url.map(String::from),
-    );
-
-    match planner
-        .complete(system_prompt, conversation, SUMMARIZATION_MAX_TOKENS)
-        .await
-    {
-        Ok(summary) => summary,
-        Err(e) => {
-            tracing::warn!(
-                "GLM summarization failed: {}, falling back to basic summary",
-                e
-            );
-            generate_fallback_summary(conversation)
-        }
-    }
-}
-
-/// Summarize using Modal model with simple completion (no agent loop).
-#[allow(dead_code)]
-async fn summarize_with_modal(
-    api_key: &str,
-    model: Option<&str>,
-    base_url: Option<&str>,
-    system_prompt: &str,
-    conversation: &str,
-) -> String {
-    let planner = ModalClient::new(
-        api_key.to_string(),
-        model.map(String::from),
-        base_url.map(String::from),
-    );
-
-    match planner
-        .complete(system_prompt, conversation, SUMMARIZATION_MAX_TOKENS)
-        .await
-    {
-        Ok(summary) => summary,
-        Err(e) => {
-            tracing::warn!(
-                "Modal summarization failed: {}, falling back to basic summary",
-                e
-            );
-            generate_fallback_summary(conversation)
-        }
-    }
-}
-
-/// Summarize using Gemini model with simple completion (no agent loop).
-#[allow(dead_code)]
-async fn summarize_with_gemini(
-    api_key: &str,
-    model: Option<&str>,
-    base_url: Option<&str>,
-    system_prompt: &str,
-    conversation: &str,
-) -> String {
-    let planner = GeminiClient::new(
-        api_key.to_string(),
-        model.map(String::from),
-        base_url.map(String::from),
-    );
-
-    match planner
-        .complete(system_prompt, conversation, SUMMARIZATION_MAX_TOKENS)
-        .await
-    {
-        Ok(summary) => summary,
-        Err(e) => {
-            tracing::warn!(
-                "Gemini summarization failed: {}, falling back to basic summary",
-                e
-            );
-            generate_fallback_summary(conversation)
-        }
-    }
-}
-
 /// Generate a fallback summary when API calls fail.
 fn generate_fallback_summary(conversation: &str) -> String {
     let char_count = conversation.len();
@@ -1,6 +1,6 @@
 //! WorktreeManager unit tests
 
-use crate::runtime::worktree::{WorktreeManager, WorktreeStrategy};
+use crate::runtime::worktree::{MergeStrategy, WorktreeManager, WorktreeStrategy};
 use crate::tests::{cleanup, init_git_repo, temp_workspace};
 use uuid::Uuid;
 
@@ -183,6 +183,115 @@ fn test_worktree_merge_conflict_detection() {
     cleanup(&workspace);
 }
 
+#[test]
+fn test_conflict_worktree_resolves_and_fast_forwards() {
+    let workspace = temp_workspace();
+    init_git_repo(&workspace);
+
+    let manager = WorktreeManager::new();
+    let run_id = Uuid::new_v4().to_string();
+    let agent_a = Uuid::new_v4().to_string();
+    let agent_b = Uuid::new_v4().to_string();
+    let resolver = Uuid::new_v4().to_string();
+
+    let info_a = manager
+        .create_worktree(&workspace, &run_id, &agent_a)
+        .unwrap();
+    let info_b = manager
+        .create_worktree(&workspace, &run_id, &agent_b)
+        .unwrap();
+    std::fs::write(info_a.path.join("shared.txt"), "Agent A was here\n").unwrap();
+    std::fs::write(info_b.path.join("shared.txt"), "Agent B was here\n").unwrap();
+    assert!(
+        manager
+            .merge_worktree(&workspace, &agent_a)
+            .unwrap()
+            .success
+    );
+
+    let conflict = manager.merge_worktree(&workspace, &agent_b).unwrap();
+    assert_eq!(conflict.strategy, MergeStrategy::Conflict);
+    assert_eq!(conflict.conflicted_files, vec!["shared.txt".to_string()]);
+
+    let context = manager
+        .conflict_context(&workspace, &agent_b, &conflict.conflicted_files)
+        .unwrap();
+    assert!(context.base_diff.contains("+Agent A was here"));
+    assert!(context.branch_diff.contains("+Agent B was here"));
+
+    let info = manager
+        .create_conflict_worktree(&workspace, &run_id, &resolver, &context.branch)
+        .unwrap();
+    let conflicted = std::fs::read_to_string(info.path.join("shared.txt")).unwrap();
+    assert!(conflicted.contains("<<<<<<<"));
+    // Markers left in place are refused
+    assert!(manager
+        .commit_conflict_resolution(&resolver, &context.branch, &conflict.conflicted_files)
+        .is_err());
+
+    std::fs::write(info.path.join("shared.txt"), "Agents A and B were here\n").unwrap();
+    let diffstat = manager
+        .commit_conflict_resolution(&resolver, &context.branch, &conflict.conflicted_files)
+        .unwrap();
+    assert!(diffstat.contains("shared.txt"));
+
+    let result = manager
+        .fast_forward_to_resolution(&workspace, &resolver)
+        .unwrap();
+    assert!(result.success, "{result:?}");
+    assert_eq!(result.strategy, MergeStrategy::AgentResolved);
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("shared.txt")).unwrap(),
+        "Agents A and B were here\n"
+    );
+
+    cleanup(&workspace);
+}
+
+#[test]
+fn test_conflict_resolution_refuses_an_aborted_merge() {
+    let workspace = temp_workspace();
+    init_git_repo(&workspace);
+
+    let manager = WorktreeManager::new();
+    let run_id = Uuid::new_v4().to_string();
+    let agent_a = Uuid::new_v4().to_string();
+    let agent_b = Uuid::new_v4().to_string();
+    let resolver = Uuid::new_v4().to_string();
+
+    let info_a = manager
+        .create_worktree(&workspace, &run_id, &agent_a)
+        .unwrap();
+    let info_b = manager
+        .create_worktree(&workspace, &run_id, &agent_b)
+        .unwrap();
+    std::fs::write(info_a.path.join("shared.txt"), "Agent A was here\n").unwrap();
+    std::fs::write(info_b.path.join("shared.txt"), "Agent B was here\n").unwrap();
+    assert!(
+        manager
+            .merge_worktree(&workspace, &agent_a)
+            .unwrap()
+            .success
+    );
+    let conflict = manager.merge_worktree(&workspace, &agent_b).unwrap();
+    let branch = info_b.branch.clone().unwrap();
+
+    let info = manager
+        .create_conflict_worktree(&workspace, &run_id, &resolver, &branch)
+        .unwrap();
+    let aborted = std::process::Command::new("git")
+        .args(["merge", "--abort"])
+        .current_dir(&info.path)
+        .output()
+        .unwrap();
+    assert!(aborted.status.success());
+
+    let result = manager.commit_conflict_resolution(&resolver, &branch, &conflict.conflicted_files);
+    assert!(result.is_err(), "{result:?}");
+
+    cleanup(&workspace);
+}
+
 #[test]
 fn test_worktree_cleanup() {
     let workspace = temp_workspace();
@@ -1,17 +1,88 @@
+use std::future::Future;
 use std::path::Path;
+use std::sync::OnceLock;
 use std::time::Duration;
 
+use dashmap::DashMap;
 use serde_json::json;
+use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
 
+use crate::bus::{CATEGORY_TOOL, EVENT_TOOL_OUTPUT_DELTA, EVENT_TOOL_PROGRESS};
 use crate::db::{queries, Database};
-use crate::policy::PolicyEngine;
+use crate::policy::{redaction, PolicyEngine};
 use crate::runtime::approval::ApprovalGate;
 use crate::runtime::planner::emit_and_record;
 use crate::runtime::questions::UserQuestionGate;
-use crate::tools::{ToolCallInput, ToolCallOutput, ToolError, ToolRegistry};
+use crate::runtime::snapshots::PendingSnapshot;
+use crate::tools::process::OutputStream;
+use crate::tools::types::ProgressReporter;
+use crate::tools::{
+    ToolCallInput, ToolCallOutput, ToolContext, ToolError, ToolProgress, ToolRegistry,
+};
 
-pub fn invoke_tool_with_special_cases(
+/// Partial lines are flushed after this long without a newline.
+const OUTPUT_FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Partial lines longer than this are flushed without waiting.
+const OUTPUT_FLUSH_BYTES: usize = 4 * 1024;
+
+/// Streaming stops after this much output per call; the final result still
+/// carries the head and tail.
+const MAX_STREAMED_OUTPUT_BYTES: usize = 256 * 1024;
+
+/// The tool call being run; its ids go into the tool's context and onto its
+/// progress events.
+pub struct ToolCallScope<'a> {
+    pub run_id: &'a str,
+    pub sub_agent_id: Option<&'a str>,
+    pub tool_call_id: &'a str,
+}
+
+/// Cancellation tokens of running tool calls by tool call id, with the task
+/// each belongs to.
+static ACTIVE_TOOL_CALLS: OnceLock<DashMap<String, (String, CancellationToken)>> = OnceLock::new();
+
+fn active_tool_calls() -> &'static DashMap<String, (String, CancellationToken)> {
+    ACTIVE_TOOL_CALLS.get_or_init(DashMap::new)
+}
+
+/// Cancel every running tool call of `task_id`. Returns how many were
+/// signalled.
+pub fn cancel_task_tool_calls(task_id: &str) -> usize {
+    let mut cancelled = 0;
+    for entry in active_tool_calls().iter() {
+        let (owner, token) = entry.value();
+        if owner == task_id {
+            token.cancel();
+            cancelled += 1;
+        }
+    }
+    cancelled
+}
+
+/// Keeps a tool call cancellable until dropped.
+struct ActiveToolCall(String);
+
+impl ActiveToolCall {
+    fn register(task_id: &str, tool_call_id: &str, cancel: CancellationToken) -> Self {
+        active_tool_calls().insert(tool_call_id.to_string(), (task_id.to_string(), cancel));
+        Self(tool_call_id.to_string())
+    }
+}
+
+impl Drop for ActiveToolCall {
+    fn drop(&mut self) {
+        // Also reached when the call's future is dropped mid-flight, which
+        // must not leave its work running.
+        if let Some((_, (_, token))) = active_tool_calls().remove(&self.0) {
+            token.cancel();
+        }
+    }
+}
+
+pub async fn invoke_tool_with_special_cases(
     db: &Database,
     bus: &crate::bus::EventBus,
     task_id: &str,
@@ -20,6 +91,7 @@ pub fn invoke_tool_with_special_cases(
     worktree_path: &Path,
     tool_name: &str,
     tool_args: &serde_json::Value,
+    scope: &ToolCallScope<'_>,
 ) -> Result<ToolCallOutput, ToolError> {
     if tool_name == "diagram.read_graph" {
         return crate::tools::canvas::handle_read_graph(db, task_id);
@@ -31,17 +103,235 @@ pub fn invoke_tool_with_special_cases(
         return crate::tools::canvas::handle_apply_ops(db, bus, task_id, batch);
     }
 
-    tool_registry.invoke(
-        policy,
-        worktree_path,
-        ToolCallInput {
+    // Pre-images of the files the call may change, so it can be reverted
+    let snapshot = PendingSnapshot::capture(tool_name, tool_args, worktree_path);
+
+    let (progress, receiver) = ProgressReporter::channel();
+    let ctx = ToolContext {
+        run_id: Some(scope.run_id.to_string()),
+        task_id: Some(task_id.to_string()),
+        sub_agent_id: scope.sub_agent_id.map(str::to_string),
+        tool_call_id: Some(scope.tool_call_id.to_string()),
+        cancel: CancellationToken::new(),
+        progress,
+    };
+    // Cancelling the task cancels the call
+    let _active = ActiveToolCall::register(task_id, scope.tool_call_id, ctx.cancel.clone());
+
+    let invoke = async move {
+        let call = ToolCallInput {
             name: tool_name.to_string(),
             args: tool_args.clone(),
+        };
+        let result = tool_registry
+            .invoke_with_context(&ctx, policy, worktree_path, call)
+            .await;
+        // Closes the progress channel so forwarding finishes
+        drop(ctx);
+        result
+    };
+    let forward = forward_tool_progress(db, bus, task_id, tool_name, scope, receiver);
+    let (result, ()) = tokio::join!(invoke, forward);
+
+    if let Some(snapshot) = snapshot {
+        if let Err(error) = snapshot.record(db, scope.run_id, scope.tool_call_id) {
+            tracing::warn!(
+                "failed to record file snapshots for tool call {}: {error}",
+                scope.tool_call_id
+            );
+        }
+    }
+    result
+}
+
+/// Run a tool outside of a recorded tool call, such as the workspace check
+/// after edits. Cancelling the task cancels it; it reports no progress.
+pub async fn invoke_internal_tool(
+    task_id: &str,
+    tool_registry: &ToolRegistry,
+    policy: &PolicyEngine,
+    worktree_path: &Path,
+    call: ToolCallInput,
+) -> Result<ToolCallOutput, ToolError> {
+    let ctx = ToolContext {
+        run_id: None,
+        task_id: Some(task_id.to_string()),
+        sub_agent_id: None,
+        tool_call_id: None,
+        cancel: CancellationToken::new(),
+        progress: ProgressReporter::default(),
+    };
+    let _active = ActiveToolCall::register(
+        task_id,
+        &format!("internal-{}", uuid::Uuid::new_v4()),
+        ctx.cancel.clone(),
+    );
+    tool_registry
+        .invoke_with_context(&ctx, policy, worktree_path, call)
+        .await
+}
+
+/// Emit a tool call's progress until the call finishes: command output as
+/// `tool.output_delta` events, one or more whole lines at a time, and status
+/// lines as `tool.progress` events.
+async fn forward_tool_progress(
+    db: &Database,
+    bus: &crate::bus::EventBus,
+    task_id: &str,
+    tool_name: &str,
+    scope: &ToolCallScope<'_>,
+    mut receiver: UnboundedReceiver<ToolProgress>,
+) {
+    let event_payload = |fields: serde_json::Value| {
+        let mut payload = serde_json::Map::new();
+        payload.insert("task_id".into(), json!(task_id));
+        payload.insert("tool_call_id".into(), json!(scope.tool_call_id));
+        payload.insert("tool_name".into(), json!(tool_name));
+        if let Some(sub_agent_id) = scope.sub_agent_id {
+            payload.insert("sub_agent_id".into(), json!(sub_agent_id));
+        }
+        if let serde_json::Value::Object(fields) = fields {
+            payload.extend(fields);
+        }
+        serde_json::Value::Object(payload)
+    };
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut streamed = 0usize;
+
+    let mut emit = |stream: OutputStream, bytes: Vec<u8>| {
+        if bytes.is_empty() || streamed >= MAX_STREAMED_OUTPUT_BYTES {
+            return;
+        }
+        let room = MAX_STREAMED_OUTPUT_BYTES - streamed;
+        let truncated = bytes.len() > room;
+        let bytes = &bytes[..bytes.len().min(room)];
+        streamed += bytes.len();
+
+        let mut fields = json!({
+            "stream": stream,
+            "content": String::from_utf8_lossy(bytes),
+        });
+        if truncated {
+            fields["truncated"] = json!(true);
+        }
+        let _ = emit_and_record(
+            db,
+            bus,
+            CATEGORY_TOOL,
+            EVENT_TOOL_OUTPUT_DELTA,
+            Some(scope.run_id.to_string()),
+            event_payload(fields),
+        );
+    };
+
+    loop {
+        match timeout(OUTPUT_FLUSH_INTERVAL, receiver.recv()).await {
+            Ok(Some(ToolProgress::Output(chunk))) => {
+                let pending = match chunk.stream {
+                    OutputStream::Stdout => &mut stdout,
+                    OutputStream::Stderr => &mut stderr,
+                };
+                pending.extend_from_slice(&chunk.bytes);
+                let complete = if pending.len() >= OUTPUT_FLUSH_BYTES {
+                    Some(pending.len())
+                } else {
+                    pending
+                        .iter()
+                        .rposition(|byte| *byte == b'\n')
+                        .map(|idx| idx + 1)
+                };
+                if let Some(end) = complete {
+                    let lines: Vec<u8> = pending.drain(..end).collect();
+                    emit(chunk.stream, lines);
+                }
+            }
+            Ok(Some(ToolProgress::Status(message))) => {
+                emit(OutputStream::Stdout, std::mem::take(&mut stdout));
+                emit(OutputStream::Stderr, std::mem::take(&mut stderr));
+                let _ = emit_and_record(
+                    db,
+                    bus,
+                    CATEGORY_TOOL,
+                    EVENT_TOOL_PROGRESS,
+                    Some(scope.run_id.to_string()),
+                    event_payload(json!({ "message": message })),
+                );
+            }
+            Err(_) => {
+                emit(OutputStream::Stdout, std::mem::take(&mut stdout));
+                emit(OutputStream::Stderr, std::mem::take(&mut stderr));
+            }
+            Ok(None) => {
+                emit(OutputStream::Stdout, std::mem::take(&mut stdout));
+                emit(OutputStream::Stderr, std::mem::take(&mut stderr));
+                break;
+            }
+        }
+    }
+}
+
+/// Mask secrets in a finished call before it is stored in `tool_calls`,
+/// emitted, or fed back to the model. The count is added to the output's
+/// policy record so it lands in `tool_calls.policy_json` too.
+pub fn redact_tool_result(
+    mut invocation: Result<ToolCallOutput, ToolError>,
+) -> (Result<ToolCallOutput, ToolError>, usize) {
+    let count = match &mut invocation {
+        Ok(output) => {
+            let mut count = redaction::redact_value(&mut output.data);
+            if let Some(error) = output.error.as_mut() {
+                let (redacted, hits) = redaction::redact_text(error);
+                *error = redacted;
+                count += hits;
+            }
+            if count > 0 {
+                if let Some(data) = output.data.as_object_mut() {
+                    let policy = data.entry("policy").or_insert_with(|| json!({}));
+                    if let Some(policy) = policy.as_object_mut() {
+                        policy.insert("secrets_redacted".into(), json!(count));
+                    }
+                }
+            }
+            count
+        }
+        Err(
+            ToolError::InvalidInput(message)
+            | ToolError::PolicyDenied(message)
+            | ToolError::Execution(message),
+        ) => {
+            let (redacted, count) = redaction::redact_text(message);
+            *message = redacted;
+            count
+        }
+        Err(_) => 0,
+    };
+    (invocation, count)
+}
+
+/// Record the policy side of a finished call on its `tool_calls` row: path
+/// classes and redactions reported by the tool, or the reason it was denied.
+pub fn record_policy_outcome(
+    db: &Database,
+    tool_call_id: &str,
+    invocation: &Result<ToolCallOutput, ToolError>,
+) {
+    let record = match invocation {
+        Ok(output) => match output.data.get("policy") {
+            Some(record) if record.is_object() => record.clone(),
+            _ => return,
         },
-    )
+        Err(ToolError::PolicyDenied(reason)) => json!({
+            "decision": "deny",
+            "reason": reason,
+        }),
+        Err(_) => return,
+    };
+    let _ = queries::merge_tool_call_policy(db, tool_call_id, &record);
 }
 
-pub async fn resolve_human_gates(
+pub async fn resolve_human_gates<R>(
     db: &Database,
     bus: &crate::bus::EventBus,
     approval_gate: &ApprovalGate,
@@ -53,8 +343,11 @@ pub async fn resolve_human_gates(
     tool_call_id: &str,
     tool_name: &str,
     mut invocation: Result<ToolCallOutput, ToolError>,
-    mut reinvoke: impl FnMut() -> Result<ToolCallOutput, ToolError>,
-) -> Result<Result<ToolCallOutput, ToolError>, String> {
+    mut reinvoke: impl FnMut() -> R,
+) -> Result<Result<ToolCallOutput, ToolError>, String>
+where
+    R: Future<Output = Result<ToolCallOutput, ToolError>>,
+{
     let sub_agent = sub_agent_id.unwrap_or("");
 
     if let Err(ToolError::ApprovalRequired { scope, reason }) = &invocation {
@@ -122,9 +415,20 @@ pub async fn resolve_human_gates(
             serde_json::Value::Object(resolved_payload),
         );
 
+        let _ = queries::merge_tool_call_policy(
+            db,
+            tool_call_id,
+            &json!({
+                "decision": "needs_approval",
+                "scope": scope,
+                "reason": reason,
+                "approved": approved,
+            }),
+        );
+
         invocation = if approved {
             policy.allow_scope(scope);
-            reinvoke()
+            reinvoke().await
         } else {
             Err(ToolError::PolicyDenied(format!(
                 "approval denied for scope: {scope}"
@@ -223,3 +527,76 @@ pub async fn resolve_human_gates(
 
     Ok(invocation)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::EventBus;
+    use crate::tools::process::OutputChunk;
+
+    #[tokio::test]
+    async fn forward_tool_progress_emits_whole_lines_per_stream() {
+        let db = Database::open_in_memory().expect("in-memory DB");
+        let bus = EventBus::new();
+        let scope = ToolCallScope {
+            run_id: "run-1",
+            sub_agent_id: Some("sub-1"),
+            tool_call_id: "call-1",
+        };
+        let (progress, receiver) = ProgressReporter::channel();
+        for (stream, bytes) in [
+            (OutputStream::Stdout, "hel"),
+            (OutputStream::Stdout, "lo\nwor"),
+            (OutputStream::Stderr, "oops\n"),
+        ] {
+            progress.output(OutputChunk {
+                stream,
+                bytes: bytes.as_bytes().to_vec(),
+            });
+        }
+        progress.status("still running");
+        drop(progress);
+
+        forward_tool_progress(&db, &bus, "task-1", "cmd.exec", &scope, receiver).await;
+
+        let conn = db.conn();
+        let mut stmt = conn
+            .prepare("SELECT payload_json FROM events WHERE event_type = ?1 ORDER BY seq")
+            .expect("prepare");
+        let payloads: Vec<serde_json::Value> = stmt
+            .query_map([EVENT_TOOL_OUTPUT_DELTA], |row| row.get::<_, String>(0))
+            .expect("query")
+            .map(|row| serde_json::from_str(&row.expect("row")).expect("json"))
+            .collect();
+
+        let chunks: Vec<(&str, &str)> = payloads
+            .iter()
+            .map(|p| {
+                (
+                    p["stream"].as_str().unwrap(),
+                    p["content"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![
+                ("stdout", "hello\n"),
+                ("stderr", "oops\n"),
+                ("stdout", "wor")
+            ]
+        );
+        assert_eq!(payloads[0]["tool_call_id"], "call-1");
+        assert_eq!(payloads[0]["sub_agent_id"], "sub-1");
+
+        let status: String = conn
+            .query_row(
+                "SELECT payload_json FROM events WHERE event_type = ?1",
+                [EVENT_TOOL_PROGRESS],
+                |row| row.get(0),
+            )
+            .expect("progress event");
+        let status: serde_json::Value = serde_json::from_str(&status).expect("json");
+        assert_eq!(status["message"], "still running");
+    }
+}
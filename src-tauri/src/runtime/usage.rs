@@ -0,0 +1,184 @@
+//! Token usage and cost accounting for model calls.
+//!
+//! Every worker and planner turn reports the provider's `usage` block here so it
+//! can be priced against `ModelCatalog`, persisted to `api_requests`, rolled into
+//! the run totals, and surfaced on the timeline as an `agent.usage` event.
+//! Auxiliary completions made for a run go through `MeteredCompletion`, which
+//! has no `usage` block to report and records estimated counts instead.
+
+use std::str::FromStr;
+use std::time::Instant;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::bus::{EventBus, CATEGORY_AGENT, EVENT_AGENT_USAGE};
+use crate::db::{queries, Database};
+use crate::model::structured::OutputSchema;
+use crate::model::{CompletionClient, ModelCatalog, ModelError, ProviderId, TokenUsage};
+use crate::runtime::planner::emit_and_record;
+
+/// Identifies where a model call happened within a run.
+pub struct UsageScope<'a> {
+    pub run_id: &'a str,
+    pub task_id: &'a str,
+    pub sub_agent_id: Option<&'a str>,
+    pub step_idx: Option<u32>,
+}
+
+/// Price a call's usage, returning `None` when the model has no known rates.
+pub fn cost_for_usage(provider: &str, model: &str, usage: &TokenUsage) -> Option<f64> {
+    let provider = ProviderId::from_str(provider).ok()?;
+    ModelCatalog::pricing_for(provider, model).map(|pricing| pricing.cost_usd(usage))
+}
+
+/// Persist one model call and emit an `agent.usage` event for it.
+pub fn record_model_usage(
+    db: &Database,
+    bus: &EventBus,
+    scope: &UsageScope<'_>,
+    provider: &str,
+    model: &str,
+    usage: &TokenUsage,
+    latency_ms: u64,
+) -> Result<(), String> {
+    let row = usage_row(scope, provider, model, usage, latency_ms);
+    record_usage_row(db, bus, scope, &row)
+}
+
+fn usage_row(
+    scope: &UsageScope<'_>,
+    provider: &str,
+    model: &str,
+    usage: &TokenUsage,
+    latency_ms: u64,
+) -> queries::ApiRequestRow {
+    queries::ApiRequestRow {
+        id: Uuid::new_v4().to_string(),
+        run_id: scope.run_id.to_string(),
+        sub_agent_id: scope.sub_agent_id.map(str::to_string),
+        step_idx: scope.step_idx.map(i64::from),
+        provider: provider.to_string(),
+        model: model.to_string(),
+        tokens_in: usage.prompt_tokens as i64,
+        tokens_out: usage.completion_tokens as i64,
+        tokens_reasoning: usage.reasoning_tokens as i64,
+        tokens_cached: usage.cached_tokens as i64,
+        latency_ms: Some(latency_ms as i64),
+        cost_usd: cost_for_usage(provider, model, usage),
+        estimated: false,
+        created_at: Utc::now().to_rfc3339(),
+    }
+}
+
+fn record_usage_row(
+    db: &Database,
+    bus: &EventBus,
+    scope: &UsageScope<'_>,
+    row: &queries::ApiRequestRow,
+) -> Result<(), String> {
+    queries::insert_api_request(db, row).map_err(|e| e.to_string())?;
+
+    emit_and_record(
+        db,
+        bus,
+        CATEGORY_AGENT,
+        EVENT_AGENT_USAGE,
+        Some(scope.run_id.to_string()),
+        serde_json::json!({
+            "task_id": scope.task_id,
+            "sub_agent_id": scope.sub_agent_id,
+            "step_idx": scope.step_idx,
+            "provider": row.provider,
+            "model": row.model,
+            "prompt_tokens": row.tokens_in,
+            "completion_tokens": row.tokens_out,
+            "reasoning_tokens": row.tokens_reasoning,
+            "cached_tokens": row.tokens_cached,
+            "latency_ms": row.latency_ms,
+            "cost_usd": row.cost_usd,
+            "estimated": row.estimated,
+        }),
+    )?;
+
+    Ok(())
+}
+
+/// Plain completions carry no `usage` block; they are charged at roughly four
+/// characters per token, as context summaries are sized.
+const ESTIMATED_CHARS_PER_TOKEN: usize = 4;
+
+/// A completion client whose calls are recorded against a run like worker
+/// turns. The token counts are estimates, so the rows and events are marked
+/// `estimated` and budgets do not count them.
+pub struct MeteredCompletion<'a, C> {
+    pub client: &'a C,
+    pub db: &'a Database,
+    pub bus: &'a EventBus,
+    pub scope: UsageScope<'a>,
+    pub provider: &'a str,
+    pub model: &'a str,
+}
+
+impl<C> MeteredCompletion<'_, C> {
+    fn meter(
+        &self,
+        system: &str,
+        conversation: &str,
+        result: &Result<String, ModelError>,
+        started: Instant,
+    ) {
+        let Ok(response) = result else {
+            return;
+        };
+        let estimate = |len: usize| len.div_ceil(ESTIMATED_CHARS_PER_TOKEN) as u64;
+        let usage = TokenUsage {
+            prompt_tokens: estimate(system.len() + conversation.len()),
+            completion_tokens: estimate(response.len()),
+            ..TokenUsage::default()
+        };
+        let row = queries::ApiRequestRow {
+            estimated: true,
+            ..usage_row(
+                &self.scope,
+                self.provider,
+                self.model,
+                &usage,
+                started.elapsed().as_millis() as u64,
+            )
+        };
+        if let Err(error) = record_usage_row(self.db, self.bus, &self.scope, &row) {
+            tracing::warn!("failed to record completion usage: {error}");
+        }
+    }
+}
+
+impl<C: CompletionClient> CompletionClient for MeteredCompletion<'_, C> {
+    async fn complete(
+        &self,
+        system: &str,
+        conversation: &str,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        let started = Instant::now();
+        let result = self.client.complete(system, conversation, max_tokens).await;
+        self.meter(system, conversation, &result, started);
+        result
+    }
+
+    async fn complete_json(
+        &self,
+        system: &str,
+        conversation: &str,
+        schema: &OutputSchema,
+        max_tokens: u32,
+    ) -> Result<String, ModelError> {
+        let started = Instant::now();
+        let result = self
+            .client
+            .complete_json(system, conversation, schema, max_tokens)
+            .await;
+        self.meter(system, conversation, &result, started);
+        result
+    }
+}
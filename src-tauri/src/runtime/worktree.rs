@@ -66,6 +66,8 @@ pub enum MergeStrategy {
     FastForward,
     ThreeWayMerge,
     Conflict,
+    /// Conflicts resolved by a resolver sub-agent, then fast-forwarded.
+    AgentResolved,
     NoBranch,
     Skipped,
 }
@@ -76,12 +78,29 @@ impl std::fmt::Display for MergeStrategy {
             Self::FastForward => write!(f, "fast-forward"),
             Self::ThreeWayMerge => write!(f, "three-way-merge"),
             Self::Conflict => write!(f, "conflict"),
+            Self::AgentResolved => write!(f, "agent-resolved"),
             Self::NoBranch => write!(f, "no-branch"),
             Self::Skipped => write!(f, "skipped"),
         }
     }
 }
 
+/// What each side of a conflicted merge changed since the agent branch
+/// forked, limited to the conflicted files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictContext {
+    /// The agent branch that failed to merge.
+    pub branch: String,
+    /// Common ancestor of the base branch and the agent branch.
+    pub fork_point: String,
+    /// `<short hash> <subject>` of base branch commits since the fork point.
+    pub base_log: String,
+    pub base_diff: String,
+    /// `<short hash> <subject>` of agent branch commits since the fork point.
+    pub branch_log: String,
+    pub branch_diff: String,
+}
+
 #[derive(Debug, Error)]
 pub enum WorktreeError {
     #[error("io error: {0}")]
@@ -294,6 +313,206 @@ impl WorktreeManager {
         merge_branch_into_main(workspace_root, branch)
     }
 
+    /// Describe both sides of a merge of `sub_agent_id`'s branch that
+    /// conflicted in `conflicted_files`.
+    pub fn conflict_context(
+        &self,
+        workspace_root: &Path,
+        sub_agent_id: &str,
+        conflicted_files: &[String],
+    ) -> Result<ConflictContext, WorktreeError> {
+        let info = self.tracked(sub_agent_id)?;
+        let branch = info
+            .branch
+            .ok_or_else(|| WorktreeError::Git(format!("worktree {sub_agent_id} has no branch")))?;
+        let fork_point = run_git_command(workspace_root, &["merge-base", "HEAD", &branch])
+            .map_err(WorktreeError::Git)?
+            .trim()
+            .to_string();
+
+        let side = |tip: &str| {
+            let range = format!("{fork_point}..{tip}");
+            let log = run_git_command(workspace_root, &["log", "--format=%h %s", &range])
+                .unwrap_or_default();
+            let mut diff_args = vec!["diff", fork_point.as_str(), tip, "--"];
+            diff_args.extend(conflicted_files.iter().map(String::as_str));
+            let diff = run_git_command(workspace_root, &diff_args).unwrap_or_default();
+            (log, diff)
+        };
+        let (base_log, base_diff) = side("HEAD");
+        let (branch_log, branch_diff) = side(&branch);
+
+        Ok(ConflictContext {
+            branch,
+            fork_point,
+            base_log,
+            base_diff,
+            branch_log,
+            branch_diff,
+        })
+    }
+
+    /// Create a temporary worktree for resolving a conflicted merge: a new
+    /// branch at the base branch's tip, with the merge of `branch` started and
+    /// stopped at its conflicts.
+    ///
+    /// The worktree is tracked under `resolver_id`, so the resolver
+    /// sub-agent's own `create_worktree` call picks it up.
+    pub fn create_conflict_worktree(
+        &self,
+        workspace_root: &Path,
+        run_id: &str,
+        resolver_id: &str,
+        branch: &str,
+    ) -> Result<WorktreeInfo, WorktreeError> {
+        let info = self.create_worktree(workspace_root, run_id, resolver_id)?;
+        if info.strategy != WorktreeStrategy::GitWorktree {
+            let _ = self.remove_worktree(workspace_root, resolver_id);
+            return Err(WorktreeError::Create(
+                "conflict resolution needs a git worktree".to_string(),
+            ));
+        }
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&info.path)
+            .arg("merge")
+            .arg("--no-ff")
+            .arg("--no-edit")
+            .arg(branch)
+            .env("GIT_AUTHOR_NAME", "Orchestrix")
+            .env("GIT_AUTHOR_EMAIL", "orchestrix@local")
+            .env("GIT_COMMITTER_NAME", "Orchestrix")
+            .env("GIT_COMMITTER_EMAIL", "orchestrix@local")
+            .output()
+            .map_err(|e| WorktreeError::Git(format!("merge failed: {e}")))?;
+
+        // Stopping at conflicts is the expected outcome; any other failure
+        // leaves nothing to resolve.
+        if !output.status.success() && collect_conflict_files(&info.path).is_empty() {
+            let _ = self.remove_worktree(workspace_root, resolver_id);
+            return Err(WorktreeError::Git(format!(
+                "merge of {branch} failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(info)
+    }
+
+    /// Commit the resolved merge in `resolver_id`'s conflict worktree and
+    /// return its diffstat against the base branch.
+    ///
+    /// Fails while git still lists unmerged paths or any of
+    /// `conflicted_files` keeps conflict markers.
+    pub fn commit_conflict_resolution(
+        &self,
+        resolver_id: &str,
+        branch: &str,
+        conflicted_files: &[String],
+    ) -> Result<String, WorktreeError> {
+        let info = self.tracked(resolver_id)?;
+
+        let mut unresolved = collect_conflict_files(&info.path);
+        for file in conflicted_files {
+            if !unresolved.contains(file) && has_conflict_markers(&info.path.join(file)) {
+                unresolved.push(file.clone());
+            }
+        }
+        if !unresolved.is_empty() {
+            return Err(WorktreeError::Git(format!(
+                "unresolved conflicts remain in: {}",
+                unresolved.join(", ")
+            )));
+        }
+
+        let merging =
+            run_git_command(&info.path, &["rev-parse", "-q", "--verify", "MERGE_HEAD"]).is_ok();
+        // Without the merge in progress or already committed, an aborted or
+        // reset merge would be "resolved" into the base branch unchanged.
+        if !merging && !branch_is_merged(&info.path, branch) {
+            return Err(WorktreeError::Git(format!(
+                "merge of {branch} is no longer in progress and was not committed"
+            )));
+        }
+
+        let _ = run_git_command(&info.path, &["add", "-A"]);
+        let status = run_git_command(&info.path, &["status", "--porcelain"]).unwrap_or_default();
+        // The resolver may already have committed the merge itself.
+        if merging || !status.trim().is_empty() {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(&info.path)
+                .arg("commit")
+                .arg("-m")
+                .arg(format!(
+                    "orchestrix: merge {branch} with resolved conflicts"
+                ))
+                .env("GIT_AUTHOR_NAME", "Orchestrix")
+                .env("GIT_AUTHOR_EMAIL", "orchestrix@local")
+                .env("GIT_COMMITTER_NAME", "Orchestrix")
+                .env("GIT_COMMITTER_EMAIL", "orchestrix@local")
+                .output()
+                .map_err(|e| WorktreeError::Git(format!("commit failed: {e}")))?;
+            if !output.status.success() {
+                return Err(WorktreeError::Git(format!(
+                    "commit failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+        }
+        if !branch_is_merged(&info.path, branch) {
+            return Err(WorktreeError::Git(format!(
+                "resolved commit does not contain {branch}"
+            )));
+        }
+
+        let base = info.base_ref.as_deref().unwrap_or("HEAD^1");
+        Ok(run_git_command(&info.path, &["diff", "--stat", base, "HEAD"]).unwrap_or_default())
+    }
+
+    /// Fast-forward the base branch in the main workspace to the resolved
+    /// merge committed in `resolver_id`'s conflict worktree.
+    pub fn fast_forward_to_resolution(
+        &self,
+        workspace_root: &Path,
+        resolver_id: &str,
+    ) -> Result<MergeResult, WorktreeError> {
+        let info = self.tracked(resolver_id)?;
+        let branch = info
+            .branch
+            .ok_or_else(|| WorktreeError::Git(format!("worktree {resolver_id} has no branch")))?;
+
+        Ok(
+            match run_git_command(workspace_root, &["merge", "--ff-only", &branch]) {
+                Ok(_) => MergeResult {
+                    success: true,
+                    strategy: MergeStrategy::AgentResolved,
+                    message: format!("fast-forwarded to resolved merge {branch}"),
+                    conflicted_files: vec![],
+                },
+                // The base branch moved while the resolver worked.
+                Err(stderr) => MergeResult {
+                    success: false,
+                    strategy: MergeStrategy::Conflict,
+                    message: format!(
+                        "could not fast-forward to resolved merge {branch}: {}",
+                        stderr.trim()
+                    ),
+                    conflicted_files: vec![],
+                },
+            },
+        )
+    }
+
+    /// The tracked worktree of `sub_agent_id`.
+    fn tracked(&self, sub_agent_id: &str) -> Result<WorktreeInfo, WorktreeError> {
+        let guard = self.active.lock().expect("worktree manager mutex poisoned");
+        guard
+            .get(sub_agent_id)
+            .cloned()
+            .ok_or_else(|| WorktreeError::NotFound(sub_agent_id.to_string()))
+    }
+
     /// Remove a worktree from disk and from git's worktree list.
     /// Also removes the tracking entry.
     pub fn remove_worktree(
@@ -596,6 +815,23 @@ fn collect_conflict_files(workspace_root: &Path) -> Vec<String> {
     }
 }
 
+/// Whether `branch` is part of the history of `worktree_path`'s HEAD.
+fn branch_is_merged(worktree_path: &Path, branch: &str) -> bool {
+    run_git_command(
+        worktree_path,
+        &["merge-base", "--is-ancestor", branch, "HEAD"],
+    )
+    .is_ok()
+}
+
+/// Whether a file still holds `<<<<<<<` or `>>>>>>>` conflict markers.
+fn has_conflict_markers(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|text| {
+        text.lines()
+            .any(|line| line.starts_with("<<<<<<<") || line.starts_with(">>>>>>>"))
+    })
+}
+
 /// List all branches matching the `orchestrix/*` pattern.
 fn list_orchestrix_branches(workspace_root: &Path) -> Vec<String> {
     let output = run_git_command(
@@ -114,6 +114,7 @@ pub mod tests {
             },
             reasoning: Some("I need to create a file to store the test data".to_string()),
             raw_response: None,
+            usage: None,
         };
 
         match &decision.action {
@@ -152,6 +153,7 @@ pub mod tests {
             action: WorkerAction::ToolCalls { calls },
             reasoning: None,
             raw_response: None,
+            usage: None,
         };
 
         match &decision.action {
@@ -172,6 +174,7 @@ pub mod tests {
             },
             reasoning: Some("All files have been created and verified".to_string()),
             raw_response: None,
+            usage: None,
         };
 
         match &decision.action {
@@ -192,6 +195,7 @@ pub mod tests {
                 "This task should be delegated to a specialized testing agent".to_string(),
             ),
             raw_response: None,
+            usage: None,
         };
 
         match &decision.action {
@@ -218,6 +222,7 @@ pub mod tests {
             },
             reasoning: Some(reasoning_text.to_string()),
             raw_response: None,
+            usage: None,
         };
 
         assert!(decision.reasoning.is_some());
@@ -234,6 +239,7 @@ pub mod tests {
             },
             reasoning: None,
             raw_response: None,
+            usage: None,
         };
 
         assert!(decision.reasoning.is_none());
@@ -836,6 +842,7 @@ pub mod tests {
             },
             reasoning: None,
             raw_response: None,
+            usage: None,
         };
 
         let action = &decision.action;
@@ -974,6 +981,7 @@ pub mod tests {
             },
             reasoning: Some("Reasoning text".to_string()),
             raw_response: None,
+            usage: None,
         };
 
         let decision_json = json!({
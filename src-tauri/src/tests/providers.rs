@@ -126,6 +126,7 @@ pub mod tests {
             },
             reasoning: Some("All tasks completed successfully".to_string()),
             raw_response: None,
+            usage: None,
         };
 
         assert!(decision.reasoning.is_some());
@@ -140,6 +141,7 @@ pub mod tests {
             },
             reasoning: None,
             raw_response: None,
+            usage: None,
         };
 
         assert!(decision.reasoning.is_none());
@@ -0,0 +1,197 @@
+//! Offline orchestration tests driven by recorded model cassettes.
+//!
+//! These tests run the real plan → approve → build flow against the `replay`
+//! provider, so they need no API keys or network access.
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::bus::EventBus;
+    use crate::db::{queries, Database};
+    use crate::model::cassette::{ReplayClient, REPLAY_PROVIDER};
+    use crate::runtime::orchestrator::Orchestrator;
+    use crate::runtime::planner::generate_plan_markdown_artifact;
+    use crate::tests::{cleanup, temp_workspace};
+
+    const TASK_PROMPT: &str = "Create hello.txt containing a greeting";
+
+    fn decide(action: serde_json::Value, deltas: serde_json::Value) -> serde_json::Value {
+        json!({
+            "kind": "decide",
+            "model": "replay-model",
+            "request": {"task_prompt": TASK_PROMPT},
+            "deltas": deltas,
+            "response": {"decision": {
+                "action": action,
+                "usage": {
+                    "prompt_tokens": 120,
+                    "completion_tokens": 30,
+                    "reasoning_tokens": 0,
+                    "cached_tokens": 0
+                }
+            }}
+        })
+    }
+
+    /// Planner turn, structured plan extraction, then two build turns.
+    fn write_cassette(path: &Path) {
+        let cassette = json!({
+            "version": 1,
+            "interactions": [
+                decide(
+                    json!({
+                        "action": "tool_call",
+                        "tool_name": "agent.create_artifact",
+                        "tool_args": {
+                            "filename": "plan.md",
+                            "kind": "plan",
+                            "content": "# Plan: Greeting\n\n## Implementation Steps\n1. Write hello.txt"
+                        },
+                        "rationale": "submit the plan"
+                    }),
+                    json!([]),
+                ),
+                {
+                    "kind": "complete_json",
+                    "model": "replay-model",
+                    "request": {"schema": "implementation_plan"},
+                    "response": {"text": json!({
+                        "goal_summary": "Write a greeting file",
+                        "steps": [{
+                            "title": "Write hello.txt",
+                            "description": "Create hello.txt with a greeting",
+                            "tool_intent": "fs.write"
+                        }],
+                        "completion_criteria": ["hello.txt exists"]
+                    }).to_string()}
+                },
+                decide(
+                    json!({
+                        "action": "tool_call",
+                        "tool_name": "fs.write",
+                        "tool_args": {"path": "hello.txt", "content": "hello from replay\n"},
+                        "rationale": null
+                    }),
+                    json!([{"type": "reasoning", "text": "Writing the file."}]),
+                ),
+                decide(
+                    json!({"action": "complete", "summary": "Created hello.txt."}),
+                    json!([{"type": "content", "text": "Created hello.txt."}]),
+                ),
+            ]
+        });
+        std::fs::write(path, serde_json::to_string_pretty(&cassette).unwrap())
+            .expect("write cassette");
+    }
+
+    fn insert_task(db: &Database, workspace: &Path) -> queries::TaskRow {
+        let now = Utc::now().to_rfc3339();
+        let task = queries::TaskRow {
+            id: Uuid::new_v4().to_string(),
+            prompt: TASK_PROMPT.to_string(),
+            parent_task_id: None,
+            status: "pending".to_string(),
+            created_at: now.clone(),
+            updated_at: now,
+            workspace_root: Some(workspace.to_string_lossy().to_string()),
+        };
+        queries::insert_task(db, &task).expect("insert task");
+        task
+    }
+
+    async fn wait_for_terminal_status(db: &Database, task_id: &str) -> String {
+        for _ in 0..300 {
+            let status = queries::get_task(db, task_id)
+                .expect("load task")
+                .expect("task exists")
+                .status;
+            if status == "completed" || status == "failed" {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("task did not finish within 30s");
+    }
+
+    #[tokio::test]
+    async fn test_replayed_plan_approve_build_flow() {
+        let workspace = temp_workspace();
+        let cassette_path = workspace.with_extension("cassette.json");
+        write_cassette(&cassette_path);
+
+        let db = Arc::new(Database::open_in_memory().expect("in-memory DB"));
+        let bus = Arc::new(EventBus::new());
+        let orchestrator = Orchestrator::new(db.clone(), bus.clone(), workspace.clone());
+        let task = insert_task(&db, &workspace);
+        let api_key = cassette_path.to_string_lossy().to_string();
+
+        // PLAN
+        let outcome = generate_plan_markdown_artifact(
+            db.clone(),
+            bus.clone(),
+            task.id.clone(),
+            task.prompt.clone(),
+            REPLAY_PROVIDER.to_string(),
+            api_key.clone(),
+            None,
+            None,
+            workspace.clone(),
+            None,
+            None,
+            orchestrator.tool_registry().list_all(false),
+            orchestrator.tool_registry().clone(),
+            orchestrator.approval_gate().clone(),
+            orchestrator.question_gate().clone(),
+            false,
+        )
+        .await
+        .expect("replayed planning");
+
+        let plan = std::fs::read_to_string(&outcome.artifact_path).expect("plan artifact");
+        assert!(plan.contains("# Plan: Greeting"), "plan: {plan}");
+
+        let events = queries::list_events_for_run(&db, &outcome.run_id).expect("events");
+        let plan_ready = events
+            .iter()
+            .find(|event| event.event_type == "agent.plan_ready")
+            .expect("agent.plan_ready emitted");
+        let payload: serde_json::Value =
+            serde_json::from_str(&plan_ready.payload_json).expect("payload json");
+        assert_eq!(payload["plan"]["source"], "structured");
+        assert_eq!(payload["plan"]["steps"][0]["title"], "Write hello.txt");
+
+        // APPROVE + BUILD
+        orchestrator
+            .approve_plan(
+                task.clone(),
+                REPLAY_PROVIDER.to_string(),
+                api_key,
+                None,
+                None,
+            )
+            .expect("approve plan");
+        let status = wait_for_terminal_status(&db, &task.id).await;
+        assert_eq!(status, "completed");
+
+        let written = std::fs::read_to_string(workspace.join("hello.txt")).expect("hello.txt");
+        assert_eq!(written, "hello from replay\n");
+
+        let events = queries::list_events_for_run(&db, &outcome.run_id).expect("events");
+        assert!(events.iter().any(|event| {
+            event.event_type == "agent.message" && event.payload_json.contains("Created hello.txt.")
+        }));
+
+        // Every recorded interaction was served
+        assert_eq!(ReplayClient::open(&cassette_path, None).remaining(), 0);
+
+        let _ = std::fs::remove_file(&cassette_path);
+        cleanup(&workspace);
+    }
+}
@@ -609,6 +609,7 @@ impl Tool for AgentCreatePresetTool {
             description,
             mode: mode_clone,
             model,
+            fallback_models: Vec::new(),
             temperature,
             steps,
             tools,
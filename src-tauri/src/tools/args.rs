@@ -83,6 +83,18 @@ pub struct GitDiffArgs {
     /// If true, show staged (cached) changes instead of unstaged
     #[serde(default)]
     pub staged: Option<bool>,
+    /// Revision to diff from (e.g. "main", "HEAD~3"). Alone, diffs it against the worktree
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Revision to diff to; requires `from`
+    #[serde(default)]
+    pub to: Option<String>,
+    /// Only diff these paths (relative to the worktree)
+    #[serde(default)]
+    pub paths: Option<Vec<String>>,
+    /// If true, show a diffstat instead of the patch
+    #[serde(default)]
+    pub stat: Option<bool>,
 }
 
 /// Arguments for `git.apply_patch` tool.
@@ -110,6 +122,80 @@ pub struct GitLogArgs {
     pub count: Option<u64>,
 }
 
+/// Arguments for `git.blame` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GitBlameArgs {
+    /// File to blame (relative to the worktree)
+    pub path: String,
+    /// First line to blame (1-based)
+    #[serde(default)]
+    pub start_line: Option<u32>,
+    /// Last line to blame (inclusive)
+    #[serde(default)]
+    pub end_line: Option<u32>,
+    /// Blame the file as of this revision instead of the worktree
+    #[serde(default)]
+    pub revision: Option<String>,
+}
+
+/// Arguments for `git.show` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GitShowArgs {
+    /// Commit, tag or branch to show (default: HEAD)
+    #[serde(default)]
+    pub revision: Option<String>,
+    /// Show this file's content at the revision instead of the commit
+    #[serde(default)]
+    pub path: Option<String>,
+    /// If true, show a diffstat instead of the full patch
+    #[serde(default)]
+    pub stat: Option<bool>,
+}
+
+/// Arguments for `git.branch` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GitBranchArgs {
+    /// Action to perform: list, create, switch (default: list)
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Branch name, for create and switch
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Revision the new branch starts at, for create (default: HEAD)
+    #[serde(default)]
+    pub start_point: Option<String>,
+}
+
+/// Arguments for `git.stash` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GitStashArgs {
+    /// Action to perform: list, push, pop, apply, drop (default: list)
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Stash message, for push
+    #[serde(default)]
+    pub message: Option<String>,
+    /// If true, also stash untracked files, for push
+    #[serde(default)]
+    pub include_untracked: Option<bool>,
+    /// Stash entry for pop, apply and drop (default: 0, the latest)
+    #[serde(default)]
+    pub index: Option<u32>,
+}
+
+/// Arguments for `git.conflicts` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GitConflictsArgs {
+    /// Only report this conflicted file (default: all)
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
 // ============================================================================
 // Command execution tools (cmd.rs)
 // ============================================================================
@@ -130,6 +216,9 @@ pub struct CmdExecArgs {
     /// Optional relative working directory (e.g. 'frontend'). Avoid using shell 'cd'.
     #[serde(default)]
     pub workdir: Option<String>,
+    /// Seconds before the command and its children are killed (default 120)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
 }
 
 // ============================================================================
@@ -536,3 +625,98 @@ pub struct DevServerLogsArgs {
     #[serde(default)]
     pub stream: Option<String>,
 }
+
+// ============================================================================
+// Code intelligence tools (code.rs)
+// ============================================================================
+
+/// Arguments for `code.definition` and `code.hover` tools.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CodePositionArgs {
+    /// File containing the symbol
+    pub path: String,
+    /// Line of the symbol (1-indexed)
+    pub line: u32,
+    /// Column of the symbol (1-indexed, in characters)
+    pub column: u32,
+}
+
+/// Arguments for `code.references` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CodeReferencesArgs {
+    /// File containing the symbol
+    pub path: String,
+    /// Line of the symbol (1-indexed)
+    pub line: u32,
+    /// Column of the symbol (1-indexed, in characters)
+    pub column: u32,
+    /// Include the declaration itself (default: true)
+    #[serde(default)]
+    pub include_declaration: Option<bool>,
+    /// Maximum number of references to return (default: 200)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Arguments for `code.diagnostics` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CodeDiagnosticsArgs {
+    /// File to check
+    pub path: String,
+    /// Seconds to wait for the language server to report (default: 10, max: 60)
+    #[serde(default)]
+    pub wait_secs: Option<u64>,
+}
+
+/// Arguments for `code.rename` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CodeRenameArgs {
+    /// File containing the symbol
+    pub path: String,
+    /// Line of the symbol (1-indexed)
+    pub line: u32,
+    /// Column of the symbol (1-indexed, in characters)
+    pub column: u32,
+    /// New name for the symbol
+    pub new_name: String,
+}
+
+// ============================================================================
+// Syntax tools (syntax.rs)
+// ============================================================================
+
+/// Arguments for `code.outline` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CodeOutlineArgs {
+    /// File to outline
+    pub path: String,
+}
+
+/// Arguments for `code.symbols` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CodeSymbolsArgs {
+    /// File or directory to search (relative to workspace root, default: '.')
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Only symbols whose name contains this text (case-insensitive)
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Only symbols of this kind (function, method, struct, enum, trait, impl, class, interface, type, module, constant, macro)
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Tree-sitter query (S-expression) to match instead of listing symbols, e.g. '(call_expression function: (identifier) @fn)'
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Language of the query: rust, typescript, tsx, javascript or python. Required when querying a directory
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Maximum number of results (default: 100, max: 1000)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
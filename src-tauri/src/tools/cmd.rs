@@ -3,15 +3,20 @@
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use async_trait::async_trait;
+
 use crate::core::tool::ToolDescriptor;
 use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::tools::args::{schema_for_type, CmdExecArgs};
-use crate::tools::types::{Tool, ToolCallOutput, ToolError};
+use crate::tools::process::{self, ExecLimits, ExecOutcome, ExitReason};
+use crate::tools::sandbox::Sandbox;
+use crate::tools::types::{AsyncTool, ToolCallOutput, ToolContext, ToolError};
 
 /// Tool for executing shell commands.
 pub struct CommandExecTool;
 
-impl Tool for CommandExecTool {
+#[async_trait]
+impl AsyncTool for CommandExecTool {
     fn descriptor(&self) -> ToolDescriptor {
         ToolDescriptor {
             name: "cmd.exec".into(),
@@ -19,136 +24,190 @@ impl Tool for CommandExecTool {
                 "Execute a command. The 'cmd' field is the binary name (e.g. 'mkdir', 'bun', 'git'). ",
                 "The 'args' field is an array of string arguments. ",
                 "Optionally pass 'workdir' (relative to workspace root) to run in a subdirectory. ",
-                "Alternatively you can pass a single 'command' string and it will be run via the system shell."
+                "Alternatively you can pass a single 'command' string and it will be run via the system shell. ",
+                "Commands time out after 'timeout_secs' (default 120, capped by policy). ",
+                "Long output keeps only its head and tail; the full log is saved as an artifact. ",
+                "The workspace policy may run commands in a sandbox where only the workspace is writable and network is off."
             ).into(),
             input_schema: schema_for_type::<CmdExecArgs>(),
             output_schema: None,
         }
     }
 
-    fn invoke(
+    async fn invoke(
         &self,
+        ctx: &ToolContext,
         policy: &PolicyEngine,
         cwd: &Path,
         input: serde_json::Value,
     ) -> Result<ToolCallOutput, ToolError> {
-        let args: CmdExecArgs = serde_json::from_value(input)
-            .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
-
-        let command_cwd = normalize_workdir(cwd, args.workdir.as_deref());
+        // The command is waited on synchronously; keep it off the runtime
+        let ctx = ctx.clone();
+        let policy = policy.clone();
+        let cwd = cwd.to_path_buf();
+        tokio::task::spawn_blocking(move || exec_command(&ctx, &policy, &cwd, input))
+            .await
+            .map_err(|error| ToolError::Execution(format!("command task failed: {error}")))?
+    }
+}
 
-        match policy.evaluate_path(&command_cwd) {
-            PolicyDecision::Allow => {}
-            PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
-            PolicyDecision::NeedsApproval { scope, reason } => {
-                return Err(ToolError::ApprovalRequired { scope, reason })
-            }
+fn exec_command(
+    ctx: &ToolContext,
+    policy: &PolicyEngine,
+    cwd: &Path,
+    input: serde_json::Value,
+) -> Result<ToolCallOutput, ToolError> {
+    let args: CmdExecArgs = serde_json::from_value(input)
+        .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
+
+    let command_cwd = normalize_workdir(cwd, args.workdir.as_deref());
+
+    match policy.evaluate_path(&command_cwd) {
+        PolicyDecision::Allow => {}
+        PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
+        PolicyDecision::NeedsApproval { scope, reason } => {
+            return Err(ToolError::ApprovalRequired { scope, reason })
         }
+    }
 
-        if !command_cwd.exists() || !command_cwd.is_dir() {
-            return Err(ToolError::InvalidInput(format!(
-                "workdir does not exist or is not a directory: {}",
-                command_cwd.to_string_lossy()
-            )));
-        }
+    if !command_cwd.exists() || !command_cwd.is_dir() {
+        return Err(ToolError::InvalidInput(format!(
+            "workdir does not exist or is not a directory: {}",
+            command_cwd.to_string_lossy()
+        )));
+    }
 
-        let command_field = args.command.clone();
-        let explicit_args = args.args.clone();
-
-        let raw_cmd = args
-            .cmd
-            .as_deref()
-            .or_else(|| args.command.as_deref())
-            .or_else(|| {
-                explicit_args
-                    .as_ref()
-                    .and_then(|items| items.first().map(|s| s.as_str()))
-            })
-            .ok_or_else(|| ToolError::InvalidInput("cmd required".into()))?;
-
-        let (binary, mut final_args) = if explicit_args.is_some() {
-            (raw_cmd.to_string(), explicit_args.unwrap())
-        } else if raw_cmd.contains(' ') {
-            let parts: Vec<&str> = raw_cmd.split_whitespace().collect();
-            let bin = parts[0].to_string();
-            let rest: Vec<String> = parts[1..].iter().map(|s| s.to_string()).collect();
-            (bin, rest)
-        } else {
-            (raw_cmd.to_string(), Vec::new())
-        };
-
-        if final_args.first().map(|v| v == &binary).unwrap_or(false) {
-            let _ = final_args.remove(0);
-        }
+    let command_field = args.command.clone();
+    let explicit_args = args.args.clone();
+
+    let raw_cmd = args
+        .cmd
+        .as_deref()
+        .or_else(|| args.command.as_deref())
+        .or_else(|| {
+            explicit_args
+                .as_ref()
+                .and_then(|items| items.first().map(|s| s.as_str()))
+        })
+        .ok_or_else(|| ToolError::InvalidInput("cmd required".into()))?;
+
+    let (binary, mut final_args) = if explicit_args.is_some() {
+        (raw_cmd.to_string(), explicit_args.unwrap())
+    } else if raw_cmd.contains(' ') {
+        let parts: Vec<&str> = raw_cmd.split_whitespace().collect();
+        let bin = parts[0].to_string();
+        let rest: Vec<String> = parts[1..].iter().map(|s| s.to_string()).collect();
+        (bin, rest)
+    } else {
+        (raw_cmd.to_string(), Vec::new())
+    };
 
-        if binary.eq_ignore_ascii_case("cd") {
-            if let Some(target) =
-                resolve_cd_target(&command_cwd, command_field.as_deref(), &final_args)
-            {
-                match policy.evaluate_path(&target) {
-                    PolicyDecision::Allow => {}
-                    PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
-                    PolicyDecision::NeedsApproval { scope, reason } => {
-                        return Err(ToolError::ApprovalRequired { scope, reason })
-                    }
+    if final_args.first().map(|v| v == &binary).unwrap_or(false) {
+        let _ = final_args.remove(0);
+    }
+
+    // Every `cd` of a shell string is checked with the rest of the string
+    if command_field.is_none() && binary.eq_ignore_ascii_case("cd") {
+        if let Some(target) = resolve_cd_target(&command_cwd, &final_args) {
+            match policy.evaluate_path(&target) {
+                PolicyDecision::Allow => {}
+                PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
+                PolicyDecision::NeedsApproval { scope, reason } => {
+                    return Err(ToolError::ApprovalRequired { scope, reason })
                 }
             }
         }
+    }
 
-        match policy.evaluate_command(&binary) {
-            PolicyDecision::Allow => {}
-            PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
-            PolicyDecision::NeedsApproval { reason, .. } => {
-                return Err(ToolError::PolicyDenied(reason))
-            }
+    // Shell strings are checked segment by segment; direct invocations
+    // are checked with their full argument list.
+    let decision = match command_field.as_deref() {
+        Some(command) => policy.evaluate_shell_command(command, &command_cwd),
+        None => policy.evaluate_command(&binary, &final_args, &command_cwd),
+    };
+    match decision {
+        PolicyDecision::Allow => {}
+        PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
+        PolicyDecision::NeedsApproval { scope, reason } => {
+            return Err(ToolError::ApprovalRequired { scope, reason })
         }
+    }
 
-        let output = if let Some(command) = command_field.as_deref() {
-            run_shell_command(&command_cwd, command)?
-        } else {
-            match Command::new(&binary)
-                .args(&final_args)
-                .current_dir(&command_cwd)
-                .output()
-            {
-                Ok(value) => value,
-                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
-                    #[cfg(target_os = "windows")]
-                    {
-                        let shell_command = if final_args.is_empty() {
-                            binary.clone()
-                        } else {
-                            format!("{} {}", binary, final_args.join(" "))
-                        };
-                        run_shell_command(&command_cwd, &shell_command)?
+    let sandbox = Sandbox::new(cwd, policy.command_sandbox()).map_err(ToolError::PolicyDenied)?;
+    let limits = ExecLimits {
+        timeout: policy.command_timeout(args.timeout_secs),
+        log_dir: Some(cwd.join(".orchestrix").join("artifacts").join("logs")),
+        cancel: ctx.cancel.clone(),
+        progress: ctx.progress.clone(),
+    };
+
+    let output = if let Some(command) = command_field.as_deref() {
+        run_shell_command(&command_cwd, command, &sandbox, &limits)?
+    } else {
+        let mut direct = Command::new(&binary);
+        direct.args(&final_args).current_dir(&command_cwd);
+        match process::run_with_limits(sandbox.wrap(direct), &limits) {
+            Ok(value) => value,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                #[cfg(target_os = "windows")]
+                {
+                    let shell_command = if final_args.is_empty() {
+                        binary.clone()
+                    } else {
+                        format!("{} {}", binary, final_args.join(" "))
+                    };
+                    // The joined string is re-parsed by the shell, so it
+                    // must pass the shell rules too.
+                    match policy.evaluate_shell_command(&shell_command, &command_cwd) {
+                        PolicyDecision::Allow => {}
+                        PolicyDecision::Deny(reason) => {
+                            return Err(ToolError::PolicyDenied(reason))
+                        }
+                        PolicyDecision::NeedsApproval { scope, reason } => {
+                            return Err(ToolError::ApprovalRequired { scope, reason })
+                        }
                     }
-                    #[cfg(not(target_os = "windows"))]
-                    {
-                        return Err(ToolError::Execution(format!(
+                    run_shell_command(&command_cwd, &shell_command, &sandbox, &limits)?
+                }
+                #[cfg(not(target_os = "windows"))]
+                {
+                    return Err(ToolError::Execution(format!(
                             "program not found: {binary}. Try cmd.exec with the 'command' field for shell built-ins"
                         )));
-                    }
                 }
-                Err(error) => return Err(ToolError::Execution(error.to_string())),
             }
-        };
-
-        Ok(ToolCallOutput {
-            ok: output.status.success(),
-            data: serde_json::json!({
-                "stdout": String::from_utf8_lossy(&output.stdout),
-                "stderr": String::from_utf8_lossy(&output.stderr),
-                "code": output.status.code(),
-                "workdir": command_cwd,
-                "invoked": if let Some(command) = command_field {
-                    serde_json::json!({"mode": "shell", "command": command})
-                } else {
-                    serde_json::json!({"mode": "binary", "cmd": binary, "args": final_args})
-                },
-            }),
-            error: None,
-        })
-    }
+            Err(error) => return Err(ToolError::Execution(error.to_string())),
+        }
+    };
+
+    let error = match output.exit_reason {
+        ExitReason::TimedOut => Some(format!(
+            "command timed out after {}s",
+            limits.timeout.as_secs()
+        )),
+        ExitReason::Cancelled => Some("command cancelled".to_string()),
+        ExitReason::Exited | ExitReason::Signaled => None,
+    };
+
+    Ok(ToolCallOutput {
+        ok: output.exit_reason == ExitReason::Exited && output.code == Some(0),
+        data: serde_json::json!({
+            "stdout": output.stdout,
+            "stderr": output.stderr,
+            "code": output.code,
+            "exit_reason": output.exit_reason,
+            "duration_ms": output.duration_ms,
+            "full_log": output.full_log,
+            "sandbox": sandbox.report(),
+            "workdir": command_cwd,
+            "invoked": if let Some(command) = command_field {
+                serde_json::json!({"mode": "shell", "command": command})
+            } else {
+                serde_json::json!({"mode": "binary", "cmd": binary, "args": final_args})
+            },
+        }),
+        error,
+    })
 }
 
 #[cfg(target_os = "windows")]
@@ -247,35 +306,34 @@ pub fn translate_unix_to_windows(command: &str) -> String {
     command.to_string()
 }
 
-fn run_shell_command(cwd: &Path, command: &str) -> Result<std::process::Output, ToolError> {
+fn run_shell_command(
+    cwd: &Path,
+    command: &str,
+    sandbox: &Sandbox,
+    limits: &ExecLimits,
+) -> Result<ExecOutcome, ToolError> {
     #[cfg(target_os = "windows")]
-    {
+    let mut shell = {
         let translated = translate_unix_to_windows(command);
         let utf8_command = format!("chcp 65001 >nul 2>&1 && {}", translated);
-        Command::new("cmd")
-            .args(["/C", &utf8_command])
-            .current_dir(cwd)
-            .output()
-            .map_err(|e| ToolError::Execution(e.to_string()))
-    }
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", &utf8_command]);
+        shell
+    };
 
     #[cfg(not(target_os = "windows"))]
-    {
-        Command::new("sh")
-            .args(["-lc", command])
-            .current_dir(cwd)
-            .output()
-            .map_err(|e| ToolError::Execution(e.to_string()))
-    }
-}
+    let mut shell = {
+        let mut shell = Command::new("sh");
+        shell.args(["-lc", command]);
+        shell
+    };
 
-fn resolve_cd_target(cwd: &Path, command_field: Option<&str>, args: &[String]) -> Option<PathBuf> {
-    if let Some(command) = command_field {
-        if let Some(raw_target) = parse_cd_target_from_shell_command(command) {
-            return Some(resolve_path_from_cd_arg(cwd, &raw_target));
-        }
-    }
+    shell.current_dir(cwd);
+    process::run_with_limits(sandbox.wrap(shell), limits)
+        .map_err(|e| ToolError::Execution(e.to_string()))
+}
 
+fn resolve_cd_target(cwd: &Path, args: &[String]) -> Option<PathBuf> {
     let raw_target = args.first()?.as_str();
     if raw_target.eq_ignore_ascii_case("/d") {
         let second = args.get(1)?;
@@ -284,56 +342,6 @@ fn resolve_cd_target(cwd: &Path, command_field: Option<&str>, args: &[String]) -
     Some(resolve_path_from_cd_arg(cwd, raw_target))
 }
 
-fn parse_cd_target_from_shell_command(command: &str) -> Option<String> {
-    let trimmed = command.trim_start();
-    if !trimmed.to_ascii_lowercase().starts_with("cd") {
-        return None;
-    }
-
-    let first_segment = trimmed
-        .split("&&")
-        .next()
-        .unwrap_or(trimmed)
-        .split(';')
-        .next()
-        .unwrap_or(trimmed)
-        .trim();
-
-    if first_segment.len() < 2 {
-        return None;
-    }
-
-    let rest_trimmed = first_segment[2..].trim_start().to_string();
-    if rest_trimmed.is_empty() {
-        return None;
-    }
-
-    let rest = if rest_trimmed.to_ascii_lowercase().starts_with("/d") {
-        let after_d = rest_trimmed[2..].trim_start();
-        if after_d.is_empty() {
-            return None;
-        }
-        after_d.to_string()
-    } else {
-        rest_trimmed
-    };
-
-    let rest = rest.as_str();
-
-    if rest.starts_with('"') {
-        let closing = rest[1..].find('"')? + 1;
-        return Some(rest[1..closing].to_string());
-    }
-
-    if rest.starts_with('\'') {
-        let closing = rest[1..].find('\'')? + 1;
-        return Some(rest[1..closing].to_string());
-    }
-
-    let token = rest.split_whitespace().next()?;
-    Some(token.to_string())
-}
-
 fn resolve_path_from_cd_arg(cwd: &Path, raw: &str) -> PathBuf {
     let candidate = PathBuf::from(raw);
     if candidate.is_absolute() {
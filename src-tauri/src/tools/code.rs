@@ -0,0 +1,680 @@
+//! Code intelligence tools backed by language servers.
+//!
+//! Each call opens (or re-syncs) the file with the language server for its
+//! extension, see [`crate::lsp`]. Starting a server is a command execution
+//! and is gated like one: by the command rules, inside the `cmd.exec`
+//! sandbox and within the command timeout. Reading results is gated like `fs.read`, and
+//! `code.rename` checks write access to every file it edits before touching
+//! any of them.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::core::tool::ToolDescriptor;
+use crate::lsp::{self, LspClient};
+use crate::policy::{PolicyDecision, PolicyEngine};
+use crate::tools::args::{
+    schema_for_type, CodeDiagnosticsArgs, CodePositionArgs, CodeReferencesArgs, CodeRenameArgs,
+};
+use crate::tools::sandbox::Sandbox;
+use crate::tools::types::{Tool, ToolCallOutput, ToolError};
+
+const DEFAULT_REFERENCE_LIMIT: usize = 200;
+const DEFAULT_DIAGNOSTICS_WAIT_SECS: u64 = 10;
+const MAX_DIAGNOSTICS_WAIT_SECS: u64 = 60;
+
+fn check(decision: PolicyDecision) -> Result<(), ToolError> {
+    match decision {
+        PolicyDecision::Allow => Ok(()),
+        PolicyDecision::Deny(reason) => Err(ToolError::PolicyDenied(reason)),
+        PolicyDecision::NeedsApproval { scope, reason } => {
+            Err(ToolError::ApprovalRequired { scope, reason })
+        }
+    }
+}
+
+fn parse_args<T: serde::de::DeserializeOwned>(input: Value) -> Result<T, ToolError> {
+    serde_json::from_value(input)
+        .map_err(|e| ToolError::InvalidInput(format!("invalid input: {e}")))
+}
+
+/// A file opened with its language server.
+struct Document {
+    client: Arc<LspClient>,
+    path: PathBuf,
+    uri: String,
+    text: String,
+}
+
+impl Document {
+    fn open(policy: &PolicyEngine, cwd: &Path, path: &str) -> Result<Self, ToolError> {
+        let full = cwd.join(path);
+        check(policy.evaluate_read(&full))?;
+        let server = lsp::server_for_path(&full).ok_or_else(|| {
+            ToolError::InvalidInput(format!(
+                "no language server for {path}; supported: {}",
+                lsp::LANGUAGE_SERVERS
+                    .iter()
+                    .map(|server| server.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })?;
+        let server_args: Vec<String> = server.args.iter().map(|arg| arg.to_string()).collect();
+        check(policy.evaluate_command(server.command, &server_args, cwd))?;
+
+        let text = std::fs::read_to_string(&full)
+            .map_err(|e| ToolError::Execution(format!("failed to read {path}: {e}")))?;
+        let root = lsp::project_root(server, &full, cwd);
+        let sandbox =
+            Sandbox::new(cwd, policy.command_sandbox()).map_err(ToolError::PolicyDenied)?;
+        let client = lsp::client_for(server, &root, &sandbox, policy.command_timeout(None))
+            .map_err(ToolError::Execution)?;
+        Ok(Self {
+            client,
+            uri: lsp::path_to_uri(&full),
+            path: full,
+            text,
+        })
+    }
+
+    /// Send the file's current content to the server. Returns whether it
+    /// changed since the server last saw it.
+    fn sync(&self) -> Result<bool, ToolError> {
+        self.client
+            .sync_document(&self.path, lsp::language_id(&self.path), &self.text)
+            .map_err(ToolError::Execution)
+    }
+
+    fn position_params(&self, line: u32, column: u32) -> Value {
+        json!({
+            "textDocument": { "uri": self.uri },
+            "position": lsp::to_lsp_position(&self.text, line, column),
+        })
+    }
+
+    fn request(&self, method: &str, params: Value) -> Result<Value, ToolError> {
+        self.client
+            .request(method, params)
+            .map_err(ToolError::Execution)
+    }
+}
+
+/// Path as shown to the model: relative to `cwd` when inside it.
+fn display_path(path: &Path, cwd: &Path) -> String {
+    path.strip_prefix(cwd)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// 1-based line and column of `position`, counted in characters when the
+/// text is known and in UTF-16 units otherwise.
+fn position(text: Option<&str>, position: &Value) -> (u32, u32) {
+    match text {
+        Some(text) => lsp::from_lsp_position(text, position),
+        None => {
+            let field = |name| position.get(name).and_then(Value::as_u64).unwrap_or(0) as u32;
+            (field("line") + 1, field("character") + 1)
+        }
+    }
+}
+
+/// Turns server locations into `{path, line, column, ...}` objects, reading
+/// each file at most once and only when policy allows.
+struct LocationRenderer<'a> {
+    policy: &'a PolicyEngine,
+    cwd: &'a Path,
+    texts: HashMap<PathBuf, Option<String>>,
+}
+
+impl<'a> LocationRenderer<'a> {
+    fn new(policy: &'a PolicyEngine, cwd: &'a Path) -> Self {
+        Self {
+            policy,
+            cwd,
+            texts: HashMap::new(),
+        }
+    }
+
+    fn text(&mut self, path: &Path) -> Option<&str> {
+        let policy = self.policy;
+        self.texts
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                matches!(policy.evaluate_read(path), PolicyDecision::Allow)
+                    .then(|| std::fs::read_to_string(path).ok())
+                    .flatten()
+            })
+            .as_deref()
+    }
+
+    fn render(&mut self, uri: &str, range: &Value) -> Option<Value> {
+        let path = lsp::uri_to_path(uri)?;
+        let display = display_path(&path, self.cwd);
+        let text = self.text(&path);
+        let (line, column) = position(text, &range["start"]);
+        let (end_line, end_column) = position(text, &range["end"]);
+        let preview = text
+            .and_then(|text| text.lines().nth(line as usize - 1))
+            .map(|line| line.trim().to_string());
+        Some(json!({
+            "path": display,
+            "line": line,
+            "column": column,
+            "end_line": end_line,
+            "end_column": end_column,
+            "text": preview,
+        }))
+    }
+}
+
+/// `(uri, range)` pairs of a `Location`, `Location[]` or `LocationLink[]`
+/// result.
+fn location_targets(result: &Value) -> Vec<(&str, &Value)> {
+    fn one(item: &Value) -> Option<(&str, &Value)> {
+        if let Some(uri) = item.get("uri").and_then(Value::as_str) {
+            return Some((uri, item.get("range")?));
+        }
+        let uri = item.get("targetUri").and_then(Value::as_str)?;
+        let range = item
+            .get("targetSelectionRange")
+            .or_else(|| item.get("targetRange"))?;
+        Some((uri, range))
+    }
+    match result {
+        Value::Array(items) => items.iter().filter_map(one).collect(),
+        Value::Object(_) => one(result).into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Tool for jumping to the definition of a symbol.
+pub struct CodeDefinitionTool;
+
+impl Tool for CodeDefinitionTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "code.definition".into(),
+            description: concat!(
+                "Find where the symbol at a position is defined, using the language server for the file ",
+                "(rust-analyzer, typescript-language-server or pyright). ",
+                "Lines and columns are 1-indexed. Returns each definition's path, range and source line."
+            )
+            .into(),
+            input_schema: schema_for_type::<CodePositionArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: CodePositionArgs = parse_args(input)?;
+        let document = Document::open(policy, cwd, &args.path)?;
+        document.sync()?;
+        let result = document.request(
+            "textDocument/definition",
+            document.position_params(args.line, args.column),
+        )?;
+
+        let mut renderer = LocationRenderer::new(policy, cwd);
+        let definitions: Vec<Value> = location_targets(&result)
+            .into_iter()
+            .filter_map(|(uri, range)| renderer.render(uri, range))
+            .collect();
+        Ok(ToolCallOutput {
+            ok: true,
+            data: json!({ "definitions": definitions }),
+            error: None,
+        })
+    }
+}
+
+/// Tool for listing the references to a symbol.
+pub struct CodeReferencesTool;
+
+impl Tool for CodeReferencesTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "code.references".into(),
+            description: concat!(
+                "Find all references to the symbol at a position, using the language server for the file. ",
+                "Unlike a text search this resolves the symbol, so same-named items elsewhere are not included. ",
+                "Lines and columns are 1-indexed."
+            )
+            .into(),
+            input_schema: schema_for_type::<CodeReferencesArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: CodeReferencesArgs = parse_args(input)?;
+        let limit = args.limit.unwrap_or(DEFAULT_REFERENCE_LIMIT);
+        let document = Document::open(policy, cwd, &args.path)?;
+        document.sync()?;
+        let mut params = document.position_params(args.line, args.column);
+        params["context"] = json!({
+            "includeDeclaration": args.include_declaration.unwrap_or(true),
+        });
+        let result = document.request("textDocument/references", params)?;
+
+        let targets = location_targets(&result);
+        let total = targets.len();
+        let mut renderer = LocationRenderer::new(policy, cwd);
+        let references: Vec<Value> = targets
+            .into_iter()
+            .take(limit)
+            .filter_map(|(uri, range)| renderer.render(uri, range))
+            .collect();
+        Ok(ToolCallOutput {
+            ok: true,
+            data: json!({
+                "references": references,
+                "total": total,
+                "truncated": total > limit,
+            }),
+            error: None,
+        })
+    }
+}
+
+/// Text of hover `contents`: `MarkupContent`, a `MarkedString`, or an array
+/// of them.
+fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(hover_text)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Value::Object(object) => {
+            let value = object
+                .get("value")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            match object.get("language").and_then(Value::as_str) {
+                Some(language) => format!("```{language}\n{value}\n```"),
+                None => value.to_string(),
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+/// Tool for showing the type and documentation of a symbol.
+pub struct CodeHoverTool;
+
+impl Tool for CodeHoverTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "code.hover".into(),
+            description: concat!(
+                "Show the type signature and documentation of the symbol at a position, ",
+                "as the language server would in an editor tooltip. Lines and columns are 1-indexed."
+            )
+            .into(),
+            input_schema: schema_for_type::<CodePositionArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: CodePositionArgs = parse_args(input)?;
+        let document = Document::open(policy, cwd, &args.path)?;
+        document.sync()?;
+        let result = document.request(
+            "textDocument/hover",
+            document.position_params(args.line, args.column),
+        )?;
+
+        let contents = result
+            .get("contents")
+            .map(hover_text)
+            .filter(|text| !text.trim().is_empty());
+        Ok(ToolCallOutput {
+            ok: true,
+            data: json!({
+                "found": contents.is_some(),
+                "contents": contents,
+            }),
+            error: None,
+        })
+    }
+}
+
+fn severity_name(severity: Option<u64>) -> &'static str {
+    match severity {
+        Some(1) => "error",
+        Some(2) => "warning",
+        Some(3) => "information",
+        Some(4) => "hint",
+        _ => "error",
+    }
+}
+
+/// Tool for collecting the errors and warnings the language server reports.
+pub struct CodeDiagnosticsTool;
+
+impl Tool for CodeDiagnosticsTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "code.diagnostics".into(),
+            description: concat!(
+                "Get the errors and warnings the language server reports for a file, without running a build. ",
+                "Waits up to wait_secs for the server to analyse the file; 'pending: true' means it had not ",
+                "reported yet and the list may be incomplete."
+            )
+            .into(),
+            input_schema: schema_for_type::<CodeDiagnosticsArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: CodeDiagnosticsArgs = parse_args(input)?;
+        let wait_secs = args
+            .wait_secs
+            .unwrap_or(DEFAULT_DIAGNOSTICS_WAIT_SECS)
+            .min(MAX_DIAGNOSTICS_WAIT_SECS);
+        let document = Document::open(policy, cwd, &args.path)?;
+
+        let generation = document.client.diagnostics_generation(&document.path);
+        let changed = document.sync()?;
+        // Nothing new to analyse, and the server already reported on it
+        let wait = if !changed && generation > 0 {
+            Duration::ZERO
+        } else {
+            // Servers that check on save (rust-analyzer's cargo check) need this
+            document
+                .client
+                .notify(
+                    "textDocument/didSave",
+                    json!({ "textDocument": { "uri": document.uri } }),
+                )
+                .map_err(ToolError::Execution)?;
+            Duration::from_secs(wait_secs)
+        };
+        let (items, fresh) = document
+            .client
+            .wait_for_diagnostics(&document.path, generation, wait);
+
+        let diagnostics: Vec<Value> = items
+            .iter()
+            .map(|item| {
+                let (line, column) = position(Some(&document.text), &item["range"]["start"]);
+                let (end_line, end_column) = position(Some(&document.text), &item["range"]["end"]);
+                json!({
+                    "severity": severity_name(item.get("severity").and_then(Value::as_u64)),
+                    "message": item.get("message").and_then(Value::as_str).unwrap_or_default(),
+                    "source": item.get("source"),
+                    "code": item.get("code"),
+                    "line": line,
+                    "column": column,
+                    "end_line": end_line,
+                    "end_column": end_column,
+                })
+            })
+            .collect();
+        let errors = diagnostics
+            .iter()
+            .filter(|d| d["severity"] == "error")
+            .count();
+        Ok(ToolCallOutput {
+            ok: true,
+            data: json!({
+                "path": args.path,
+                "diagnostics": diagnostics,
+                "error_count": errors,
+                "warning_count": diagnostics.iter().filter(|d| d["severity"] == "warning").count(),
+                "pending": !fresh && wait > Duration::ZERO,
+            }),
+            error: None,
+        })
+    }
+}
+
+/// Text edits of a `WorkspaceEdit` by file. Resource operations (create,
+/// rename, delete) are refused rather than half-applied.
+fn workspace_edit_files(edit: &Value) -> Result<BTreeMap<PathBuf, Vec<Value>>, ToolError> {
+    let mut files: BTreeMap<PathBuf, Vec<Value>> = BTreeMap::new();
+    let mut add = |uri: &str, edits: &Value| -> Result<(), ToolError> {
+        let path = lsp::uri_to_path(uri)
+            .ok_or_else(|| ToolError::Execution(format!("unsupported document URI {uri}")))?;
+        let edits = edits.as_array().cloned().unwrap_or_default();
+        files.entry(path).or_default().extend(edits);
+        Ok(())
+    };
+
+    if let Some(changes) = edit.get("documentChanges").and_then(Value::as_array) {
+        for change in changes {
+            if let Some(kind) = change.get("kind").and_then(Value::as_str) {
+                return Err(ToolError::Execution(format!(
+                    "rename needs a file {kind} operation, which code.rename does not apply"
+                )));
+            }
+            let uri = change["textDocument"]["uri"].as_str().unwrap_or_default();
+            add(uri, &change["edits"])?;
+        }
+    } else if let Some(changes) = edit.get("changes").and_then(Value::as_object) {
+        for (uri, edits) in changes {
+            add(uri, edits)?;
+        }
+    }
+    Ok(files)
+}
+
+/// Apply LSP `TextEdit`s to `text`. Edits must not overlap, as the protocol
+/// requires.
+fn apply_text_edits(text: &str, edits: &[Value]) -> String {
+    let mut ranges: Vec<(usize, usize, &str)> = edits
+        .iter()
+        .map(|edit| {
+            let start = lsp::lsp_position_offset(text, &edit["range"]["start"]);
+            let end = lsp::lsp_position_offset(text, &edit["range"]["end"]).max(start);
+            let new_text = edit
+                .get("newText")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            (start, end, new_text)
+        })
+        .collect();
+    // Back to front, so earlier offsets stay valid
+    ranges.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+    let mut result = text.to_string();
+    for (start, end, new_text) in ranges {
+        result.replace_range(start..end, new_text);
+    }
+    result
+}
+
+/// Temporary sibling a renamed file is written to before it replaces `path`.
+fn staging_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.orchestrix-rename"))
+}
+
+/// Tool for renaming a symbol across the project.
+pub struct CodeRenameTool;
+
+impl Tool for CodeRenameTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "code.rename".into(),
+            description: concat!(
+                "Rename the symbol at a position everywhere it is used, using the language server's ",
+                "semantic rename, and write the edited files. Lines and columns are 1-indexed. ",
+                "Returns the files changed and the number of edits in each."
+            )
+            .into(),
+            input_schema: schema_for_type::<CodeRenameArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: CodeRenameArgs = parse_args(input)?;
+        if args.new_name.trim().is_empty() {
+            return Err(ToolError::InvalidInput("new_name must not be empty".into()));
+        }
+        let document = Document::open(policy, cwd, &args.path)?;
+        document.sync()?;
+        let mut params = document.position_params(args.line, args.column);
+        params["newName"] = json!(args.new_name);
+        let result = document.request("textDocument/rename", params)?;
+        if result.is_null() {
+            return Err(ToolError::Execution(format!(
+                "no renameable symbol at {}:{}:{}",
+                args.path, args.line, args.column
+            )));
+        }
+
+        let files = workspace_edit_files(&result)?;
+        for path in files.keys() {
+            check(policy.evaluate_write(path))?;
+        }
+
+        let mut updated = Vec::with_capacity(files.len());
+        for (path, edits) in &files {
+            let text = std::fs::read_to_string(path).map_err(|e| {
+                ToolError::Execution(format!("failed to read {}: {e}", path.display()))
+            })?;
+            updated.push((path, apply_text_edits(&text, edits), edits.len()));
+        }
+        // Stage every file next to its original before replacing any, so a
+        // failed write leaves the project untouched
+        let mut staged: Vec<PathBuf> = Vec::with_capacity(updated.len());
+        for (path, text, _) in &updated {
+            let temp = staging_path(path);
+            let written = std::fs::write(&temp, text).and_then(|()| {
+                let permissions = std::fs::metadata(path)?.permissions();
+                std::fs::set_permissions(&temp, permissions)
+            });
+            if let Err(e) = written {
+                for file in staged.iter().chain([&temp]) {
+                    let _ = std::fs::remove_file(file);
+                }
+                return Err(ToolError::Execution(format!(
+                    "failed to write {}: {e}",
+                    path.display()
+                )));
+            }
+            staged.push(temp);
+        }
+        let mut changed = Vec::with_capacity(updated.len());
+        for ((path, text, edit_count), temp) in updated.iter().zip(&staged) {
+            std::fs::rename(temp, path).map_err(|e| {
+                ToolError::Execution(format!("failed to write {}: {e}", path.display()))
+            })?;
+            let _ = document
+                .client
+                .sync_document(path, lsp::language_id(path), text);
+            changed.push(json!({ "path": display_path(path, cwd), "edits": edit_count }));
+        }
+
+        let paths: Vec<PathBuf> = files.keys().cloned().collect();
+        let mut data = json!({
+            "files": changed,
+            "edit_count": updated.iter().map(|(_, _, count)| count).sum::<usize>(),
+        });
+        if let Some(classes) = policy.path_classes_record(&paths) {
+            data["policy"] = classes;
+        }
+        Ok(ToolCallOutput {
+            ok: true,
+            data,
+            error: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> Value {
+        json!({
+            "range": {
+                "start": { "line": start.0, "character": start.1 },
+                "end": { "line": end.0, "character": end.1 },
+            },
+            "newText": new_text,
+        })
+    }
+
+    #[test]
+    fn applies_workspace_edits_back_to_front() {
+        let text = "fn old() {}\nfn main() { old(); old(); }\n";
+        let edits = [
+            edit((1, 12), (1, 15), "renamed"),
+            edit((0, 3), (0, 6), "renamed"),
+            edit((1, 18), (1, 21), "renamed"),
+        ];
+        assert_eq!(
+            apply_text_edits(text, &edits),
+            "fn renamed() {}\nfn main() { renamed(); renamed(); }\n"
+        );
+
+        let workspace_edit = json!({
+            "documentChanges": [
+                { "textDocument": { "uri": "file:///w/a.rs", "version": 1 }, "edits": edits },
+                { "kind": "rename", "oldUri": "file:///w/a.rs", "newUri": "file:///w/b.rs" },
+            ],
+        });
+        assert!(workspace_edit_files(&workspace_edit).is_err());
+        let files = workspace_edit_files(&json!({ "changes": { "file:///w/a.rs": edits } }))
+            .expect("text edits only");
+        assert_eq!(files[Path::new("/w/a.rs")].len(), 3);
+    }
+
+    #[test]
+    fn normalizes_locations_and_links() {
+        let range =
+            json!({ "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 1 } });
+        let link = json!([{ "targetUri": "file:///w/a.rs", "targetRange": {}, "targetSelectionRange": range }]);
+        assert_eq!(location_targets(&link), vec![("file:///w/a.rs", &range)]);
+        let location = json!({ "uri": "file:///w/b.rs", "range": range });
+        assert_eq!(
+            location_targets(&location),
+            vec![("file:///w/b.rs", &range)]
+        );
+        assert!(location_targets(&Value::Null).is_empty());
+        assert_eq!(
+            hover_text(&json!([{ "language": "rust", "value": "fn f()" }, "Docs"])),
+            "```rust\nfn f()\n```\n\nDocs"
+        );
+    }
+}
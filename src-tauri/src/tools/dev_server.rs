@@ -12,6 +12,7 @@ use std::process::Stdio;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
+use async_trait::async_trait;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncBufReadExt, BufReader};
@@ -23,7 +24,7 @@ use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::tools::args::{
     schema_for_type, DevServerLogsArgs, DevServerStartArgs, DevServerStatusArgs, DevServerStopArgs,
 };
-use crate::tools::types::{Tool, ToolCallOutput, ToolError};
+use crate::tools::types::{AsyncTool, Tool, ToolCallOutput, ToolContext, ToolError};
 
 const DEFAULT_LOG_BUFFER_SIZE: usize = 1000;
 const HEALTH_CHECK_TIMEOUT_SECS: u64 = 5;
@@ -102,7 +103,8 @@ pub struct HealthCheckResult {
 /// Tool for starting a development server.
 pub struct DevServerStartTool;
 
-impl Tool for DevServerStartTool {
+#[async_trait]
+impl AsyncTool for DevServerStartTool {
     fn descriptor(&self) -> ToolDescriptor {
         ToolDescriptor {
             name: "dev_server.start".into(),
@@ -118,8 +120,9 @@ impl Tool for DevServerStartTool {
         }
     }
 
-    fn invoke(
+    async fn invoke(
         &self,
+        ctx: &ToolContext,
         policy: &PolicyEngine,
         cwd: &Path,
         input: serde_json::Value,
@@ -143,12 +146,10 @@ impl Tool for DevServerStartTool {
         };
 
         // Check command policy
-        let binary = args
-            .command
-            .split_whitespace()
-            .next()
-            .unwrap_or(&args.command);
-        match policy.evaluate_command(binary) {
+        let mut parts = args.command.split_whitespace().map(String::from);
+        let binary = parts.next().unwrap_or_default();
+        let command_args: Vec<String> = parts.collect();
+        match policy.evaluate_command(&binary, &command_args, &workdir) {
             PolicyDecision::Allow => {}
             PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
             PolicyDecision::NeedsApproval { scope, reason } => {
@@ -164,35 +165,22 @@ impl Tool for DevServerStartTool {
         // Generate server ID
         let server_id = Uuid::new_v4().to_string();
 
-        // We'll need to capture run_id and sub_agent_id from context
-        // For now, use placeholder - these should be passed via tool context
-        let run_id = "unknown".to_string();
-        let sub_agent_id = "unknown".to_string();
-
-        // Start the process asynchronously
-        let runtime = tokio::runtime::Handle::try_current()
-            .map_err(|e| ToolError::Execution(format!("no async runtime: {}", e)))?;
-
-        // We must spawn a dedicated thread to block on the async task,
-        // because we are already inside a Tokio runtime worker thread (the tool invocation),
-        // and calling block_on directly on the current thread would panic.
-        let output = std::thread::spawn(move || {
-            runtime.block_on(async {
-                start_dev_server(
-                    server_id.clone(),
-                    run_id,
-                    sub_agent_id,
-                    args.command.clone(),
-                    workdir.clone(),
-                    port,
-                    args.health_check_url,
-                    args.max_wait_secs.unwrap_or(30),
-                )
-                .await
-            })
-        })
-        .join()
-        .map_err(|_| ToolError::Execution("dev server start thread panicked".to_string()))??;
+        // Servers are stopped with the run that started them
+        let run_id = ctx.run_id.clone().unwrap_or_default();
+        let sub_agent_id = ctx.sub_agent_id.clone().unwrap_or_default();
+
+        let output = start_dev_server(
+            ctx,
+            server_id,
+            run_id,
+            sub_agent_id,
+            args.command.clone(),
+            workdir,
+            port,
+            args.health_check_url,
+            args.max_wait_secs.unwrap_or(30),
+        )
+        .await?;
 
         Ok(ToolCallOutput {
             ok: true,
@@ -204,6 +192,7 @@ impl Tool for DevServerStartTool {
 
 /// Start a dev server and return its handle info.
 async fn start_dev_server(
+    ctx: &ToolContext,
     server_id: String,
     run_id: String,
     sub_agent_id: String,
@@ -288,9 +277,13 @@ async fn start_dev_server(
     // Store in registry
     dev_server_registry().insert(server_id.clone(), handle);
 
-    // Wait for health check
+    // Wait for health check; a cancelled call leaves the server running
+    // until it is stopped or its run ends.
     let url = health_check_url.unwrap_or_else(|| format!("http://localhost:{}", port));
-    let health_result = wait_for_health_check(&url, max_wait_secs).await;
+    ctx.progress.status(format!("waiting for {url}"));
+    let health_result = ctx
+        .cancellable(async { Ok(wait_for_health_check(&url, max_wait_secs).await) })
+        .await?;
 
     // Update stored URL
     if let Some(mut entry) = dev_server_registry().get_mut(&server_id) {
@@ -394,7 +387,8 @@ async fn wait_for_health_check(url: &str, max_wait_secs: u64) -> Option<HealthCh
 /// Tool for stopping a dev server.
 pub struct DevServerStopTool;
 
-impl Tool for DevServerStopTool {
+#[async_trait]
+impl AsyncTool for DevServerStopTool {
     fn descriptor(&self) -> ToolDescriptor {
         ToolDescriptor {
             name: "dev_server.stop".into(),
@@ -408,8 +402,9 @@ impl Tool for DevServerStopTool {
         }
     }
 
-    fn invoke(
+    async fn invoke(
         &self,
+        _ctx: &ToolContext,
         _policy: &PolicyEngine,
         _cwd: &Path,
         input: serde_json::Value,
@@ -419,15 +414,8 @@ impl Tool for DevServerStopTool {
 
         let graceful_timeout = 5;
 
-        let runtime = tokio::runtime::Handle::try_current()
-            .map_err(|e| ToolError::Execution(format!("no async runtime: {}", e)))?;
-
-        let server_id = args.server_id.clone();
-        let result = std::thread::spawn(move || {
-            runtime.block_on(stop_dev_server(&server_id, graceful_timeout))
-        })
-        .join()
-        .map_err(|_| ToolError::Execution("dev server stop thread panicked".to_string()))??;
+        // Not cancellable: the server is already out of the registry
+        let result = stop_dev_server(&args.server_id, graceful_timeout).await?;
 
         let has_error = result.error.clone();
 
@@ -519,7 +507,8 @@ async fn force_kill_process(
 /// Tool for checking dev server status.
 pub struct DevServerStatusTool;
 
-impl Tool for DevServerStatusTool {
+#[async_trait]
+impl AsyncTool for DevServerStatusTool {
     fn descriptor(&self) -> ToolDescriptor {
         ToolDescriptor {
             name: "dev_server.status".into(),
@@ -533,8 +522,9 @@ impl Tool for DevServerStatusTool {
         }
     }
 
-    fn invoke(
+    async fn invoke(
         &self,
+        ctx: &ToolContext,
         _policy: &PolicyEngine,
         _cwd: &Path,
         input: serde_json::Value,
@@ -542,61 +532,38 @@ impl Tool for DevServerStatusTool {
         let args: DevServerStatusArgs = serde_json::from_value(input)
             .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
 
-        let entry = dev_server_registry().get(&args.server_id).ok_or_else(|| {
-            ToolError::InvalidInput(format!("server not found: {}", args.server_id))
-        })?;
-
-        let handle = entry.value();
-        let is_running = handle.is_running();
-        let uptime_secs = handle.uptime_secs();
-        let exit_code = if is_running { None } else { handle.exit_code() };
-
-        // Get last few stderr lines for error context
-        let recent_errors: Vec<String> = {
-            let buffer = handle.stderr_buffer.lock().unwrap();
-            buffer.iter().rev().take(5).cloned().collect()
+        // Copy what we need so the registry entry is not held across awaits
+        let (is_running, uptime_secs, exit_code, url, port, command, recent_errors) = {
+            let entry = dev_server_registry().get(&args.server_id).ok_or_else(|| {
+                ToolError::InvalidInput(format!("server not found: {}", args.server_id))
+            })?;
+            let handle = entry.value();
+            let is_running = handle.is_running();
+            let exit_code = if is_running { None } else { handle.exit_code() };
+
+            // Get last few stderr lines for error context
+            let recent_errors: Vec<String> = {
+                let buffer = handle.stderr_buffer.lock().unwrap();
+                buffer.iter().rev().take(5).cloned().collect()
+            };
+            (
+                is_running,
+                handle.uptime_secs(),
+                exit_code,
+                handle.url.clone(),
+                handle.port,
+                handle.command.clone(),
+                recent_errors,
+            )
         };
 
-        let runtime = tokio::runtime::Handle::try_current()
-            .map_err(|e| ToolError::Execution(format!("no async runtime: {}", e)))?;
-
         // Perform health check if running and has URL
-        let health_result = if is_running {
-            if let Some(ref url) = handle.url {
-                let url = url.clone();
-                std::thread::spawn(move || {
-                    runtime.block_on(async {
-                        let client = match reqwest::Client::builder()
-                            .timeout(std::time::Duration::from_secs(5))
-                            .build()
-                        {
-                            Ok(c) => c,
-                            Err(_) => return None,
-                        };
-                        let start = Instant::now();
-                        match client.get(&url).send().await {
-                            Ok(response) => Some(HealthCheckResult {
-                                success: response.status().is_success(),
-                                status_code: Some(response.status().as_u16()),
-                                response_time_ms: start.elapsed().as_millis() as u64,
-                                error: None,
-                            }),
-                            Err(e) => Some(HealthCheckResult {
-                                success: false,
-                                status_code: None,
-                                response_time_ms: start.elapsed().as_millis() as u64,
-                                error: Some(e.to_string()),
-                            }),
-                        }
-                    })
-                })
-                .join()
-                .unwrap_or(None)
-            } else {
-                None
+        let health_result = match url.as_deref() {
+            Some(url) if is_running => {
+                ctx.cancellable(async { Ok(check_health(url).await) })
+                    .await?
             }
-        } else {
-            None
+            _ => None,
         };
 
         let status = DevServerStatusOutput {
@@ -604,9 +571,9 @@ impl Tool for DevServerStatusTool {
             is_running,
             uptime_secs,
             exit_code,
-            url: handle.url.clone(),
-            port: handle.port,
-            command: handle.command.clone(),
+            url,
+            port,
+            command,
             health_check: health_result,
             recent_errors: if recent_errors.is_empty() {
                 None
@@ -623,6 +590,29 @@ impl Tool for DevServerStatusTool {
     }
 }
 
+/// One health check request against a running server.
+async fn check_health(url: &str) -> Option<HealthCheckResult> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS))
+        .build()
+        .ok()?;
+    let start = Instant::now();
+    match client.get(url).send().await {
+        Ok(response) => Some(HealthCheckResult {
+            success: response.status().is_success(),
+            status_code: Some(response.status().as_u16()),
+            response_time_ms: start.elapsed().as_millis() as u64,
+            error: None,
+        }),
+        Err(e) => Some(HealthCheckResult {
+            success: false,
+            status_code: None,
+            response_time_ms: start.elapsed().as_millis() as u64,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
 /// Output for dev_server.status tool.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DevServerStatusOutput {
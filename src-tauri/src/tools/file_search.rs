@@ -4,7 +4,7 @@
 //! for fuzzy scoring. This provides fast filename discovery without shelling
 //! out to external processes.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use ignore::WalkBuilder;
 use nucleo_matcher::pattern::{AtomKind, CaseMatching, Normalization, Pattern};
@@ -15,6 +15,19 @@ use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::tools::args::{schema_for_type, SearchFilesArgs};
 use crate::tools::types::{Tool, ToolCallOutput, ToolError};
 
+/// Files under `root`, honouring `.gitignore` and `.ignore` files whether or
+/// not `root` is in a git repository. Hidden files are included.
+pub(crate) fn walk_files(root: &Path) -> impl Iterator<Item = PathBuf> {
+    WalkBuilder::new(root)
+        .hidden(false)
+        .follow_links(true)
+        .require_git(false)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|ft| !ft.is_dir()))
+        .map(ignore::DirEntry::into_path)
+}
+
 /// Tool for fuzzy file name search.
 pub struct SearchFilesTool;
 
@@ -65,26 +78,12 @@ impl Tool for SearchFilesTool {
             )));
         }
 
-        let mut file_paths: Vec<String> = Vec::new();
-        let walker = WalkBuilder::new(&full_path)
-            .hidden(false)
-            .follow_links(true)
-            .require_git(false)
-            .build();
-
-        for entry in walker {
-            let entry = match entry {
-                Ok(e) => e,
-                Err(_) => continue,
-            };
-            if entry.file_type().map_or(true, |ft| ft.is_dir()) {
-                continue;
-            }
-            if let Ok(relative) = entry.path().strip_prefix(cwd) {
-                let rel_str = relative.to_string_lossy().replace('\\', "/");
-                file_paths.push(rel_str);
-            }
-        }
+        let file_paths: Vec<String> = walk_files(&full_path)
+            .filter_map(|path| {
+                let relative = path.strip_prefix(cwd).ok()?;
+                Some(relative.to_string_lossy().replace('\\', "/"))
+            })
+            .collect();
 
         let pattern = Pattern::new(
             &args.pattern,
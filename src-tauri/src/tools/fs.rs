@@ -5,6 +5,7 @@ use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 
 use crate::core::tool::ToolDescriptor;
+use crate::policy::paths::{PathClass, SecretRedactor};
 use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::tools::args::{schema_for_type, FsListArgs, FsReadArgs, FsWriteArgs};
 use crate::tools::types::{Tool, ToolCallOutput, ToolError};
@@ -37,7 +38,7 @@ impl Tool for FsReadTool {
 
         let full = cwd.join(&args.path);
 
-        match policy.evaluate_path(&full) {
+        match policy.evaluate_read(&full) {
             PolicyDecision::Allow => {}
             PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
             PolicyDecision::NeedsApproval { scope, reason } => {
@@ -45,6 +46,10 @@ impl Tool for FsReadTool {
             }
         }
 
+        // Secret files reach the model with their values masked
+        let mut redactor =
+            (policy.path_class(&full) == Some(PathClass::Secret)).then(SecretRedactor::default);
+
         let file = File::open(&full)
             .map_err(|e| ToolError::Execution(format!("failed to open file: {}", e)))?;
         let reader = BufReader::new(file);
@@ -56,8 +61,11 @@ impl Tool for FsReadTool {
             .enumerate()
             .map(|(idx, line_res)| {
                 let line_num = offset + (idx as u64);
-                let line_content =
+                let mut line_content =
                     line_res.unwrap_or_else(|_| "<binary or read error>".to_string());
+                if let Some(redactor) = redactor.as_mut() {
+                    line_content = redactor.redact_line(&line_content);
+                }
                 if show_line_numbers {
                     format!("{}: {}", line_num, line_content)
                 } else {
@@ -68,14 +76,23 @@ impl Tool for FsReadTool {
 
         let content = lines.join("\n");
 
+        let mut data = serde_json::json!({
+            "path": full,
+            "content": content,
+            "offset": offset,
+            "limit": limit
+        });
+        if let Some(mut record) = policy.path_classes_record(std::slice::from_ref(&full)) {
+            if let Some(redactor) = &redactor {
+                record["action"] = "redacted".into();
+                record["redactions"] = redactor.redactions.into();
+            }
+            data["policy"] = record;
+        }
+
         Ok(ToolCallOutput {
             ok: true,
-            data: serde_json::json!({
-                "path": full,
-                "content": content,
-                "offset": offset,
-                "limit": limit
-            }),
+            data,
             error: None,
         })
     }
@@ -105,7 +122,7 @@ impl Tool for FsWriteTool {
 
         let full = cwd.join(&args.path);
 
-        match policy.evaluate_path(&full) {
+        match policy.evaluate_write(&full) {
             PolicyDecision::Allow => {}
             PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
             PolicyDecision::NeedsApproval { scope, reason } => {
@@ -118,9 +135,14 @@ impl Tool for FsWriteTool {
         }
         std::fs::write(&full, &args.content).map_err(|e| ToolError::Execution(e.to_string()))?;
 
+        let mut data = serde_json::json!({"path": full});
+        if let Some(record) = policy.path_classes_record(std::slice::from_ref(&full)) {
+            data["policy"] = record;
+        }
+
         Ok(ToolCallOutput {
             ok: true,
-            data: serde_json::json!({"path": full}),
+            data,
             error: None,
         })
     }
@@ -1,16 +1,106 @@
 //! Git tools for repository operations.
 
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use serde::Serialize;
+
 use crate::core::tool::ToolDescriptor;
+use crate::policy::paths::{PathClass, SecretRedactor};
 use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::tools::args::{
-    schema_for_type, GitApplyPatchArgs, GitCommitArgs, GitDiffArgs, GitLogArgs,
+    schema_for_type, GitApplyPatchArgs, GitBlameArgs, GitBranchArgs, GitCommitArgs,
+    GitConflictsArgs, GitDiffArgs, GitLogArgs, GitShowArgs, GitStashArgs,
 };
 use crate::tools::types::{Tool, ToolCallOutput, ToolError};
 
+/// Author and committer for commits the tools create.
+const ORCHESTRIX_IDENTITY: &[(&str, &str)] = &[
+    ("GIT_AUTHOR_NAME", "Orchestrix"),
+    ("GIT_AUTHOR_EMAIL", "orchestrix@local"),
+    ("GIT_COMMITTER_NAME", "Orchestrix"),
+    ("GIT_COMMITTER_EMAIL", "orchestrix@local"),
+];
+
+fn check(decision: PolicyDecision) -> Result<(), ToolError> {
+    match decision {
+        PolicyDecision::Allow => Ok(()),
+        PolicyDecision::Deny(reason) => Err(ToolError::PolicyDenied(reason)),
+        PolicyDecision::NeedsApproval { scope, reason } => {
+            Err(ToolError::ApprovalRequired { scope, reason })
+        }
+    }
+}
+
+/// A revision or branch name, rejected when git would parse it as an option.
+fn revision_arg(value: &str) -> Result<&str, ToolError> {
+    let value = value.trim();
+    // `rev:path` names a file, which must go through the read policy instead
+    if value.is_empty() || value.starts_with('-') || value.contains(':') {
+        return Err(ToolError::InvalidInput(format!(
+            "invalid revision: {value:?}"
+        )));
+    }
+    Ok(value)
+}
+
+/// A worktree-relative file argument, checked against the read policy.
+fn read_path<'a>(policy: &PolicyEngine, cwd: &Path, path: &'a str) -> Result<&'a str, ToolError> {
+    check(policy.evaluate_read(&cwd.join(path)))?;
+    Ok(path)
+}
+
+/// `:(exclude)` pathspecs for secret and read-denied files, so diffs and
+/// commits shown to the model leave them out.
+fn secret_exclusions(policy: &PolicyEngine) -> Vec<String> {
+    policy
+        .search_exclusions()
+        .iter()
+        .map(|pattern| {
+            let (pattern, suffix) = match pattern.strip_suffix('/') {
+                Some(dir) => (dir, "/**"),
+                None => (pattern.as_str(), ""),
+            };
+            if pattern.contains('/') {
+                format!(":(exclude,glob){pattern}{suffix}")
+            } else {
+                format!(":(exclude,glob)**/{pattern}{suffix}")
+            }
+        })
+        .collect()
+}
+
+/// Agent worktrees are on `orchestrix/<run>/<agent>` branches, which are
+/// merged back when the agent finishes.
+const AGENT_BRANCH_PREFIX: &str = "orchestrix/";
+
+/// Files in `cwd` that a git command listing names (`diff --name-only`,
+/// `stash show --name-only`) reports.
+pub(crate) fn git_listed_paths(cwd: &Path, args: &[&str]) -> Vec<PathBuf> {
+    let Ok(output) = Command::new("git").arg("-C").arg(cwd).args(args).output() else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| cwd.join(line.trim()))
+        .collect()
+}
+
 fn run_git(policy: &PolicyEngine, cwd: &Path, args: &[&str]) -> Result<ToolCallOutput, ToolError> {
+    run_git_with_env(policy, cwd, args, &[])
+}
+
+fn run_git_with_env(
+    policy: &PolicyEngine,
+    cwd: &Path,
+    args: &[&str],
+    envs: &[(&str, &str)],
+) -> Result<ToolCallOutput, ToolError> {
     match policy.evaluate_path(cwd) {
         PolicyDecision::Allow => {}
         PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
@@ -23,6 +113,7 @@ fn run_git(policy: &PolicyEngine, cwd: &Path, args: &[&str]) -> Result<ToolCallO
         .arg("-C")
         .arg(cwd)
         .args(args)
+        .envs(envs.iter().copied())
         .output()
         .map_err(|e| ToolError::Execution(format!("git failed: {e}")))?;
 
@@ -70,7 +161,7 @@ impl Tool for GitDiffTool {
     fn descriptor(&self) -> ToolDescriptor {
         ToolDescriptor {
             name: "git.diff".into(),
-            description: "Run git diff. Shows unstaged changes in the current worktree. Pass {\"staged\": true} to see staged changes.".into(),
+            description: "Run git diff. Shows unstaged changes in the current worktree. Pass {\"staged\": true} to see staged changes, `from` (and optionally `to`) to diff revisions such as {\"from\": \"main\", \"to\": \"HEAD\"}, `paths` to limit the diff to some files, and {\"stat\": true} for a diffstat.".into(),
             input_schema: schema_for_type::<GitDiffArgs>(),
             output_schema: None,
         }
@@ -85,11 +176,33 @@ impl Tool for GitDiffTool {
         let args: GitDiffArgs = serde_json::from_value(input)
             .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
 
+        let exclusions = secret_exclusions(policy);
+        let mut git_args = vec!["diff"];
         if args.staged.unwrap_or(false) {
-            run_git(policy, cwd, &["diff", "--cached"])
-        } else {
-            run_git(policy, cwd, &["diff"])
+            git_args.push("--cached");
         }
+        if args.stat.unwrap_or(false) {
+            git_args.push("--stat");
+        }
+        match (args.from.as_deref(), args.to.as_deref()) {
+            (Some(from), to) => {
+                git_args.push(revision_arg(from)?);
+                if let Some(to) = to {
+                    git_args.push(revision_arg(to)?);
+                }
+            }
+            (None, Some(_)) => {
+                return Err(ToolError::InvalidInput("`to` requires `from`".to_string()))
+            }
+            (None, None) => {}
+        }
+        let paths = args.paths.unwrap_or_default();
+        git_args.push("--");
+        for path in &paths {
+            git_args.push(read_path(policy, cwd, path)?);
+        }
+        git_args.extend(exclusions.iter().map(String::as_str));
+        run_git(policy, cwd, &git_args)
     }
 }
 
@@ -115,6 +228,20 @@ impl Tool for GitApplyPatchTool {
         let args: GitApplyPatchArgs = serde_json::from_value(input)
             .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
 
+        let targets: Vec<_> = patch_target_paths(&args.patch)
+            .into_iter()
+            .map(|target| cwd.join(target))
+            .collect();
+        for target in &targets {
+            match policy.evaluate_write(target) {
+                PolicyDecision::Allow => {}
+                PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
+                PolicyDecision::NeedsApproval { scope, reason } => {
+                    return Err(ToolError::ApprovalRequired { scope, reason })
+                }
+            }
+        }
+
         let patch_path = cwd.join(".orchestrix").join("patch.diff");
         if let Some(parent) = patch_path.parent() {
             std::fs::create_dir_all(parent).map_err(|e| ToolError::Execution(e.to_string()))?;
@@ -122,8 +249,34 @@ impl Tool for GitApplyPatchTool {
         std::fs::write(&patch_path, &args.patch)
             .map_err(|e| ToolError::Execution(e.to_string()))?;
 
-        run_git(policy, cwd, &["apply", &patch_path.to_string_lossy()])
+        let mut output = run_git(policy, cwd, &["apply", &patch_path.to_string_lossy()])?;
+        if let Some(record) = policy.path_classes_record(&targets) {
+            output.data["policy"] = record;
+        }
+        Ok(output)
+    }
+}
+
+/// Files a unified diff creates, modifies, renames or deletes.
+pub(crate) fn patch_target_paths(patch: &str) -> Vec<&str> {
+    let mut targets = Vec::new();
+    for line in patch.lines() {
+        let path = if let Some(rest) = line.strip_prefix("+++ ") {
+            rest.strip_prefix("b/").unwrap_or(rest)
+        } else if let Some(rest) = line.strip_prefix("--- ") {
+            rest.strip_prefix("a/").unwrap_or(rest)
+        } else if let Some(rest) = line.strip_prefix("rename to ") {
+            rest
+        } else {
+            continue;
+        };
+        // Drop a trailing timestamp (`--- file\t2024-01-01 ...`)
+        let path = path.split('\t').next().unwrap_or(path).trim();
+        if path != "/dev/null" && !path.is_empty() && !targets.contains(&path) {
+            targets.push(path);
+        }
     }
+    targets
 }
 
 /// Tool for committing changes.
@@ -207,3 +360,583 @@ impl Tool for GitLogTool {
         run_git(policy, cwd, &["log", "--oneline", &count_str])
     }
 }
+
+/// Tool for line-by-line authorship of a file.
+pub struct GitBlameTool;
+
+impl Tool for GitBlameTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "git.blame".into(),
+            description: "Show which commit last changed each line of a file, with author, date and commit summary. Limit to a range with start_line/end_line; pass `revision` to blame an older version.".into(),
+            input_schema: schema_for_type::<GitBlameArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: GitBlameArgs = serde_json::from_value(input)
+            .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
+
+        let path = read_path(policy, cwd, &args.path)?;
+        let range = match (args.start_line, args.end_line) {
+            (None, None) => None,
+            (start, end) => {
+                let start = start.unwrap_or(1).max(1);
+                Some(match end {
+                    Some(end) if end < start => {
+                        return Err(ToolError::InvalidInput(
+                            "end_line must not be before start_line".to_string(),
+                        ))
+                    }
+                    Some(end) => format!("{start},{end}"),
+                    None => format!("{start},"),
+                })
+            }
+        };
+
+        let mut git_args = vec!["blame", "--line-porcelain"];
+        if let Some(range) = range.as_deref() {
+            git_args.extend(["-L", range]);
+        }
+        if let Some(revision) = args.revision.as_deref() {
+            git_args.push(revision_arg(revision)?);
+        }
+        git_args.extend(["--", path]);
+
+        let mut output = run_git(policy, cwd, &git_args)?;
+        if output.ok {
+            let stdout = output.data["stdout"].as_str().unwrap_or_default();
+            output.data = serde_json::json!({
+                "path": path,
+                "lines": parse_blame(stdout),
+            });
+        }
+        Ok(output)
+    }
+}
+
+/// One line of `git blame --line-porcelain` output.
+#[derive(Debug, Serialize)]
+struct BlameLine {
+    line: u32,
+    commit: String,
+    author: String,
+    date: Option<String>,
+    summary: String,
+    content: String,
+}
+
+fn parse_blame(porcelain: &str) -> Vec<BlameLine> {
+    let mut lines = Vec::new();
+    let (mut commit, mut line) = ("", 0);
+    let (mut author, mut date, mut summary) = ("", None, "");
+    for entry in porcelain.lines() {
+        if let Some(content) = entry.strip_prefix('\t') {
+            lines.push(BlameLine {
+                line,
+                commit: commit.chars().take(12).collect(),
+                author: author.to_string(),
+                date: date.clone(),
+                summary: summary.to_string(),
+                content: content.to_string(),
+            });
+            continue;
+        }
+        let (key, value) = entry.split_once(' ').unwrap_or((entry, ""));
+        match key {
+            "author" => author = value,
+            "author-time" => {
+                date = value
+                    .parse()
+                    .ok()
+                    .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                    .map(|time| time.to_rfc3339());
+            }
+            "summary" => summary = value,
+            // Header: `<sha> <original line> <final line> [<group size>]`
+            _ if key.len() >= 40 && key.chars().all(|c| c.is_ascii_hexdigit()) => {
+                commit = key;
+                line = value
+                    .split(' ')
+                    .nth(1)
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+    lines
+}
+
+/// Tool for showing a commit or a file at a revision.
+pub struct GitShowTool;
+
+impl Tool for GitShowTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "git.show".into(),
+            description: "Show a commit's message and patch (default: HEAD). Pass `path` to get that file's content at the revision instead, or {\"stat\": true} for a diffstat.".into(),
+            input_schema: schema_for_type::<GitShowArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: GitShowArgs = serde_json::from_value(input)
+            .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
+
+        let revision = args.revision.as_deref().unwrap_or("HEAD");
+        // Accept `rev:path` as shorthand for `path`, so it gets the same checks
+        let (revision, path) = match revision.split_once(':') {
+            Some((rev, path)) if args.path.is_none() && !path.is_empty() => (rev, Some(path)),
+            _ => (revision, args.path.as_deref()),
+        };
+        let revision = revision_arg(revision)?;
+        if let Some(path) = path {
+            let path = read_path(policy, cwd, path)?;
+            let object = format!("{revision}:{}", path.replace('\\', "/"));
+            let mut output = run_git(policy, cwd, &["show", &object])?;
+            // Secret files reach the model with their values masked, as in fs.read
+            if output.ok && policy.path_class(&cwd.join(path)) == Some(PathClass::Secret) {
+                let mut redactor = SecretRedactor::default();
+                let content = output.data["stdout"].as_str().unwrap_or_default();
+                let masked: Vec<String> = content
+                    .lines()
+                    .map(|line| redactor.redact_line(line))
+                    .collect();
+                output.data["stdout"] = masked.join("\n").into();
+            }
+            return Ok(output);
+        }
+        let exclusions = secret_exclusions(policy);
+        let mut git_args = vec!["show"];
+        if args.stat.unwrap_or(false) {
+            git_args.push("--stat");
+        }
+        git_args.extend([revision, "--"]);
+        git_args.extend(exclusions.iter().map(String::as_str));
+        run_git(policy, cwd, &git_args)
+    }
+}
+
+/// Tool for listing, creating and switching branches.
+pub struct GitBranchTool;
+
+impl Tool for GitBranchTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "git.branch".into(),
+            description: "Manage branches. Actions: list (default; branches with their commit and the current one), create (new branch `name` at `start_point`, default HEAD), switch (check out branch `name`).".into(),
+            input_schema: schema_for_type::<GitBranchArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: GitBranchArgs = serde_json::from_value(input)
+            .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
+
+        let name = || {
+            args.name.as_deref().map(revision_arg).unwrap_or_else(|| {
+                Err(ToolError::InvalidInput(
+                    "name is required for this action".to_string(),
+                ))
+            })
+        };
+        match args.action.as_deref().unwrap_or("list") {
+            "list" => {
+                let mut output = run_git(
+                    policy,
+                    cwd,
+                    &[
+                        "branch",
+                        "--list",
+                        "--format=%(HEAD)%09%(refname:short)%09%(objectname:short)%09%(upstream:short)",
+                    ],
+                )?;
+                if output.ok {
+                    let stdout = output.data["stdout"].as_str().unwrap_or_default();
+                    let branches: Vec<_> = stdout
+                        .lines()
+                        .filter_map(|line| {
+                            let mut fields = line.split('\t');
+                            let current = fields.next()? == "*";
+                            let name = fields.next()?;
+                            let commit = fields.next().unwrap_or_default();
+                            let upstream = fields.next().filter(|u| !u.is_empty());
+                            Some(serde_json::json!({
+                                "name": name,
+                                "commit": commit,
+                                "current": current,
+                                "upstream": upstream,
+                            }))
+                        })
+                        .collect();
+                    let current = branches
+                        .iter()
+                        .find(|branch| branch["current"] == true)
+                        .map(|branch| branch["name"].clone());
+                    output.data = serde_json::json!({
+                        "branches": branches,
+                        "current": current,
+                    });
+                }
+                Ok(output)
+            }
+            "create" => {
+                let name = name()?;
+                match args.start_point.as_deref() {
+                    Some(start) => run_git(policy, cwd, &["branch", name, revision_arg(start)?]),
+                    None => run_git(policy, cwd, &["branch", name]),
+                }
+            }
+            "switch" => {
+                let name = name()?;
+                let current = run_git(policy, cwd, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+                let current = current.data["stdout"].as_str().unwrap_or_default().trim();
+                if current.starts_with(AGENT_BRANCH_PREFIX) {
+                    return Err(ToolError::PolicyDenied(format!(
+                        "cannot switch away from {current}: agent worktrees stay on their branch so it can be merged back"
+                    )));
+                }
+                run_git(policy, cwd, &["switch", name])
+            }
+            other => Err(ToolError::InvalidInput(format!(
+                "unknown action: {other} (expected list, create or switch)"
+            ))),
+        }
+    }
+}
+
+/// Tool for stashing uncommitted changes.
+pub struct GitStashTool;
+
+impl Tool for GitStashTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "git.stash".into(),
+            description: "Stash uncommitted changes. Actions: list (default), push (with optional `message` and `include_untracked`), pop, apply, drop (entry `index`, default 0 = latest).".into(),
+            input_schema: schema_for_type::<GitStashArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: GitStashArgs = serde_json::from_value(input)
+            .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
+
+        match args.action.as_deref().unwrap_or("list") {
+            "list" => run_git(policy, cwd, &["stash", "list"]),
+            "push" => {
+                let mut git_args = vec!["stash", "push"];
+                if args.include_untracked.unwrap_or(false) {
+                    git_args.push("--include-untracked");
+                }
+                if let Some(message) = args.message.as_deref() {
+                    git_args.extend(["-m", message]);
+                }
+                // Stashing creates commits, which need an identity
+                run_git_with_env(policy, cwd, &git_args, ORCHESTRIX_IDENTITY)
+            }
+            action @ ("pop" | "apply" | "drop") => {
+                let entry = format!("stash@{{{}}}", args.index.unwrap_or(0));
+                run_git(policy, cwd, &["stash", action, &entry])
+            }
+            other => Err(ToolError::InvalidInput(format!(
+                "unknown action: {other} (expected list, push, pop, apply or drop)"
+            ))),
+        }
+    }
+}
+
+/// Tool for inspecting unresolved merge conflicts.
+pub struct GitConflictsTool;
+
+impl Tool for GitConflictsTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "git.conflicts".into(),
+            description: "List files with unresolved merge conflicts and their conflict hunks: 1-based start_line/end_line of each hunk (markers included) with the ours, base and theirs content. Resolve a hunk by replacing those lines with fs.patch or fs.write, then git.commit.".into(),
+            input_schema: schema_for_type::<GitConflictsArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: GitConflictsArgs = serde_json::from_value(input)
+            .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
+
+        let mut output = run_git(policy, cwd, &["ls-files", "--unmerged", "-z"])?;
+        if !output.ok {
+            return Ok(output);
+        }
+
+        // `<mode> <blob> <stage>\t<path>`, one line per stage present
+        let mut stages: BTreeMap<String, ConflictStages> = BTreeMap::new();
+        let stdout = output.data["stdout"].as_str().unwrap_or_default();
+        for record in stdout.split('\0') {
+            let Some((meta, path)) = record.split_once('\t') else {
+                continue;
+            };
+            let mut fields = meta.split_whitespace().skip(1);
+            let (Some(blob), Some(stage)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let entry = stages.entry(path.to_string()).or_default();
+            match stage {
+                "1" => entry.base = Some(blob.to_string()),
+                "2" => entry.ours = Some(blob.to_string()),
+                "3" => entry.theirs = Some(blob.to_string()),
+                _ => {}
+            }
+        }
+        if let Some(only) = args.path.as_deref() {
+            let only = only.replace('\\', "/");
+            stages.retain(|path, _| *path == only);
+        }
+
+        let mut files = Vec::new();
+        for (path, stages) in &stages {
+            read_path(policy, cwd, path)?;
+            let text = std::fs::read_to_string(cwd.join(path)).unwrap_or_default();
+            let mut hunks = parse_conflict_hunks(&text);
+            if hunks.iter().any(|hunk| hunk.base.is_none()) {
+                fill_conflict_bases(cwd, stages, &mut hunks);
+            }
+            files.push(serde_json::json!({
+                "path": path,
+                "status": stages.status(),
+                "hunks": hunks,
+            }));
+        }
+        output.data = serde_json::json!({ "files": files });
+        Ok(output)
+    }
+}
+
+/// Index blobs of a conflicted file, by merge stage.
+#[derive(Debug, Default)]
+struct ConflictStages {
+    base: Option<String>,
+    ours: Option<String>,
+    theirs: Option<String>,
+}
+
+impl ConflictStages {
+    fn status(&self) -> &'static str {
+        match (
+            self.base.is_some(),
+            self.ours.is_some(),
+            self.theirs.is_some(),
+        ) {
+            (true, true, true) => "both_modified",
+            (false, true, true) => "both_added",
+            (_, false, true) => "deleted_by_us",
+            (_, true, false) => "deleted_by_them",
+            _ => "both_deleted",
+        }
+    }
+}
+
+/// One `<<<<<<<` ... `>>>>>>>` region of a conflicted file.
+#[derive(Debug, Default, Serialize)]
+struct ConflictHunk {
+    start_line: usize,
+    end_line: usize,
+    ours_label: String,
+    theirs_label: String,
+    ours: String,
+    base: Option<String>,
+    theirs: String,
+}
+
+/// Text after a conflict `marker` at the start of `line`.
+fn conflict_marker<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(marker)?;
+    (rest.is_empty() || rest.starts_with(' ')).then_some(rest.trim())
+}
+
+fn parse_conflict_hunks(text: &str) -> Vec<ConflictHunk> {
+    #[derive(PartialEq)]
+    enum Section {
+        Outside,
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    let mut hunks = Vec::new();
+    let mut hunk = ConflictHunk::default();
+    let mut section = Section::Outside;
+    for (idx, line) in text.lines().enumerate() {
+        if section == Section::Outside {
+            if let Some(label) = conflict_marker(line, "<<<<<<<") {
+                hunk = ConflictHunk {
+                    start_line: idx + 1,
+                    ours_label: label.to_string(),
+                    ..Default::default()
+                };
+                section = Section::Ours;
+            }
+            continue;
+        }
+        if section == Section::Ours && conflict_marker(line, "|||||||").is_some() {
+            hunk.base = Some(String::new());
+            section = Section::Base;
+        } else if section != Section::Theirs && conflict_marker(line, "=======").is_some() {
+            section = Section::Theirs;
+        } else if let Some(label) =
+            conflict_marker(line, ">>>>>>>").filter(|_| section == Section::Theirs)
+        {
+            hunk.end_line = idx + 1;
+            hunk.theirs_label = label.to_string();
+            hunks.push(std::mem::take(&mut hunk));
+            section = Section::Outside;
+        } else {
+            let content = match section {
+                Section::Ours => &mut hunk.ours,
+                Section::Base => hunk.base.get_or_insert_with(String::new),
+                _ => &mut hunk.theirs,
+            };
+            content.push_str(line);
+            content.push('\n');
+        }
+    }
+    hunks
+}
+
+/// Recovers the base side of hunks written without it (the default `merge`
+/// conflict style) by re-merging the index stages in `zdiff3` style and
+/// matching hunks on their ours and theirs content.
+fn fill_conflict_bases(cwd: &Path, stages: &ConflictStages, hunks: &mut [ConflictHunk]) {
+    let (Some(base), Some(ours), Some(theirs)) = (&stages.base, &stages.ours, &stages.theirs)
+    else {
+        return;
+    };
+    let scratch = std::env::temp_dir().join(format!("orchestrix-merge-{}", uuid::Uuid::new_v4()));
+    if std::fs::create_dir_all(&scratch).is_err() {
+        return;
+    }
+    let mut files = Vec::new();
+    for (name, blob) in [("ours", ours), ("base", base), ("theirs", theirs)] {
+        let Ok(content) = Command::new("git")
+            .arg("-C")
+            .arg(cwd)
+            .args(["cat-file", "blob", blob.as_str()])
+            .output()
+        else {
+            break;
+        };
+        let file = scratch.join(name);
+        if std::fs::write(&file, &content.stdout).is_err() {
+            break;
+        }
+        files.push(file);
+    }
+
+    if files.len() == 3 {
+        // `merge-file` exits with the conflict count, or above 127 on error
+        // (older git does not know `--zdiff3`)
+        for style in ["--zdiff3", "--diff3"] {
+            let Ok(merged) = Command::new("git")
+                .arg("merge-file")
+                .args(["-p", style])
+                .args(&files)
+                .output()
+            else {
+                break;
+            };
+            if merged.status.code().is_some_and(|code| code <= 127) {
+                let merged = String::from_utf8_lossy(&merged.stdout);
+                let with_bases = parse_conflict_hunks(&merged);
+                for hunk in hunks.iter_mut().filter(|hunk| hunk.base.is_none()) {
+                    hunk.base = with_bases
+                        .iter()
+                        .find(|other| other.ours == hunk.ours && other.theirs == hunk.theirs)
+                        .and_then(|other| other.base.clone());
+                }
+                break;
+            }
+        }
+    }
+    let _ = std::fs::remove_dir_all(&scratch);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_conflict_hunks_with_and_without_base() {
+        let text = "\
+top
+<<<<<<< HEAD
+ours line
+=======
+theirs line
+>>>>>>> feature
+middle
+<<<<<<< ours
+a
+||||||| base
+b
+=======
+c
+>>>>>>> theirs
+";
+        let hunks = parse_conflict_hunks(text);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].start_line, hunks[0].end_line), (2, 6));
+        assert_eq!(hunks[0].ours_label, "HEAD");
+        assert_eq!(hunks[0].theirs_label, "feature");
+        assert_eq!(hunks[0].ours, "ours line\n");
+        assert_eq!(hunks[0].theirs, "theirs line\n");
+        assert_eq!(hunks[0].base, None);
+        assert_eq!((hunks[1].start_line, hunks[1].end_line), (8, 14));
+        assert_eq!(hunks[1].base.as_deref(), Some("b\n"));
+        assert_eq!(hunks[1].theirs, "c\n");
+    }
+
+    #[test]
+    fn parses_line_porcelain_blame() {
+        let sha = "a".repeat(40);
+        let porcelain = format!(
+            "{sha} 1 1 2\nauthor Ada\nauthor-time 0\nsummary first\nfilename f\n\tone\n\
+             {sha} 2 2\nauthor Ada\nauthor-time 0\nsummary first\nfilename f\n\ttwo\n"
+        );
+        let lines = parse_blame(&porcelain);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].line, 2);
+        assert_eq!(lines[1].commit, "aaaaaaaaaaaa");
+        assert_eq!(lines[1].author, "Ada");
+        assert_eq!(lines[1].summary, "first");
+        assert_eq!(lines[1].content, "two");
+        assert!(lines[0].date.as_deref().unwrap().starts_with("1970-01-01"));
+    }
+}
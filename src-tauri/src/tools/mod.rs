@@ -15,7 +15,7 @@
 //!
 //! # Module Structure
 //!
-//! - `types`: Core types (Tool trait, ToolCallInput/Output, ToolError)
+//! - `types`: Core types (Tool/AsyncTool traits, ToolContext, ToolCallInput/Output, ToolError)
 //! - `registry`: ToolRegistry for managing and invoking tools
 //! - `fs`: Filesystem tools (read, write, list)
 //! - `patch`: Structured file patching (apply-patch format)
@@ -23,6 +23,8 @@
 //! - `file_search`: Fuzzy filename search (nucleo-based)
 //! - `cmd`: Command execution tools
 //! - `git`: Git repository tools
+//! - `code`: Code intelligence tools backed by language servers
+//! - `syntax`: Tree-sitter outline and structural search tools
 //! - `agent`: Agent management tools (todo, mode switching)
 //! - `skills`: Skills management tools
 //! - `dev_server`: Development server management tools
@@ -32,30 +34,34 @@
 //!
 //! 1. Implement the tool logic in the appropriate submodule
 //! 2. Register in the ToolRegistry (in `registry.rs`)
-//! 3. Add to policy allowlist if needed
+//! 3. Add a command rule in `policy/commands.rs` if needed
 //! 4. Update tool descriptors for LLM
 
 // Public exports
 pub use registry::ToolRegistry;
 pub use semantic_search::set_semantic_index_service;
 #[allow(unused_imports)]
-pub use types::{ToolCallInput, ToolCallOutput, ToolError};
+pub use types::{ToolCallInput, ToolCallOutput, ToolContext, ToolError, ToolProgress};
 
 // Submodules
 mod agent;
 pub mod args;
 pub mod canvas;
 mod cmd;
+mod code;
 pub mod dev_server;
-mod file_search;
+pub(crate) mod file_search;
 mod fs;
-mod git;
+pub mod git;
 mod memory;
 pub mod patch;
+pub mod process;
 mod registry;
+pub mod sandbox;
 mod search;
 mod semantic_search;
 mod skills;
+mod syntax;
 mod types;
 mod web_snapshot;
 
@@ -14,9 +14,10 @@
 mod parser;
 mod seek;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::core::tool::ToolDescriptor;
+use crate::policy::paths::PathClass;
 use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::tools::args::{schema_for_type, FsPatchArgs};
 use crate::tools::types::{Tool, ToolCallOutput, ToolError};
@@ -59,9 +60,10 @@ impl Tool for FsPatchTool {
         }
 
         // Check all paths against policy before making any changes
+        let mut touched: Vec<PathBuf> = Vec::new();
         for hunk in &hunks {
             let path = hunk.resolve_path(cwd);
-            match policy.evaluate_path(&path) {
+            match policy.evaluate_write(&path) {
                 PolicyDecision::Allow => {}
                 PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
                 PolicyDecision::NeedsApproval { scope, reason } => {
@@ -75,14 +77,16 @@ impl Tool for FsPatchTool {
             } = hunk
             {
                 let move_target = cwd.join(mp);
-                match policy.evaluate_path(&move_target) {
+                match policy.evaluate_write(&move_target) {
                     PolicyDecision::Allow => {}
                     PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
                     PolicyDecision::NeedsApproval { scope, reason } => {
                         return Err(ToolError::ApprovalRequired { scope, reason })
                     }
                 }
+                touched.push(move_target);
             }
+            touched.push(path);
         }
 
         // Apply the patch
@@ -133,7 +137,14 @@ impl Tool for FsPatchTool {
                     // Generate unified diff for transparency / audit trail
                     let text_diff = similar::TextDiff::from_lines(&original, &new_contents);
                     let unified = text_diff.unified_diff().context_radius(3).to_string();
-                    if !unified.is_empty() {
+                    if policy.path_class(&full) == Some(PathClass::Secret) {
+                        // Keep secret values out of the tool output
+                        diffs.push(format!(
+                            "--- {}\n+++ {}\n[diff of secret file omitted]",
+                            path.display(),
+                            path.display()
+                        ));
+                    } else if !unified.is_empty() {
                         diffs.push(format!(
                             "--- {}\n+++ {}\n{}",
                             path.display(),
@@ -176,15 +187,20 @@ impl Tool for FsPatchTool {
 
         let summary = build_summary(&added, &modified, &deleted);
 
+        let mut data = serde_json::json!({
+            "summary": summary,
+            "added": added,
+            "modified": modified,
+            "deleted": deleted,
+            "diffs": diffs,
+        });
+        if let Some(record) = policy.path_classes_record(&touched) {
+            data["policy"] = record;
+        }
+
         Ok(ToolCallOutput {
             ok: true,
-            data: serde_json::json!({
-                "summary": summary,
-                "added": added,
-                "modified": modified,
-                "deleted": deleted,
-                "diffs": diffs,
-            }),
+            data,
             error: None,
         })
     }
@@ -0,0 +1,391 @@
+//! Child processes started by `cmd.exec`.
+//!
+//! Commands run in their own process group so a timeout or a cancelled task
+//! kills everything they spawned. Output is streamed into a head/tail
+//! capture for the tool result and into a full log file, which is kept only
+//! when the inline output had to be truncated. The raw output is also
+//! forwarded to the tool call's progress reporter as it arrives, and the
+//! call's cancellation token kills the command.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::tools::types::ProgressReporter;
+
+/// Bytes kept from the start of each stream in the tool output.
+const HEAD_BYTES: usize = 4 * 1024;
+
+/// Bytes kept from the end of each stream; errors usually show up last.
+const TAIL_BYTES: usize = 12 * 1024;
+
+/// Full logs stop growing past this size.
+const MAX_LOG_BYTES: u64 = 64 * 1024 * 1024;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// How long to wait for the output pipes after the process is gone; a
+/// grandchild that left the process group can keep them open.
+const PIPE_DRAIN_GRACE: Duration = Duration::from_secs(2);
+
+/// Why a command stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitReason {
+    Exited,
+    /// Killed by a signal that did not come from us.
+    Signaled,
+    TimedOut,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Raw bytes read from a running command, in arrival order per stream.
+#[derive(Debug)]
+pub struct OutputChunk {
+    pub stream: OutputStream,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FullLog {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+#[derive(Debug)]
+pub struct ExecOutcome {
+    pub stdout: String,
+    pub stderr: String,
+    pub code: Option<i32>,
+    pub exit_reason: ExitReason,
+    pub duration_ms: u64,
+    /// Set when stdout or stderr was truncated.
+    pub full_log: Option<FullLog>,
+}
+
+pub struct ExecLimits {
+    pub timeout: Duration,
+    /// Directory for the full log; no log is written when `None`.
+    pub log_dir: Option<PathBuf>,
+    /// Kills the command when cancelled.
+    pub cancel: CancellationToken,
+    /// Receives stdout and stderr while the command runs.
+    pub progress: ProgressReporter,
+}
+
+/// Kill a process and everything in its process group (its tree on Windows).
+fn kill_process_tree(pid: u32) {
+    #[cfg(unix)]
+    let mut kill = {
+        let mut command = Command::new("kill");
+        command.args(["-KILL", "--", &format!("-{pid}")]);
+        command
+    };
+    #[cfg(windows)]
+    let mut kill = {
+        let mut command = Command::new("taskkill");
+        command.args(["/T", "/F", "/PID", &pid.to_string()]);
+        command
+    };
+    let _ = kill.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
+
+/// Start `command` in its own process group and wait for it within `limits`.
+///
+/// Spawn errors are returned as-is so callers can react to `NotFound`.
+pub fn run_with_limits(mut command: Command, limits: &ExecLimits) -> std::io::Result<ExecOutcome> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    isolate_process_group(&mut command);
+
+    let started = Instant::now();
+    let mut child = command.spawn()?;
+    let log = Arc::new(Mutex::new(LogFile::create(limits.log_dir.as_deref())));
+    let stdout = Arc::new(Mutex::new(StreamCapture::default()));
+    let stderr = Arc::new(Mutex::new(StreamCapture::default()));
+    // Readers hand chunks to this thread rather than to the reporter, so a
+    // reader kept alive by a detached grandchild cannot outlive the call.
+    let (live_tx, live_rx) = if limits.progress.is_active() {
+        let (tx, rx) = mpsc::channel();
+        (Some(tx), Some(rx))
+    } else {
+        (None, None)
+    };
+    let forward = |live_rx: &Option<Receiver<OutputChunk>>| {
+        if let Some(receiver) = live_rx {
+            for chunk in receiver.try_iter() {
+                limits.progress.output(chunk);
+            }
+        }
+    };
+
+    let mut readers = Vec::new();
+    if let Some(pipe) = child.stdout.take() {
+        readers.push(spawn_reader(
+            pipe,
+            OutputStream::Stdout,
+            stdout.clone(),
+            log.clone(),
+            live_tx.clone(),
+        ));
+    }
+    if let Some(pipe) = child.stderr.take() {
+        readers.push(spawn_reader(
+            pipe,
+            OutputStream::Stderr,
+            stderr.clone(),
+            log.clone(),
+            live_tx,
+        ));
+    }
+
+    let (status, killed_for) = loop {
+        // A cancelled process may already be gone by the time we look
+        let cancelled_reason = || {
+            limits
+                .cancel
+                .is_cancelled()
+                .then_some(ExitReason::Cancelled)
+        };
+        match child.try_wait() {
+            Ok(Some(status)) => break (Some(status), cancelled_reason()),
+            Ok(None) => {}
+            Err(_) => break (child.wait().ok(), cancelled_reason()),
+        }
+        let reason = cancelled_reason()
+            .or_else(|| (started.elapsed() >= limits.timeout).then_some(ExitReason::TimedOut));
+        if let Some(reason) = reason {
+            kill_process_tree(child.id());
+            let _ = child.kill();
+            break (child.wait().ok(), Some(reason));
+        }
+        forward(&live_rx);
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let drain_deadline = Instant::now() + PIPE_DRAIN_GRACE;
+    while readers.iter().any(|reader| !reader.is_finished()) && Instant::now() < drain_deadline {
+        forward(&live_rx);
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    forward(&live_rx);
+    for reader in readers {
+        if reader.is_finished() {
+            let _ = reader.join();
+        }
+    }
+
+    let code = status.and_then(|status| status.code());
+    let exit_reason = killed_for.unwrap_or(if code.is_some() {
+        ExitReason::Exited
+    } else {
+        ExitReason::Signaled
+    });
+
+    let stdout = stdout.lock().map(|c| c.clone()).unwrap_or_default();
+    let stderr = stderr.lock().map(|c| c.clone()).unwrap_or_default();
+    let truncated = stdout.truncated() || stderr.truncated();
+    let full_log = log.lock().ok().and_then(|mut log| log.finish(truncated));
+    let log_path = full_log.as_ref().map(|log| log.path.as_path());
+
+    Ok(ExecOutcome {
+        stdout: stdout.render(log_path),
+        stderr: stderr.render(log_path),
+        code,
+        exit_reason,
+        duration_ms: started.elapsed().as_millis() as u64,
+        full_log,
+    })
+}
+
+#[cfg(unix)]
+fn isolate_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn isolate_process_group(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+}
+
+fn spawn_reader(
+    mut pipe: impl Read + Send + 'static,
+    stream: OutputStream,
+    capture: Arc<Mutex<StreamCapture>>,
+    log: Arc<Mutex<LogFile>>,
+    live: Option<Sender<OutputChunk>>,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut buffer = [0u8; 8192];
+        loop {
+            let read = match pipe.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            if let Ok(mut capture) = capture.lock() {
+                capture.push(&buffer[..read]);
+            }
+            if let Ok(mut log) = log.lock() {
+                log.write(&buffer[..read]);
+            }
+            if let Some(live) = live.as_ref() {
+                let _ = live.send(OutputChunk {
+                    stream,
+                    bytes: buffer[..read].to_vec(),
+                });
+            }
+        }
+    })
+}
+
+/// First `HEAD_BYTES` and last `TAIL_BYTES` of a stream.
+#[derive(Debug, Clone, Default)]
+struct StreamCapture {
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    total: u64,
+}
+
+impl StreamCapture {
+    fn push(&mut self, mut chunk: &[u8]) {
+        self.total += chunk.len() as u64;
+        if self.head.len() < HEAD_BYTES {
+            let take = chunk.len().min(HEAD_BYTES - self.head.len());
+            self.head.extend_from_slice(&chunk[..take]);
+            chunk = &chunk[take..];
+        }
+        self.tail.extend(chunk);
+        let excess = self.tail.len().saturating_sub(TAIL_BYTES);
+        self.tail.drain(..excess);
+    }
+
+    fn omitted(&self) -> u64 {
+        self.total - (self.head.len() + self.tail.len()) as u64
+    }
+
+    fn truncated(&self) -> bool {
+        self.omitted() > 0
+    }
+
+    fn render(&self, log_path: Option<&Path>) -> String {
+        let mut text = String::from_utf8_lossy(&self.head).into_owned();
+        if self.truncated() {
+            let location = match log_path {
+                Some(path) => format!("; full output in {}", path.display()),
+                None => String::new(),
+            };
+            text.push_str(&format!(
+                "\n... [{} bytes omitted{location}] ...\n",
+                self.omitted()
+            ));
+        }
+        let (front, back) = self.tail.as_slices();
+        let tail = [front, back].concat();
+        text.push_str(&String::from_utf8_lossy(&tail));
+        text
+    }
+}
+
+/// Both streams interleaved as they arrive, like a terminal would show them.
+struct LogFile {
+    file: Option<File>,
+    path: Option<PathBuf>,
+    written: u64,
+}
+
+impl LogFile {
+    fn create(dir: Option<&Path>) -> Self {
+        let created = dir.and_then(|dir| {
+            std::fs::create_dir_all(dir).ok()?;
+            let path = dir.join(format!("cmd-{}.log", Uuid::new_v4()));
+            let file = File::create(&path).ok()?;
+            Some((file, path))
+        });
+        match created {
+            Some((file, path)) => Self {
+                file: Some(file),
+                path: Some(path),
+                written: 0,
+            },
+            None => Self {
+                file: None,
+                path: None,
+                written: 0,
+            },
+        }
+    }
+
+    fn write(&mut self, chunk: &[u8]) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        let room = MAX_LOG_BYTES.saturating_sub(self.written) as usize;
+        let chunk = &chunk[..chunk.len().min(room)];
+        if !chunk.is_empty() && file.write_all(chunk).is_ok() {
+            self.written += chunk.len() as u64;
+        }
+    }
+
+    /// Keep the log when it is needed, otherwise delete it.
+    fn finish(&mut self, keep: bool) -> Option<FullLog> {
+        self.file.take();
+        let path = self.path.take()?;
+        if keep {
+            Some(FullLog {
+                path,
+                bytes: self.written,
+            })
+        } else {
+            let _ = std::fs::remove_file(&path);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_keeps_head_and_tail() {
+        let mut capture = StreamCapture::default();
+        capture.push(&vec![b'a'; HEAD_BYTES + 100]);
+        capture.push(&vec![b'b'; TAIL_BYTES]);
+        capture.push(b"end");
+
+        assert_eq!(capture.omitted(), 103);
+        let text = capture.render(Some(Path::new("/tmp/cmd.log")));
+        assert!(text.starts_with(&"a".repeat(HEAD_BYTES)));
+        assert!(text.contains("[103 bytes omitted; full output in /tmp/cmd.log]"));
+        assert!(text.ends_with("bend"));
+
+        let mut small = StreamCapture::default();
+        small.push(b"hello\n");
+        assert!(!small.truncated());
+        assert_eq!(small.render(None), "hello\n");
+    }
+}
@@ -5,13 +5,17 @@
 //! - MCP tools loaded from external servers
 //!
 //! Tools are accessed by name and invoked with policy-checked permissions.
+//! Synchronous tools run on the blocking pool; async tools and MCP calls run
+//! on the caller's runtime and observe the call's cancellation token.
 
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::Path;
+use std::sync::Arc;
 
-use crate::core::mcp::{call_mcp_tool_by_server_and_name, load_mcp_tools_cache};
+use crate::core::mcp::{call_mcp_tool_async, load_mcp_tools_cache};
 use crate::core::tool::ToolDescriptor;
-use crate::policy::PolicyEngine;
+use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::tools::agent::{
     AgentAskUserTool, AgentCompleteTool, AgentCreatePresetTool, AgentMemoryUpsertTool,
     AgentTaskTool, CreateArtifactTool, RequestBuildModeTool, RequestPlanModeTool,
@@ -19,12 +23,18 @@ use crate::tools::agent::{
 };
 use crate::tools::canvas::{CanvasApplyOpsTool, CanvasReadStateTool};
 use crate::tools::cmd::CommandExecTool;
+use crate::tools::code::{
+    CodeDefinitionTool, CodeDiagnosticsTool, CodeHoverTool, CodeReferencesTool, CodeRenameTool,
+};
 use crate::tools::dev_server::{
     DevServerLogsTool, DevServerStartTool, DevServerStatusTool, DevServerStopTool,
 };
 use crate::tools::file_search::SearchFilesTool;
 use crate::tools::fs::{FsListTool, FsReadTool, FsWriteTool};
-use crate::tools::git::{GitApplyPatchTool, GitCommitTool, GitDiffTool, GitLogTool, GitStatusTool};
+use crate::tools::git::{
+    GitApplyPatchTool, GitBlameTool, GitBranchTool, GitCommitTool, GitConflictsTool, GitDiffTool,
+    GitLogTool, GitShowTool, GitStashTool, GitStatusTool,
+};
 use crate::tools::memory::{
     MemoryCompactTool, MemoryDeleteTool, MemoryListTool, MemoryReadTool, MemoryUpsertTool,
 };
@@ -34,109 +44,137 @@ use crate::tools::semantic_search::SearchEmbeddingsTool;
 use crate::tools::skills::{
     SkillsInstallTool, SkillsListInstalledTool, SkillsLoadTool, SkillsRemoveTool, SkillsSearchTool,
 };
-use crate::tools::types::{Tool, ToolCallInput, ToolCallOutput, ToolError};
+use crate::tools::syntax::{CodeOutlineTool, CodeSymbolsTool};
+use crate::tools::types::{AsyncTool, Tool, ToolCallInput, ToolCallOutput, ToolContext, ToolError};
 use crate::tools::web_snapshot::WebSnapshotTool;
 
+/// Built-in tools that only read, so calls to them can run concurrently.
+const READ_ONLY_TOOLS: &[&str] = &[
+    "fs.read",
+    "fs.list",
+    "search.rg",
+    "search.files",
+    "search.embeddings",
+    "git.status",
+    "git.diff",
+    "git.log",
+    "git.blame",
+    "git.show",
+    "git.conflicts",
+    "code.definition",
+    "code.references",
+    "code.hover",
+    "code.diagnostics",
+    "code.outline",
+    "code.symbols",
+    "memory.list",
+    "memory.read",
+    "skills.list_installed",
+    "skills.search",
+    "dev_server.status",
+    "dev_server.logs",
+];
+
+/// A built-in tool, by how it runs.
+#[derive(Clone)]
+enum RegisteredTool {
+    Blocking(Arc<dyn Tool>),
+    Async(Arc<dyn AsyncTool>),
+}
+
+impl RegisteredTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        match self {
+            Self::Blocking(tool) => tool.descriptor(),
+            Self::Async(tool) => tool.descriptor(),
+        }
+    }
+}
+
 /// Registry of all available tools.
 pub struct ToolRegistry {
-    tools: HashMap<String, Box<dyn Tool>>,
+    tools: HashMap<String, RegisteredTool>,
 }
 
 impl ToolRegistry {
     /// Creates a new registry with all built-in tools registered.
     pub fn default() -> Self {
-        let mut tools: HashMap<String, Box<dyn Tool>> = HashMap::new();
+        let mut tools = ToolMap::default();
 
         // Filesystem tools
-        tools.insert("fs.read".to_string(), Box::new(FsReadTool));
-        tools.insert("fs.write".to_string(), Box::new(FsWriteTool));
-        tools.insert("fs.list".to_string(), Box::new(FsListTool));
-        tools.insert("fs.patch".to_string(), Box::new(FsPatchTool));
+        tools.insert("fs.read", FsReadTool);
+        tools.insert("fs.write", FsWriteTool);
+        tools.insert("fs.list", FsListTool);
+        tools.insert("fs.patch", FsPatchTool);
 
         // Search tools
-        tools.insert("search.rg".to_string(), Box::new(SearchRgTool));
-        tools.insert("search.files".to_string(), Box::new(SearchFilesTool));
-        tools.insert(
-            "search.embeddings".to_string(),
-            Box::new(SearchEmbeddingsTool),
-        );
+        tools.insert("search.rg", SearchRgTool);
+        tools.insert("search.files", SearchFilesTool);
+        tools.insert_async("search.embeddings", SearchEmbeddingsTool);
 
         // Command execution
-        tools.insert("cmd.exec".to_string(), Box::new(CommandExecTool));
+        tools.insert_async("cmd.exec", CommandExecTool);
 
         // Canvas tools
-        tools.insert(
-            "diagram.read_graph".to_string(),
-            Box::new(CanvasReadStateTool),
-        );
-        tools.insert(
-            "diagram.apply_ops".to_string(),
-            Box::new(CanvasApplyOpsTool),
-        );
+        tools.insert("diagram.read_graph", CanvasReadStateTool);
+        tools.insert("diagram.apply_ops", CanvasApplyOpsTool);
 
         // Git tools
-        tools.insert("git.status".to_string(), Box::new(GitStatusTool));
-        tools.insert("git.diff".to_string(), Box::new(GitDiffTool));
-        tools.insert("git.apply_patch".to_string(), Box::new(GitApplyPatchTool));
-        tools.insert("git.commit".to_string(), Box::new(GitCommitTool));
-        tools.insert("git.log".to_string(), Box::new(GitLogTool));
+        tools.insert("git.status", GitStatusTool);
+        tools.insert("git.diff", GitDiffTool);
+        tools.insert("git.apply_patch", GitApplyPatchTool);
+        tools.insert("git.commit", GitCommitTool);
+        tools.insert("git.log", GitLogTool);
+        tools.insert("git.blame", GitBlameTool);
+        tools.insert("git.show", GitShowTool);
+        tools.insert("git.branch", GitBranchTool);
+        tools.insert("git.stash", GitStashTool);
+        tools.insert("git.conflicts", GitConflictsTool);
+
+        // Code intelligence tools
+        tools.insert("code.definition", CodeDefinitionTool);
+        tools.insert("code.references", CodeReferencesTool);
+        tools.insert("code.hover", CodeHoverTool);
+        tools.insert("code.diagnostics", CodeDiagnosticsTool);
+        tools.insert("code.rename", CodeRenameTool);
+        tools.insert("code.outline", CodeOutlineTool);
+        tools.insert("code.symbols", CodeSymbolsTool);
 
         // Skills tools
-        tools.insert(
-            "skills.list_installed".to_string(),
-            Box::new(SkillsListInstalledTool),
-        );
-        tools.insert("skills.search".to_string(), Box::new(SkillsSearchTool));
-        tools.insert("skills.load".to_string(), Box::new(SkillsLoadTool));
-        tools.insert("skills.install".to_string(), Box::new(SkillsInstallTool));
-        tools.insert("skills.remove".to_string(), Box::new(SkillsRemoveTool));
+        tools.insert("skills.list_installed", SkillsListInstalledTool);
+        tools.insert_async("skills.search", SkillsSearchTool);
+        tools.insert("skills.load", SkillsLoadTool);
+        tools.insert("skills.install", SkillsInstallTool);
+        tools.insert("skills.remove", SkillsRemoveTool);
 
         // Memory tools
-        tools.insert("memory.list".to_string(), Box::new(MemoryListTool));
-        tools.insert("memory.read".to_string(), Box::new(MemoryReadTool));
-        tools.insert("memory.upsert".to_string(), Box::new(MemoryUpsertTool));
-        tools.insert("memory.delete".to_string(), Box::new(MemoryDeleteTool));
-        tools.insert("memory.compact".to_string(), Box::new(MemoryCompactTool));
+        tools.insert("memory.list", MemoryListTool);
+        tools.insert("memory.read", MemoryReadTool);
+        tools.insert("memory.upsert", MemoryUpsertTool);
+        tools.insert("memory.delete", MemoryDeleteTool);
+        tools.insert("memory.compact", MemoryCompactTool);
 
         // Agent tools
-        tools.insert("agent.task".to_string(), Box::new(AgentTaskTool));
-        tools.insert("agent.ask_user".to_string(), Box::new(AgentAskUserTool));
-        tools.insert(
-            "agent.memory_upsert".to_string(),
-            Box::new(AgentMemoryUpsertTool),
-        );
-        tools.insert("agent.complete".to_string(), Box::new(AgentCompleteTool));
-        tools.insert(
-            "agent.create_preset".to_string(),
-            Box::new(AgentCreatePresetTool),
-        );
-        tools.insert("subagent.spawn".to_string(), Box::new(SubAgentSpawnTool));
-        tools.insert(
-            "agent.request_build_mode".to_string(),
-            Box::new(RequestBuildModeTool),
-        );
-        tools.insert(
-            "agent.request_plan_mode".to_string(),
-            Box::new(RequestPlanModeTool),
-        );
-        tools.insert(
-            "agent.create_artifact".to_string(),
-            Box::new(CreateArtifactTool),
-        );
+        tools.insert("agent.task", AgentTaskTool);
+        tools.insert("agent.ask_user", AgentAskUserTool);
+        tools.insert("agent.memory_upsert", AgentMemoryUpsertTool);
+        tools.insert("agent.complete", AgentCompleteTool);
+        tools.insert("agent.create_preset", AgentCreatePresetTool);
+        tools.insert("subagent.spawn", SubAgentSpawnTool);
+        tools.insert("agent.request_build_mode", RequestBuildModeTool);
+        tools.insert("agent.request_plan_mode", RequestPlanModeTool);
+        tools.insert("agent.create_artifact", CreateArtifactTool);
 
         // Dev server tools
-        tools.insert("dev_server.start".to_string(), Box::new(DevServerStartTool));
-        tools.insert("dev_server.stop".to_string(), Box::new(DevServerStopTool));
-        tools.insert(
-            "dev_server.status".to_string(),
-            Box::new(DevServerStatusTool),
-        );
-        tools.insert("dev_server.logs".to_string(), Box::new(DevServerLogsTool));
+        tools.insert_async("dev_server.start", DevServerStartTool);
+        tools.insert_async("dev_server.stop", DevServerStopTool);
+        tools.insert_async("dev_server.status", DevServerStatusTool);
+        tools.insert("dev_server.logs", DevServerLogsTool);
 
         // Web snapshot tool
-        tools.insert("web.snapshot".to_string(), Box::new(WebSnapshotTool));
+        tools.insert_async("web.snapshot", WebSnapshotTool);
 
-        Self { tools }
+        Self { tools: tools.0 }
     }
 
     /// List ALL available tools (unified list for cache-safe execution).
@@ -158,8 +196,11 @@ impl ToolRegistry {
 
     /// List all available tools including MCP tools.
     pub fn list(&self) -> Vec<ToolDescriptor> {
-        let mut descriptors: Vec<ToolDescriptor> =
-            self.tools.values().map(|t| t.descriptor()).collect();
+        let mut descriptors: Vec<ToolDescriptor> = self
+            .tools
+            .values()
+            .map(RegisteredTool::descriptor)
+            .collect();
 
         // Add MCP tools from cache
         let mcp_descriptors = load_mcp_tools_cache()
@@ -176,22 +217,77 @@ impl ToolRegistry {
         descriptors
     }
 
-    /// Invoke a tool by name with the given arguments.
-    pub fn invoke(
+    /// Whether a call to `name` only reads, so it may run alongside other
+    /// read-only calls. MCP tools count when their server marks them
+    /// read-only.
+    pub fn is_read_only(&self, name: &str) -> bool {
+        if self.tools.contains_key(name) {
+            return READ_ONLY_TOOLS.contains(&name);
+        }
+        let Some((server_id, tool_name)) = parse_mcp_tool_name(name) else {
+            return false;
+        };
+        load_mcp_tools_cache().iter().any(|entry| {
+            entry.server_id == server_id
+                && entry.tool_name == tool_name
+                && entry.read_only_hint == Some(true)
+        })
+    }
+
+    /// Invoke a tool for a tool call. The call stops early when
+    /// `ctx.cancel` is cancelled, except that a blocking tool that writes
+    /// finishes first and the cancellation is reported after it.
+    pub async fn invoke_with_context(
         &self,
+        ctx: &ToolContext,
         policy: &PolicyEngine,
         cwd: &Path,
         call: ToolCallInput,
     ) -> Result<ToolCallOutput, ToolError> {
+        check_tool_policy(policy, &call.name)?;
+
         // Try built-in tools first
-        if let Some(tool) = self.tools.get(&call.name) {
-            return tool.invoke(policy, cwd, call.args);
+        match self.tools.get(&call.name).cloned() {
+            Some(RegisteredTool::Async(tool)) => {
+                return tool.invoke(ctx, policy, cwd, call.args).await;
+            }
+            Some(RegisteredTool::Blocking(tool)) => {
+                if ctx.cancel.is_cancelled() {
+                    return Err(ToolError::cancelled());
+                }
+                let read_only = READ_ONLY_TOOLS.contains(&call.name.as_str());
+                let policy = policy.clone();
+                let cwd = cwd.to_path_buf();
+                let task =
+                    tokio::task::spawn_blocking(move || tool.invoke(&policy, &cwd, call.args));
+                let finished = async {
+                    task.await.map_err(|error| {
+                        ToolError::Execution(format!("tool task failed: {error}"))
+                    })?
+                };
+                if read_only {
+                    return ctx.cancellable(finished).await;
+                }
+                // A blocking tool cannot be stopped once running; wait for a
+                // writing one so its changes are done before the call ends
+                let result = finished.await;
+                if ctx.cancel.is_cancelled() {
+                    return Err(ToolError::cancelled());
+                }
+                return result;
+            }
+            None => {}
         }
 
         // Try MCP tools
         if let Some((server_id, tool_name)) = parse_mcp_tool_name(&call.name) {
-            let result = call_mcp_tool_by_server_and_name(&server_id, &tool_name, call.args)
-                .map_err(ToolError::Execution)?;
+            let result = ctx
+                .cancellable(async {
+                    call_mcp_tool_async(&server_id, &tool_name, call.args)
+                        .await
+                        .map_err(ToolError::Execution)
+                })
+                .await?;
             return Ok(ToolCallOutput {
                 ok: true,
                 data: result,
@@ -204,6 +300,68 @@ impl ToolRegistry {
             call.name
         )))
     }
+
+    /// Invoke a tool outside of a tool call (tests, benchmarks), blocking
+    /// until it finishes.
+    pub fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        call: ToolCallInput,
+    ) -> Result<ToolCallOutput, ToolError> {
+        if let Some(RegisteredTool::Blocking(tool)) = self.tools.get(&call.name) {
+            check_tool_policy(policy, &call.name)?;
+            return tool.invoke(policy, cwd, call.args);
+        }
+        block_on(self.invoke_with_context(&ToolContext::default(), policy, cwd, call))
+    }
+}
+
+fn check_tool_policy(policy: &PolicyEngine, name: &str) -> Result<(), ToolError> {
+    match policy.evaluate_tool(name) {
+        PolicyDecision::Allow => Ok(()),
+        PolicyDecision::Deny(reason) => Err(ToolError::PolicyDenied(reason)),
+        PolicyDecision::NeedsApproval { scope, reason } => {
+            Err(ToolError::ApprovalRequired { scope, reason })
+        }
+    }
+}
+
+#[derive(Default)]
+struct ToolMap(HashMap<String, RegisteredTool>);
+
+impl ToolMap {
+    fn insert(&mut self, name: &str, tool: impl Tool + 'static) {
+        self.0
+            .insert(name.to_string(), RegisteredTool::Blocking(Arc::new(tool)));
+    }
+
+    fn insert_async(&mut self, name: &str, tool: impl AsyncTool + 'static) {
+        self.0
+            .insert(name.to_string(), RegisteredTool::Async(Arc::new(tool)));
+    }
+}
+
+/// Drive `future` to completion from synchronous code: on the current
+/// runtime from a helper thread when there is one, otherwise on a
+/// single-threaded runtime built for the call.
+fn block_on<F: Future + Send>(future: F) -> F::Output
+where
+    F::Output: Send,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => std::thread::scope(|scope| {
+            scope
+                .spawn(|| handle.block_on(future))
+                .join()
+                .expect("tool thread panicked")
+        }),
+        Err(_) => tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("build tool runtime")
+            .block_on(future),
+    }
 }
 
 /// Parse an MCP tool name in the format "mcp.{server_id}.{tool_name}".
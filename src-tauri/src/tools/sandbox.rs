@@ -0,0 +1,367 @@
+//! OS-level confinement for `cmd.exec`.
+//!
+//! On Linux, commands run under bubblewrap (`bwrap`): only the system
+//! directories are mounted, read-only and without credential stores, the
+//! home directory is an empty tmpfs apart from read-only toolchain
+//! directories, and the worktree, a private `/tmp` and the policy's
+//! `sandbox.writable` paths are writable. The command gets its own PID
+//! namespace and, when network is disabled, an empty network namespace. CPU and memory limits
+//! are applied with `ulimit` and work with or without a backend.
+//!
+//! Whether `bwrap` works is probed once per process, since it needs
+//! unprivileged user namespaces. In `auto` mode an unusable backend falls
+//! back to running unconfined and the tool result says so; in `required`
+//! mode the command is refused.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::policy::config::{SandboxMode, SandboxRules};
+
+const BWRAP: &str = "bwrap";
+
+/// Host directories mounted read-only when they exist.
+const SYSTEM_DIRS: &[&str] = &[
+    "/usr", "/bin", "/sbin", "/lib", "/lib32", "/lib64", "/libx32", "/etc", "/opt", "/nix",
+];
+
+/// Secrets under the system directories, hidden behind an empty mount.
+const MASKED_SYSTEM_PATHS: &[&str] = &[
+    "/etc/ssh",
+    "/etc/ssl/private",
+    "/etc/shadow",
+    "/etc/gshadow",
+];
+
+/// Toolchains installed under the home directory, mounted read-only.
+const HOME_TOOLCHAIN_DIRS: &[&str] = &[
+    ".cargo",
+    ".rustup",
+    ".nvm",
+    ".bun",
+    ".deno",
+    ".pyenv",
+    ".local/bin",
+];
+
+/// How a command was run, reported in the `cmd.exec` result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SandboxReport {
+    pub mode: SandboxMode,
+    /// `bwrap`, or `none` when the command ran unconfined.
+    pub backend: &'static str,
+    pub network: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u64>,
+    /// Why the sandbox was requested but not used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
+}
+
+/// Sandbox settings resolved for one worktree.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    root: PathBuf,
+    rules: SandboxRules,
+    confined: bool,
+    report: Option<SandboxReport>,
+}
+
+impl Sandbox {
+    /// Resolve `rules` for commands running in `root`. Fails only in
+    /// `required` mode when no backend is usable.
+    pub fn new(root: &Path, rules: SandboxRules) -> Result<Self, String> {
+        let mode = rules.mode.unwrap_or_default();
+        let limited = rules.cpu_secs.is_some() || rules.memory_mb.is_some();
+        if mode == SandboxMode::Off && !limited {
+            return Ok(Self {
+                root: root.to_path_buf(),
+                rules,
+                confined: false,
+                report: None,
+            });
+        }
+
+        let unavailable = match mode {
+            SandboxMode::Off => None,
+            SandboxMode::Auto | SandboxMode::Required => backend_unavailable(),
+        };
+        if mode == SandboxMode::Required {
+            if let Some(reason) = unavailable {
+                return Err(format!("sandbox is required but unavailable: {reason}"));
+            }
+        }
+
+        let confined = mode != SandboxMode::Off && unavailable.is_none();
+        let report = SandboxReport {
+            mode,
+            backend: if confined { BWRAP } else { "none" },
+            network: !confined || rules.network != Some(false),
+            cpu_secs: rules.cpu_secs,
+            memory_mb: rules.memory_mb,
+            fallback: unavailable.map(str::to_string),
+        };
+        Ok(Self {
+            root: root.to_path_buf(),
+            rules,
+            confined,
+            report: Some(report),
+        })
+    }
+
+    pub fn report(&self) -> Option<&SandboxReport> {
+        self.report.as_ref()
+    }
+
+    /// Rewrite `command` to run under the sandbox. Its program, arguments,
+    /// working directory and environment are carried over.
+    pub fn wrap(&self, command: Command) -> Command {
+        if self.report.is_none() {
+            return command;
+        }
+
+        let cwd = command
+            .get_current_dir()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.root.clone());
+        let mut argv: Vec<OsString> = std::iter::once(command.get_program().to_os_string())
+            .chain(command.get_args().map(|arg| arg.to_os_string()))
+            .collect();
+
+        if let Some(prefix) = self.limit_prefix() {
+            argv.splice(0..0, prefix);
+        }
+        if self.confined {
+            argv.splice(0..0, self.bwrap_args(&cwd));
+        }
+
+        let mut wrapped = Command::new(&argv[0]);
+        wrapped.args(&argv[1..]).current_dir(&cwd);
+        for (key, value) in command.get_envs() {
+            match value {
+                Some(value) => wrapped.env(key, value),
+                None => wrapped.env_remove(key),
+            };
+        }
+        wrapped
+    }
+
+    /// `sh -c 'ulimit … && exec "$@"' sh`, placed before the real command.
+    #[cfg(unix)]
+    fn limit_prefix(&self) -> Option<Vec<OsString>> {
+        let mut script = Vec::new();
+        if let Some(secs) = self.rules.cpu_secs {
+            script.push(format!("ulimit -t {secs}"));
+        }
+        if let Some(mb) = self.rules.memory_mb {
+            script.push(format!("ulimit -v {}", mb.saturating_mul(1024)));
+        }
+        if script.is_empty() {
+            return None;
+        }
+        script.push("exec \"$@\"".to_string());
+        Some(vec![
+            "sh".into(),
+            "-c".into(),
+            script.join(" && ").into(),
+            "sh".into(),
+        ])
+    }
+
+    #[cfg(not(unix))]
+    fn limit_prefix(&self) -> Option<Vec<OsString>> {
+        None
+    }
+
+    fn bwrap_args(&self, cwd: &Path) -> Vec<OsString> {
+        let bind =
+            |flag: &str, path: &Path| -> [OsString; 3] { [flag.into(), path.into(), path.into()] };
+        let mut args: Vec<OsString> = vec![BWRAP.into()];
+        for dir in SYSTEM_DIRS {
+            args.extend(bind("--ro-bind-try", Path::new(dir)));
+        }
+        for masked in MASKED_SYSTEM_PATHS.iter().map(Path::new) {
+            if masked.is_dir() {
+                args.extend(["--tmpfs".into(), masked.into()]);
+            } else if masked.exists() {
+                args.extend(["--ro-bind".into(), "/dev/null".into(), masked.into()]);
+            }
+        }
+        args.extend(["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"].map(OsString::from));
+        if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+            args.extend(["--tmpfs".into(), home.as_os_str().to_os_string()]);
+            for dir in HOME_TOOLCHAIN_DIRS {
+                args.extend(bind("--ro-bind-try", &home.join(dir)));
+            }
+        }
+        // A worktree's git metadata lives in the main repository
+        if let Some(git_dir) = git_common_dir(&self.root) {
+            args.extend(bind("--ro-bind-try", &git_dir));
+        }
+        args.extend(bind("--bind", &self.root));
+        for path in &self.rules.writable {
+            args.extend(bind("--bind-try", &expand_home(path)));
+        }
+        args.extend(
+            [
+                "--unshare-pid",
+                "--unshare-ipc",
+                "--unshare-uts",
+                "--die-with-parent",
+            ]
+            .map(OsString::from),
+        );
+        if self.rules.network == Some(false) {
+            args.push("--unshare-net".into());
+        }
+        args.extend(["--chdir".into(), cwd.into(), "--".into()]);
+        args
+    }
+}
+
+/// The repository `.git` directory of a linked worktree at `root`, which is
+/// outside the worktree itself.
+fn git_common_dir(root: &Path) -> Option<PathBuf> {
+    let link = std::fs::read_to_string(root.join(".git")).ok()?;
+    let git_dir = root.join(link.strip_prefix("gitdir:")?.trim());
+    let common = std::fs::read_to_string(git_dir.join("commondir"))
+        .map(|common| git_dir.join(common.trim()))
+        .unwrap_or_else(|_| git_dir.join("../.."));
+    common.canonicalize().ok()
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Why no sandbox backend can be used here, or `None` when `bwrap` works.
+fn backend_unavailable() -> Option<&'static str> {
+    static PROBE: OnceLock<Option<String>> = OnceLock::new();
+    PROBE.get_or_init(probe_bwrap).as_deref()
+}
+
+#[cfg(target_os = "linux")]
+fn probe_bwrap() -> Option<String> {
+    let output = Command::new(BWRAP)
+        .args([
+            "--ro-bind",
+            "/",
+            "/",
+            "--unshare-pid",
+            "--unshare-net",
+            "--die-with-parent",
+            "--",
+            "true",
+        ])
+        .stdin(std::process::Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => None,
+        Ok(output) => Some(format!(
+            "bwrap cannot create namespaces: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            Some("bwrap is not installed".to_string())
+        }
+        Err(error) => Some(format!("bwrap failed to start: {error}")),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn probe_bwrap() -> Option<String> {
+    Some("command sandboxing is only supported on Linux".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn confined(rules: SandboxRules) -> Sandbox {
+        let mut sandbox = Sandbox::new(Path::new("/work/tree"), SandboxRules::default())
+            .expect("off mode always resolves");
+        sandbox.rules = rules;
+        sandbox.confined = true;
+        sandbox.report = Some(SandboxReport {
+            mode: SandboxMode::Required,
+            backend: BWRAP,
+            network: false,
+            cpu_secs: None,
+            memory_mb: None,
+            fallback: None,
+        });
+        sandbox
+    }
+
+    fn argv(command: &Command) -> Vec<String> {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn off_mode_leaves_command_untouched() {
+        let sandbox = Sandbox::new(Path::new("/work/tree"), SandboxRules::default()).unwrap();
+        let mut command = Command::new("ls");
+        command.arg("-la");
+
+        assert!(sandbox.report().is_none());
+        assert_eq!(argv(&sandbox.wrap(command)), vec!["ls", "-la"]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn wraps_command_in_bwrap_with_limits() {
+        let sandbox = confined(SandboxRules {
+            mode: Some(SandboxMode::Required),
+            network: Some(false),
+            cpu_secs: Some(30),
+            memory_mb: Some(512),
+            writable: vec!["/opt/cache".into()],
+        });
+        let mut command = Command::new("cargo");
+        command.arg("test").current_dir("/work/tree/crate");
+
+        let wrapped = sandbox.wrap(command);
+        let args = argv(&wrapped);
+        let joined = args.join(" ");
+
+        assert_eq!(args[0], "bwrap");
+        assert!(!joined.contains("--ro-bind / /"), "{joined}");
+        assert!(joined.contains("--ro-bind-try /usr /usr"), "{joined}");
+        if let Some(home) = std::env::var_os("HOME") {
+            let home = home.to_string_lossy();
+            assert!(joined.contains(&format!("--tmpfs {home}")), "{joined}");
+            assert!(
+                joined.contains(&format!("--ro-bind-try {home}/.cargo {home}/.cargo")),
+                "{joined}"
+            );
+        }
+        assert!(joined.contains("--bind /work/tree /work/tree"), "{joined}");
+        assert!(
+            joined.contains("--bind-try /opt/cache /opt/cache"),
+            "{joined}"
+        );
+        assert!(joined.contains("--unshare-net"), "{joined}");
+        assert!(
+            joined.ends_with(
+                "--chdir /work/tree/crate -- sh -c ulimit -t 30 && ulimit -v 524288 && exec \"$@\" sh cargo test"
+            ),
+            "{joined}"
+        );
+        assert_eq!(
+            wrapped.get_current_dir(),
+            Some(Path::new("/work/tree/crate"))
+        );
+    }
+}
@@ -4,6 +4,7 @@ use std::path::Path;
 use std::process::Command;
 
 use crate::core::tool::ToolDescriptor;
+use crate::policy::paths::PathClass;
 use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::tools::args::{schema_for_type, SearchRgArgs};
 use crate::tools::types::{Tool, ToolCallOutput, ToolError};
@@ -47,7 +48,7 @@ impl Tool for SearchRgTool {
 
         let full = cwd.join(path);
 
-        match policy.evaluate_path(&full) {
+        match policy.evaluate_read(&full) {
             PolicyDecision::Allow => {}
             PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
             PolicyDecision::NeedsApproval { scope, reason } => {
@@ -55,8 +56,19 @@ impl Tool for SearchRgTool {
             }
         }
 
+        if policy.path_class(&full) == Some(PathClass::Secret) {
+            return Err(ToolError::PolicyDenied(format!(
+                "searching secret file {path} is not allowed; use fs.read for a redacted view"
+            )));
+        }
+
         let mut cmd = Command::new("rg");
 
+        // Keep secret and read-denied files out of the results
+        for pattern in policy.search_exclusions() {
+            cmd.arg("--glob").arg(format!("!{pattern}"));
+        }
+
         if json_output {
             cmd.arg("--json");
         } else {
@@ -1,11 +1,13 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use async_trait::async_trait;
+
 use crate::core::tool::ToolDescriptor;
 use crate::embeddings::SemanticIndexService;
 use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::tools::args::{schema_for_type, SearchEmbeddingsArgs};
-use crate::tools::types::{Tool, ToolCallOutput, ToolError};
+use crate::tools::types::{AsyncTool, ToolCallOutput, ToolContext, ToolError};
 
 static SEMANTIC_INDEX_SERVICE: std::sync::OnceLock<Arc<SemanticIndexService>> =
     std::sync::OnceLock::new();
@@ -22,7 +24,8 @@ fn semantic_index_service() -> Result<&'static Arc<SemanticIndexService>, ToolEr
 
 pub struct SearchEmbeddingsTool;
 
-impl Tool for SearchEmbeddingsTool {
+#[async_trait]
+impl AsyncTool for SearchEmbeddingsTool {
     fn descriptor(&self) -> ToolDescriptor {
         ToolDescriptor {
             name: "search.embeddings".into(),
@@ -37,8 +40,9 @@ impl Tool for SearchEmbeddingsTool {
         }
     }
 
-    fn invoke(
+    async fn invoke(
         &self,
+        ctx: &ToolContext,
         policy: &PolicyEngine,
         cwd: &Path,
         input: serde_json::Value,
@@ -66,27 +70,14 @@ impl Tool for SearchEmbeddingsTool {
 
         let service = Arc::clone(semantic_index_service()?);
 
-        let response = if let Ok(runtime) = tokio::runtime::Handle::try_current() {
-            tokio::task::block_in_place(|| {
-                runtime.block_on(async move {
-                    service.semantic_search(workspace_root, query, limit).await
-                })
+        let response = ctx
+            .cancellable(async move {
+                service
+                    .semantic_search(workspace_root, query, limit)
+                    .await
+                    .map_err(|error| ToolError::Execution(error.to_string()))
             })
-            .map_err(|error| ToolError::Execution(error.to_string()))?
-        } else {
-            let runtime = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .map_err(|error| {
-                    ToolError::Execution(format!("failed to create async runtime: {error}"))
-                })?;
-
-            runtime
-                .block_on(
-                    async move { service.semantic_search(workspace_root, query, limit).await },
-                )
-                .map_err(|error| ToolError::Execution(error.to_string()))?
-        };
+            .await?;
 
         Ok(ToolCallOutput {
             ok: true,
@@ -10,6 +10,8 @@
 
 use std::path::Path;
 
+use async_trait::async_trait;
+
 use crate::core::skills::search_agent_skills;
 use crate::core::tool::ToolDescriptor;
 use crate::core::workspace_skills::{scan_workspace_skills, WorkspaceSkill};
@@ -18,7 +20,7 @@ use crate::tools::args::{
     schema_for_type, SkillsInstallArgs, SkillsListInstalledArgs, SkillsLoadArgs, SkillsRemoveArgs,
     SkillsSearchArgs,
 };
-use crate::tools::types::{Tool, ToolCallOutput, ToolError};
+use crate::tools::types::{AsyncTool, Tool, ToolCallOutput, ToolContext, ToolError};
 
 /// Lightweight skill info for listing (without full content)
 #[derive(serde::Serialize)]
@@ -92,7 +94,8 @@ impl Tool for SkillsListInstalledTool {
 /// Tool for searching skills from remote sources.
 pub struct SkillsSearchTool;
 
-impl Tool for SkillsSearchTool {
+#[async_trait]
+impl AsyncTool for SkillsSearchTool {
     fn descriptor(&self) -> ToolDescriptor {
         ToolDescriptor {
             name: "skills.search".into(),
@@ -102,8 +105,9 @@ impl Tool for SkillsSearchTool {
         }
     }
 
-    fn invoke(
+    async fn invoke(
         &self,
+        ctx: &ToolContext,
         _policy: &PolicyEngine,
         _cwd: &Path,
         input: serde_json::Value,
@@ -114,10 +118,9 @@ impl Tool for SkillsSearchTool {
         let limit = args.limit.unwrap_or(10) as usize;
 
         let query = args.query.clone();
-        let results = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current()
-                .block_on(async { search_agent_skills(&query, limit).await })
-        });
+        let results = ctx
+            .cancellable(async { Ok(search_agent_skills(&query, limit).await) })
+            .await?;
 
         match results {
             Ok(items) => {
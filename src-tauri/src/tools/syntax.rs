@@ -0,0 +1,609 @@
+//! Syntax-aware outline and structural search tools.
+//!
+//! Files are parsed with tree-sitter, so these work without a language
+//! server or a build: `code.outline` lists the functions, types and impls of
+//! a file with their line ranges, `code.symbols` finds symbols across a
+//! directory or runs a tree-sitter query. Directory walks use the same
+//! ignore rules as `search.files`.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde_json::{json, Value};
+use tree_sitter::{Language, Node, Parser, Query, QueryCursor, Tree};
+
+use crate::core::tool::ToolDescriptor;
+use crate::policy::{PolicyDecision, PolicyEngine};
+use crate::tools::args::{schema_for_type, CodeOutlineArgs, CodeSymbolsArgs};
+use crate::tools::file_search::walk_files;
+use crate::tools::types::{Tool, ToolCallOutput, ToolError};
+
+/// Files larger than this are not parsed: code.outline refuses them and
+/// code.symbols skips them when walking a directory.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+const DEFAULT_SYMBOL_LIMIT: usize = 100;
+const MAX_SYMBOL_LIMIT: usize = 1000;
+
+/// Captured text longer than this is cut in query results.
+const MAX_CAPTURE_CHARS: usize = 200;
+
+const RUST_OUTLINE: &str = r#"
+(function_item name: (identifier) @name) @function
+(function_signature_item name: (identifier) @name) @function
+(struct_item name: (type_identifier) @name) @struct
+(enum_item name: (type_identifier) @name) @enum
+(union_item name: (type_identifier) @name) @struct
+(trait_item name: (type_identifier) @name) @trait
+(type_item name: (type_identifier) @name) @type
+(impl_item type: (_) @name) @impl
+(mod_item name: (identifier) @name) @module
+(const_item name: (identifier) @name) @constant
+(static_item name: (identifier) @name) @constant
+(macro_definition name: (identifier) @name) @macro
+"#;
+
+const JAVASCRIPT_OUTLINE: &str = r#"
+(function_declaration name: (identifier) @name) @function
+(generator_function_declaration name: (identifier) @name) @function
+(class_declaration name: (_) @name) @class
+(method_definition name: (_) @name) @method
+(variable_declarator
+  name: (identifier) @name
+  value: [(arrow_function) (function_expression)]) @function
+"#;
+
+const TYPESCRIPT_OUTLINE_EXTRA: &str = r#"
+(abstract_class_declaration name: (_) @name) @class
+(interface_declaration name: (_) @name) @interface
+(type_alias_declaration name: (_) @name) @type
+(enum_declaration name: (_) @name) @enum
+(internal_module name: (_) @name) @module
+"#;
+
+const PYTHON_OUTLINE: &str = r#"
+(function_definition name: (identifier) @name) @function
+(class_definition name: (identifier) @name) @class
+"#;
+
+/// Symbol kinds reported by the outline queries.
+const SYMBOL_KINDS: &[&str] = &[
+    "function",
+    "method",
+    "struct",
+    "enum",
+    "trait",
+    "impl",
+    "class",
+    "interface",
+    "type",
+    "module",
+    "constant",
+    "macro",
+];
+
+/// Kinds whose functions are reported as methods.
+const METHOD_CONTAINERS: &[&str] = &["impl", "trait", "class", "interface"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SyntaxLanguage {
+    Rust,
+    TypeScript,
+    Tsx,
+    JavaScript,
+    Python,
+}
+
+impl SyntaxLanguage {
+    const ALL: [Self; 5] = [
+        Self::Rust,
+        Self::TypeScript,
+        Self::Tsx,
+        Self::JavaScript,
+        Self::Python,
+    ];
+
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "rs" => Some(Self::Rust),
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::Tsx),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "py" | "pyi" => Some(Self::Python),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.name().eq_ignore_ascii_case(name))
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Rust => "rust",
+            Self::TypeScript => "typescript",
+            Self::Tsx => "tsx",
+            Self::JavaScript => "javascript",
+            Self::Python => "python",
+        }
+    }
+
+    fn grammar(self) -> Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            // The JavaScript grammar parses JSX too
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+        }
+    }
+
+    fn outline_query(self) -> String {
+        match self {
+            Self::Rust => RUST_OUTLINE.to_string(),
+            Self::TypeScript | Self::Tsx => {
+                format!("{JAVASCRIPT_OUTLINE}{TYPESCRIPT_OUTLINE_EXTRA}")
+            }
+            Self::JavaScript => JAVASCRIPT_OUTLINE.to_string(),
+            Self::Python => PYTHON_OUTLINE.to_string(),
+        }
+    }
+}
+
+/// Parsers and compiled queries for one tool call, per language.
+struct Grammars {
+    parsers: HashMap<SyntaxLanguage, Parser>,
+    queries: HashMap<SyntaxLanguage, Query>,
+    /// Query source for `queries`; the outline query when `None`.
+    query: Option<String>,
+}
+
+impl Grammars {
+    fn outline() -> Self {
+        Self {
+            parsers: HashMap::new(),
+            queries: HashMap::new(),
+            query: None,
+        }
+    }
+
+    fn with_query(query: &str) -> Self {
+        Self {
+            query: Some(query.to_string()),
+            ..Self::outline()
+        }
+    }
+
+    fn parse(&mut self, language: SyntaxLanguage, source: &str) -> Result<Tree, ToolError> {
+        let parser = match self.parsers.entry(language) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut parser = Parser::new();
+                parser.set_language(&language.grammar()).map_err(|e| {
+                    ToolError::Execution(format!("failed to load {} grammar: {e}", language.name()))
+                })?;
+                entry.insert(parser)
+            }
+        };
+        parser.parse(source, None).ok_or_else(|| {
+            ToolError::Execution(format!("failed to parse {} source", language.name()))
+        })
+    }
+
+    fn query(&mut self, language: SyntaxLanguage) -> Result<&Query, ToolError> {
+        if !self.queries.contains_key(&language) {
+            let source = match &self.query {
+                Some(query) => query.clone(),
+                None => language.outline_query(),
+            };
+            let query = Query::new(&language.grammar(), &source).map_err(|e| {
+                ToolError::InvalidInput(format!("invalid {} query: {e}", language.name()))
+            })?;
+            self.queries.insert(language, query);
+        }
+        Ok(&self.queries[&language])
+    }
+}
+
+/// A definition found by the outline query.
+#[derive(Debug, Clone)]
+struct Symbol {
+    name: String,
+    kind: &'static str,
+    line: usize,
+    end_line: usize,
+    /// Number of enclosing symbols.
+    depth: usize,
+    signature: String,
+    start_byte: usize,
+    end_byte: usize,
+}
+
+impl Symbol {
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "kind": self.kind,
+            "line": self.line,
+            "end_line": self.end_line,
+            "depth": self.depth,
+            "signature": self.signature,
+        })
+    }
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}...", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+/// Whitespace runs collapsed to single spaces.
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 1-based character column of `node`'s start.
+fn char_column(source: &str, node: Node) -> usize {
+    let start = node.start_byte();
+    let line_start = start - node.start_position().column;
+    source[line_start..start].chars().count() + 1
+}
+
+/// Definitions in `tree`, ordered by position, with nesting depth.
+fn outline(
+    grammars: &mut Grammars,
+    language: SyntaxLanguage,
+    source: &str,
+    tree: &Tree,
+) -> Result<Vec<Symbol>, ToolError> {
+    let query = grammars.query(language)?;
+    let names = query.capture_names();
+    let mut cursor = QueryCursor::new();
+    let mut symbols: Vec<Symbol> = Vec::new();
+
+    for found in cursor.matches(query, tree.root_node(), source.as_bytes()) {
+        let mut name_node = None;
+        let mut definition = None;
+        for capture in found.captures {
+            match names[capture.index as usize] {
+                "name" => name_node = Some(capture.node),
+                kind => {
+                    definition = SYMBOL_KINDS
+                        .iter()
+                        .find(|known| **known == kind)
+                        .map(|known| (*known, capture.node))
+                }
+            }
+        }
+        let (Some(name_node), Some((kind, node))) = (name_node, definition) else {
+            continue;
+        };
+
+        // An impl is named by its header: `impl<T> Display for Wrapper<T>`
+        let header_end = node
+            .child_by_field_name("body")
+            .map(|body| body.start_byte())
+            .unwrap_or(node.end_byte());
+        let header = &source[node.start_byte()..header_end];
+        let name = if kind == "impl" {
+            single_line(header)
+        } else {
+            source[name_node.byte_range()].to_string()
+        };
+        let first_line = header.lines().next().unwrap_or_default().trim();
+
+        symbols.push(Symbol {
+            name,
+            kind,
+            line: node.start_position().row + 1,
+            end_line: node.end_position().row + 1,
+            depth: 0,
+            signature: truncate_chars(first_line, MAX_CAPTURE_CHARS),
+            start_byte: node.start_byte(),
+            end_byte: node.end_byte(),
+        });
+    }
+
+    symbols.sort_by(|a, b| {
+        a.start_byte
+            .cmp(&b.start_byte)
+            .then(b.end_byte.cmp(&a.end_byte))
+    });
+    symbols.dedup_by(|a, b| a.start_byte == b.start_byte && a.kind == b.kind);
+
+    let mut enclosing: Vec<(usize, &'static str)> = Vec::new();
+    for symbol in &mut symbols {
+        while enclosing
+            .last()
+            .is_some_and(|(end, _)| *end <= symbol.start_byte)
+        {
+            enclosing.pop();
+        }
+        if symbol.kind == "function"
+            && enclosing
+                .last()
+                .is_some_and(|(_, kind)| METHOD_CONTAINERS.contains(kind))
+        {
+            symbol.kind = "method";
+        }
+        symbol.depth = enclosing.len();
+        enclosing.push((symbol.end_byte, symbol.kind));
+    }
+    Ok(symbols)
+}
+
+/// Matches of the user's query in `tree`, as
+/// `{line, end_line, captures: [{name, text, line, column}]}`.
+fn query_matches(
+    grammars: &mut Grammars,
+    language: SyntaxLanguage,
+    source: &str,
+    tree: &Tree,
+) -> Result<Vec<Value>, ToolError> {
+    let query = grammars.query(language)?;
+    let names = query.capture_names();
+    let mut cursor = QueryCursor::new();
+    let mut matches = Vec::new();
+    for found in cursor.matches(query, tree.root_node(), source.as_bytes()) {
+        let Some(first) = found
+            .captures
+            .iter()
+            .map(|c| c.node)
+            .min_by_key(Node::start_byte)
+        else {
+            continue;
+        };
+        let last = found
+            .captures
+            .iter()
+            .map(|c| c.node.end_position().row)
+            .max()
+            .unwrap_or(first.end_position().row);
+        let captures: Vec<Value> = found
+            .captures
+            .iter()
+            .map(|capture| {
+                json!({
+                    "name": names[capture.index as usize],
+                    "text": truncate_chars(&source[capture.node.byte_range()], MAX_CAPTURE_CHARS),
+                    "line": capture.node.start_position().row + 1,
+                    "column": char_column(source, capture.node),
+                })
+            })
+            .collect();
+        matches.push(json!({
+            "line": first.start_position().row + 1,
+            "end_line": last + 1,
+            "captures": captures,
+        }));
+    }
+    Ok(matches)
+}
+
+fn read_source(path: &Path, display: &str) -> Result<String, ToolError> {
+    std::fs::read_to_string(path)
+        .map_err(|e| ToolError::Execution(format!("failed to read {display}: {e}")))
+}
+
+/// Tool for listing the definitions in a file.
+pub struct CodeOutlineTool;
+
+impl Tool for CodeOutlineTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "code.outline".into(),
+            description: concat!(
+                "List the functions, methods, types, traits, impls and classes defined in a file with their line ranges, ",
+                "nested by depth. Use it to see the skeleton of a large file, then read only the parts you need ",
+                "with fs.read offset/limit. Supports Rust, TypeScript, JavaScript and Python."
+            )
+            .into(),
+            input_schema: schema_for_type::<CodeOutlineArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: CodeOutlineArgs = serde_json::from_value(input)
+            .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
+
+        let full = cwd.join(&args.path);
+        match policy.evaluate_read(&full) {
+            PolicyDecision::Allow => {}
+            PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
+            PolicyDecision::NeedsApproval { scope, reason } => {
+                return Err(ToolError::ApprovalRequired { scope, reason })
+            }
+        }
+        let language = SyntaxLanguage::from_path(&full).ok_or_else(|| {
+            ToolError::InvalidInput(format!(
+                "unsupported file type for {}; supported: .rs, .ts, .tsx, .js, .jsx, .py",
+                args.path
+            ))
+        })?;
+
+        if std::fs::metadata(&full).is_ok_and(|meta| meta.len() > MAX_FILE_BYTES) {
+            return Err(ToolError::InvalidInput(format!(
+                "{} is larger than {} KiB; read the part you need with fs.read offset/limit",
+                args.path,
+                MAX_FILE_BYTES / 1024
+            )));
+        }
+        let source = read_source(&full, &args.path)?;
+        let mut grammars = Grammars::outline();
+        let tree = grammars.parse(language, &source)?;
+        let symbols = outline(&mut grammars, language, &source, &tree)?;
+
+        Ok(ToolCallOutput {
+            ok: true,
+            data: json!({
+                "path": args.path,
+                "language": language.name(),
+                "line_count": source.lines().count(),
+                "has_syntax_errors": tree.root_node().has_error(),
+                "symbols": symbols.iter().map(Symbol::to_json).collect::<Vec<_>>(),
+            }),
+            error: None,
+        })
+    }
+}
+
+/// Tool for finding symbols, or structural query matches, across files.
+pub struct CodeSymbolsTool;
+
+impl Tool for CodeSymbolsTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            name: "code.symbols".into(),
+            description: concat!(
+                "Find definitions across a file or directory by name and kind, without a build or language server. ",
+                "Respects .gitignore. With 'query', runs a tree-sitter S-expression query instead and returns each ",
+                "match with its captures, e.g. '(call_expression function: (identifier) @fn (#eq? @fn \"unwrap\"))'; ",
+                "set 'language' when querying a directory."
+            )
+            .into(),
+            input_schema: schema_for_type::<CodeSymbolsArgs>(),
+            output_schema: None,
+        }
+    }
+
+    fn invoke(
+        &self,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolCallOutput, ToolError> {
+        let args: CodeSymbolsArgs = serde_json::from_value(input)
+            .map_err(|e| ToolError::InvalidInput(format!("invalid input: {}", e)))?;
+
+        let search_path = args.path.as_deref().unwrap_or(".");
+        let limit = args
+            .limit
+            .unwrap_or(DEFAULT_SYMBOL_LIMIT)
+            .clamp(1, MAX_SYMBOL_LIMIT);
+        let full = cwd.join(search_path);
+        match policy.evaluate_read(&full) {
+            PolicyDecision::Allow => {}
+            PolicyDecision::Deny(reason) => return Err(ToolError::PolicyDenied(reason)),
+            PolicyDecision::NeedsApproval { scope, reason } => {
+                return Err(ToolError::ApprovalRequired { scope, reason })
+            }
+        }
+        if !full.exists() {
+            return Err(ToolError::Execution(format!(
+                "path does not exist: {search_path}"
+            )));
+        }
+
+        let language = match args.language.as_deref() {
+            Some(name) => Some(SyntaxLanguage::from_name(name).ok_or_else(|| {
+                ToolError::InvalidInput(format!(
+                    "unknown language '{name}'; expected rust, typescript, tsx, javascript or python"
+                ))
+            })?),
+            None => None,
+        };
+        let query = args.query.as_deref().filter(|q| !q.trim().is_empty());
+        if query.is_some() && language.is_none() && full.is_dir() {
+            return Err(ToolError::InvalidInput(
+                "language is required when querying a directory".into(),
+            ));
+        }
+        let name_filter = args.name.as_deref().map(str::to_lowercase);
+        let kind_filter = args.kind.as_deref();
+
+        let files: Vec<_> = if full.is_dir() {
+            walk_files(&full).collect()
+        } else {
+            vec![full.clone()]
+        };
+        let mut grammars = match query {
+            Some(query) => Grammars::with_query(query),
+            None => Grammars::outline(),
+        };
+        let mut results = Vec::new();
+        let mut total = 0;
+        let mut files_scanned = 0;
+
+        for path in files {
+            let Some(file_language) = SyntaxLanguage::from_path(&path) else {
+                continue;
+            };
+            if language.is_some_and(|language| language != file_language) {
+                continue;
+            }
+            if path != full {
+                // Skip what fs.read could not show, and generated blobs
+                if !matches!(policy.evaluate_read(&path), PolicyDecision::Allow)
+                    || std::fs::metadata(&path).map_or(true, |meta| meta.len() > MAX_FILE_BYTES)
+                {
+                    continue;
+                }
+            }
+            let display = path
+                .strip_prefix(cwd)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let Ok(source) = read_source(&path, &display) else {
+                continue;
+            };
+            let tree = grammars.parse(file_language, &source)?;
+            files_scanned += 1;
+
+            if query.is_some() {
+                for mut found in query_matches(&mut grammars, file_language, &source, &tree)? {
+                    total += 1;
+                    if results.len() < limit {
+                        found["path"] = json!(display);
+                        results.push(found);
+                    }
+                }
+                continue;
+            }
+
+            for symbol in outline(&mut grammars, file_language, &source, &tree)? {
+                let name_matches = name_filter
+                    .as_deref()
+                    .is_none_or(|filter| symbol.name.to_lowercase().contains(filter));
+                let kind_matches = kind_filter.is_none_or(|kind| symbol.kind == kind);
+                if !name_matches || !kind_matches {
+                    continue;
+                }
+                total += 1;
+                if results.len() < limit {
+                    let mut entry = symbol.to_json();
+                    entry["path"] = json!(display);
+                    results.push(entry);
+                }
+            }
+        }
+
+        let key = if query.is_some() {
+            "matches"
+        } else {
+            "symbols"
+        };
+        Ok(ToolCallOutput {
+            ok: true,
+            data: json!({
+                key: results,
+                "total": total,
+                "truncated": total > limit,
+                "files_scanned": files_scanned,
+            }),
+            error: None,
+        })
+    }
+}
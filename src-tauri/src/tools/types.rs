@@ -2,16 +2,22 @@
 //!
 //! This module defines the core abstractions for tools:
 //! - Tool input/output types
-//! - Tool trait for implementing new tools
+//! - Tool and AsyncTool traits for implementing new tools
+//! - The per-call context (cancellation, progress, run/task ids)
 //! - Error types for tool execution
 
+use std::future::Future;
 use std::path::Path;
 
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio_util::sync::CancellationToken;
 
 use crate::core::tool::ToolDescriptor;
 use crate::policy::PolicyEngine;
 use crate::runtime::questions::UserQuestionRequest;
+use crate::tools::process::OutputChunk;
 
 /// Input to a tool invocation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,10 +49,19 @@ pub enum ToolError {
     UserQuestionRequired { question: UserQuestionRequest },
 }
 
-/// Trait for implementing tools.
+impl ToolError {
+    /// The error a cancelled tool call ends with.
+    pub(crate) fn cancelled() -> Self {
+        Self::Execution("tool call cancelled".into())
+    }
+}
+
+/// Trait for implementing quick, synchronous tools.
 ///
 /// Tools are invoked by the orchestrator and must be Send + Sync for use
-/// across async boundaries.
+/// across async boundaries. The registry runs them on the blocking pool;
+/// anything that waits on I/O or may need cancelling implements
+/// [`AsyncTool`] instead.
 pub trait Tool: Send + Sync {
     /// Returns the descriptor for this tool, including name, description,
     /// and JSON schema for inputs.
@@ -65,3 +80,96 @@ pub trait Tool: Send + Sync {
         input: serde_json::Value,
     ) -> Result<ToolCallOutput, ToolError>;
 }
+
+/// Trait for tools that wait on I/O, child processes or the network.
+///
+/// Implementations must stop promptly once `ctx.cancel` is cancelled; most
+/// can wrap their work in [`ToolContext::cancellable`].
+#[async_trait]
+pub trait AsyncTool: Send + Sync {
+    fn descriptor(&self) -> ToolDescriptor;
+
+    async fn invoke(
+        &self,
+        ctx: &ToolContext,
+        policy: &PolicyEngine,
+        cwd: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolCallOutput, ToolError>;
+}
+
+/// What a tool call belongs to and how it reports back while running.
+///
+/// The default context has no ids, a token that is never cancelled and a
+/// reporter that drops everything; tests and benchmarks use it.
+#[derive(Debug, Clone, Default)]
+pub struct ToolContext {
+    pub run_id: Option<String>,
+    pub task_id: Option<String>,
+    pub sub_agent_id: Option<String>,
+    pub tool_call_id: Option<String>,
+    pub cancel: CancellationToken,
+    pub progress: ProgressReporter,
+}
+
+impl ToolContext {
+    /// Run `work` unless the call is cancelled first.
+    pub async fn cancellable<T>(
+        &self,
+        work: impl Future<Output = Result<T, ToolError>>,
+    ) -> Result<T, ToolError> {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(ToolError::cancelled()),
+            result = work => result,
+        }
+    }
+}
+
+/// Progress reported by a running tool.
+#[derive(Debug)]
+pub enum ToolProgress {
+    /// Raw command output, streamed as `tool.output_delta` events.
+    Output(OutputChunk),
+    /// A short status line, such as what the tool is waiting for.
+    Status(String),
+}
+
+/// Sends [`ToolProgress`] to whoever runs the tool call.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressReporter {
+    sender: Option<UnboundedSender<ToolProgress>>,
+}
+
+impl ProgressReporter {
+    pub fn channel() -> (Self, UnboundedReceiver<ToolProgress>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (
+            Self {
+                sender: Some(sender),
+            },
+            receiver,
+        )
+    }
+
+    /// Whether anyone listens; lets tools skip work that only feeds progress.
+    pub fn is_active(&self) -> bool {
+        self.sender
+            .as_ref()
+            .is_some_and(|sender| !sender.is_closed())
+    }
+
+    pub fn output(&self, chunk: OutputChunk) {
+        self.send(ToolProgress::Output(chunk));
+    }
+
+    pub fn status(&self, message: impl Into<String>) {
+        self.send(ToolProgress::Status(message.into()));
+    }
+
+    fn send(&self, progress: ToolProgress) {
+        if let Some(sender) = self.sender.as_ref() {
+            let _ = sender.send(progress);
+        }
+    }
+}
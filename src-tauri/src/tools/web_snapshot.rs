@@ -4,15 +4,16 @@
 
 use std::path::Path;
 
+use async_trait::async_trait;
 use headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption;
 use headless_chrome::{Browser, LaunchOptions};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::core::tool::ToolDescriptor;
-use crate::policy::PolicyEngine;
+use crate::policy::{PolicyDecision, PolicyEngine};
 use crate::tools::args::{schema_for_type, WebSnapshotArgs};
-use crate::tools::types::{Tool, ToolCallOutput, ToolError};
+use crate::tools::types::{AsyncTool, ToolCallOutput, ToolContext, ToolError};
 
 const DEFAULT_VIEWPORT_WIDTH: u32 = 1280;
 const DEFAULT_VIEWPORT_HEIGHT: u32 = 720;
@@ -55,7 +56,8 @@ pub struct FailedRequest {
     pub error: Option<String>,
 }
 
-impl Tool for WebSnapshotTool {
+#[async_trait]
+impl AsyncTool for WebSnapshotTool {
     fn descriptor(&self) -> ToolDescriptor {
         ToolDescriptor {
             name: "web.snapshot".into(),
@@ -69,9 +71,10 @@ impl Tool for WebSnapshotTool {
         }
     }
 
-    fn invoke(
+    async fn invoke(
         &self,
-        _policy: &PolicyEngine,
+        ctx: &ToolContext,
+        policy: &PolicyEngine,
         cwd: &Path,
         input: serde_json::Value,
     ) -> Result<ToolCallOutput, ToolError> {
@@ -85,27 +88,25 @@ impl Tool for WebSnapshotTool {
             ));
         }
 
-        // Only allow localhost or local network URLs by default for security
-        let is_localhost = args.url.contains("localhost")
-            || args.url.contains("127.0.0.1")
-            || args.url.contains("192.168.")
-            || args.url.contains("10.")
-            || args.url.contains("172.");
-
-        if !is_localhost {
-            return Err(ToolError::PolicyDenied(
-                "Only localhost and local network URLs are allowed".into(),
-            ));
+        // Only localhost, local network and policy-allowed hosts
+        if let PolicyDecision::Deny(reason) = policy.evaluate_browser_url(&args.url) {
+            return Err(ToolError::PolicyDenied(reason));
         }
 
-        let runtime = tokio::runtime::Handle::try_current()
-            .map_err(|e| ToolError::Execution(format!("no async runtime: {}", e)))?;
-
+        // The browser driver blocks, so the capture gets its own thread.
+        // Cancelling abandons it; the browser closes when the capture ends.
+        ctx.progress.status(format!("capturing {}", args.url));
         let cwd_owned = cwd.to_path_buf();
-        let result =
-            std::thread::spawn(move || runtime.block_on(capture_snapshot(&cwd_owned, args)))
-                .join()
-                .map_err(|_| ToolError::Execution("web snapshot thread panicked".to_string()))??;
+        let capture = tokio::task::spawn_blocking(move || {
+            tokio::runtime::Handle::current().block_on(capture_snapshot(&cwd_owned, args))
+        });
+        let result = ctx
+            .cancellable(async {
+                capture
+                    .await
+                    .map_err(|_| ToolError::Execution("web snapshot thread panicked".to_string()))?
+            })
+            .await?;
 
         Ok(ToolCallOutput {
             ok: true,
@@ -30,6 +30,8 @@ export const eventHandlers: Record<string, EventHandler> = {
 
   "tool.call_started": tool.handleToolCallStarted,
   "tool.call_finished": tool.handleToolCallFinished,
+  "tool.output_delta": tool.handleToolOutputDelta,
+  "tool.progress": tool.handleToolProgress,
 
   "agent.subagent_created": subagent.handleSubagentCreated,
   "agent.subagent_started": subagent.handleSubagentStarted,
@@ -86,6 +86,36 @@ export function handleToolCallStarted(ctx: HandlerContext): HandlerResult {
   return { planChanged: false, timelineChanged: true };
 }
 
+export function handleToolOutputDelta(ctx: HandlerContext): HandlerResult {
+  const toolCallId = ctx.event.payload?.tool_call_id as string | undefined;
+  const content = ctx.event.payload?.content as string | undefined;
+  if (!toolCallId || !content) return { planChanged: false, timelineChanged: false };
+
+  const ref = ctx.getActiveToolCall(toolCallId);
+  const item = ref ? ctx.items[ref.itemIndex] : undefined;
+  if (!item || item.type !== "toolCall" || item.toolStatus !== "running") {
+    return { planChanged: false, timelineChanged: false };
+  }
+
+  item.toolResult = (item.toolResult ?? "") + content;
+  return { planChanged: false, timelineChanged: true };
+}
+
+export function handleToolProgress(ctx: HandlerContext): HandlerResult {
+  const toolCallId = ctx.event.payload?.tool_call_id as string | undefined;
+  const message = ctx.event.payload?.message as string | undefined;
+  if (!toolCallId || !message) return { planChanged: false, timelineChanged: false };
+
+  const ref = ctx.getActiveToolCall(toolCallId);
+  const item = ref ? ctx.items[ref.itemIndex] : undefined;
+  if (!item || item.type !== "toolCall" || item.toolStatus !== "running") {
+    return { planChanged: false, timelineChanged: false };
+  }
+
+  item.toolProgress = message;
+  return { planChanged: false, timelineChanged: true };
+}
+
 export function handleToolCallFinished(ctx: HandlerContext): HandlerResult {
   const toolCallId = ctx.event.payload?.tool_call_id as string | undefined;
   const ok = ctx.event.payload?.status === "succeeded";
@@ -28,6 +28,8 @@ export interface ConversationItem {
   toolRationale?: string;
   toolStatus?: "running" | "success" | "error";
   toolResult?: string;
+  /** Latest status line reported by a running tool. */
+  toolProgress?: string;
   toolError?: string;
   toolDurationMs?: number;
   filePath?: string;